@@ -41,6 +41,9 @@ pub struct GlutinWindow {
     // Used to fake capturing of cursor,
     // to get relative mouse events.
     is_capturing_cursor: bool,
+    // Used to fake confining of cursor,
+    // when the platform does not support it.
+    is_faking_cursor_confinement: bool,
     // Stores the last known cursor position.
     last_cursor_pos: Option<[f64; 2]>,
     // Stores relative coordinates to emit on next poll.
@@ -53,8 +56,13 @@ pub struct GlutinWindow {
     event_loop: winit::event_loop::EventLoop<UserEvent>,
     // Stores list of events ready for processing.
     events: VecDeque<winit::event::Event<'static, UserEvent>>,
+    // Receives raw events that are not handled by Piston.
+    unhandled_event_hook: Option<UnhandledEventHook>,
 }
 
+/// A callback receiving raw winit events that Piston does not handle.
+pub type UnhandledEventHook = Box<dyn FnMut(&winit::event::Event<UserEvent>)>;
+
 fn window_builder_from_settings(settings: &WindowSettings) -> winit::window::WindowBuilder {
     let Size { width, height } = settings.get_size();
     let size = winit::dpi::LogicalSize { width, height };
@@ -73,7 +81,7 @@ fn window_builder_from_settings(settings: &WindowSettings) -> winit::window::Win
     builder
 }
 
-fn graphics_api_from_settings(settings: &WindowSettings) -> Result<Api, Box<dyn Error>> {
+pub(crate) fn graphics_api_from_settings(settings: &WindowSettings) -> Result<Api, Box<dyn Error>> {
     let api = settings
         .get_maybe_graphics_api()
         .unwrap_or(Api::opengl(3, 2));
@@ -219,14 +227,32 @@ impl GlutinWindow {
             automatic_close: settings.get_automatic_close(),
             cursor_pos: None,
             is_capturing_cursor: false,
+            is_faking_cursor_confinement: false,
             last_cursor_pos: None,
             mouse_relative: None,
             last_key_pressed: None,
             event_loop,
             events: VecDeque::new(),
+            unhandled_event_hook: None,
         })
     }
 
+    /// Sets a callback receiving raw winit events that Piston does not handle.
+    ///
+    /// This is useful to react to platform specific events,
+    /// e.g. IME or scale factor changes, without replacing the window back-end.
+    pub fn set_unhandled_event_hook<F>(&mut self, f: F)
+    where
+        F: 'static + FnMut(&winit::event::Event<UserEvent>),
+    {
+        self.unhandled_event_hook = Some(Box::new(f));
+    }
+
+    /// Removes the callback for unhandled events.
+    pub fn clear_unhandled_event_hook(&mut self) {
+        self.unhandled_event_hook = None;
+    }
+
     fn wait_event(&mut self) -> Event {
         // First check for and handle any pending events.
         if let Some(event) = self.poll_event() {
@@ -235,8 +261,9 @@ impl GlutinWindow {
         loop {
             {
                 let events = &mut self.events;
+                let hook = &mut self.unhandled_event_hook;
                 self.event_loop.run_return(|ev, _, control_flow| {
-                    if let Some(event) = to_static_event(ev) {
+                    if let Some(event) = to_static_event(ev, hook) {
                         events.push_back(event);
                     }
                     *control_flow = winit::event_loop::ControlFlow::Exit;
@@ -263,8 +290,9 @@ impl GlutinWindow {
         });
         {
             let events = &mut self.events;
+            let hook = &mut self.unhandled_event_hook;
             self.event_loop.run_return(|ev, _, control_flow| {
-                if let Some(event) = to_static_event(ev) {
+                if let Some(event) = to_static_event(ev, hook) {
                     events.push_back(event);
                 }
                 *control_flow = winit::event_loop::ControlFlow::Exit;
@@ -324,9 +352,10 @@ impl GlutinWindow {
 
         // Poll events currently in the queue, stopping when the queue is empty.
         let events = &mut self.events;
+        let hook = &mut self.unhandled_event_hook;
         self.event_loop.run_return(|ev, _, control_flow| {
             *control_flow = winit::event_loop::ControlFlow::Wait;
-            if let Some(event) = to_static_event(ev) {
+            if let Some(event) = to_static_event(ev, hook) {
                 if event == winit::event::Event::UserEvent(UserEvent::WakeUp) {
                     *control_flow = winit::event_loop::ControlFlow::Exit;
                 }
@@ -507,8 +536,11 @@ impl GlutinWindow {
             }) => {
                 let scale = self.window.scale_factor();
                 let position = position.to_logical::<f64>(scale);
-                let x = position.x;
-                let y = position.y;
+                let mut x = position.x;
+                let mut y = position.y;
+                if self.is_faking_cursor_confinement {
+                    [x, y] = self.fake_confinement([x, y]);
+                }
 
                 if let Some(pos) = self.last_cursor_pos {
                     let dx = x - pos[0];
@@ -533,7 +565,20 @@ impl GlutinWindow {
             Some(E::WindowEvent {
                 event: WE::CursorLeft { .. },
                 ..
-            }) => Some(Input::Cursor(false)),
+            }) => {
+                if self.is_faking_cursor_confinement {
+                    if let Some(pos) = self.last_cursor_pos {
+                        // Move the cursor back before it gets lost.
+                        // The last position is already inside the window.
+                        let _ = self
+                            .window
+                            .set_cursor_position(winit::dpi::LogicalPosition::new(pos[0], pos[1]));
+                        *unknown = true;
+                        return None;
+                    }
+                }
+                Some(Input::Cursor(false))
+            }
             Some(E::WindowEvent {
                 event:
                     WE::MouseWheel {
@@ -602,7 +647,10 @@ impl GlutinWindow {
                 Some(Input::Close(CloseArgs))
             }
             Some(E::UserEvent(UserEvent::WakeUp)) => None,
-            _ => {
+            Some(ev) => {
+                if let Some(hook) = &mut self.unhandled_event_hook {
+                    hook(&ev);
+                }
                 *unknown = true;
                 None
             }
@@ -625,6 +673,21 @@ impl GlutinWindow {
             }
         }
     }
+
+    // Clamps the cursor position to the window edges,
+    // moving the cursor back when it is outside.
+    fn fake_confinement(&mut self, pos: [f64; 2]) -> [f64; 2] {
+        let size = self.size();
+        // Keep cursor one point inside to avoid `CursorLeft` events.
+        let x = pos[0].max(1.0).min((size.width - 2.0).max(1.0));
+        let y = pos[1].max(1.0).min((size.height - 2.0).max(1.0));
+        if x != pos[0] || y != pos[1] {
+            let _ = self
+                .window
+                .set_cursor_position(winit::dpi::LogicalPosition::new(x, y));
+        }
+        [x, y]
+    }
 }
 
 impl Window for GlutinWindow {
@@ -696,6 +759,19 @@ impl AdvancedWindow for GlutinWindow {
             self.fake_capture();
         }
     }
+    fn set_confine_cursor(&mut self, value: bool) {
+        use winit::window::CursorGrabMode;
+
+        let mode = if value {
+            CursorGrabMode::Confined
+        } else {
+            CursorGrabMode::None
+        };
+        let res = self.window.set_cursor_grab(mode);
+        // Some platforms, e.g. macOS, only support locking the cursor,
+        // in which case confinement is faked by moving the cursor back.
+        self.is_faking_cursor_confinement = value && res.is_err();
+    }
     fn show(&mut self) {
         self.window.set_visible(true);
     }
@@ -894,8 +970,10 @@ pub enum UserEvent {
 
 // XXX Massive Hack XXX: `wait_event` and `wait_event_timeout` can't handle non-'static events, so
 // they need to ignore events like `WindowEvent::ScaleFactorChanged` that contain references.
+// Events that can not be converted are passed to the unhandled event hook.
 fn to_static_event(
     event: winit::event::Event<UserEvent>,
+    hook: &mut Option<UnhandledEventHook>,
 ) -> Option<winit::event::Event<'static, UserEvent>> {
     use winit::event::{Event as E, WindowEvent as WE};
     let event = match event {
@@ -921,7 +999,6 @@ fn to_static_event(
                     input,
                     is_synthetic,
                 },
-                WE::ModifiersChanged(_) => return None, // XXX?
                 #[allow(deprecated)]
                 WE::CursorMoved {
                     device_id,
@@ -977,13 +1054,19 @@ fn to_static_event(
                     value,
                 },
                 WE::Touch(touch) => WE::Touch(touch),
-                WE::ScaleFactorChanged { .. } => return None,
                 WE::ThemeChanged(theme) => WE::ThemeChanged(theme),
-                WE::Ime(_) => return None,
-                WE::TouchpadMagnify { .. } => return None,
-                WE::TouchpadRotate { .. } => return None,
-                WE::Occluded(_) => return None,
-                WE::SmartMagnify { .. } => return None,
+                event @ (WE::ModifiersChanged(_)
+                | WE::ScaleFactorChanged { .. }
+                | WE::Ime(_)
+                | WE::TouchpadMagnify { .. }
+                | WE::TouchpadRotate { .. }
+                | WE::Occluded(_)
+                | WE::SmartMagnify { .. }) => {
+                    if let Some(hook) = hook {
+                        hook(&E::WindowEvent { window_id, event });
+                    }
+                    return None;
+                }
             },
         },
         E::DeviceEvent { device_id, event } => E::DeviceEvent { device_id, event },
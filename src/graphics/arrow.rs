@@ -0,0 +1,221 @@
+//! Draw arrow
+
+use super::{
+    math::{Affine2, Cap, Join, Scalar, Vec2d},
+    triangulation::{self, tx, ty},
+    types,
+    types::{Color, Radius},
+    DrawState, Graphics, Transformed,
+};
+
+/// The style of an arrow head.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ArrowHead {
+    /// A filled triangle.
+    Triangle,
+    /// Two hooks stroked with the line radius.
+    Open,
+}
+
+/// The ends of a line that get arrow heads.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ArrowEnds {
+    /// A head at the end of the line.
+    End,
+    /// A head at the start of the line.
+    Start,
+    /// Heads at both ends of the line.
+    Both,
+}
+
+/// A line with arrow heads.
+///
+/// The shaft stops where the heads begin,
+/// so semi-transparent arrows are not blended twice.
+#[derive(Copy, Clone, Debug)]
+pub struct Arrow {
+    /// The arrow color.
+    pub color: Color,
+    /// The radius of the shaft, half the width.
+    pub radius: Radius,
+    /// The style of the heads.
+    pub head: ArrowHead,
+    /// The ends with heads.
+    pub ends: ArrowEnds,
+    /// The length of a head along the line.
+    pub head_length: Scalar,
+    /// The width of a head across the line.
+    pub head_width: Scalar,
+}
+
+impl Arrow {
+    /// Creates a new arrow with a triangle head at the end.
+    ///
+    /// The head size is proportional to the radius.
+    pub fn new(color: Color, radius: Radius) -> Arrow {
+        Arrow {
+            color,
+            radius,
+            head: ArrowHead::Triangle,
+            ends: ArrowEnds::End,
+            head_length: 6.0 * radius,
+            head_width: 6.0 * radius,
+        }
+    }
+
+    /// Sets color.
+    pub fn color(mut self, value: Color) -> Self {
+        self.color = value;
+        self
+    }
+
+    /// Sets radius.
+    pub fn radius(mut self, value: Radius) -> Self {
+        self.radius = value;
+        self
+    }
+
+    /// Sets head style.
+    pub fn head(mut self, value: ArrowHead) -> Self {
+        self.head = value;
+        self
+    }
+
+    /// Sets the ends with heads.
+    pub fn ends(mut self, value: ArrowEnds) -> Self {
+        self.ends = value;
+        self
+    }
+
+    /// Sets head length along the line and width across the line.
+    pub fn head_size(mut self, length: Scalar, width: Scalar) -> Self {
+        self.head_length = length;
+        self.head_width = width;
+        self
+    }
+
+    /// Draws arrow from the start to the end of line.
+    pub fn draw<L: Into<types::Line>, G>(
+        &self,
+        line: L,
+        draw_state: &DrawState,
+        transform: Affine2,
+        g: &mut G,
+    ) where
+        G: Graphics,
+    {
+        let line = line.into();
+        let (dx, dy) = (line[2] - line[0], line[3] - line[1]);
+        let len = (dx * dx + dy * dy).sqrt();
+        if self.color[3] == 0.0 || len == 0.0 {
+            return;
+        }
+        let at_start = self.ends != ArrowEnds::End;
+        let at_end = self.ends != ArrowEnds::Start;
+        // Coordinates with the line along the x axis, and heads with tip at the origin.
+        let m = transform.trans(line[0], line[1]).orient(dx, dy);
+        let m_start = transform.trans(line[0], line[1]).orient(-dx, -dy);
+        let m_end = transform.trans(line[2], line[3]).orient(dx, dy);
+        let (start_side, start_tip) = if at_start {
+            self.shaft_end()
+        } else {
+            (0.0, 0.0)
+        };
+        let (end_side, end_tip) = if at_end { self.shaft_end() } else { (0.0, 0.0) };
+        let r = self.radius;
+        let hl = self.head_length;
+        let hw = 0.5 * self.head_width;
+        g.tri_list(draw_state, &self.color, |f| {
+            let mut vertices: Vec<[f32; 2]> = vec![];
+            let mut push =
+                |m: Affine2, p: Vec2d| vertices.push([tx(m, p[0], p[1]), ty(m, p[0], p[1])]);
+            let (x0, x1) = (start_side, len - end_side);
+            if x0 < x1 {
+                let shaft = [
+                    [start_tip, 0.0],
+                    [x0, -r],
+                    [x1, -r],
+                    [len - end_tip, 0.0],
+                    [x1, r],
+                    [x0, r],
+                ];
+                for i in 2..shaft.len() {
+                    for &p in &[shaft[0], shaft[i - 1], shaft[i]] {
+                        push(m, p);
+                    }
+                }
+            }
+            if self.head == ArrowHead::Triangle {
+                for (&has_head, &m) in [at_start, at_end].iter().zip(&[m_start, m_end]) {
+                    if has_head {
+                        for &p in &[[0.0, 0.0], [-hl, -hw], [-hl, hw]] {
+                            push(m, p);
+                        }
+                    }
+                }
+            }
+            f(&vertices);
+            if self.head == ArrowHead::Open {
+                for (&has_head, &m) in [at_start, at_end].iter().zip(&[m_start, m_end]) {
+                    if has_head {
+                        triangulation::with_polyline_tri_list(
+                            m,
+                            &[[-hl, -hw], [0.0, 0.0], [-hl, hw]],
+                            r,
+                            Join::Miter(4.0),
+                            Cap::Butt,
+                            |vertices| f(vertices),
+                        );
+                    }
+                }
+            }
+        });
+    }
+
+    // Returns the distances from a tip where the shaft ends,
+    // at the sides and at the center line.
+    fn shaft_end(&self) -> (Scalar, Scalar) {
+        match self.head {
+            ArrowHead::Triangle => (self.head_length, self.head_length),
+            ArrowHead::Open => {
+                // Match the inner edges of the hooks,
+                // which meet at the center line behind the tip.
+                let hw = 0.5 * self.head_width;
+                let side = (hw * hw + self.head_length * self.head_length).sqrt();
+                if hw == 0.0 || side == 0.0 {
+                    return (0.0, 0.0);
+                }
+                let tip = self.radius * side / hw;
+                (tip + self.radius * self.head_length / hw, tip)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::software_graphics::SoftwareGraphics;
+
+    #[test]
+    fn test_arrow() {
+        for &head in &[ArrowHead::Triangle, ArrowHead::Open] {
+            let mut g = SoftwareGraphics::new(32, 16);
+            g.draw(|c, g| {
+                Arrow::new([1.0, 1.0, 1.0, 0.5], 1.0)
+                    .head(head)
+                    .ends(ArrowEnds::Both)
+                    .draw([2.0, 8.0, 30.0, 8.0], &c.draw_state, c.transform, g);
+            });
+            // The shaft is not blended twice with the heads.
+            let shaft = g.get_pixel(16, 7);
+            assert_ne!(shaft, [0; 4]);
+            for x in 2..30 {
+                let p = g.get_pixel(x, 7);
+                assert!(p == shaft || p == [0; 4], "{:?} at {}", p, x);
+            }
+            assert_eq!(g.get_pixel(16, 5), [0; 4]);
+            assert_eq!(g.get_pixel(3, 7), shaft);
+        }
+    }
+}
@@ -0,0 +1,431 @@
+//! Atlas layouts with named regions
+//!
+//! An atlas layout describes where named images are placed in the pages of a texture atlas.
+//! Layouts can be packed offline, saved next to the page images,
+//! and loaded by the application to look up source rectangles by name:
+//!
+//! ```ignore
+//! let layout = AtlasLayout::from_bytes(&std::fs::read("sprites.atlas")?)?;
+//! let region = layout.region("player").unwrap();
+//! Image::new()
+//!     .src_rect(layout.src_rect("player").unwrap())
+//!     .draw(&pages[region.page], &c.draw_state, c.transform, g);
+//! ```
+//!
+//! Layouts implement `Serialize` and `Deserialize`, to be stored in any serde format,
+//! e.g. JSON for readability, or with `to_bytes` in a compact binary format.
+//!
+//! Sprite sheets exported by the `TexturePacker` tool (JSON hash or array)
+//! or Aseprite (sheet JSON) can be imported directly:
+//!
+//! ```ignore
+//! let (layout, tags) = AtlasLayout::from_aseprite_json(&std::fs::read_to_string("hero.json")?)?;
+//! let walk = tags.iter().find(|tag| tag.name == "walk").unwrap();
+//! let src_rect = layout.src_rect(&walk.frames[frame]).unwrap();
+//! ```
+
+use std::collections::BTreeMap;
+
+use super::{
+    texture_packer::{AtlasRegion, PackingHeuristic, RectPacker},
+    types::SourceRectangle,
+};
+
+const MAGIC: &[u8; 4] = b"PATL";
+const VERSION: u32 = 1;
+
+/// Named regions in the pages of a texture atlas.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct AtlasLayout {
+    /// The size of each page.
+    pub pages: Vec<[u32; 2]>,
+    /// The regions by name.
+    pub regions: BTreeMap<String, AtlasRegion>,
+}
+
+impl AtlasLayout {
+    /// Creates a new empty layout.
+    pub fn new() -> AtlasLayout {
+        AtlasLayout::default()
+    }
+
+    /// Packs named images of some sizes into pages.
+    ///
+    /// Images are sorted by decreasing height before packing, for better utilization.
+    /// Pages are larger than the page size for images that do not fit otherwise.
+    pub fn pack<S>(
+        images: &[(S, [u32; 2])],
+        page_size: [u32; 2],
+        heuristic: PackingHeuristic,
+    ) -> AtlasLayout
+    where
+        S: AsRef<str>,
+    {
+        let mut sorted: Vec<_> = images.iter().collect();
+        sorted.sort_by_key(|(_, size)| std::cmp::Reverse((size[1], size[0])));
+        let mut packers: Vec<RectPacker> = vec![];
+        let mut layout = AtlasLayout::new();
+        for (name, size) in sorted {
+            let found = packers
+                .iter_mut()
+                .enumerate()
+                .find_map(|(page, p)| p.insert(*size).map(|offset| (page, offset)));
+            let (page, offset) = found.unwrap_or_else(|| {
+                let page_size = [size[0].max(page_size[0]), size[1].max(page_size[1])];
+                let mut packer = RectPacker::new(page_size, heuristic);
+                let offset = packer.insert(*size).unwrap_or([0, 0]);
+                packers.push(packer);
+                (packers.len() - 1, offset)
+            });
+            let region = AtlasRegion {
+                page,
+                offset,
+                size: *size,
+            };
+            layout.regions.insert(name.as_ref().into(), region);
+        }
+        layout.pages = packers.iter().map(RectPacker::size).collect();
+        layout
+    }
+
+    /// Returns a region by name.
+    pub fn region(&self, name: &str) -> Option<&AtlasRegion> {
+        self.regions.get(name)
+    }
+
+    /// Returns the source rectangle of a region by name, for `Image::src_rect`.
+    pub fn src_rect(&self, name: &str) -> Option<SourceRectangle> {
+        self.region(name).map(|r| {
+            [
+                f64::from(r.offset[0]),
+                f64::from(r.offset[1]),
+                f64::from(r.size[0]),
+                f64::from(r.size[1]),
+            ]
+        })
+    }
+
+    /// Encodes the layout in a compact binary format.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut res = MAGIC.to_vec();
+        let u32 = |res: &mut Vec<u8>, v: u32| res.extend_from_slice(&v.to_le_bytes());
+        u32(&mut res, VERSION);
+        u32(&mut res, self.pages.len() as u32);
+        for &[w, h] in &self.pages {
+            u32(&mut res, w);
+            u32(&mut res, h);
+        }
+        u32(&mut res, self.regions.len() as u32);
+        for (name, r) in &self.regions {
+            u32(&mut res, name.len() as u32);
+            res.extend_from_slice(name.as_bytes());
+            for v in [
+                r.page as u32,
+                r.offset[0],
+                r.offset[1],
+                r.size[0],
+                r.size[1],
+            ] {
+                u32(&mut res, v);
+            }
+        }
+        res
+    }
+
+    /// Decodes a layout in the binary format of `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<AtlasLayout, String> {
+        if bytes.get(..4) != Some(&MAGIC[..]) {
+            return Err("Not an atlas layout".into());
+        }
+        let mut r = Reader { bytes, pos: 4 };
+        let version = r.u32()?;
+        if version != VERSION {
+            return Err(format!("Unsupported atlas layout version {}", version));
+        }
+        let mut layout = AtlasLayout::new();
+        for _ in 0..r.u32()? {
+            layout.pages.push([r.u32()?, r.u32()?]);
+        }
+        for _ in 0..r.u32()? {
+            let len = r.u32()? as usize;
+            let name = String::from_utf8(r.take(len)?.to_vec()).map_err(|e| e.to_string())?;
+            let page = r.u32()? as usize;
+            if page >= layout.pages.len() {
+                return Err(format!("Region `{}` refers to missing page {}", name, page));
+            }
+            let region = AtlasRegion {
+                page,
+                offset: [r.u32()?, r.u32()?],
+                size: [r.u32()?, r.u32()?],
+            };
+            layout.regions.insert(name, region);
+        }
+        Ok(layout)
+    }
+
+    /// Imports a single page sprite sheet exported by the `TexturePacker` tool,
+    /// in the JSON hash or JSON array format.
+    ///
+    /// Regions are named by the file names of the frames.
+    /// Trimming is ignored, the regions are the trimmed frames.
+    /// Rotated frames are not supported.
+    pub fn from_texture_packer_json(text: &str) -> Result<AtlasLayout, String> {
+        let sheet: Sheet = serde_json::from_str(text).map_err(|e| e.to_string())?;
+        sheet_frames(&sheet).map(|(layout, _)| layout)
+    }
+
+    /// Imports a sprite sheet exported by Aseprite, with its animation tags.
+    ///
+    /// Regions are named by the file names of the frames,
+    /// which Aseprite puts in the hash or array of frames.
+    pub fn from_aseprite_json(text: &str) -> Result<(AtlasLayout, Vec<FrameTag>), String> {
+        let sheet: Sheet = serde_json::from_str(text).map_err(|e| e.to_string())?;
+        let (layout, frames) = sheet_frames(&sheet)?;
+        let mut tags = vec![];
+        for tag in &sheet.meta.frame_tags {
+            let (from, to) = (tag.from as usize, tag.to as usize);
+            if from > to || to >= frames.len() {
+                return Err(format!("Invalid frame range in tag `{}`", tag.name));
+            }
+            let mut range: Vec<usize> = (from..=to).collect();
+            match tag.direction.as_deref() {
+                Some("reverse") => range.reverse(),
+                Some("pingpong") => {
+                    let back: Vec<usize> = range
+                        .iter()
+                        .rev()
+                        .skip(1)
+                        .take((to - from).saturating_sub(1))
+                        .copied()
+                        .collect();
+                    range.extend(back);
+                }
+                _ => {}
+            }
+            tags.push(FrameTag {
+                name: tag.name.clone(),
+                frames: range.iter().map(|&i| frames[i].0.clone()).collect(),
+                durations: range.iter().map(|&i| frames[i].1).collect(),
+            });
+        }
+        Ok((layout, tags))
+    }
+}
+
+/// An animation tag of an Aseprite sprite sheet.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct FrameTag {
+    /// The name of the tag.
+    pub name: String,
+    /// The names of the frames in playing order, with ping-pong unfolded.
+    pub frames: Vec<String>,
+    /// The duration of each frame in seconds.
+    pub durations: Vec<f64>,
+}
+
+// Reads the frames of a sprite sheet with their durations, in file order.
+fn sheet_frames(sheet: &Sheet) -> Result<(AtlasLayout, Vec<(String, f64)>), String> {
+    let list: Vec<(&str, &SheetFrame)> = match &sheet.frames {
+        SheetFrames::Hash(frames) => frames.0.iter().map(|(k, v)| (&**k, v)).collect(),
+        SheetFrames::Array(frames) => frames.iter().map(|v| (&*v.filename, &v.frame)).collect(),
+    };
+    let mut layout = AtlasLayout::new();
+    let mut frames = vec![];
+    let mut extent = [0, 0];
+    for (name, v) in list {
+        if v.rotated {
+            return Err(format!("Rotated frame `{}` is not supported", name));
+        }
+        let SheetRect { x, y, w, h } = v.frame;
+        let (right, bottom) = match (x.checked_add(w), y.checked_add(h)) {
+            (Some(right), Some(bottom)) => (right, bottom),
+            _ => return Err(format!("Invalid frame `{}`", name)),
+        };
+        extent = [extent[0].max(right), extent[1].max(bottom)];
+        let region = AtlasRegion {
+            page: 0,
+            offset: [x, y],
+            size: [w, h],
+        };
+        layout.regions.insert(name.into(), region);
+        frames.push((name.to_string(), v.duration.unwrap_or(100.0) / 1000.0));
+    }
+    let size = sheet.meta.size.as_ref().map(|s| [s.w, s.h]);
+    layout.pages.push(size.unwrap_or(extent));
+    Ok((layout, frames))
+}
+
+// The JSON of a sprite sheet.
+#[derive(Deserialize)]
+struct Sheet {
+    frames: SheetFrames,
+    #[serde(default)]
+    meta: SheetMeta,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SheetFrames {
+    Hash(OrderedFrames),
+    Array(Vec<NamedSheetFrame>),
+}
+
+#[derive(Deserialize)]
+struct NamedSheetFrame {
+    filename: String,
+    #[serde(flatten)]
+    frame: SheetFrame,
+}
+
+#[derive(Deserialize)]
+struct SheetFrame {
+    frame: SheetRect,
+    #[serde(default)]
+    rotated: bool,
+    duration: Option<f64>,
+}
+
+#[derive(Deserialize)]
+struct SheetRect {
+    x: u32,
+    y: u32,
+    w: u32,
+    h: u32,
+}
+
+#[derive(Default, Deserialize)]
+struct SheetMeta {
+    size: Option<SheetSize>,
+    #[serde(default, rename = "frameTags")]
+    frame_tags: Vec<SheetTag>,
+}
+
+#[derive(Deserialize)]
+struct SheetSize {
+    w: u32,
+    h: u32,
+}
+
+#[derive(Deserialize)]
+struct SheetTag {
+    name: String,
+    from: u32,
+    to: u32,
+    direction: Option<String>,
+}
+
+// Frames by name, in file order, which Aseprite uses for frame indices.
+struct OrderedFrames(Vec<(String, SheetFrame)>);
+
+impl<'de> serde::Deserialize<'de> for OrderedFrames {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct Visitor;
+
+        impl<'de> serde::de::Visitor<'de> for Visitor {
+            type Value = OrderedFrames;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str("a map of frames")
+            }
+
+            fn visit_map<A>(self, mut map: A) -> Result<OrderedFrames, A::Error>
+            where
+                A: serde::de::MapAccess<'de>,
+            {
+                let mut frames = vec![];
+                while let Some(entry) = map.next_entry()? {
+                    frames.push(entry);
+                }
+                Ok(OrderedFrames(frames))
+            }
+        }
+
+        deserializer.deserialize_map(Visitor)
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], String> {
+        let res = self
+            .bytes
+            .get(self.pos..self.pos + n)
+            .ok_or_else(|| "Unexpected end of atlas layout".to_string())?;
+        self.pos += n;
+        Ok(res)
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        self.take(4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_atlas_layout() {
+        let images = [
+            ("a", [16, 8]),
+            ("b", [8, 16]),
+            ("c", [16, 16]),
+            ("d", [40, 4]),
+        ];
+        let layout = AtlasLayout::pack(
+            &images,
+            [32, 32],
+            PackingHeuristic::MaxRectsBestShortSideFit,
+        );
+        assert_eq!(layout.pages, vec![[32, 32], [40, 32]]);
+        assert_eq!(layout.regions.len(), 4);
+        assert_eq!(layout.region("c").unwrap().offset, [0, 0]);
+        assert_eq!(layout.src_rect("d"), Some([0.0, 0.0, 40.0, 4.0]));
+        assert_eq!(layout.src_rect("e"), None);
+
+        let bytes = layout.to_bytes();
+        assert_eq!(AtlasLayout::from_bytes(&bytes), Ok(layout));
+        assert!(AtlasLayout::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_import_sprite_sheets() {
+        let hash = r#"{
+            "frames": {
+                "idle.png": {"frame": {"x": 0, "y": 0, "w": 16, "h": 16}, "rotated": false},
+                "jump é.png": {"frame": {"x": 16, "y": 0, "w": 8, "h": 24}}
+            },
+            "meta": {"image": "sheet.png", "size": {"w": 32, "h": 32}, "scale": "1"}
+        }"#;
+        let layout = AtlasLayout::from_texture_packer_json(hash).unwrap();
+        assert_eq!(layout.pages, vec![[32, 32]]);
+        assert_eq!(layout.src_rect("jump é.png"), Some([16.0, 0.0, 8.0, 24.0]));
+
+        let aseprite = r#"{
+            "frames": [
+                {"filename": "hero 0", "frame": {"x": 0, "y": 0, "w": 8, "h": 8}, "duration": 100},
+                {"filename": "hero 1", "frame": {"x": 8, "y": 0, "w": 8, "h": 8}, "duration": 200},
+                {"filename": "hero 2", "frame": {"x": 16, "y": 0, "w": 8, "h": 8}, "duration": 100}
+            ],
+            "meta": {"frameTags": [{"name": "walk", "from": 0, "to": 2, "direction": "pingpong"}]}
+        }"#;
+        let (layout, tags) = AtlasLayout::from_aseprite_json(aseprite).unwrap();
+        assert_eq!(layout.pages, vec![[24, 8]]);
+        assert_eq!(tags[0].frames, ["hero 0", "hero 1", "hero 2", "hero 1"]);
+        assert_eq!(tags[0].durations, [0.1, 0.2, 0.1, 0.2]);
+
+        assert!(AtlasLayout::from_texture_packer_json("{\"frames\": [}").is_err());
+        let overflow = r#"{"frames": {"a": {"frame": {"x": 4294967295, "y": 0, "w": 1, "h": 1}}}}"#;
+        assert!(AtlasLayout::from_texture_packer_json(overflow).is_err());
+        let nested = "[".repeat(100_000);
+        assert!(AtlasLayout::from_texture_packer_json(&nested).is_err());
+    }
+}
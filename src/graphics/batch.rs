@@ -0,0 +1,200 @@
+//! Batching of shapes
+//!
+//! Drawing many small shapes one by one spends most of the time
+//! on calling back-end methods rather than on the triangles themselves.
+//! A `ShapeBatch` collects the triangles of shapes with per-vertex colors,
+//! and sends them to the back-end using a few calls to `Graphics::tri_list_c`.
+//!
+//! ```ignore
+//! let mut batch = ShapeBatch::new(c.draw_state);
+//! for (i, tile) in tiles.iter().enumerate() {
+//!     batch.rectangle(&Rectangle::new(tile.color), tile.rect, c.transform);
+//! }
+//! batch.draw(g);
+//! ```
+
+use super::{
+    math::Affine2,
+    types,
+    types::{Color, Rectangle as Rect},
+    DrawState, Ellipse, Graphics, ImageSize, Line, Polygon, Rectangle,
+    BACK_END_MAX_VERTEX_COUNT as BUFFER_SIZE,
+};
+
+/// Collects triangles of shapes sharing a draw state.
+///
+/// The batch keeps the triangles after drawing,
+/// so a static set of shapes can be drawn every frame without triangulating again.
+#[derive(Clone, Debug)]
+pub struct ShapeBatch {
+    /// The draw state used for all shapes.
+    pub draw_state: DrawState,
+    vertices: Vec<[f32; 2]>,
+    colors: Vec<[f32; 4]>,
+}
+
+impl ShapeBatch {
+    /// Creates a new empty batch.
+    pub fn new(draw_state: DrawState) -> ShapeBatch {
+        ShapeBatch {
+            draw_state,
+            vertices: vec![],
+            colors: vec![],
+        }
+    }
+
+    /// Returns the number of vertices in the batch.
+    pub fn len(&self) -> usize {
+        self.vertices.len()
+    }
+
+    /// Returns `true` if the batch has no triangles.
+    pub fn is_empty(&self) -> bool {
+        self.vertices.is_empty()
+    }
+
+    /// Removes all triangles from the batch.
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+        self.colors.clear();
+    }
+
+    /// Adds a rectangle.
+    pub fn rectangle<R: Into<Rect>>(&mut self, r: &Rectangle, rectangle: R, transform: Affine2) {
+        let draw_state = self.draw_state;
+        r.draw_tri(rectangle, &draw_state, transform, &mut self.recorder());
+    }
+
+    /// Adds an ellipse.
+    pub fn ellipse<R: Into<Rect>>(&mut self, e: &Ellipse, rectangle: R, transform: Affine2) {
+        let draw_state = self.draw_state;
+        e.draw_tri(rectangle, &draw_state, transform, &mut self.recorder());
+    }
+
+    /// Adds a line.
+    pub fn line<L: Into<types::Line>>(&mut self, l: &Line, line: L, transform: Affine2) {
+        let draw_state = self.draw_state;
+        l.draw_tri(line, &draw_state, transform, &mut self.recorder());
+    }
+
+    /// Adds a polygon.
+    pub fn polygon(&mut self, p: &Polygon, polygon: types::Polygon<'_>, transform: Affine2) {
+        let draw_state = self.draw_state;
+        p.draw_tri(polygon, &draw_state, transform, &mut self.recorder());
+    }
+
+    /// Adds triangles with a color.
+    pub fn tri_list(&mut self, color: Color, vertices: &[[f32; 2]]) {
+        self.vertices.extend_from_slice(vertices);
+        self.colors
+            .extend(core::iter::repeat_n(color, vertices.len()));
+    }
+
+    /// Draws all triangles in the batch.
+    pub fn draw<G: Graphics>(&self, g: &mut G) {
+        if self.is_empty() {
+            return;
+        }
+        // Keep whole triangles in each chunk.
+        let chunk = BUFFER_SIZE - BUFFER_SIZE % 3;
+        g.tri_list_c(&self.draw_state, |f| {
+            for (vertices, colors) in self.vertices.chunks(chunk).zip(self.colors.chunks(chunk)) {
+                f(vertices, colors);
+            }
+        });
+    }
+
+    fn recorder(&mut self) -> Recorder<'_> {
+        Recorder { batch: self }
+    }
+}
+
+// Records triangles drawn by shapes into a batch.
+struct Recorder<'a> {
+    batch: &'a mut ShapeBatch,
+}
+
+// Shapes in a batch do not use textures.
+#[derive(Copy, Clone)]
+struct NoTexture;
+
+impl ImageSize for NoTexture {
+    fn get_size(&self) -> (u32, u32) {
+        (0, 0)
+    }
+}
+
+impl Graphics for Recorder<'_> {
+    type Texture = NoTexture;
+
+    fn clear_color(&mut self, _color: Color) {}
+
+    fn clear_stencil(&mut self, _value: u8) {}
+
+    fn tri_list<F>(&mut self, _draw_state: &DrawState, color: &[f32; 4], mut f: F)
+    where
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]])),
+    {
+        f(&mut |vertices| self.batch.tri_list(*color, vertices));
+    }
+
+    fn tri_list_c<F>(&mut self, _draw_state: &DrawState, mut f: F)
+    where
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]], &[[f32; 4]])),
+    {
+        f(&mut |vertices, colors| {
+            self.batch.vertices.extend_from_slice(vertices);
+            self.batch.colors.extend_from_slice(colors);
+        });
+    }
+
+    fn tri_list_uv<F>(
+        &mut self,
+        _draw_state: &DrawState,
+        _color: &[f32; 4],
+        _texture: &NoTexture,
+        _f: F,
+    ) where
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]], &[[f32; 2]])),
+    {
+    }
+
+    fn tri_list_uv_c<F>(&mut self, _draw_state: &DrawState, _texture: &NoTexture, _f: F)
+    where
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]], &[[f32; 2]], &[[f32; 4]])),
+    {
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::software_graphics::SoftwareGraphics;
+
+    #[test]
+    fn test_shape_batch() {
+        let mut batch = ShapeBatch::new(DrawState::default());
+        let mut g = SoftwareGraphics::new(8, 8);
+        g.draw(|c, g| {
+            for x in 0..4 {
+                let color = [x as f32 / 3.0, 0.0, 0.0, 1.0];
+                batch.rectangle(
+                    &Rectangle::new(color),
+                    [2.0 * x as f64, 0.0, 2.0, 2.0],
+                    c.transform,
+                );
+            }
+            batch.line(
+                &Line::new([0.0, 1.0, 0.0, 1.0], 1.0),
+                [0.0, 5.0, 8.0, 5.0],
+                c.transform,
+            );
+            assert_eq!(batch.len(), 4 * 6 + 6);
+            batch.draw(g);
+        });
+        assert_eq!(g.get_pixel(0, 0), [0, 0, 0, 255]);
+        assert_eq!(g.get_pixel(7, 1), [255, 0, 0, 255]);
+        assert_eq!(g.get_pixel(3, 4), [0, 255, 0, 255]);
+        assert_eq!(g.get_pixel(3, 7), [0; 4]);
+    }
+}
@@ -0,0 +1,207 @@
+//! Draw Bézier curves
+//!
+//! Curves are flattened into line segments before stroking.
+//! The number of segments adapts to how much the curve bends
+//! and to how large it appears on the screen,
+//! such that zooming in does not reveal corners.
+
+pub use super::math::{Cap, Join};
+use super::{
+    curve::{flatten_cubic_bezier, flatten_quadratic_bezier},
+    math::{get_scale, Scalar, Vec2d},
+    triangulation, types,
+    types::{Color, Radius},
+    Context, Graphics,
+};
+
+/// A stroke along a quadratic Bézier curve.
+#[derive(Copy, Clone, Debug)]
+pub struct QuadBezier {
+    /// The line color.
+    pub color: Color,
+    /// The line radius, half the width.
+    pub radius: Radius,
+    /// The maximum distance between the curve and its line segments,
+    /// in view coordinates.
+    pub tolerance: Scalar,
+    /// How segments are joined.
+    pub join: Join,
+    /// How the ends are drawn.
+    pub cap: Cap,
+}
+
+/// A stroke along a cubic Bézier curve.
+#[derive(Copy, Clone, Debug)]
+pub struct CubicBezier {
+    /// The line color.
+    pub color: Color,
+    /// The line radius, half the width.
+    pub radius: Radius,
+    /// The maximum distance between the curve and its line segments,
+    /// in view coordinates.
+    pub tolerance: Scalar,
+    /// How segments are joined.
+    pub join: Join,
+    /// How the ends are drawn.
+    pub cap: Cap,
+}
+
+/// The default tolerance of flattening, a quarter of a view unit.
+pub const DEFAULT_TOLERANCE: Scalar = 0.25;
+
+macro_rules! bezier_setters {
+    ($name:ident) => {
+        impl $name {
+            /// Creates a new curve stroke with miter joins, butt caps and default tolerance.
+            pub fn new(color: Color, radius: Radius) -> $name {
+                $name {
+                    color,
+                    radius,
+                    tolerance: DEFAULT_TOLERANCE,
+                    join: Join::Miter(4.0),
+                    cap: Cap::Butt,
+                }
+            }
+
+            /// Sets color.
+            pub fn color(mut self, value: Color) -> Self {
+                self.color = value;
+                self
+            }
+
+            /// Sets radius.
+            pub fn radius(mut self, value: Radius) -> Self {
+                self.radius = value;
+                self
+            }
+
+            /// Sets width.
+            pub fn width(mut self, value: types::Width) -> Self {
+                self.radius = 0.5 * value;
+                self
+            }
+
+            /// Sets flattening tolerance in view coordinates.
+            pub fn tolerance(mut self, value: Scalar) -> Self {
+                self.tolerance = value;
+                self
+            }
+
+            /// Sets how segments are joined.
+            pub fn join(mut self, value: Join) -> Self {
+                self.join = value;
+                self
+            }
+
+            /// Sets how the ends are drawn.
+            pub fn cap(mut self, value: Cap) -> Self {
+                self.cap = value;
+                self
+            }
+        }
+    };
+}
+
+bezier_setters!(QuadBezier);
+bezier_setters!(CubicBezier);
+
+impl QuadBezier {
+    /// Draws quadratic Bézier curve.
+    ///
+    /// Uses the draw state and transform of the context,
+    /// and the view to find the scale on the screen.
+    pub fn draw<G>(&self, curve: types::QuadraticBezier, c: &Context, g: &mut G)
+    where
+        G: Graphics,
+    {
+        let mut points = vec![];
+        let tolerance = curve_tolerance(self.tolerance, c);
+        flatten_quadratic_bezier(curve, tolerance, |p| points.push(p));
+        stroke(&points, self.color, self.radius, self.join, self.cap, c, g);
+    }
+}
+
+impl CubicBezier {
+    /// Draws cubic Bézier curve.
+    ///
+    /// Uses the draw state and transform of the context,
+    /// and the view to find the scale on the screen.
+    pub fn draw<G>(&self, curve: types::CubicBezier, c: &Context, g: &mut G)
+    where
+        G: Graphics,
+    {
+        let mut points = vec![];
+        let tolerance = curve_tolerance(self.tolerance, c);
+        flatten_cubic_bezier(curve, tolerance, |p| points.push(p));
+        stroke(&points, self.color, self.radius, self.join, self.cap, c, g);
+    }
+}
+
+// Converts tolerance in view coordinates to curve coordinates,
+// using the largest scale of the transform relative to the view.
+pub(crate) fn curve_tolerance(tolerance: Scalar, c: &Context) -> Scalar {
+    let t = c.transform;
+    let view = get_scale(c.view);
+    if view[0] == 0.0 || view[1] == 0.0 {
+        return tolerance;
+    }
+    let len = |x: Scalar, y: Scalar| {
+        let (x, y) = (x / view[0], y / view[1]);
+        (x * x + y * y).sqrt()
+    };
+    let scale = len(t[0][0], t[1][0]).max(len(t[0][1], t[1][1]));
+    if scale > 0.0 && scale.is_finite() {
+        tolerance / scale
+    } else {
+        tolerance
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn stroke<G>(
+    points: &[Vec2d],
+    color: Color,
+    radius: Radius,
+    join: Join,
+    cap: Cap,
+    c: &Context,
+    g: &mut G,
+) where
+    G: Graphics,
+{
+    if color[3] == 0.0 {
+        return;
+    }
+    g.tri_list(&c.draw_state, &color, |f| {
+        triangulation::with_polyline_tri_list(c.transform, points, radius, join, cap, |vertices| {
+            f(vertices)
+        })
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{graphics::Transformed, software_graphics::SoftwareGraphics};
+
+    #[test]
+    fn test_bezier_tolerance() {
+        let mut g = SoftwareGraphics::new(16, 16);
+        g.draw(|c, g| {
+            // Zooming in reduces the tolerance in curve coordinates.
+            assert_eq!(curve_tolerance(0.25, &c), 0.25);
+            assert_eq!(curve_tolerance(0.25, &c.zoom(4.0)), 0.0625);
+
+            CubicBezier::new([1.0; 4], 1.0).draw(
+                [[2.0, 14.0], [2.0, 2.0], [14.0, 2.0], [14.0, 14.0]],
+                &c,
+                g,
+            );
+            QuadBezier::new([1.0; 4], 1.0).draw([[2.0, 14.0], [8.0, 14.0], [14.0, 14.0]], &c, g);
+        });
+        // The cubic curve peaks at `y = 5`.
+        assert_eq!(g.get_pixel(8, 5), [255; 4]);
+        assert_eq!(g.get_pixel(8, 8), [0; 4]);
+        assert_eq!(g.get_pixel(8, 13), [255; 4]);
+    }
+}
@@ -0,0 +1,159 @@
+use super::{
+    character::CharacterCache,
+    line::Shape as LineShape,
+    math::{
+        geom::bounding_box, inflate_rectangle, transform_rectangle, union_rectangle, Affine2,
+        Scalar,
+    },
+    types, Ellipse, Image, ImageSize, Line, Polygon, Rectangle, Text,
+};
+
+/// Implemented by shapes that can compute their bounding box.
+///
+/// The bounding box is axis aligned after the transform,
+/// and includes borders, feathered rims and other effects of the shape settings.
+/// This is useful in generic code for culling, picking or tracking dirty regions.
+///
+/// The type parameter is the data the shape is drawn with,
+/// e.g. the rectangle passed to `Rectangle::draw`.
+pub trait Bounded<T> {
+    /// Computes the transformed bounding box `[x, y, w, h]`.
+    ///
+    /// Returns `None` when nothing is drawn.
+    fn bounds(&self, data: T, transform: Affine2) -> Option<types::Rectangle>;
+}
+
+impl<R: Into<types::Rectangle>> Bounded<R> for Rectangle {
+    fn bounds(&self, data: R, transform: Affine2) -> Option<types::Rectangle> {
+        let border = self.border.map(|b| b.radius).unwrap_or(0.0);
+        let feather = self.feather.unwrap_or(0.0);
+        let d = border + feather;
+        Some(transform_rectangle(
+            transform,
+            inflate_rectangle(data.into(), [d, d]),
+        ))
+    }
+}
+
+impl<R: Into<types::Rectangle>> Bounded<R> for Ellipse {
+    fn bounds(&self, data: R, transform: Affine2) -> Option<types::Rectangle> {
+        let border = self.border.map(|b| b.radius).unwrap_or(0.0);
+        let feather = self.feather.unwrap_or(0.0);
+        let d = border + feather;
+        Some(transform_rectangle(
+            transform,
+            inflate_rectangle(data.into(), [d, d]),
+        ))
+    }
+}
+
+impl<L: Into<types::Line>> Bounded<L> for Line {
+    fn bounds(&self, data: L, transform: Affine2) -> Option<types::Rectangle> {
+        let [x1, y1, x2, y2] = data.into();
+        // Corners of square ends reach further out on diagonal lines.
+        let radius = match self.shape {
+            LineShape::Round | LineShape::RoundWithResolution(_) => self.radius,
+            LineShape::Square | LineShape::Bevel => self.radius * std::f64::consts::SQRT_2,
+        };
+        let d = radius + self.feather.unwrap_or(0.0);
+        let rect = bounding_box(&[[x1, y1], [x2, y2]])?;
+        Some(transform_rectangle(
+            transform,
+            inflate_rectangle(rect, [d, d]),
+        ))
+    }
+}
+
+impl Bounded<types::Polygon<'_>> for Polygon {
+    fn bounds(&self, data: types::Polygon<'_>, transform: Affine2) -> Option<types::Rectangle> {
+        let d = self.feather.unwrap_or(0.0);
+        let rect = bounding_box(data)?;
+        Some(transform_rectangle(
+            transform,
+            inflate_rectangle(rect, [d, d]),
+        ))
+    }
+}
+
+impl<T: ImageSize> Bounded<&T> for Image {
+    fn bounds(&self, texture: &T, transform: Affine2) -> Option<types::Rectangle> {
+        let rectangle = self.rectangle.unwrap_or_else(|| {
+            let (w, h) = match self.source_rectangle {
+                Some(r) => (r[2], r[3]),
+                None => {
+                    let (w, h) = texture.get_size();
+                    (w as Scalar, h as Scalar)
+                }
+            };
+            [0.0, 0.0, w, h]
+        });
+        let transform = self.center_transform(transform, rectangle);
+        Some(transform_rectangle(transform, rectangle))
+    }
+}
+
+impl<C: CharacterCache> Bounded<(&str, &mut C)> for Text {
+    /// Computes the bounding box of the glyphs, including outline and shadow.
+    ///
+    /// Returns `None` when no glyph is visible, or when the character cache fails.
+    fn bounds(
+        &self,
+        (text, cache): (&str, &mut C),
+        transform: Affine2,
+    ) -> Option<types::Rectangle> {
+        let mut rect: Option<types::Rectangle> = None;
+        self.layout(text, cache, |_, _, character, pos| {
+            let [w, h] = character.atlas_size;
+            if w > 0.0 && h > 0.0 {
+                let glyph = [pos[0], pos[1], w, h];
+                rect = Some(rect.map_or(glyph, |r| union_rectangle(r, glyph)));
+            }
+        })
+        .ok()?;
+        let rect = rect?;
+        let outline = self.outline.map(|o| o.width).unwrap_or(0.0);
+        let mut res = inflate_rectangle(rect, [outline, outline]);
+        if let Some(shadow) = self.shadow {
+            let d = outline + shadow.blur;
+            let [x, y, w, h] = inflate_rectangle(rect, [d, d]);
+            let shadow = [x + shadow.offset[0], y + shadow.offset[1], w, h];
+            res = union_rectangle(res, shadow);
+        }
+        Some(transform_rectangle(transform, res))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graphics::{math::translate, Transformed};
+    use crate::software_graphics::Texture;
+
+    #[test]
+    fn test_bounds() {
+        let m = translate([10.0, 20.0]);
+        let r = Rectangle::new_border([1.0; 4], 1.0);
+        assert_eq!(
+            r.bounds([0.0, 0.0, 4.0, 2.0], m),
+            Some([9.0, 19.0, 6.0, 4.0])
+        );
+        let l = Line::new_round([1.0; 4], 1.0);
+        assert_eq!(
+            l.bounds([0.0, 0.0, 4.0, 0.0], m),
+            Some([9.0, 19.0, 6.0, 2.0])
+        );
+        let p = Polygon::new([1.0; 4]);
+        assert_eq!(p.bounds(&[], m), None);
+        assert_eq!(
+            p.bounds(&[[0.0, 0.0], [2.0, 0.0], [0.0, 2.0]], m.scale(2.0, 1.0)),
+            Some([10.0, 20.0, 4.0, 2.0])
+        );
+
+        // Rotated a quarter turn around the center.
+        let texture = Texture::new(image::RgbaImage::new(4, 2));
+        let image = Image::new().rotation_around_center(std::f64::consts::FRAC_PI_2);
+        let [x, y, w, h] = image.bounds(&texture, m).unwrap();
+        let eq = |a: Scalar, b: Scalar| (a - b).abs() < 1e-9;
+        assert!(eq(x, 11.0) && eq(y, 19.0) && eq(w, 2.0) && eq(h, 4.0));
+    }
+}
@@ -0,0 +1,147 @@
+//! 2D camera
+//!
+//! A camera looks at a position in world coordinates,
+//! with zoom and rotation around the center of the view.
+//!
+//! ```ignore
+//! let camera = Camera2d::new()
+//!     .position(player.pos)
+//!     .zoom(2.0)
+//!     .virtual_size([320.0, 180.0]);
+//! let c = camera.context(&c);
+//! // Draws in world coordinates.
+//! rectangle(color, rect, c.transform, g);
+//!
+//! let cursor = camera.screen_to_world(&c, mouse_pos);
+//! ```
+
+use super::{
+    math::{Scalar, Vec2d},
+    Context, Transformed,
+};
+
+/// Describes what part of the world is visible.
+///
+/// With a virtual size, the world is scaled uniformly such that
+/// the virtual size fits into the view, centered with bars on two sides.
+/// A camera with zoom 1 then shows exactly the virtual size.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Camera2d {
+    /// The world position at the center of the view.
+    pub position: Vec2d,
+    /// The zoom factor, larger than 1 to magnify.
+    pub zoom: Scalar,
+    /// The rotation of the camera in radians.
+    pub rotation: Scalar,
+    /// The size of the view in world units at zoom 1, if fixed.
+    pub virtual_size: Option<Vec2d>,
+}
+
+impl Default for Camera2d {
+    fn default() -> Self {
+        Camera2d::new()
+    }
+}
+
+impl Camera2d {
+    /// Creates a new camera looking at the origin.
+    pub fn new() -> Camera2d {
+        Camera2d {
+            position: [0.0, 0.0],
+            zoom: 1.0,
+            rotation: 0.0,
+            virtual_size: None,
+        }
+    }
+
+    /// Sets the world position at the center of the view.
+    pub fn position(mut self, value: Vec2d) -> Self {
+        self.position = value;
+        self
+    }
+
+    /// Sets zoom factor.
+    pub fn zoom(mut self, value: Scalar) -> Self {
+        self.zoom = value;
+        self
+    }
+
+    /// Sets rotation (in radians).
+    pub fn rotation(mut self, value: Scalar) -> Self {
+        self.rotation = value;
+        self
+    }
+
+    /// Sets a virtual resolution that fits into the view.
+    pub fn virtual_size(mut self, value: Vec2d) -> Self {
+        self.virtual_size = Some(value);
+        self
+    }
+
+    /// Computes the rectangle of the view showing the world,
+    /// in view coordinates.
+    ///
+    /// This is the whole view without virtual size.
+    pub fn letterbox(&self, c: &Context) -> [Scalar; 4] {
+        let [w, h] = c.get_view_size();
+        match self.virtual_size {
+            Some([vw, vh]) if vw > 0.0 && vh > 0.0 => {
+                let s = (w / vw).min(h / vh);
+                let (lw, lh) = (vw * s, vh * s);
+                [0.5 * (w - lw), 0.5 * (h - lh), lw, lh]
+            }
+            _ => [0.0, 0.0, w, h],
+        }
+    }
+
+    /// Returns a context that draws in world coordinates.
+    ///
+    /// With a virtual size, drawing is clipped to the letterbox,
+    /// when the context has a viewport.
+    pub fn context(&self, c: &Context) -> Context {
+        let [x, y, w, h] = self.letterbox(c);
+        let scale = match self.virtual_size {
+            Some([vw, _]) if vw > 0.0 => w / vw,
+            _ => 1.0,
+        };
+        let c = c.view();
+        let c = match self.virtual_size {
+            Some(_) => c.clip([x, y, w, h]),
+            None => c,
+        };
+        c.trans(x + 0.5 * w, y + 0.5 * h)
+            .zoom(scale * self.zoom)
+            .rot_rad(-self.rotation)
+            .trans(-self.position[0], -self.position[1])
+    }
+
+    /// Converts a position in screen coordinates to world coordinates.
+    ///
+    /// See `Context::screen_to_local` for screen coordinates.
+    pub fn screen_to_world(&self, c: &Context, pos: Vec2d) -> Vec2d {
+        self.context(c).screen_to_local(pos)
+    }
+
+    /// Converts a position in world coordinates to screen coordinates.
+    pub fn world_to_screen(&self, c: &Context, pos: Vec2d) -> Vec2d {
+        self.context(c).local_to_screen(pos)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_camera() {
+        // A wide view with bars on the left and right.
+        let c = Context::new_abs(400.0, 100.0);
+        let camera = Camera2d::new()
+            .position([10.0, 20.0])
+            .virtual_size([100.0, 50.0]);
+        assert_eq!(camera.letterbox(&c), [100.0, 0.0, 200.0, 100.0]);
+        assert_eq!(camera.world_to_screen(&c, [10.0, 20.0]), [200.0, 50.0]);
+        let p = camera.zoom(2.0).screen_to_world(&c, [204.0, 50.0]);
+        assert!((p[0] - 11.0).abs() < 1e-9 && (p[1] - 20.0).abs() < 1e-9);
+    }
+}
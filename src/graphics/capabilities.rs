@@ -0,0 +1,21 @@
+//! Back-end capabilities
+
+/// Describes what a back-end supports, see `Graphics::capabilities`.
+///
+/// Generic code can use this to pick a strategy,
+/// e.g. splitting large images into tiles or avoiding clipping with stencil.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Capabilities {
+    /// The maximum width and height of textures in pixels, if limited.
+    pub max_texture_size: Option<u32>,
+    /// Whether the stencil buffer works, as used by `DrawState` for clipping.
+    pub stencil_clip: bool,
+    /// Whether `Graphics::sprite_batch` uses instancing.
+    pub instancing: bool,
+    /// Whether blending happens in linear color space.
+    pub srgb: bool,
+    /// Whether the back-end implements `RenderToTexture`.
+    pub render_to_texture: bool,
+    /// Whether the back-end supports custom materials.
+    pub materials: bool,
+}
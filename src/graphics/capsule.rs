@@ -0,0 +1,116 @@
+//! Draw capsule
+
+use super::{
+    math::Affine2,
+    rectangle::{Border, Shape},
+    types,
+    types::{Color, Radius, Resolution},
+    DrawState, Graphics, Rectangle,
+};
+
+/// A rectangle with fully rounded short ends, also known as stadium or pill shape.
+///
+/// The round radius is half the short side of the rectangle,
+/// so the shape adapts when the rectangle is resized.
+#[derive(Copy, Clone, Debug)]
+pub struct Capsule {
+    /// The capsule color.
+    pub color: Color,
+    /// The border.
+    pub border: Option<Border>,
+    /// The resolution per round end.
+    pub resolution: Resolution,
+}
+
+impl Capsule {
+    /// Creates a new capsule.
+    pub fn new(color: Color) -> Capsule {
+        Capsule {
+            color,
+            border: None,
+            resolution: 64,
+        }
+    }
+
+    /// Creates a new capsule border.
+    pub fn new_border(color: Color, radius: Radius) -> Capsule {
+        Capsule {
+            color: [0.0; 4],
+            border: Some(Border { color, radius }),
+            resolution: 64,
+        }
+    }
+
+    /// Sets color.
+    pub fn color(mut self, value: Color) -> Self {
+        self.color = value;
+        self
+    }
+
+    /// Sets border properties.
+    pub fn border(mut self, value: Border) -> Self {
+        self.border = Some(value);
+        self
+    }
+
+    /// Sets optional border.
+    pub fn maybe_border(mut self, value: Option<Border>) -> Self {
+        self.border = value;
+        self
+    }
+
+    /// Sets the resolution per round end.
+    pub fn resolution(mut self, value: Resolution) -> Self {
+        self.resolution = value;
+        self
+    }
+
+    /// Returns the rectangle drawing the capsule inside a rectangle.
+    pub fn to_rectangle(&self, rectangle: types::Rectangle) -> Rectangle {
+        let radius = 0.5 * rectangle[2].abs().min(rectangle[3].abs());
+        // The rectangle resolution is per corner, which is a quarter circle.
+        let resolution = (self.resolution / 2).max(1);
+        Rectangle::new(self.color)
+            .shape(Shape::Round(radius, resolution))
+            .maybe_border(self.border)
+    }
+
+    /// Draws capsule inside rectangle.
+    pub fn draw<R: Into<types::Rectangle>, G>(
+        &self,
+        rectangle: R,
+        draw_state: &DrawState,
+        transform: Affine2,
+        g: &mut G,
+    ) where
+        G: Graphics,
+    {
+        let rectangle = rectangle.into();
+        self.to_rectangle(rectangle)
+            .draw(rectangle, draw_state, transform, g);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::software_graphics::SoftwareGraphics;
+
+    #[test]
+    fn test_capsule() {
+        let mut g = SoftwareGraphics::new(32, 16);
+        g.draw(|c, g| {
+            Capsule::new([1.0; 4])
+                .border(Border {
+                    color: [1.0, 0.0, 0.0, 1.0],
+                    radius: 1.0,
+                })
+                .draw([2.0, 2.0, 28.0, 12.0], &c.draw_state, c.transform, g);
+        });
+        assert_eq!(g.get_pixel(16, 8), [255; 4]);
+        assert_eq!(g.get_pixel(16, 2), [255, 0, 0, 255]);
+        // The ends are half circles.
+        assert_eq!(g.get_pixel(2, 2), [0; 4]);
+        assert_eq!(g.get_pixel(8, 8), [255; 4]);
+    }
+}
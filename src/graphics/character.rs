@@ -21,6 +21,11 @@ pub struct Character<'a, T: ImageSize> {
     pub texture: &'a T,
     /// if this is an "invalid character" character
     pub is_invalid: bool,
+    /// Whether the glyph is a full-color bitmap, e.g. an emoji.
+    ///
+    /// Color glyphs are drawn with their own colors instead of the text color,
+    /// keeping only its opacity.
+    pub is_color: bool,
 }
 
 impl<T: ImageSize> Character<'_, T> {
@@ -43,6 +48,42 @@ impl<T: ImageSize> Character<'_, T> {
     pub fn advance_height(&self) -> Scalar {
         self.advance_size[1]
     }
+
+    /// The bearing, from the pen position on the baseline
+    /// to the upper left corner of the glyph, with y axis pointing up.
+    pub fn bearing(&self) -> [Scalar; 2] {
+        self.offset
+    }
+
+    /// The bounding box `[x, y, w, h]` of the glyph relative to the pen position,
+    /// with y axis pointing down.
+    pub fn bounds(&self) -> [Scalar; 4] {
+        [
+            self.left(),
+            -self.top(),
+            self.atlas_size[0],
+            self.atlas_size[1],
+        ]
+    }
+}
+
+/// Vertical metrics of a font at a size.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FontMetrics {
+    /// The distance from the baseline to the top of the highest glyphs.
+    pub ascent: Scalar,
+    /// The distance from the baseline to the bottom of the lowest glyphs,
+    /// positive below the baseline.
+    pub descent: Scalar,
+    /// The recommended extra space between lines.
+    pub line_gap: Scalar,
+}
+
+impl FontMetrics {
+    /// The recommended distance between baselines.
+    pub fn line_height(&self) -> Scalar {
+        self.ascent + self.descent + self.line_gap
+    }
 }
 
 /// Stores characters in a buffer and loads them by demand.
@@ -59,12 +100,98 @@ pub trait CharacterCache {
         ch: char,
     ) -> Result<Character<'_, Self::Texture>, Self::Error>;
 
+    /// Get reference to character rendered at a subpixel offset.
+    ///
+    /// The offset is in pixels within `[0, 1)`, with y axis pointing down,
+    /// and lets glyphs be placed at fractional positions.
+    /// The default implementation, for caches that do not render glyph variants,
+    /// moves the character by the offset instead.
+    fn character_at(
+        &mut self,
+        font_size: FontSize,
+        ch: char,
+        offset: [Scalar; 2],
+    ) -> Result<Character<'_, Self::Texture>, Self::Error> {
+        let mut character = self.character(font_size, ch)?;
+        character.offset[0] += offset[0];
+        character.offset[1] -= offset[1];
+        Ok(character)
+    }
+
+    /// Get the vertical metrics of the font at a size.
+    ///
+    /// Text of different fonts and sizes aligns to a common baseline
+    /// by offsetting with the ascent.
+    /// The default approximates the ascent with the font size,
+    /// for caches that do not know the metrics.
+    fn metrics(&mut self, font_size: FontSize) -> Result<FontMetrics, Self::Error> {
+        Ok(FontMetrics {
+            ascent: Scalar::from(font_size),
+            descent: 0.0,
+            line_gap: 0.0,
+        })
+    }
+
+    /// Loads characters at a size, e.g. when loading a game level.
+    ///
+    /// Warming the cache avoids rendering glyphs while drawing frames.
+    fn preload_chars<I>(&mut self, font_size: FontSize, chars: I) -> Result<(), Self::Error>
+    where
+        I: Iterator<Item = char>,
+        Self: Sized,
+    {
+        for ch in chars {
+            self.character(font_size, ch)?;
+        }
+        Ok(())
+    }
+
+    /// Loads the characters of a string at a size.
+    fn preload_str(&mut self, font_size: FontSize, text: &str) -> Result<(), Self::Error>
+    where
+        Self: Sized,
+    {
+        self.preload_chars(font_size, text.chars())
+    }
+
+    /// Removes the least recently used glyphs, keeping at most `max_glyphs`.
+    ///
+    /// Returns the number of removed glyphs.
+    /// Caches with atlases may render the remaining glyphs again
+    /// to free texture memory, so call this between frames,
+    /// not while drawing.
+    /// The default keeps all glyphs, for caches without eviction.
+    fn evict(&mut self, max_glyphs: usize) -> Result<usize, Self::Error> {
+        let _ = max_glyphs;
+        Ok(0)
+    }
+
+    /// Get the kerning between a pair of characters.
+    ///
+    /// The kerning is added to the advance of the left character,
+    /// usually negative to move e.g. "AV" closer together.
+    /// The default returns zero, for caches without kerning tables.
+    fn kerning(
+        &mut self,
+        font_size: FontSize,
+        left: char,
+        right: char,
+    ) -> Result<Scalar, Self::Error> {
+        let _ = (font_size, left, right);
+        Ok(0.0)
+    }
+
     /// Return the width for some given text.
     fn width(&mut self, size: FontSize, text: &str) -> Result<math::Scalar, Self::Error> {
         let mut width = 0.0;
+        let mut prev = None;
         for ch in text.chars() {
+            if let Some(prev) = prev {
+                width += self.kerning(size, prev, ch)?;
+            }
             let character = self.character(size, ch)?;
             width += character.advance_width();
+            prev = Some(ch);
         }
         Ok(width)
     }
@@ -24,6 +24,12 @@ pub struct CircleArc {
 
     /// The resolution for the arc.
     pub resolution: Resolution,
+
+    /// Optional tolerance in pixels for automatic resolution.
+    ///
+    /// When set, the resolution is computed from the radius on the screen,
+    /// if the back-end knows its viewport.
+    pub tolerance: Option<Scalar>,
 }
 
 impl CircleArc {
@@ -35,6 +41,7 @@ impl CircleArc {
             start,
             end,
             resolution: 128,
+            tolerance: None,
         }
     }
 
@@ -68,6 +75,12 @@ impl CircleArc {
         self
     }
 
+    /// Enables automatic resolution with a tolerance in pixels.
+    pub fn tolerance(mut self, value: Scalar) -> Self {
+        self.tolerance = Some(value);
+        self
+    }
+
     /// Draws circle arc using default method.
     #[inline(always)]
     pub fn draw<R: Into<Rectangle>, G>(
@@ -93,11 +106,20 @@ impl CircleArc {
         G: Graphics,
     {
         let rectangle = rectangle.into();
+        let resolution = match (self.tolerance, g.current_viewport()) {
+            (Some(tolerance), Some(viewport)) => triangulation::auto_resolution(
+                transform,
+                &viewport,
+                0.5 * rectangle[2].abs().max(rectangle[3].abs()) + self.radius,
+                tolerance,
+            ),
+            _ => self.resolution,
+        };
         g.tri_list(draw_state, &self.color, |f| {
             triangulation::with_arc_tri_list(
                 self.start,
                 self.end,
-                self.resolution,
+                resolution,
                 transform,
                 rectangle,
                 self.radius,
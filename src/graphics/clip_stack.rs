@@ -0,0 +1,138 @@
+//! Nested clipping with the stencil buffer
+
+use super::{draw_state::Stencil, Context, DrawState};
+
+/// Keeps track of nested clip shapes in the stencil buffer.
+///
+/// Each clip shape increments the stencil buffer where it is drawn,
+/// and content is drawn only where the stencil value equals the clip depth.
+/// Since pixels outside a parent clip never reach the depth of a child clip,
+/// nested clips are intersected.
+/// The shape is drawn again when the clip is popped, to decrement the stencil buffer.
+///
+/// The stencil buffer must be cleared to 0 before the first clip,
+/// and clip shapes should not overlap themselves,
+/// since overlapping triangles increment the stencil buffer twice.
+///
+/// ```ignore
+/// let mut clips = ClipStack::new();
+/// clear_stencil(0, g);
+/// clips.with_clip(&c, g, |ds, g| panel.draw(rect, ds, c.transform, g), |c, g, clips| {
+///     // Draws inside the panel.
+///     clips.with_clip(&c, g, |ds, g| circle.draw(rect, ds, c.transform, g), |c, g, _| {
+///         // Draws inside the panel and circle.
+///     });
+/// });
+/// ```
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ClipStack {
+    depth: u8,
+}
+
+impl ClipStack {
+    /// Creates a new empty clip stack.
+    pub fn new() -> ClipStack {
+        ClipStack { depth: 0 }
+    }
+
+    /// Returns the number of active clips.
+    pub fn depth(&self) -> u8 {
+        self.depth
+    }
+
+    /// Returns draw state for drawing inside all active clips.
+    ///
+    /// The stencil test is disabled when there are no clips.
+    pub fn inside(&self, draw_state: DrawState) -> DrawState {
+        DrawState {
+            stencil: if self.depth == 0 {
+                None
+            } else {
+                Some(Stencil::Inside(self.depth))
+            },
+            ..draw_state
+        }
+    }
+
+    /// Pushes a clip shape, returning the draw state to draw the shape with.
+    pub fn push(&mut self) -> DrawState {
+        self.depth = self.depth.saturating_add(1);
+        DrawState::new_increment()
+    }
+
+    /// Pops a clip shape, returning the draw state to draw the same shape with.
+    pub fn pop(&mut self) -> DrawState {
+        self.depth = self.depth.saturating_sub(1);
+        DrawState::new_decrement()
+    }
+
+    /// Draws inside a clip shape, nested in the active clips.
+    ///
+    /// Calls `shape` to draw the clip shape with a draw state,
+    /// once before and once after drawing the content.
+    /// The content gets a context that draws inside the clip.
+    pub fn with_clip<G, S, F, U>(&mut self, c: &Context, g: &mut G, mut shape: S, f: F) -> U
+    where
+        S: FnMut(&DrawState, &mut G),
+        F: FnOnce(Context, &mut G, &mut ClipStack) -> U,
+    {
+        let ds = self.push();
+        shape(&ds, g);
+        let inside = Context {
+            draw_state: self.inside(c.draw_state),
+            ..*c
+        };
+        let res = f(inside, g, self);
+        let ds = self.pop();
+        shape(&ds, g);
+        res
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        graphics::{clear, Graphics, Rectangle},
+        software_graphics::SoftwareGraphics,
+    };
+
+    #[test]
+    fn test_clip_stack() {
+        let mut clips = ClipStack::new();
+        let mut g = SoftwareGraphics::new(8, 8);
+        g.draw(|c, g| {
+            g.clear_stencil(0);
+            clear([0.0, 0.0, 0.0, 1.0], g);
+            let shape = |r: [f64; 4]| {
+                move |ds: &DrawState, g: &mut SoftwareGraphics| {
+                    Rectangle::new([1.0; 4]).draw(r, ds, c.transform, g)
+                }
+            };
+            clips.with_clip(&c, g, shape([0.0, 0.0, 6.0, 8.0]), |c, g, clips| {
+                clips.with_clip(&c, g, shape([2.0, 0.0, 6.0, 8.0]), |c, g, _| {
+                    Rectangle::new([1.0; 4]).draw(
+                        [0.0, 0.0, 8.0, 4.0],
+                        &c.draw_state,
+                        c.transform,
+                        g,
+                    );
+                });
+                Rectangle::new([1.0, 0.0, 0.0, 1.0]).draw(
+                    [0.0, 4.0, 8.0, 4.0],
+                    &c.draw_state,
+                    c.transform,
+                    g,
+                );
+            });
+        });
+        assert_eq!(clips.depth(), 0);
+        // Inside both clips.
+        assert_eq!(g.get_pixel(3, 1), [255; 4]);
+        assert_eq!(g.get_pixel(1, 1), [0, 0, 0, 255]);
+        assert_eq!(g.get_pixel(7, 1), [0, 0, 0, 255]);
+        // Inside the outer clip after popping the inner one.
+        assert_eq!(g.get_pixel(1, 6), [255, 0, 0, 255]);
+        assert_eq!(g.get_pixel(7, 6), [0, 0, 0, 255]);
+    }
+}
@@ -65,6 +65,228 @@ pub fn hex(hex: &str) -> Color {
     ]
 }
 
+/// Converts from a hexadecimal color string.
+///
+/// Accepts `rgb`, `rgba`, `rrggbb` and `rrggbbaa`, with an optional leading `#`.
+/// Returns an error when the string is not a valid color.
+pub fn from_hex(hex: &str) -> Result<Color, String> {
+    let digits = hex.strip_prefix('#').unwrap_or(hex);
+    let values = digits
+        .chars()
+        .map(crate::read_color::char_to_hex)
+        .collect::<Option<Vec<u8>>>()
+        .ok_or_else(|| format!("Invalid hex color `{}`", hex))?;
+    let mut rgba = match values.len() {
+        3 | 4 => values.iter().map(|&v| v * 17).collect::<Vec<u8>>(),
+        6 | 8 => values.chunks(2).map(|v| v[0] * 16 + v[1]).collect(),
+        _ => return Err(format!("Invalid hex color `{}`", hex)),
+    };
+    if rgba.len() == 3 {
+        rgba.push(255);
+    }
+    let inv_255 = 1.0f32 / 255.0f32;
+    Ok([
+        rgba[0] as f32 * inv_255,
+        rgba[1] as f32 * inv_255,
+        rgba[2] as f32 * inv_255,
+        rgba[3] as f32 * inv_255,
+    ])
+}
+
+/// Returns a CSS named color, e.g. `"cornflowerblue"`.
+///
+/// The name is case insensitive.
+/// Note that some CSS colors differ from the constants in this module,
+/// e.g. CSS `silver` is lighter than `SILVER`.
+pub fn named(name: &str) -> Option<Color> {
+    let name = name.to_ascii_lowercase();
+    if name == "transparent" {
+        return Some(TRANSPARENT);
+    }
+    let i = CSS_COLORS
+        .binary_search_by(|(n, _)| (*n).cmp(&name[..]))
+        .ok()?;
+    let rgb = CSS_COLORS[i].1;
+    let inv_255 = 1.0f32 / 255.0f32;
+    Some([
+        (rgb >> 16) as f32 * inv_255,
+        ((rgb >> 8) & 0xFF) as f32 * inv_255,
+        (rgb & 0xFF) as f32 * inv_255,
+        1.0,
+    ])
+}
+
+/// The CSS named colors, sorted by name.
+pub const CSS_COLORS: &[(&str, u32)] = &[
+    ("aliceblue", 0xF0F8FF),
+    ("antiquewhite", 0xFAEBD7),
+    ("aqua", 0x00FFFF),
+    ("aquamarine", 0x7FFFD4),
+    ("azure", 0xF0FFFF),
+    ("beige", 0xF5F5DC),
+    ("bisque", 0xFFE4C4),
+    ("black", 0x000000),
+    ("blanchedalmond", 0xFFEBCD),
+    ("blue", 0x0000FF),
+    ("blueviolet", 0x8A2BE2),
+    ("brown", 0xA52A2A),
+    ("burlywood", 0xDEB887),
+    ("cadetblue", 0x5F9EA0),
+    ("chartreuse", 0x7FFF00),
+    ("chocolate", 0xD2691E),
+    ("coral", 0xFF7F50),
+    ("cornflowerblue", 0x6495ED),
+    ("cornsilk", 0xFFF8DC),
+    ("crimson", 0xDC143C),
+    ("cyan", 0x00FFFF),
+    ("darkblue", 0x00008B),
+    ("darkcyan", 0x008B8B),
+    ("darkgoldenrod", 0xB8860B),
+    ("darkgray", 0xA9A9A9),
+    ("darkgreen", 0x006400),
+    ("darkgrey", 0xA9A9A9),
+    ("darkkhaki", 0xBDB76B),
+    ("darkmagenta", 0x8B008B),
+    ("darkolivegreen", 0x556B2F),
+    ("darkorange", 0xFF8C00),
+    ("darkorchid", 0x9932CC),
+    ("darkred", 0x8B0000),
+    ("darksalmon", 0xE9967A),
+    ("darkseagreen", 0x8FBC8F),
+    ("darkslateblue", 0x483D8B),
+    ("darkslategray", 0x2F4F4F),
+    ("darkslategrey", 0x2F4F4F),
+    ("darkturquoise", 0x00CED1),
+    ("darkviolet", 0x9400D3),
+    ("deeppink", 0xFF1493),
+    ("deepskyblue", 0x00BFFF),
+    ("dimgray", 0x696969),
+    ("dimgrey", 0x696969),
+    ("dodgerblue", 0x1E90FF),
+    ("firebrick", 0xB22222),
+    ("floralwhite", 0xFFFAF0),
+    ("forestgreen", 0x228B22),
+    ("fuchsia", 0xFF00FF),
+    ("gainsboro", 0xDCDCDC),
+    ("ghostwhite", 0xF8F8FF),
+    ("gold", 0xFFD700),
+    ("goldenrod", 0xDAA520),
+    ("gray", 0x808080),
+    ("green", 0x008000),
+    ("greenyellow", 0xADFF2F),
+    ("grey", 0x808080),
+    ("honeydew", 0xF0FFF0),
+    ("hotpink", 0xFF69B4),
+    ("indianred", 0xCD5C5C),
+    ("indigo", 0x4B0082),
+    ("ivory", 0xFFFFF0),
+    ("khaki", 0xF0E68C),
+    ("lavender", 0xE6E6FA),
+    ("lavenderblush", 0xFFF0F5),
+    ("lawngreen", 0x7CFC00),
+    ("lemonchiffon", 0xFFFACD),
+    ("lightblue", 0xADD8E6),
+    ("lightcoral", 0xF08080),
+    ("lightcyan", 0xE0FFFF),
+    ("lightgoldenrodyellow", 0xFAFAD2),
+    ("lightgray", 0xD3D3D3),
+    ("lightgreen", 0x90EE90),
+    ("lightgrey", 0xD3D3D3),
+    ("lightpink", 0xFFB6C1),
+    ("lightsalmon", 0xFFA07A),
+    ("lightseagreen", 0x20B2AA),
+    ("lightskyblue", 0x87CEFA),
+    ("lightslategray", 0x778899),
+    ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xB0C4DE),
+    ("lightyellow", 0xFFFFE0),
+    ("lime", 0x00FF00),
+    ("limegreen", 0x32CD32),
+    ("linen", 0xFAF0E6),
+    ("magenta", 0xFF00FF),
+    ("maroon", 0x800000),
+    ("mediumaquamarine", 0x66CDAA),
+    ("mediumblue", 0x0000CD),
+    ("mediumorchid", 0xBA55D3),
+    ("mediumpurple", 0x9370DB),
+    ("mediumseagreen", 0x3CB371),
+    ("mediumslateblue", 0x7B68EE),
+    ("mediumspringgreen", 0x00FA9A),
+    ("mediumturquoise", 0x48D1CC),
+    ("mediumvioletred", 0xC71585),
+    ("midnightblue", 0x191970),
+    ("mintcream", 0xF5FFFA),
+    ("mistyrose", 0xFFE4E1),
+    ("moccasin", 0xFFE4B5),
+    ("navajowhite", 0xFFDEAD),
+    ("navy", 0x000080),
+    ("oldlace", 0xFDF5E6),
+    ("olive", 0x808000),
+    ("olivedrab", 0x6B8E23),
+    ("orange", 0xFFA500),
+    ("orangered", 0xFF4500),
+    ("orchid", 0xDA70D6),
+    ("palegoldenrod", 0xEEE8AA),
+    ("palegreen", 0x98FB98),
+    ("paleturquoise", 0xAFEEEE),
+    ("palevioletred", 0xDB7093),
+    ("papayawhip", 0xFFEFD5),
+    ("peachpuff", 0xFFDAB9),
+    ("peru", 0xCD853F),
+    ("pink", 0xFFC0CB),
+    ("plum", 0xDDA0DD),
+    ("powderblue", 0xB0E0E6),
+    ("purple", 0x800080),
+    ("rebeccapurple", 0x663399),
+    ("red", 0xFF0000),
+    ("rosybrown", 0xBC8F8F),
+    ("royalblue", 0x4169E1),
+    ("saddlebrown", 0x8B4513),
+    ("salmon", 0xFA8072),
+    ("sandybrown", 0xF4A460),
+    ("seagreen", 0x2E8B57),
+    ("seashell", 0xFFF5EE),
+    ("sienna", 0xA0522D),
+    ("silver", 0xC0C0C0),
+    ("skyblue", 0x87CEEB),
+    ("slateblue", 0x6A5ACD),
+    ("slategray", 0x708090),
+    ("slategrey", 0x708090),
+    ("snow", 0xFFFAFA),
+    ("springgreen", 0x00FF7F),
+    ("steelblue", 0x4682B4),
+    ("tan", 0xD2B48C),
+    ("teal", 0x008080),
+    ("thistle", 0xD8BFD8),
+    ("tomato", 0xFF6347),
+    ("turquoise", 0x40E0D0),
+    ("violet", 0xEE82EE),
+    ("wheat", 0xF5DEB3),
+    ("white", 0xFFFFFF),
+    ("whitesmoke", 0xF5F5F5),
+    ("yellow", 0xFFFF00),
+    ("yellowgreen", 0x9ACD32),
+];
+
+/// Multiplies the color components by alpha.
+///
+/// Use with `DrawState::new_premultiplied`.
+/// For gamma-correct results with an sRGB frame buffer,
+/// premultiply in linear color space, e.g. `LinearRgba::premultiply`.
+pub fn premultiply(c: Color) -> Color {
+    [c[0] * c[3], c[1] * c[3], c[2] * c[3], c[3]]
+}
+
+/// Divides the color components by alpha, undoing `premultiply`.
+///
+/// Returns transparent when alpha is zero.
+pub fn unpremultiply(c: Color) -> Color {
+    if c[3] == 0.0 {
+        return TRANSPARENT;
+    }
+    [c[0] / c[3], c[1] / c[3], c[2] / c[3], c[3]]
+}
+
 #[inline(always)]
 fn component_srgb_to_linear(f: ColorComponent) -> ColorComponent {
     if f <= 0.04045 {
@@ -87,6 +309,18 @@ pub fn gamma_srgb_to_linear(c: Color) -> Color {
     ]
 }
 
+/// Converts gamma (brightness) of many colors from sRGB to linear color space.
+///
+/// The colors are converted in place,
+/// e.g. when a back-end converts vertex colors before uploading them.
+pub fn gamma_srgb_to_linear_batch(colors: &mut [Color]) {
+    for c in colors {
+        c[0] = component_srgb_to_linear(c[0]);
+        c[1] = component_srgb_to_linear(c[1]);
+        c[2] = component_srgb_to_linear(c[2]);
+    }
+}
+
 #[inline(always)]
 fn component_linear_to_srgb(f: ColorComponent) -> ColorComponent {
     if f <= 0.0031308 {
@@ -108,3 +342,662 @@ pub fn gamma_linear_to_srgb(c: Color) -> Color {
         c[3],
     ]
 }
+
+/// Converts gamma (brightness) of many colors from linear color space to sRGB.
+///
+/// The colors are converted in place.
+pub fn gamma_linear_to_srgb_batch(colors: &mut [Color]) {
+    for c in colors {
+        c[0] = component_linear_to_srgb(c[0]);
+        c[1] = component_linear_to_srgb(c[1]);
+        c[2] = component_linear_to_srgb(c[2]);
+    }
+}
+
+/// How a back-end interprets the colors passed to it.
+///
+/// Embedders that compose with other renderers can use this
+/// to match color pipelines and avoid converting colors twice.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ColorSpace {
+    /// Colors are sRGB and converted to linear for an sRGB frame buffer.
+    ///
+    /// This is the default.
+    #[default]
+    Srgb,
+    /// Colors are already linear and written to an sRGB frame buffer.
+    Linear,
+    /// Colors are written unchanged, without sRGB frame buffer conversion.
+    Passthrough,
+}
+
+impl ColorSpace {
+    /// Returns `true` if the frame buffer should convert linear output to sRGB.
+    pub fn is_srgb_framebuffer(self) -> bool {
+        self != ColorSpace::Passthrough
+    }
+
+    /// Converts an input color to the color written by the shader.
+    pub fn to_shader(self, c: Color) -> Color {
+        match self {
+            ColorSpace::Srgb => gamma_srgb_to_linear(c),
+            ColorSpace::Linear | ColorSpace::Passthrough => c,
+        }
+    }
+
+    /// Converts many input colors in place to the colors written by the shader.
+    pub fn to_shader_batch(self, colors: &mut [Color]) {
+        if self == ColorSpace::Srgb {
+            gamma_srgb_to_linear_batch(colors);
+        }
+    }
+}
+
+/// A color in sRGB color space.
+///
+/// This is the color space used by `types::Color`,
+/// image editors, pictures, internet etc.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Srgba(pub Color);
+
+/// A color in linear color space.
+///
+/// Linear colors yield better results when doing math with colors,
+/// e.g. blending or interpolation.
+///
+/// The components are not clamped, such that values outside
+/// `[0.0, 1.0]` can represent colors of wider gamut than sRGB.
+/// These are converted using extended sRGB,
+/// which mirrors the transfer function for negative values.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LinearRgba(pub Color);
+
+#[inline(always)]
+fn component_extended_srgb_to_linear(f: ColorComponent) -> ColorComponent {
+    if f < 0.0 {
+        -component_srgb_to_linear(-f)
+    } else {
+        component_srgb_to_linear(f)
+    }
+}
+
+#[inline(always)]
+fn component_extended_linear_to_srgb(f: ColorComponent) -> ColorComponent {
+    if f < 0.0 {
+        -component_linear_to_srgb(-f)
+    } else {
+        component_linear_to_srgb(f)
+    }
+}
+
+impl Srgba {
+    /// Creates a new sRGB color.
+    pub fn new(
+        r: ColorComponent,
+        g: ColorComponent,
+        b: ColorComponent,
+        a: ColorComponent,
+    ) -> Srgba {
+        Srgba([r, g, b, a])
+    }
+
+    /// Converts to linear color space.
+    pub fn to_linear(self) -> LinearRgba {
+        let c = self.0;
+        LinearRgba([
+            component_extended_srgb_to_linear(c[0]),
+            component_extended_srgb_to_linear(c[1]),
+            component_extended_srgb_to_linear(c[2]),
+            c[3],
+        ])
+    }
+}
+
+impl LinearRgba {
+    /// Creates a new linear color.
+    pub fn new(
+        r: ColorComponent,
+        g: ColorComponent,
+        b: ColorComponent,
+        a: ColorComponent,
+    ) -> LinearRgba {
+        LinearRgba([r, g, b, a])
+    }
+
+    /// Converts to sRGB color space.
+    pub fn to_srgb(self) -> Srgba {
+        let c = self.0;
+        Srgba([
+            component_extended_linear_to_srgb(c[0]),
+            component_extended_linear_to_srgb(c[1]),
+            component_extended_linear_to_srgb(c[2]),
+            c[3],
+        ])
+    }
+
+    /// Returns `true` if all components are within `[0.0, 1.0]`.
+    pub fn is_in_gamut(&self) -> bool {
+        self.0.iter().all(|&c| (0.0..=1.0).contains(&c))
+    }
+
+    /// Clamps all components to `[0.0, 1.0]`.
+    pub fn clamp(self) -> LinearRgba {
+        let c = self.0;
+        LinearRgba([
+            c[0].clamp(0.0, 1.0),
+            c[1].clamp(0.0, 1.0),
+            c[2].clamp(0.0, 1.0),
+            c[3].clamp(0.0, 1.0),
+        ])
+    }
+
+    /// Multiplies the color components by alpha in linear color space.
+    ///
+    /// Convert back with `to_srgb` to pass as color
+    /// when the back-end uses `ColorSpace::Srgb`.
+    pub fn premultiply(self) -> LinearRgba {
+        LinearRgba(premultiply(self.0))
+    }
+
+    /// Interpolates linearly between two colors.
+    pub fn lerp(self, other: LinearRgba, t: ColorComponent) -> LinearRgba {
+        let (a, b) = (self.0, other.0);
+        LinearRgba([
+            a[0] + (b[0] - a[0]) * t,
+            a[1] + (b[1] - a[1]) * t,
+            a[2] + (b[2] - a[2]) * t,
+            a[3] + (b[3] - a[3]) * t,
+        ])
+    }
+}
+
+/// A color with hue, saturation, value and alpha.
+///
+/// The hue is in degrees within `[0.0, 360.0)`,
+/// and the other components are within `[0.0, 1.0]`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Hsva(pub [ColorComponent; 4]);
+
+/// A color with hue, saturation, lightness and alpha.
+///
+/// The hue is in degrees within `[0.0, 360.0)`,
+/// and the other components are within `[0.0, 1.0]`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Hsla(pub [ColorComponent; 4]);
+
+/// A color in Oklab color space, with lightness, a, b and alpha.
+///
+/// Oklab is perceptually uniform,
+/// such that interpolated colors change evenly in brightness and hue.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Oklaba(pub [ColorComponent; 4]);
+
+// Computes hue in degrees, max and min of the RGB components.
+fn hue_max_min(c: Color) -> (ColorComponent, ColorComponent, ColorComponent) {
+    let max = c[0].max(c[1]).max(c[2]);
+    let min = c[0].min(c[1]).min(c[2]);
+    let d = max - min;
+    let h = if d == 0.0 {
+        0.0
+    } else if max == c[0] {
+        60.0 * ((c[1] - c[2]) / d).rem_euclid(6.0)
+    } else if max == c[1] {
+        60.0 * ((c[2] - c[0]) / d + 2.0)
+    } else {
+        60.0 * ((c[0] - c[1]) / d + 4.0)
+    };
+    (h, max, min)
+}
+
+// Computes RGB from hue in degrees, chroma and the smallest component.
+fn hue_to_rgb(
+    h: ColorComponent,
+    chroma: ColorComponent,
+    min: ColorComponent,
+) -> [ColorComponent; 3] {
+    let h = h.rem_euclid(360.0) / 60.0;
+    let x = chroma * (1.0 - (h.rem_euclid(2.0) - 1.0).abs());
+    let [r, g, b] = match h as u32 {
+        0 => [chroma, x, 0.0],
+        1 => [x, chroma, 0.0],
+        2 => [0.0, chroma, x],
+        3 => [0.0, x, chroma],
+        4 => [x, 0.0, chroma],
+        _ => [chroma, 0.0, x],
+    };
+    [r + min, g + min, b + min]
+}
+
+impl Srgba {
+    /// Converts to hue, saturation and value.
+    pub fn to_hsv(self) -> Hsva {
+        let c = self.0;
+        let (h, max, min) = hue_max_min(c);
+        let s = if max == 0.0 { 0.0 } else { (max - min) / max };
+        Hsva([h, s, max, c[3]])
+    }
+
+    /// Converts to hue, saturation and lightness.
+    pub fn to_hsl(self) -> Hsla {
+        let c = self.0;
+        let (h, max, min) = hue_max_min(c);
+        let l = 0.5 * (max + min);
+        let s = if max == min {
+            0.0
+        } else {
+            (max - min) / (1.0 - (2.0 * l - 1.0).abs())
+        };
+        Hsla([h, s, l, c[3]])
+    }
+}
+
+impl Hsva {
+    /// Creates a new color from hue (in degrees), saturation and value.
+    pub fn new(h: ColorComponent, s: ColorComponent, v: ColorComponent, a: ColorComponent) -> Hsva {
+        Hsva([h, s, v, a])
+    }
+
+    /// Converts to sRGB color space.
+    pub fn to_srgb(self) -> Srgba {
+        let [h, s, v, a] = self.0;
+        let chroma = v * s;
+        let [r, g, b] = hue_to_rgb(h, chroma, v - chroma);
+        Srgba([r, g, b, a])
+    }
+}
+
+impl Hsla {
+    /// Creates a new color from hue (in degrees), saturation and lightness.
+    pub fn new(h: ColorComponent, s: ColorComponent, l: ColorComponent, a: ColorComponent) -> Hsla {
+        Hsla([h, s, l, a])
+    }
+
+    /// Converts to sRGB color space.
+    pub fn to_srgb(self) -> Srgba {
+        let [h, s, l, a] = self.0;
+        let chroma = (1.0 - (2.0 * l - 1.0).abs()) * s;
+        let [r, g, b] = hue_to_rgb(h, chroma, l - 0.5 * chroma);
+        Srgba([r, g, b, a])
+    }
+}
+
+impl LinearRgba {
+    /// Converts to Oklab color space.
+    #[allow(clippy::excessive_precision)] // Reference constants.
+    pub fn to_oklab(self) -> Oklaba {
+        let [r, g, b, a] = self.0;
+        let l = (0.4122214708 * r + 0.5363325363 * g + 0.0514459929 * b).cbrt();
+        let m = (0.2119034982 * r + 0.6806995451 * g + 0.1073969566 * b).cbrt();
+        let s = (0.0883024619 * r + 0.2817188376 * g + 0.6299787005 * b).cbrt();
+        Oklaba([
+            0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s,
+            1.9779984951 * l - 2.4285922050 * m + 0.4505937099 * s,
+            0.0259040371 * l + 0.7827717662 * m - 0.8086757660 * s,
+            a,
+        ])
+    }
+}
+
+impl Oklaba {
+    /// Creates a new color from lightness, a and b.
+    pub fn new(
+        l: ColorComponent,
+        a: ColorComponent,
+        b: ColorComponent,
+        alpha: ColorComponent,
+    ) -> Oklaba {
+        Oklaba([l, a, b, alpha])
+    }
+
+    /// Converts to linear color space.
+    #[allow(clippy::excessive_precision)] // Reference constants.
+    pub fn to_linear(self) -> LinearRgba {
+        let [l, a, b, alpha] = self.0;
+        let l_ = l + 0.3963377774 * a + 0.2158037573 * b;
+        let m_ = l - 0.1055613458 * a - 0.0638541728 * b;
+        let s_ = l - 0.0894841775 * a - 1.2914855480 * b;
+        let (l, m, s) = (l_ * l_ * l_, m_ * m_ * m_, s_ * s_ * s_);
+        LinearRgba([
+            4.0767416621 * l - 3.3077115913 * m + 0.2309699292 * s,
+            -1.2684380046 * l + 2.6097574011 * m - 0.3413193965 * s,
+            -0.0041960863 * l - 0.7034186147 * m + 1.7076147010 * s,
+            alpha,
+        ])
+    }
+}
+
+/// The color space used by `lerp`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LerpSpace {
+    /// Interpolates sRGB components directly.
+    Srgb,
+    /// Interpolates in linear color space.
+    Linear,
+    /// Interpolates hue along the shortest way around, saturation and value.
+    Hsv,
+    /// Interpolates hue along the shortest way around, saturation and lightness.
+    Hsl,
+    /// Interpolates in Oklab color space, which is perceptually uniform.
+    Oklab,
+}
+
+// Interpolates components, where the first is a hue in degrees.
+fn lerp_hue(
+    a: [ColorComponent; 4],
+    b: [ColorComponent; 4],
+    t: ColorComponent,
+) -> [ColorComponent; 4] {
+    let mut dh = (b[0] - a[0]).rem_euclid(360.0);
+    if dh > 180.0 {
+        dh -= 360.0;
+    }
+    let mut c = lerp_components(a, b, t);
+    c[0] = (a[0] + dh * t).rem_euclid(360.0);
+    c
+}
+
+fn lerp_components(
+    a: [ColorComponent; 4],
+    b: [ColorComponent; 4],
+    t: ColorComponent,
+) -> [ColorComponent; 4] {
+    [
+        a[0] + (b[0] - a[0]) * t,
+        a[1] + (b[1] - a[1]) * t,
+        a[2] + (b[2] - a[2]) * t,
+        a[3] + (b[3] - a[3]) * t,
+    ]
+}
+
+/// Interpolates between two sRGB colors in a color space.
+///
+/// Interpolating sRGB components directly gives muddy colors in between,
+/// e.g. when animating hue.
+pub fn lerp(a: Color, b: Color, t: ColorComponent, space: LerpSpace) -> Color {
+    let (a, b) = (Srgba(a), Srgba(b));
+    match space {
+        LerpSpace::Srgb => lerp_components(a.0, b.0, t),
+        LerpSpace::Linear => a.to_linear().lerp(b.to_linear(), t).into(),
+        LerpSpace::Hsv => Hsva(lerp_hue(a.to_hsv().0, b.to_hsv().0, t)).to_srgb().0,
+        LerpSpace::Hsl => Hsla(lerp_hue(a.to_hsl().0, b.to_hsl().0, t)).to_srgb().0,
+        LerpSpace::Oklab => {
+            let (a, b) = (a.to_linear().to_oklab(), b.to_linear().to_oklab());
+            Oklaba(lerp_components(a.0, b.0, t)).to_linear().into()
+        }
+    }
+}
+
+/// A list of colors, used as indexed palette or as color ramp.
+///
+/// As a ramp, the colors are evenly spaced stops between 0 and 1,
+/// interpolated in a color space.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Palette {
+    /// The colors of the palette.
+    pub colors: Vec<Color>,
+    /// The color space used for interpolating between stops.
+    pub space: LerpSpace,
+}
+
+impl Palette {
+    /// Creates a new palette interpolating in sRGB.
+    pub fn new(colors: Vec<Color>) -> Palette {
+        Palette {
+            colors,
+            space: LerpSpace::Srgb,
+        }
+    }
+
+    /// Creates a palette from hexadecimal colors, see `from_hex`.
+    ///
+    /// Returns an error when a color is not valid.
+    pub fn from_hex(colors: &[&str]) -> Result<Palette, String> {
+        let colors = colors
+            .iter()
+            .map(|c| from_hex(c))
+            .collect::<Result<_, _>>()?;
+        Ok(Palette::new(colors))
+    }
+
+    /// Creates a perceptually uniform ramp from dark blue over green to yellow,
+    /// similar to viridis.
+    pub fn viridis() -> Palette {
+        Palette::from_hex(&[
+            "440154", "482878", "3e4a89", "31688e", "26828e", "1f9e89", "35b779", "6ece58",
+            "b5de2b", "fde725",
+        ])
+        .expect("Valid colors")
+    }
+
+    /// Creates a perceptually uniform ramp from black over purple to light yellow,
+    /// similar to magma.
+    pub fn magma() -> Palette {
+        Palette::from_hex(&[
+            "000004", "1c1044", "4f127b", "812581", "b5367a", "e55064", "fb8761", "fec287",
+            "fcfdbf",
+        ])
+        .expect("Valid colors")
+    }
+
+    /// Sets the color space used for interpolating between stops.
+    pub fn space(mut self, value: LerpSpace) -> Self {
+        self.space = value;
+        self
+    }
+
+    /// Returns the number of colors.
+    pub fn len(&self) -> usize {
+        self.colors.len()
+    }
+
+    /// Returns `true` if the palette has no colors.
+    pub fn is_empty(&self) -> bool {
+        self.colors.is_empty()
+    }
+
+    /// Returns the color at an index.
+    pub fn get(&self, index: usize) -> Option<Color> {
+        self.colors.get(index).copied()
+    }
+
+    /// Samples the ramp at `t` within `[0.0, 1.0]`.
+    ///
+    /// Values outside the range are clamped.
+    /// Returns transparent when the palette is empty.
+    pub fn sample(&self, t: ColorComponent) -> Color {
+        let n = self.colors.len();
+        if n == 0 {
+            return TRANSPARENT;
+        }
+        let x = t.clamp(0.0, 1.0) * (n - 1) as ColorComponent;
+        let i = (x as usize).min(n.saturating_sub(2));
+        match self.colors.get(i + 1) {
+            Some(&b) => lerp(self.colors[i], b, x - i as ColorComponent, self.space),
+            None => self.colors[i],
+        }
+    }
+}
+
+impl From<Hsva> for Srgba {
+    fn from(color: Hsva) -> Srgba {
+        color.to_srgb()
+    }
+}
+
+impl From<Srgba> for Hsva {
+    fn from(color: Srgba) -> Hsva {
+        color.to_hsv()
+    }
+}
+
+impl From<Hsla> for Srgba {
+    fn from(color: Hsla) -> Srgba {
+        color.to_srgb()
+    }
+}
+
+impl From<Srgba> for Hsla {
+    fn from(color: Srgba) -> Hsla {
+        color.to_hsl()
+    }
+}
+
+impl From<Oklaba> for LinearRgba {
+    fn from(color: Oklaba) -> LinearRgba {
+        color.to_linear()
+    }
+}
+
+impl From<LinearRgba> for Oklaba {
+    fn from(color: LinearRgba) -> Oklaba {
+        color.to_oklab()
+    }
+}
+
+impl From<Color> for Srgba {
+    fn from(color: Color) -> Srgba {
+        Srgba(color)
+    }
+}
+
+impl From<Srgba> for Color {
+    fn from(color: Srgba) -> Color {
+        color.0
+    }
+}
+
+impl From<LinearRgba> for Color {
+    fn from(color: LinearRgba) -> Color {
+        color.to_srgb().0
+    }
+}
+
+impl From<Srgba> for LinearRgba {
+    fn from(color: Srgba) -> LinearRgba {
+        color.to_linear()
+    }
+}
+
+impl From<LinearRgba> for Srgba {
+    fn from(color: LinearRgba) -> Srgba {
+        color.to_srgb()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_gamma_batch() {
+        let colors = [[0.0, 0.02, 0.5, 0.3], [1.0, 0.75, 0.25, 1.0]];
+        let mut batch = colors;
+        gamma_srgb_to_linear_batch(&mut batch);
+        assert_eq!(batch[0], gamma_srgb_to_linear(colors[0]));
+        assert_eq!(batch[1], gamma_srgb_to_linear(colors[1]));
+        gamma_linear_to_srgb_batch(&mut batch);
+        for (a, b) in batch.iter().zip(colors.iter()) {
+            for i in 0..4 {
+                assert!((a[i] - b[i]).abs() < 0.00001);
+            }
+        }
+    }
+
+    #[test]
+    fn test_color_spaces() {
+        let srgb = Srgba::new(0.5, 0.02, 1.0, 0.5);
+        let linear = srgb.to_linear();
+        assert_eq!(linear.0, gamma_srgb_to_linear(srgb.0));
+        let color: Color = linear.into();
+        for (a, b) in color.iter().zip(srgb.0.iter()) {
+            assert!((a - b).abs() < 0.00001);
+        }
+        // Colors outside sRGB gamut survive the round trip.
+        let wide = LinearRgba::new(-0.25, 1.5, 0.5, 1.0);
+        assert!(!wide.is_in_gamut());
+        let back = wide.to_srgb().to_linear();
+        for (a, b) in back.0.iter().zip(wide.0.iter()) {
+            assert!((a - b).abs() < 0.00001);
+        }
+        assert_eq!(wide.clamp().0, [0.0, 1.0, 0.5, 1.0]);
+    }
+
+    #[test]
+    fn test_hsv_hsl_oklab() {
+        let close =
+            |a: Color, b: Color| a.iter().zip(b.iter()).all(|(a, b)| (a - b).abs() < 0.0001);
+        let orange = Srgba::new(1.0, 0.5, 0.0, 1.0);
+        assert!(close(orange.to_hsv().0, [30.0, 1.0, 1.0, 1.0]));
+        assert!(close(orange.to_hsl().0, [30.0, 1.0, 0.5, 1.0]));
+        for &c in &[
+            [1.0, 0.5, 0.0, 1.0],
+            [0.2, 0.4, 0.9, 0.5],
+            [0.3, 0.3, 0.3, 1.0],
+        ] {
+            assert!(close(Srgba(c).to_hsv().to_srgb().0, c));
+            assert!(close(Srgba(c).to_hsl().to_srgb().0, c));
+            let linear = Srgba(c).to_linear();
+            assert!(close(linear.to_oklab().to_linear().0, linear.0));
+        }
+        assert!(close(
+            Srgba(WHITE).to_linear().to_oklab().0,
+            [1.0, 0.0, 0.0, 1.0]
+        ));
+
+        // Hue goes the short way from red to magenta.
+        let c = lerp(RED, MAGENTA, 0.5, LerpSpace::Hsv);
+        assert!(close(Srgba(c).to_hsv().0, [330.0, 1.0, 1.0, 1.0]));
+        assert_eq!(lerp(BLACK, WHITE, 0.5, LerpSpace::Srgb), grey(0.5));
+        assert!(lerp(BLACK, WHITE, 0.5, LerpSpace::Linear)[0] > 0.5);
+    }
+
+    #[test]
+    fn test_from_hex_and_named() {
+        assert_eq!(from_hex("#ff0000"), Ok(RED));
+        assert_eq!(from_hex("00f"), Ok(BLUE));
+        assert_eq!(from_hex("#ffffff00"), Ok([1.0, 1.0, 1.0, 0.0]));
+        assert_eq!(from_hex("#f0f8"), Ok([1.0, 0.0, 1.0, 8.0 * 17.0 / 255.0]));
+        assert!(from_hex("#ff00").is_ok());
+        assert!(from_hex("#ff000").is_err());
+        assert!(from_hex("#gg0000").is_err());
+        assert_eq!(named("Cyan"), Some(CYAN));
+        assert_eq!(named("transparent"), Some(TRANSPARENT));
+        assert_eq!(named("rebeccapurple"), Some(hex("663399")));
+        assert_eq!(named("nope"), None);
+    }
+
+    #[test]
+    fn test_palette() {
+        let palette = Palette::new(vec![BLACK, WHITE, RED]);
+        assert_eq!(palette.get(2), Some(RED));
+        assert_eq!(palette.sample(-1.0), BLACK);
+        assert_eq!(palette.sample(0.25), grey(0.5));
+        assert_eq!(palette.sample(0.5), WHITE);
+        assert_eq!(palette.sample(2.0), RED);
+        assert_eq!(Palette::new(vec![]).sample(0.5), TRANSPARENT);
+        assert_eq!(
+            Palette::from_hex(&["000", "#ffffff"]).unwrap().get(1),
+            Some(WHITE)
+        );
+        assert!(Palette::from_hex(&["000", "nope"]).is_err());
+        // Brightness increases along the ramp.
+        let viridis = Palette::viridis();
+        let l = |t| Srgba(viridis.sample(t)).to_linear().to_oklab().0[0];
+        assert!(l(0.0) < l(0.5) && l(0.5) < l(1.0));
+    }
+
+    #[test]
+    fn test_color_space_policy() {
+        let color = [0.5, 0.25, 1.0, 0.5];
+        assert_eq!(
+            ColorSpace::Srgb.to_shader(color),
+            gamma_srgb_to_linear(color)
+        );
+        assert_eq!(ColorSpace::Linear.to_shader(color), color);
+        let mut batch = [color];
+        ColorSpace::Passthrough.to_shader_batch(&mut batch);
+        assert_eq!(batch, [color]);
+        assert!(ColorSpace::Linear.is_srgb_framebuffer());
+        assert!(!ColorSpace::Passthrough.is_srgb_framebuffer());
+    }
+}
@@ -1,9 +1,15 @@
 //! Transformation context
 
 use super::{
-    math::{abs_transform, get_scale, identity, Affine2, Scalar, Vec2d},
-    DrawState, Viewport,
+    draw_state::Blend,
+    math::{
+        abs_transform, get_scale, identity, invert, multiply, transform_pos, Affine2, Scalar, Vec2d,
+    },
+    opacity::Opacity,
+    render_texture::RenderToTexture,
+    DrawState, Graphics, Image, Viewport,
 };
+use crate::viewport::ViewportFit;
 
 /// Drawing 2d context.
 #[derive(Copy, Clone, Debug)]
@@ -72,6 +78,17 @@ impl Context {
         }
     }
 
+    /// Uses the virtual coordinates of a viewport fit as view,
+    /// and moves the current transform to it.
+    ///
+    /// The context should have the viewport returned by `ViewportFit::viewport`.
+    #[inline(always)]
+    pub fn virtual_view(mut self, fit: &ViewportFit) -> Self {
+        self.view = fit.abs_transform();
+        self.transform = self.view;
+        self
+    }
+
     /// Moves the current transform to the view coordinate system.
     ///
     /// This is usually [0.0, 0.0] in the upper left corner
@@ -107,6 +124,203 @@ impl Context {
         let scale = get_scale(self.view);
         [2.0 / scale[0], 2.0 / scale[1]]
     }
+
+    /// Computes the current transform relative to the view.
+    ///
+    /// This maps current coordinates to view coordinates.
+    #[inline(always)]
+    pub fn relative_transform(&self) -> Affine2 {
+        multiply(invert(self.view), self.transform)
+    }
+
+    /// Converts a position in screen coordinates to current coordinates.
+    ///
+    /// Screen coordinates are window coordinates in points,
+    /// with origin in the upper left corner of the window,
+    /// as used by mouse cursor events.
+    /// Without viewport, the view coordinates are used as screen coordinates.
+    ///
+    /// Useful for hit-testing the cursor against drawn shapes.
+    #[inline(always)]
+    pub fn screen_to_local(&self, pos: Vec2d) -> Vec2d {
+        transform_pos(invert(self.screen_transform()), pos)
+    }
+
+    /// Converts a position in current coordinates to screen coordinates.
+    ///
+    /// This is the inverse of `screen_to_local`.
+    #[inline(always)]
+    pub fn local_to_screen(&self, pos: Vec2d) -> Vec2d {
+        transform_pos(self.screen_transform(), pos)
+    }
+
+    // Computes the transform from current coordinates to screen coordinates.
+    fn screen_transform(&self) -> Affine2 {
+        let vp = match self.viewport {
+            Some(vp) => vp,
+            None => return self.relative_transform(),
+        };
+        let [x, y, w, h] = vp.rect;
+        let (x, y, w, h) = (
+            Scalar::from(x),
+            Scalar::from(y),
+            Scalar::from(w),
+            Scalar::from(h),
+        );
+        // Convert from normalized coordinates to frame buffer pixels,
+        // then to points with origin in the upper left corner.
+        let sx = vp.window_size[0] / Scalar::from(vp.draw_size[0]);
+        let dh = Scalar::from(vp.draw_size[1]);
+        let sy = vp.window_size[1] / dh;
+        let from_normalized = [
+            [0.5 * w * sx, 0.0, (x + 0.5 * w) * sx],
+            [0.0, -0.5 * h * sy, (dh - y - 0.5 * h) * sy],
+        ];
+        multiply(from_normalized, self.transform)
+    }
+
+    /// Restricts drawing to a rectangle `[x, y, w, h]` in current coordinates.
+    ///
+    /// The rectangle is transformed and its axis-aligned bounding box
+    /// in frame buffer pixels is set as scissor of the draw state.
+    /// When a scissor is already set, the intersection is used,
+    /// so nested clips never draw outside their parent.
+    ///
+    /// Does nothing when the context has no viewport.
+    pub fn clip(mut self, rect: [Scalar; 4]) -> Self {
+        let vp = match self.viewport {
+            Some(vp) => vp,
+            None => return self,
+        };
+        let [x, y, w, h] = rect;
+        let corners = [[x, y], [x + w, y], [x, y + h], [x + w, y + h]];
+        let (mut x0, mut y0) = (Scalar::INFINITY, Scalar::INFINITY);
+        let (mut x1, mut y1) = (Scalar::NEG_INFINITY, Scalar::NEG_INFINITY);
+        for &p in &corners {
+            let [px, py] = transform_pos(self.transform, p);
+            // Convert from normalized coordinates to pixels,
+            // with origin in the upper left corner.
+            let px = Scalar::from(vp.rect[0]) + (px + 1.0) * 0.5 * Scalar::from(vp.rect[2]);
+            let py = (1.0 - py) * 0.5 * Scalar::from(vp.rect[3]);
+            x0 = x0.min(px);
+            y0 = y0.min(py);
+            x1 = x1.max(px);
+            y1 = y1.max(py);
+        }
+        let clamp = |v: Scalar| v.round().max(0.0) as u32;
+        let mut r = [clamp(x0), clamp(y0), clamp(x1), clamp(y1)];
+        if let Some([sx, sy, sw, sh]) = self.draw_state.scissor {
+            r[0] = r[0].max(sx);
+            r[1] = r[1].max(sy);
+            r[2] = r[2].min(sx + sw);
+            r[3] = r[3].min(sy + sh);
+        }
+        self.draw_state.scissor = Some([
+            r[0],
+            r[1],
+            r[2].saturating_sub(r[0]),
+            r[3].saturating_sub(r[1]),
+        ]);
+        self
+    }
+
+    /// Draws with a soft mask, where the alpha of a texture modulates the drawing.
+    ///
+    /// The mask texture is stretched over a rectangle in current coordinates,
+    /// and drawing outside the bounding box of the rectangle is hidden,
+    /// when using the draw state of the context passed to `f`.
+    /// Drawing happens in a render target, which should have the size of the viewport
+    /// in pixels, and is then composited with premultiplied alpha.
+    ///
+    /// Useful for e.g. vignettes and fog of war, where stencil clipping gives hard edges.
+    pub fn masked<G, F>(
+        &self,
+        mask: &G::Texture,
+        rect: [Scalar; 4],
+        target: &mut G::RenderTarget,
+        g: &mut G,
+        f: F,
+    ) where
+        G: RenderToTexture,
+        F: FnOnce(Context, &mut G),
+    {
+        let rel = self.relative_transform();
+        g.draw_to_texture(target, |tc, g| {
+            g.clear_color([0.0; 4]);
+            g.clear_stencil(0);
+            let tc = Context {
+                transform: multiply(tc.view, rel),
+                ..tc
+            };
+            f(tc.clip(rect), g);
+            Image::new()
+                .rect(rect)
+                .draw(mask, &tc.draw_state.blend(Blend::Mask), tc.transform, g);
+        });
+        let [w, h] = self.get_view_size();
+        Image::new().rect([0.0, 0.0, w, h]).draw(
+            G::render_target_texture(target),
+            &self.draw_state.blend(Blend::PremultipliedAlpha),
+            self.view,
+            g,
+        );
+    }
+
+    /// Draws a group with an opacity, by multiplying the alpha of all colors.
+    ///
+    /// This works with all back-ends, but overlapping shapes in the group
+    /// show through each other. See `Context::with_alpha_layer`.
+    pub fn with_alpha<G, F, U>(&self, alpha: f32, g: &mut G, f: F) -> U
+    where
+        G: Graphics,
+        F: FnOnce(Context, &mut Opacity<'_, G>) -> U,
+    {
+        f(*self, &mut Opacity::new(g, alpha))
+    }
+
+    /// Draws a group with an opacity, by compositing it from a render target.
+    ///
+    /// Overlapping shapes in the group are blended before applying the opacity,
+    /// such that the group fades as a whole.
+    /// The render target should have the size of the viewport in pixels.
+    pub fn with_alpha_layer<G, F, U>(
+        &self,
+        alpha: f32,
+        target: &mut G::RenderTarget,
+        g: &mut G,
+        f: F,
+    ) -> U
+    where
+        G: RenderToTexture,
+        F: FnOnce(Context, &mut G) -> U,
+    {
+        let rel = self.relative_transform();
+        let res = g.draw_to_texture(target, |tc, g| {
+            g.clear_color([0.0; 4]);
+            g.clear_stencil(0);
+            let tc = Context {
+                transform: multiply(tc.view, rel),
+                ..tc
+            };
+            f(tc, g)
+        });
+        let [w, h] = self.get_view_size();
+        // The render target has premultiplied alpha.
+        Image::new_color([alpha; 4]).rect([0.0, 0.0, w, h]).draw(
+            G::render_target_texture(target),
+            &self.draw_state.blend(Blend::PremultipliedAlpha),
+            self.view,
+            g,
+        );
+        res
+    }
+
+    /// Removes the clip rectangle set by `clip`.
+    #[inline(always)]
+    pub fn unclip(mut self) -> Self {
+        self.draw_state.scissor = None;
+        self
+    }
 }
 
 impl Default for Context {
@@ -139,6 +353,97 @@ mod test {
         assert!((transform[0][1] + 1.0).abs() < 0.00001);
     }
 
+    #[test]
+    fn test_clip() {
+        use super::Viewport;
+
+        let c = Context::new_viewport(Viewport {
+            rect: [0, 0, 200, 100],
+            draw_size: [200, 100],
+            window_size: [200.0, 100.0],
+        });
+        let d = c.clip([10.0, 20.0, 50.0, 30.0]);
+        assert_eq!(d.draw_state.scissor, Some([10, 20, 50, 30]));
+        let d = d.clip([40.0, 0.0, 100.0, 100.0]);
+        assert_eq!(d.draw_state.scissor, Some([40, 20, 20, 30]));
+        assert_eq!(d.unclip().draw_state.scissor, None);
+    }
+
+    #[test]
+    fn test_screen_to_local() {
+        use super::Viewport;
+
+        // A viewport in the right half of a high DPI window.
+        let c = Context::new_viewport(Viewport {
+            rect: [200, 0, 200, 200],
+            draw_size: [400, 200],
+            window_size: [200.0, 100.0],
+        })
+        .trans(10.0, 20.0)
+        .zoom(2.0);
+        let p = c.local_to_screen([5.0, 5.0]);
+        assert!((p[0] - 120.0).abs() < 1e-9 && (p[1] - 30.0).abs() < 1e-9);
+        let p = c.screen_to_local([120.0, 30.0]);
+        assert!((p[0] - 5.0).abs() < 1e-9 && (p[1] - 5.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_masked() {
+        use crate::{
+            graphics::{Rectangle, RenderToTexture},
+            software_graphics::{SoftwareGraphics, Texture},
+        };
+
+        // Opaque on the left, transparent on the right.
+        let mask = Texture::new(image::RgbaImage::from_fn(2, 1, |x, _| {
+            image::Rgba([255, 255, 255, if x == 0 { 255 } else { 0 }])
+        }));
+        let mut g = SoftwareGraphics::new(8, 8);
+        let mut target = g.create_render_target(8, 8).unwrap();
+        g.draw(|c, g| {
+            c.masked(&mask, [0.0, 0.0, 8.0, 4.0], &mut target, g, |c, g| {
+                Rectangle::new([1.0; 4]).draw([0.0, 0.0, 8.0, 8.0], &c.draw_state, c.transform, g)
+            })
+        });
+        assert_eq!(g.get_pixel(1, 1), [255; 4]);
+        assert_eq!(g.get_pixel(6, 1), [0; 4]);
+        // Outside the mask rectangle.
+        assert_eq!(g.get_pixel(1, 6), [0; 4]);
+    }
+
+    #[test]
+    fn test_with_alpha() {
+        use crate::{
+            graphics::{rectangle, RenderToTexture},
+            software_graphics::SoftwareGraphics,
+        };
+
+        // Two overlapping rectangles at half opacity.
+        let draw = |c: Context, g: &mut SoftwareGraphics, layer: bool| {
+            let mut target = g.create_render_target(4, 1).unwrap();
+            let group = |c: Context, g: &mut SoftwareGraphics| {
+                rectangle([1.0; 4], [0.0, 0.0, 3.0, 1.0], c.transform, g);
+                rectangle([1.0; 4], [1.0, 0.0, 3.0, 1.0], c.transform, g);
+            };
+            if layer {
+                c.with_alpha_layer(0.5, &mut target, g, group);
+            } else {
+                c.with_alpha(0.5, g, |c, g| {
+                    rectangle([1.0; 4], [0.0, 0.0, 3.0, 1.0], c.transform, g);
+                    rectangle([1.0; 4], [1.0, 0.0, 3.0, 1.0], c.transform, g);
+                });
+            }
+        };
+        let mut g = SoftwareGraphics::new(4, 1);
+        g.draw(|c, g| draw(c, g, false));
+        // Overlapping shapes show through each other.
+        assert!(g.get_pixel(1, 0)[0] > g.get_pixel(0, 0)[0]);
+        let mut g = SoftwareGraphics::new(4, 1);
+        g.draw(|c, g| draw(c, g, true));
+        assert_eq!(g.get_pixel(1, 0), g.get_pixel(0, 0));
+        assert_ne!(g.get_pixel(0, 0), [0; 4]);
+    }
+
     #[test]
     fn test_scale() {
         let c = Context::new();
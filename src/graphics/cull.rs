@@ -0,0 +1,256 @@
+//! Culling of shapes outside the view
+//!
+//! Scenes such as tile maps often draw far more than is visible.
+//! A `Culler` tells whether a bounding box is visible,
+//! such that invisible shapes can be skipped before computing their triangles.
+//!
+//! ```ignore
+//! let c = camera.context(&c);
+//! let culler = Culler::new(&c);
+//! for (tile, rect) in &tiles {
+//!     if culler.is_rect_visible(*rect, c.transform) {
+//!         image.rect(*rect).draw(&texture, &c.draw_state, c.transform, g);
+//!     }
+//! }
+//! ```
+//!
+//! Bounding boxes are in normalized coordinates,
+//! which is what `Bounded::bounds` returns with a context transform.
+//! For shapes drawn without checking, the `CulledBatch` wrapper
+//! skips triangles that fall outside the view.
+
+use super::{
+    capabilities::Capabilities,
+    math::{overlap_rectangle, transform_rectangle, try_invert, Affine2},
+    types::{Color, Rectangle},
+    Context, DrawState, Graphics, Viewport,
+};
+
+/// Tells whether bounding boxes are inside the visible area.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Culler {
+    /// The visible area in normalized coordinates.
+    pub rect: Rectangle,
+}
+
+impl Default for Culler {
+    fn default() -> Self {
+        Culler {
+            rect: [-1.0, -1.0, 2.0, 2.0],
+        }
+    }
+}
+
+impl Culler {
+    /// Creates a new culler for the visible area of a context.
+    ///
+    /// This is the whole viewport, limited to the scissor rectangle if any.
+    pub fn new(c: &Context) -> Culler {
+        let culler = Culler::default();
+        match (c.viewport, c.draw_state.scissor) {
+            (Some(viewport), Some(scissor)) => culler.scissor(&viewport, scissor),
+            _ => culler,
+        }
+    }
+
+    /// Limits the visible area to a rectangle in frame buffer pixels,
+    /// like the scissor of `DrawState`.
+    pub fn scissor(mut self, viewport: &Viewport, rect: [u32; 4]) -> Self {
+        let (vx, vw, vh) = (
+            f64::from(viewport.rect[0]),
+            f64::from(viewport.rect[2]),
+            f64::from(viewport.rect[3]),
+        );
+        let [x, y, w, h] = rect.map(f64::from);
+        // Convert from pixels with origin in the upper left corner.
+        let x0 = 2.0 * (x - vx) / vw - 1.0;
+        let y0 = 1.0 - 2.0 * (y + h) / vh;
+        let r = [x0, y0, 2.0 * w / vw, 2.0 * h / vh];
+        self.rect = overlap_rectangle(self.rect, r).unwrap_or([r[0], r[1], 0.0, 0.0]);
+        self
+    }
+
+    /// Grows the visible area by a distance in normalized coordinates,
+    /// e.g. to keep shapes alive that are about to come into view.
+    pub fn margin(mut self, value: f64) -> Self {
+        self.rect = [
+            self.rect[0] - value,
+            self.rect[1] - value,
+            self.rect[2] + 2.0 * value,
+            self.rect[3] + 2.0 * value,
+        ];
+        self
+    }
+
+    /// Returns `true` if a bounding box in normalized coordinates is visible.
+    pub fn is_visible(&self, bounds: Rectangle) -> bool {
+        let [x, y, w, h] = self.rect;
+        bounds[0] <= x + w
+            && bounds[1] <= y + h
+            && bounds[0] + bounds[2] >= x
+            && bounds[1] + bounds[3] >= y
+    }
+
+    /// Returns `true` if a rectangle is visible after a transform.
+    pub fn is_rect_visible(&self, rect: Rectangle, transform: Affine2) -> bool {
+        self.is_visible(transform_rectangle(transform, rect))
+    }
+
+    /// Computes the bounding box of the visible area in the coordinates of a transform.
+    ///
+    /// This gives e.g. the range of visible tiles in a tile map.
+    /// Returns `None` when the transform can not be inverted.
+    pub fn local_rect(&self, transform: Affine2) -> Option<Rectangle> {
+        Some(transform_rectangle(try_invert(transform)?, self.rect))
+    }
+}
+
+/// Wraps a back-end, skipping triangles outside the visible area.
+///
+/// Vertices are checked in the chunks that shapes pass to the back-end,
+/// which is usually one chunk per shape.
+/// Chunks with a visible bounding box are drawn completely.
+pub struct CulledBatch<'a, G> {
+    /// The wrapped back-end.
+    pub g: &'a mut G,
+    /// Tells whether chunks are visible.
+    pub culler: Culler,
+    /// The number of chunks skipped so far.
+    pub culled: usize,
+}
+
+impl<'a, G> CulledBatch<'a, G> {
+    /// Creates a new wrapper.
+    pub fn new(g: &'a mut G, culler: Culler) -> CulledBatch<'a, G> {
+        CulledBatch {
+            g,
+            culler,
+            culled: 0,
+        }
+    }
+}
+
+// Returns `true` if the bounding box of vertices is visible.
+fn visible(culler: &Culler, vertices: &[[f32; 2]], culled: &mut usize) -> bool {
+    let (mut min, mut max) = ([f32::INFINITY; 2], [f32::NEG_INFINITY; 2]);
+    for v in vertices {
+        min = [min[0].min(v[0]), min[1].min(v[1])];
+        max = [max[0].max(v[0]), max[1].max(v[1])];
+    }
+    let [x0, y0] = min.map(f64::from);
+    let [x1, y1] = max.map(f64::from);
+    let res = !vertices.is_empty() && culler.is_visible([x0, y0, x1 - x0, y1 - y0]);
+    if !res {
+        *culled += 1;
+    }
+    res
+}
+
+impl<G: Graphics> Graphics for CulledBatch<'_, G> {
+    type Texture = G::Texture;
+
+    fn clear_color(&mut self, color: Color) {
+        self.g.clear_color(color);
+    }
+
+    fn clear_stencil(&mut self, value: u8) {
+        self.g.clear_stencil(value);
+    }
+
+    fn tri_list<F>(&mut self, draw_state: &DrawState, color: &[f32; 4], mut f: F)
+    where
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]])),
+    {
+        let (culler, culled) = (&self.culler, &mut self.culled);
+        self.g.tri_list(draw_state, color, |g| {
+            f(&mut |vertices| {
+                if visible(culler, vertices, culled) {
+                    g(vertices)
+                }
+            })
+        });
+    }
+
+    fn tri_list_c<F>(&mut self, draw_state: &DrawState, mut f: F)
+    where
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]], &[[f32; 4]])),
+    {
+        let (culler, culled) = (&self.culler, &mut self.culled);
+        self.g.tri_list_c(draw_state, |g| {
+            f(&mut |vertices, colors| {
+                if visible(culler, vertices, culled) {
+                    g(vertices, colors)
+                }
+            })
+        });
+    }
+
+    fn tri_list_uv<F>(
+        &mut self,
+        draw_state: &DrawState,
+        color: &[f32; 4],
+        texture: &Self::Texture,
+        mut f: F,
+    ) where
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]], &[[f32; 2]])),
+    {
+        let (culler, culled) = (&self.culler, &mut self.culled);
+        self.g.tri_list_uv(draw_state, color, texture, |g| {
+            f(&mut |vertices, uvs| {
+                if visible(culler, vertices, culled) {
+                    g(vertices, uvs)
+                }
+            })
+        });
+    }
+
+    fn tri_list_uv_c<F>(&mut self, draw_state: &DrawState, texture: &Self::Texture, mut f: F)
+    where
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]], &[[f32; 2]], &[[f32; 4]])),
+    {
+        let (culler, culled) = (&self.culler, &mut self.culled);
+        self.g.tri_list_uv_c(draw_state, texture, |g| {
+            f(&mut |vertices, uvs, colors| {
+                if visible(culler, vertices, culled) {
+                    g(vertices, uvs, colors)
+                }
+            })
+        });
+    }
+
+    fn current_viewport(&self) -> Option<Viewport> {
+        self.g.current_viewport()
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        self.g.capabilities()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{graphics::rectangle, software_graphics::SoftwareGraphics};
+
+    #[test]
+    fn test_culling() {
+        let c = Context::new_abs(100.0, 100.0);
+        let culler = Culler::new(&c);
+        assert!(culler.is_rect_visible([90.0, 90.0, 20.0, 20.0], c.transform));
+        assert!(!culler.is_rect_visible([110.0, 0.0, 20.0, 20.0], c.transform));
+        let [x, y, w, h] = culler.local_rect(c.transform).unwrap();
+        assert!(x.abs() < 1e-9 && y.abs() < 1e-9);
+        assert!((w - 100.0).abs() < 1e-9 && (h - 100.0).abs() < 1e-9);
+
+        let mut g = SoftwareGraphics::new(8, 8);
+        g.draw(|c, g| {
+            let culler = Culler::new(&c.clip([0.0, 0.0, 4.0, 8.0]));
+            let g = &mut CulledBatch::new(g, culler);
+            rectangle([1.0; 4], [1.0, 1.0, 2.0, 2.0], c.transform, g);
+            rectangle([1.0; 4], [5.0, 5.0, 2.0, 2.0], c.transform, g);
+            assert_eq!(g.culled, 1);
+        });
+        assert_eq!(g.get_pixel(1, 1), [255; 4]);
+        assert_eq!(g.get_pixel(5, 5), [0; 4]);
+    }
+}
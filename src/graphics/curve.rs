@@ -0,0 +1,556 @@
+//! Methods for computing with curves.
+
+use vecmath::traits::Float;
+
+use super::{
+    math::{add, mul_scalar, square_len, sub, Scalar, Vec2d},
+    types::{CubicBezier, Polygon, QuadraticBezier, Resolution},
+};
+
+/// Computes a point on a quadratic Bézier curve.
+///
+/// The parameter `t` goes from 0 at the start to 1 at the end.
+#[allow(clippy::just_underscores_and_digits)] // Naming convention.
+#[inline(always)]
+pub fn quadratic_bezier<T>(curve: QuadraticBezier<T>, t: T) -> Vec2d<T>
+where
+    T: Float,
+{
+    use vecmath::traits::{FromPrimitive, One};
+
+    let _1: T = One::one();
+    let _2: T = FromPrimitive::from_f64(2.0);
+    let s = _1 - t;
+    add(
+        add(
+            mul_scalar(curve[0], s * s),
+            mul_scalar(curve[1], _2 * s * t),
+        ),
+        mul_scalar(curve[2], t * t),
+    )
+}
+
+/// Computes a point on a cubic Bézier curve.
+///
+/// The parameter `t` goes from 0 at the start to 1 at the end.
+#[allow(clippy::just_underscores_and_digits)] // Naming convention.
+#[inline(always)]
+pub fn cubic_bezier<T>(curve: CubicBezier<T>, t: T) -> Vec2d<T>
+where
+    T: Float,
+{
+    use vecmath::traits::{FromPrimitive, One};
+
+    let _1: T = One::one();
+    let _3: T = FromPrimitive::from_f64(3.0);
+    let s = _1 - t;
+    add(
+        add(
+            mul_scalar(curve[0], s * s * s),
+            mul_scalar(curve[1], _3 * s * s * t),
+        ),
+        add(
+            mul_scalar(curve[2], _3 * s * t * t),
+            mul_scalar(curve[3], t * t * t),
+        ),
+    )
+}
+
+/// Computes a point on a Coons patch bounded by cubic Bézier curves.
+///
+/// The edges go around the patch boundary, each starting where the previous ends:
+/// `edges[0]` is the bottom edge at `v = 0`, from `u = 0` to `u = 1`,
+/// `edges[1]` is the edge at `u = 1`, `edges[2]` the edge at `v = 1` going back,
+/// and `edges[3]` the edge at `u = 0` going back to the start.
+#[allow(clippy::just_underscores_and_digits)] // Naming convention.
+pub fn coons_patch<T>(edges: [CubicBezier<T>; 4], u: T, v: T) -> Vec2d<T>
+where
+    T: Float,
+{
+    use vecmath::traits::One;
+
+    let _1: T = One::one();
+    let (su, sv) = (_1 - u, _1 - v);
+    // Ruled surfaces between opposite edges.
+    let c0 = cubic_bezier(edges[0], u);
+    let c1 = cubic_bezier(edges[2], su);
+    let d0 = cubic_bezier(edges[3], sv);
+    let d1 = cubic_bezier(edges[1], v);
+    let ruled = add(
+        add(mul_scalar(c0, sv), mul_scalar(c1, v)),
+        add(mul_scalar(d0, su), mul_scalar(d1, u)),
+    );
+    // Subtract bilinear interpolation of corners, which is counted twice.
+    let bilinear = add(
+        add(
+            mul_scalar(edges[0][0], su * sv),
+            mul_scalar(edges[1][0], u * sv),
+        ),
+        add(
+            mul_scalar(edges[2][0], u * v),
+            mul_scalar(edges[3][0], su * v),
+        ),
+    );
+    sub(ruled, bilinear)
+}
+
+/// Calls `f` with points along a quadratic Bézier curve,
+/// such that the line segments between them deviate at most `tolerance` from the curve.
+///
+/// The curve is subdivided where it bends most, so straight parts use few points.
+/// Includes the start and end of the curve.
+pub fn flatten_quadratic_bezier<T, F>(curve: QuadraticBezier<T>, tolerance: T, mut f: F)
+where
+    T: Float,
+    F: FnMut(Vec2d<T>),
+{
+    f(curve[0]);
+    flatten_quadratic_bezier_rec(curve, tolerance, 0, &mut f);
+}
+
+/// Calls `f` with points along a cubic Bézier curve,
+/// such that the line segments between them deviate at most `tolerance` from the curve.
+///
+/// The curve is subdivided where it bends most, so straight parts use few points.
+/// Includes the start and end of the curve.
+pub fn flatten_cubic_bezier<T, F>(curve: CubicBezier<T>, tolerance: T, mut f: F)
+where
+    T: Float,
+    F: FnMut(Vec2d<T>),
+{
+    f(curve[0]);
+    flatten_cubic_bezier_rec(curve, tolerance, 0, &mut f);
+}
+
+/// Computes a point on a segment of a uniform Catmull-Rom spline.
+///
+/// The segment goes through `points[1]` at `t = 0` and `points[2]` at `t = 1`,
+/// while the outer points control the tangents.
+pub fn catmull_rom<T>(points: [Vec2d<T>; 4], t: T) -> Vec2d<T>
+where
+    T: Float,
+{
+    cubic_bezier(catmull_rom_to_bezier(points), t)
+}
+
+/// Converts a segment of a uniform Catmull-Rom spline to a cubic Bézier curve.
+#[allow(clippy::just_underscores_and_digits)] // Naming convention.
+pub fn catmull_rom_to_bezier<T>(points: [Vec2d<T>; 4]) -> CubicBezier<T>
+where
+    T: Float,
+{
+    use vecmath::traits::FromPrimitive;
+
+    let _1_6: T = FromPrimitive::from_f64(1.0 / 6.0);
+    let [p0, p1, p2, p3] = points;
+    [
+        p1,
+        add(p1, mul_scalar(sub(p2, p0), _1_6)),
+        sub(p2, mul_scalar(sub(p3, p1), _1_6)),
+        p2,
+    ]
+}
+
+/// Computes a point on a segment of a uniform cubic B-spline.
+///
+/// The curve does not go through the control points, but is smoother than
+/// a Catmull-Rom spline, with continuous curvature between segments.
+pub fn b_spline<T>(points: [Vec2d<T>; 4], t: T) -> Vec2d<T>
+where
+    T: Float,
+{
+    cubic_bezier(b_spline_to_bezier(points), t)
+}
+
+/// Converts a segment of a uniform cubic B-spline to a cubic Bézier curve.
+#[allow(clippy::just_underscores_and_digits)] // Naming convention.
+pub fn b_spline_to_bezier<T>(points: [Vec2d<T>; 4]) -> CubicBezier<T>
+where
+    T: Float,
+{
+    use vecmath::traits::FromPrimitive;
+
+    let _1_6: T = FromPrimitive::from_f64(1.0 / 6.0);
+    let _1_3: T = FromPrimitive::from_f64(1.0 / 3.0);
+    let _2: T = FromPrimitive::from_f64(2.0);
+    let _4: T = FromPrimitive::from_f64(4.0);
+    let [p0, p1, p2, p3] = points;
+    [
+        mul_scalar(add(add(p0, mul_scalar(p1, _4)), p2), _1_6),
+        mul_scalar(add(mul_scalar(p1, _2), p2), _1_3),
+        mul_scalar(add(p1, mul_scalar(p2, _2)), _1_3),
+        mul_scalar(add(add(p1, mul_scalar(p2, _4)), p3), _1_6),
+    ]
+}
+
+/// Computes a point on a Catmull-Rom spline through all points.
+///
+/// The parameter `t` goes from 0 at the first point to 1 at the last point,
+/// with equal parameter range for each segment, e.g. for camera paths.
+/// The end points are repeated to compute the tangents at the ends.
+#[allow(clippy::just_underscores_and_digits)] // Naming convention.
+pub fn catmull_rom_path<T>(points: Polygon<'_, T>, t: T) -> Vec2d<T>
+where
+    T: Float,
+{
+    use vecmath::traits::{FromPrimitive, One, Zero};
+
+    let n = points.len();
+    if n < 2 {
+        return points
+            .first()
+            .copied()
+            .unwrap_or([Zero::zero(), Zero::zero()]);
+    }
+    let _0: T = Zero::zero();
+    let _1: T = One::one();
+    let segments: T = FromPrimitive::from_f64((n - 1) as f64);
+    let t = if t < _0 {
+        _0
+    } else if t > _1 {
+        _1
+    } else {
+        t
+    } * segments;
+    // Find the segment, where the last one includes its end.
+    let (mut i, mut start) = (0, _0);
+    while i + 2 < n && t >= start + _1 {
+        i += 1;
+        start += _1;
+    }
+    catmull_rom(catmull_rom_segment(points, i), t - start)
+}
+
+/// Calls `f` with points along a Catmull-Rom spline through all points,
+/// such that the line segments between them deviate at most `tolerance` from the curve.
+///
+/// Includes the first and last point.
+pub fn flatten_catmull_rom<T, F>(points: Polygon<'_, T>, tolerance: T, mut f: F)
+where
+    T: Float,
+    F: FnMut(Vec2d<T>),
+{
+    if let Some(&first) = points.first() {
+        f(first);
+    }
+    for i in 0..points.len().saturating_sub(1) {
+        let curve = catmull_rom_to_bezier(catmull_rom_segment(points, i));
+        flatten_cubic_bezier_rec(curve, tolerance, 0, &mut f);
+    }
+}
+
+/// Calls `f` with points along a uniform cubic B-spline with control points,
+/// such that the line segments between them deviate at most `tolerance` from the curve.
+///
+/// The curve has one segment for every four consecutive control points.
+pub fn flatten_b_spline<T, F>(points: Polygon<'_, T>, tolerance: T, mut f: F)
+where
+    T: Float,
+    F: FnMut(Vec2d<T>),
+{
+    for (i, w) in points.windows(4).enumerate() {
+        let curve = b_spline_to_bezier([w[0], w[1], w[2], w[3]]);
+        if i == 0 {
+            f(curve[0]);
+        }
+        flatten_cubic_bezier_rec(curve, tolerance, 0, &mut f);
+    }
+}
+
+// Gets the control points of a Catmull-Rom segment from point `i` to `i + 1`,
+// repeating the end points.
+fn catmull_rom_segment<T: Copy>(points: Polygon<'_, T>, i: usize) -> [Vec2d<T>; 4] {
+    let n = points.len();
+    [
+        points[i.saturating_sub(1)],
+        points[i],
+        points[i + 1],
+        points[(i + 2).min(n - 1)],
+    ]
+}
+
+// Limits subdivision for degenerate input, such as infinite coordinates.
+const MAX_FLATTEN_DEPTH: u32 = 16;
+
+// The deviation of a chord from the curve is bounded by `1/8` of the maximum second derivative,
+// which is `2 * |p0 - 2 p1 + p2|` for quadratic curves.
+#[allow(clippy::just_underscores_and_digits)] // Naming convention.
+fn flatten_quadratic_bezier_rec<T, F>(
+    curve: QuadraticBezier<T>,
+    tolerance: T,
+    depth: u32,
+    f: &mut F,
+) where
+    T: Float,
+    F: FnMut(Vec2d<T>),
+{
+    use vecmath::traits::FromPrimitive;
+
+    let _0_25: T = FromPrimitive::from_f64(0.25);
+    let _0_5: T = FromPrimitive::from_f64(0.5);
+    let _2: T = FromPrimitive::from_f64(2.0);
+    let [a, b, c] = curve;
+    let d = add(sub(a, mul_scalar(b, _2)), c);
+    if depth >= MAX_FLATTEN_DEPTH || square_len(d).sqrt() * _0_25 <= tolerance {
+        f(c);
+        return;
+    }
+    // Split at the middle with de Casteljau's algorithm.
+    let mid = |p: Vec2d<T>, q: Vec2d<T>| mul_scalar(add(p, q), _0_5);
+    let (ab, bc) = (mid(a, b), mid(b, c));
+    let m = mid(ab, bc);
+    flatten_quadratic_bezier_rec([a, ab, m], tolerance, depth + 1, f);
+    flatten_quadratic_bezier_rec([m, bc, c], tolerance, depth + 1, f);
+}
+
+// The deviation of a chord from the curve is bounded by `1/8` of the maximum second derivative,
+// which is `6 * max(|p0 - 2 p1 + p2|, |p1 - 2 p2 + p3|)` for cubic curves.
+#[allow(clippy::just_underscores_and_digits)] // Naming convention.
+fn flatten_cubic_bezier_rec<T, F>(curve: CubicBezier<T>, tolerance: T, depth: u32, f: &mut F)
+where
+    T: Float,
+    F: FnMut(Vec2d<T>),
+{
+    use vecmath::traits::FromPrimitive;
+
+    let _0_5: T = FromPrimitive::from_f64(0.5);
+    let _0_75: T = FromPrimitive::from_f64(0.75);
+    let _2: T = FromPrimitive::from_f64(2.0);
+    let [a, b, c, d] = curve;
+    let d1 = square_len(add(sub(a, mul_scalar(b, _2)), c));
+    let d2 = square_len(add(sub(b, mul_scalar(c, _2)), d));
+    let max = if d1 > d2 { d1 } else { d2 };
+    if depth >= MAX_FLATTEN_DEPTH || max.sqrt() * _0_75 <= tolerance {
+        f(d);
+        return;
+    }
+    // Split at the middle with de Casteljau's algorithm.
+    let mid = |p: Vec2d<T>, q: Vec2d<T>| mul_scalar(add(p, q), _0_5);
+    let (ab, bc, cd) = (mid(a, b), mid(b, c), mid(c, d));
+    let (abc, bcd) = (mid(ab, bc), mid(bc, cd));
+    let m = mid(abc, bcd);
+    flatten_cubic_bezier_rec([a, ab, abc, m], tolerance, depth + 1, f);
+    flatten_cubic_bezier_rec([m, bcd, cd, d], tolerance, depth + 1, f);
+}
+
+/// Maps distances along a curve to curve parameters.
+///
+/// The parameter of a curve does not move at constant speed,
+/// e.g. a Bézier curve moves slower where control points cluster.
+/// This table samples the curve and sums up the lengths of the segments,
+/// such that points can be found by the distance traveled along the curve.
+/// Use a higher resolution for better precision.
+#[derive(Clone, Debug)]
+pub struct ArcLength<T = Scalar> {
+    /// Accumulated length at evenly spaced parameters from 0 to 1.
+    lengths: Vec<T>,
+}
+
+impl<T: Float> ArcLength<T> {
+    /// Creates a new arc length table by sampling a curve.
+    ///
+    /// The curve is sampled at `resolution + 1` evenly spaced parameters from 0 to 1.
+    pub fn new<F>(resolution: Resolution, mut curve: F) -> ArcLength<T>
+    where
+        F: FnMut(T) -> Vec2d<T>,
+    {
+        use vecmath::traits::{FromPrimitive, Zero};
+
+        let n = resolution.max(1);
+        let mut lengths = Vec::with_capacity(n as usize + 1);
+        let mut sum: T = Zero::zero();
+        let mut last = curve(Zero::zero());
+        lengths.push(sum);
+        for i in 1..=n {
+            let t = <T as FromPrimitive>::from_u32(i) / FromPrimitive::from_u32(n);
+            let p = curve(t);
+            sum += square_len(sub(p, last)).sqrt();
+            lengths.push(sum);
+            last = p;
+        }
+        ArcLength { lengths }
+    }
+
+    /// Creates a new arc length table for a quadratic Bézier curve.
+    pub fn quadratic_bezier(curve: QuadraticBezier<T>, resolution: Resolution) -> ArcLength<T> {
+        ArcLength::new(resolution, |t| quadratic_bezier(curve, t))
+    }
+
+    /// Creates a new arc length table for a cubic Bézier curve.
+    pub fn cubic_bezier(curve: CubicBezier<T>, resolution: Resolution) -> ArcLength<T> {
+        ArcLength::new(resolution, |t| cubic_bezier(curve, t))
+    }
+
+    /// Returns the total length of the curve.
+    pub fn length(&self) -> T {
+        self.lengths[self.lengths.len() - 1]
+    }
+
+    /// Returns the curve parameter at a distance along the curve.
+    ///
+    /// The distance is clamped to the length of the curve.
+    pub fn parameter(&self, distance: T) -> T {
+        use vecmath::traits::{FromPrimitive, One, Zero};
+
+        let n = self.lengths.len() - 1;
+        let to_t = |i: usize| -> T {
+            <T as FromPrimitive>::from_f64(i as f64) / FromPrimitive::from_f64(n as f64)
+        };
+        if distance <= Zero::zero() {
+            return Zero::zero();
+        }
+        if distance >= self.length() {
+            return One::one();
+        }
+        // Find the segment containing the distance and interpolate within it.
+        let i = self.lengths.partition_point(|&len| len < distance).max(1);
+        let (a, b) = (self.lengths[i - 1], self.lengths[i]);
+        let f = if b > a {
+            (distance - a) / (b - a)
+        } else {
+            Zero::zero()
+        };
+        to_t(i - 1) + f * (to_t(i) - to_t(i - 1))
+    }
+
+    /// Returns `n` curve parameters evenly spaced by distance along the curve.
+    ///
+    /// Includes the start and end of the curve when `n` is 2 or more.
+    pub fn even_parameters(&self, n: usize) -> Vec<T> {
+        use vecmath::traits::{FromPrimitive, Zero};
+
+        match n {
+            0 => vec![],
+            1 => vec![Zero::zero()],
+            n => {
+                let step = self.length() / FromPrimitive::from_f64((n - 1) as f64);
+                (0..n)
+                    .map(|i| self.parameter(<T as FromPrimitive>::from_f64(i as f64) * step))
+                    .collect()
+            }
+        }
+    }
+
+    /// Returns curve parameters spaced by a distance along the curve,
+    /// starting at the beginning of the curve.
+    ///
+    /// Returns an empty list if the spacing is not positive.
+    pub fn spaced_parameters(&self, spacing: T) -> Vec<T> {
+        use vecmath::traits::Zero;
+
+        let mut res = vec![];
+        if spacing <= Zero::zero() {
+            return res;
+        }
+        let length = self.length();
+        let mut distance: T = Zero::zero();
+        while distance <= length {
+            res.push(self.parameter(distance));
+            distance += spacing;
+        }
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_coons_patch() {
+        let at = |a: Vec2d, b: Vec2d, t: Scalar| add(a, mul_scalar(sub(b, a), t));
+        let line = |a: Vec2d, b: Vec2d| [a, at(a, b, 1.0 / 3.0), at(a, b, 2.0 / 3.0), b];
+        let (p00, p10, p11, p01) = ([0.0, 0.0], [2.0, 0.0], [2.0, 2.0], [0.0, 2.0]);
+        let edges = [
+            line(p00, p10),
+            line(p10, p11),
+            line(p11, p01),
+            line(p01, p00),
+        ];
+        let p = coons_patch(edges, 0.25, 0.5);
+        assert!((p[0] - 0.5).abs() < 1e-9);
+        assert!((p[1] - 1.0).abs() < 1e-9);
+        assert_eq!(coons_patch(edges, 1.0, 1.0), p11);
+    }
+
+    #[test]
+    fn test_bezier() {
+        let curve = [[0.0, 0.0], [1.0, 2.0], [2.0, 2.0], [3.0, 0.0]];
+        assert_eq!(cubic_bezier(curve, 0.0), [0.0, 0.0]);
+        assert_eq!(cubic_bezier(curve, 1.0), [3.0, 0.0]);
+        assert_eq!(cubic_bezier(curve, 0.5), [1.5, 1.5]);
+        let curve = [[0.0, 0.0], [1.0, 2.0], [2.0, 0.0]];
+        assert_eq!(quadratic_bezier(curve, 0.5), [1.0, 1.0]);
+    }
+
+    #[test]
+    fn test_arc_length() {
+        // A straight line where the control points cluster at the ends.
+        let curve = [[0.0, 0.0], [0.0, 0.0], [3.0, 0.0], [3.0, 0.0]];
+        let arc: ArcLength = ArcLength::cubic_bezier(curve, 256);
+        let eps = 0.001;
+        assert!((arc.length() - 3.0).abs() < eps);
+        assert_eq!(arc.parameter(-1.0), 0.0);
+        assert_eq!(arc.parameter(4.0), 1.0);
+        assert!((arc.parameter(1.5) - 0.5).abs() < eps);
+        for (i, t) in arc.even_parameters(4).into_iter().enumerate() {
+            assert!((cubic_bezier(curve, t)[0] - i as f64).abs() < eps);
+        }
+        assert_eq!(arc.spaced_parameters(1.0).len(), 4);
+    }
+
+    #[test]
+    fn test_flatten_bezier() {
+        let mut points = vec![];
+        flatten_cubic_bezier([[0.0, 0.0], [1.0, 0.0], [2.0, 0.0], [3.0, 0.0]], 0.1, |p| {
+            points.push(p)
+        });
+        assert_eq!(points, vec![[0.0, 0.0], [3.0, 0.0]]);
+
+        let curve = [[0.0, 0.0], [0.0, 4.0], [4.0, 4.0], [4.0, 0.0]];
+        let count = |tolerance: Scalar| {
+            let mut n = 0;
+            flatten_cubic_bezier(curve, tolerance, |_| n += 1);
+            n
+        };
+        assert!(count(0.01) > count(0.1));
+        let mut points = vec![];
+        flatten_cubic_bezier(curve, 0.01, |p| points.push(p));
+        assert_eq!(points[points.len() - 1], [4.0, 0.0]);
+        // Chord midpoints are close to the curve, which peaks at `y = 3`.
+        for w in points.windows(2) {
+            let m = mul_scalar(add(w[0], w[1]), 0.5);
+            assert!(m[1] <= 3.0);
+        }
+        assert!(points.iter().any(|p| (p[1] - 3.0).abs() < 0.01));
+
+        let mut n = 0;
+        flatten_quadratic_bezier([[0.0, 0.0], [1.0, 2.0], [2.0, 0.0]], 0.01, |_| n += 1);
+        assert!(n > 2);
+    }
+
+    #[test]
+    fn test_splines() {
+        let points = [[0.0, 0.0], [1.0, 1.0], [2.0, 0.0], [3.0, 1.0]];
+        assert_eq!(catmull_rom(points, 0.0), [1.0, 1.0]);
+        assert_eq!(catmull_rom(points, 1.0), [2.0, 0.0]);
+        assert_eq!(catmull_rom_path(&points, 0.0), [0.0, 0.0]);
+        assert_eq!(catmull_rom_path(&points, 1.0), [3.0, 1.0]);
+        let p: Vec2d = catmull_rom_path(&points, 0.5);
+        assert!((p[0] - 1.5).abs() < 1e-9 && (p[1] - 0.5).abs() < 1e-9);
+
+        // A B-spline on a line with even spacing moves linearly.
+        let line = [[0.0, 0.0], [1.0, 0.0], [2.0, 0.0], [3.0, 0.0]];
+        assert_eq!(b_spline(line, 0.0), [1.0, 0.0]);
+        assert_eq!(b_spline(line, 1.0), [2.0, 0.0]);
+
+        let mut flat = vec![];
+        flatten_catmull_rom(&points, 0.01, |p| flat.push(p));
+        assert_eq!(flat[0], [0.0, 0.0]);
+        assert_eq!(flat[flat.len() - 1], [3.0, 1.0]);
+        assert!(flat.contains(&[1.0, 1.0]) && flat.len() > 4);
+        let mut n = 0;
+        flatten_b_spline(&line, 0.01, |_| n += 1);
+        assert_eq!(n, 2);
+    }
+}
@@ -0,0 +1,285 @@
+//! Dirty-region tracking for partial redraws
+//!
+//! Applications that change little between frames, such as editors or dashboards,
+//! can report the areas that changed and redraw only those.
+//! A `DamageTracker` collects the changed areas as a union in pixels,
+//! and wraps the back-end such that clearing and drawing is limited to that union.
+//!
+//! ```ignore
+//! // When something changes.
+//! damage.add(&c, old_rect);
+//! damage.add(&c, new_rect);
+//!
+//! // Each frame.
+//! if let Some(g) = &mut damage.begin(g) {
+//!     clear([1.0; 4], g);
+//!     draw_scene(&c, g);
+//! }
+//! ```
+//!
+//! Drawing outside the dirty region is discarded using the scissor,
+//! so the whole scene can be drawn as usual.
+//! The back-end must keep the contents of the frame buffer between frames.
+
+use super::{
+    capabilities::Capabilities, math::Scalar, types::Color, Context, DrawState, Graphics, Viewport,
+};
+
+/// Collects areas that need to be redrawn.
+///
+/// Starts out with everything dirty, such that the first frame is drawn completely.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DamageTracker {
+    damage: Damage,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Damage {
+    None,
+    Region([u32; 4]),
+    All,
+}
+
+impl Default for DamageTracker {
+    fn default() -> Self {
+        DamageTracker::new()
+    }
+}
+
+impl DamageTracker {
+    /// Creates a new tracker, with everything dirty.
+    pub fn new() -> DamageTracker {
+        DamageTracker {
+            damage: Damage::All,
+        }
+    }
+
+    /// Marks a rectangle in the coordinates of a context as dirty.
+    ///
+    /// Uses the transformed bounding box, with a margin of one pixel for antialiasing.
+    /// Marks everything as dirty when the context has no viewport.
+    pub fn add(&mut self, c: &Context, rect: [Scalar; 4]) {
+        let c = Context {
+            draw_state: DrawState {
+                scissor: None,
+                ..c.draw_state
+            },
+            ..*c
+        };
+        match c.clip(rect).draw_state.scissor {
+            Some([x, y, w, h]) => {
+                let x0 = x.saturating_sub(1);
+                let y0 = y.saturating_sub(1);
+                self.add_pixels([x0, y0, x + w + 1 - x0, y + h + 1 - y0]);
+            }
+            None => self.add_all(),
+        }
+    }
+
+    /// Marks a rectangle `[x, y, w, h]` in frame buffer pixels as dirty,
+    /// with `y` measured from the top of the viewport.
+    pub fn add_pixels(&mut self, rect: [u32; 4]) {
+        self.damage = match self.damage {
+            Damage::None => Damage::Region(rect),
+            Damage::Region(r) => Damage::Region(union(r, rect)),
+            Damage::All => Damage::All,
+        };
+    }
+
+    /// Marks everything as dirty, e.g. after resizing the window.
+    pub fn add_all(&mut self) {
+        self.damage = Damage::All;
+    }
+
+    /// Returns `true` if something needs to be redrawn.
+    pub fn is_dirty(&self) -> bool {
+        self.damage != Damage::None
+    }
+
+    /// Returns the dirty rectangle in pixels, or `None` when everything is dirty
+    /// or nothing is dirty, see `is_dirty`.
+    pub fn region(&self) -> Option<[u32; 4]> {
+        match self.damage {
+            Damage::Region(r) => Some(r),
+            _ => None,
+        }
+    }
+
+    /// Starts redrawing the dirty region, resetting the tracker.
+    ///
+    /// Returns `None` when nothing is dirty, so the frame can be skipped.
+    pub fn begin<'a, G>(&mut self, g: &'a mut G) -> Option<Damaged<'a, G>> {
+        let damage = std::mem::replace(&mut self.damage, Damage::None);
+        match damage {
+            Damage::None => None,
+            Damage::Region(r) => Some(Damaged::new(g, Some(r))),
+            Damage::All => Some(Damaged::new(g, None)),
+        }
+    }
+}
+
+// Computes the union of two pixel rectangles.
+fn union(a: [u32; 4], b: [u32; 4]) -> [u32; 4] {
+    let x = a[0].min(b[0]);
+    let y = a[1].min(b[1]);
+    let x1 = (a[0] + a[2]).max(b[0] + b[2]);
+    let y1 = (a[1] + a[3]).max(b[1] + b[3]);
+    [x, y, x1 - x, y1 - y]
+}
+
+/// Wraps a back-end, limiting clearing and drawing to a dirty region.
+///
+/// Clearing fills the dirty region instead of the whole frame buffer,
+/// and draw calls are scissored to the region.
+/// Draw calls with a scissor outside the region are skipped.
+///
+/// Usually created by `DamageTracker::begin`.
+pub struct Damaged<'a, G> {
+    /// The wrapped back-end.
+    pub g: &'a mut G,
+    /// The dirty rectangle in pixels, or `None` to draw everything.
+    pub region: Option<[u32; 4]>,
+}
+
+impl<'a, G> Damaged<'a, G> {
+    /// Creates a new wrapper with a dirty region.
+    pub fn new(g: &'a mut G, region: Option<[u32; 4]>) -> Damaged<'a, G> {
+        Damaged { g, region }
+    }
+
+    // Limits the scissor of a draw state to the dirty region.
+    //
+    // Returns `None` when nothing can be drawn.
+    fn draw_state(&self, draw_state: &DrawState) -> Option<DrawState> {
+        let r = match self.region {
+            Some(r) => r,
+            None => return Some(*draw_state),
+        };
+        let scissor = match draw_state.scissor {
+            Some(s) => {
+                let x = r[0].max(s[0]);
+                let y = r[1].max(s[1]);
+                let x1 = (r[0] + r[2]).min(s[0] + s[2]);
+                let y1 = (r[1] + r[3]).min(s[1] + s[3]);
+                if x1 <= x || y1 <= y {
+                    return None;
+                }
+                [x, y, x1 - x, y1 - y]
+            }
+            None => r,
+        };
+        Some(DrawState {
+            scissor: Some(scissor),
+            ..*draw_state
+        })
+    }
+}
+
+impl<G: Graphics> Graphics for Damaged<'_, G> {
+    type Texture = G::Texture;
+
+    fn clear_color(&mut self, color: Color) {
+        match self.region {
+            Some(r) => {
+                // Replace the colors in the region, ignoring blending.
+                let draw_state = DrawState {
+                    scissor: Some(r),
+                    stencil: None,
+                    blend: None,
+                    color_mask: [true; 4],
+                };
+                self.g.tri_list(&draw_state, &color, |f| {
+                    f(&[
+                        [-1.0, -1.0],
+                        [1.0, -1.0],
+                        [1.0, 1.0],
+                        [-1.0, -1.0],
+                        [1.0, 1.0],
+                        [-1.0, 1.0],
+                    ])
+                });
+            }
+            None => self.g.clear_color(color),
+        }
+    }
+
+    fn clear_stencil(&mut self, value: u8) {
+        self.g.clear_stencil(value);
+    }
+
+    fn tri_list<F>(&mut self, draw_state: &DrawState, color: &[f32; 4], f: F)
+    where
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]])),
+    {
+        if let Some(draw_state) = self.draw_state(draw_state) {
+            self.g.tri_list(&draw_state, color, f);
+        }
+    }
+
+    fn tri_list_c<F>(&mut self, draw_state: &DrawState, f: F)
+    where
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]], &[[f32; 4]])),
+    {
+        if let Some(draw_state) = self.draw_state(draw_state) {
+            self.g.tri_list_c(&draw_state, f);
+        }
+    }
+
+    fn tri_list_uv<F>(
+        &mut self,
+        draw_state: &DrawState,
+        color: &[f32; 4],
+        texture: &Self::Texture,
+        f: F,
+    ) where
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]], &[[f32; 2]])),
+    {
+        if let Some(draw_state) = self.draw_state(draw_state) {
+            self.g.tri_list_uv(&draw_state, color, texture, f);
+        }
+    }
+
+    fn tri_list_uv_c<F>(&mut self, draw_state: &DrawState, texture: &Self::Texture, f: F)
+    where
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]], &[[f32; 2]], &[[f32; 4]])),
+    {
+        if let Some(draw_state) = self.draw_state(draw_state) {
+            self.g.tri_list_uv_c(&draw_state, texture, f);
+        }
+    }
+
+    fn current_viewport(&self) -> Option<Viewport> {
+        self.g.current_viewport()
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        self.g.capabilities()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{graphics::rectangle, software_graphics::SoftwareGraphics};
+
+    #[test]
+    fn test_damage() {
+        let mut damage = DamageTracker::new();
+        let mut g = SoftwareGraphics::new(16, 16);
+        g.draw(|c, g| {
+            let g = &mut damage.begin(g).unwrap();
+            g.clear_color([1.0, 0.0, 0.0, 1.0]);
+            assert!(!damage.is_dirty());
+
+            damage.add(&c, [4.0, 4.0, 2.0, 2.0]);
+            assert_eq!(damage.region(), Some([3, 3, 4, 4]));
+            let g = &mut damage.begin(g.g).unwrap();
+            g.clear_color([0.0, 0.0, 1.0, 1.0]);
+            rectangle([1.0; 4], [0.0, 0.0, 16.0, 16.0], c.transform, g);
+            assert!(damage.begin(g.g).is_none());
+        });
+        assert_eq!(g.get_pixel(0, 0), [255, 0, 0, 255]);
+        assert_eq!(g.get_pixel(4, 4), [255; 4]);
+        assert_eq!(g.get_pixel(8, 8), [255, 0, 0, 255]);
+    }
+}
@@ -0,0 +1,486 @@
+//! Retained display lists
+//!
+//! A display list records triangles drawn through the `Graphics` trait,
+//! such that a static scene can be replayed every frame
+//! without computing the shapes again.
+//!
+//! Record with a context without transform, e.g. `Context::new()`,
+//! then replay with the transform where the scene should be drawn:
+//!
+//! ```ignore
+//! let mut list = DisplayList::new();
+//! let c = Context::new();
+//! rectangle([1.0, 0.0, 0.0, 1.0], [0.0, 0.0, 100.0, 100.0], c.transform, &mut list);
+//! list.text(&Text::new(16), "Hello", &c.draw_state, c.transform);
+//!
+//! // Each frame.
+//! list.draw_with_cache(c.transform.trans(x, y), &mut glyphs, g)?;
+//! ```
+
+use std::rc::Rc;
+
+use super::{
+    math::{multiply, Affine2},
+    triangulation::{tx, ty},
+    types::Color,
+    CharacterCache, DrawState, Graphics, ImageSize, Text, BACK_END_MAX_VERTEX_COUNT as BUFFER_SIZE,
+};
+
+/// A recorded draw command.
+#[derive(Clone, Debug)]
+enum Command<T> {
+    ClearColor(Color),
+    ClearStencil(u8),
+    TriList {
+        draw_state: DrawState,
+        color: Color,
+        vertices: Vec<[f32; 2]>,
+    },
+    TriListC {
+        draw_state: DrawState,
+        vertices: Vec<[f32; 2]>,
+        colors: Vec<[f32; 4]>,
+    },
+    TriListUv {
+        draw_state: DrawState,
+        color: Color,
+        texture: Rc<T>,
+        vertices: Vec<[f32; 2]>,
+        uvs: Vec<[f32; 2]>,
+    },
+    TriListUvC {
+        draw_state: DrawState,
+        texture: Rc<T>,
+        vertices: Vec<[f32; 2]>,
+        uvs: Vec<[f32; 2]>,
+        colors: Vec<[f32; 4]>,
+    },
+    Text {
+        text: Text,
+        string: String,
+        draw_state: DrawState,
+        transform: Affine2,
+    },
+}
+
+/// Records draw commands for replaying them later.
+///
+/// Implements `Graphics` with textures shared through `Rc`,
+/// so images stay alive as long as the display list uses them.
+/// Text is recorded as a string and laid out when replaying with a glyph cache,
+/// since glyph textures belong to the cache.
+#[derive(Clone, Debug)]
+pub struct DisplayList<T> {
+    commands: Vec<Command<T>>,
+}
+
+impl<T> Default for DisplayList<T> {
+    fn default() -> Self {
+        DisplayList::new()
+    }
+}
+
+impl<T> DisplayList<T> {
+    /// Creates a new empty display list.
+    pub fn new() -> DisplayList<T> {
+        DisplayList { commands: vec![] }
+    }
+
+    /// Returns the number of recorded commands.
+    ///
+    /// Consecutive triangles with the same settings are merged into one command.
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    /// Returns `true` if nothing is recorded.
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    /// Removes all recorded commands.
+    pub fn clear(&mut self) {
+        self.commands.clear();
+    }
+
+    /// Groups textured triangles by texture, merging them into fewer commands.
+    ///
+    /// Commands without texture keep their order and are drawn first.
+    /// This changes the drawing order, so it only gives the same result
+    /// when the recorded shapes do not overlap, e.g. the tiles of a map.
+    pub fn sort_by_texture(&mut self) {
+        self.commands.sort_by_key(|command| match command {
+            Command::TriListUv { texture, .. } | Command::TriListUvC { texture, .. } => {
+                Rc::as_ptr(texture) as usize
+            }
+            _ => 0,
+        });
+        let commands = std::mem::take(&mut self.commands);
+        for command in commands {
+            let command = match self.commands.last_mut() {
+                Some(last) => match merge(last, command) {
+                    Some(command) => command,
+                    None => continue,
+                },
+                None => command,
+            };
+            self.commands.push(command);
+        }
+    }
+
+    /// Records text, which is drawn by `draw_with_cache`.
+    pub fn text(&mut self, text: &Text, string: &str, draw_state: &DrawState, transform: Affine2) {
+        self.commands.push(Command::Text {
+            text: *text,
+            string: string.into(),
+            draw_state: *draw_state,
+            transform,
+        });
+    }
+
+    /// Replays the recorded commands with a transform, skipping text.
+    pub fn draw<G>(&self, transform: Affine2, g: &mut G)
+    where
+        G: Graphics<Texture = T>,
+    {
+        self.replay(transform, g, &mut |_, _, _, _, _| {});
+    }
+
+    /// Replays the recorded commands with a transform,
+    /// using a glyph cache to draw text.
+    pub fn draw_with_cache<C, G>(
+        &self,
+        transform: Affine2,
+        cache: &mut C,
+        g: &mut G,
+    ) -> Result<(), C::Error>
+    where
+        C: CharacterCache<Texture = T>,
+        G: Graphics<Texture = T>,
+    {
+        let mut res = Ok(());
+        self.replay(
+            transform,
+            g,
+            &mut |text, string, draw_state, transform, g| {
+                if res.is_ok() {
+                    res = text.draw(string, cache, draw_state, transform, g);
+                }
+            },
+        );
+        res
+    }
+
+    fn replay<G>(
+        &self,
+        m: Affine2,
+        g: &mut G,
+        text: &mut dyn FnMut(&Text, &str, &DrawState, Affine2, &mut G),
+    ) where
+        G: Graphics<Texture = T>,
+    {
+        // Keep whole triangles in each chunk.
+        const CHUNK: usize = BUFFER_SIZE - BUFFER_SIZE % 3;
+
+        let mut buffer: [[f32; 2]; BUFFER_SIZE] = [[0.0; 2]; BUFFER_SIZE];
+        for command in &self.commands {
+            match command {
+                Command::ClearColor(color) => g.clear_color(*color),
+                Command::ClearStencil(value) => g.clear_stencil(*value),
+                Command::TriList {
+                    draw_state,
+                    color,
+                    vertices,
+                } => g.tri_list(draw_state, color, |f| {
+                    for vs in vertices.chunks(CHUNK) {
+                        f(transformed(m, vs, &mut buffer));
+                    }
+                }),
+                Command::TriListC {
+                    draw_state,
+                    vertices,
+                    colors,
+                } => g.tri_list_c(draw_state, |f| {
+                    for (vs, cs) in vertices.chunks(CHUNK).zip(colors.chunks(CHUNK)) {
+                        f(transformed(m, vs, &mut buffer), cs);
+                    }
+                }),
+                Command::TriListUv {
+                    draw_state,
+                    color,
+                    texture,
+                    vertices,
+                    uvs,
+                } => g.tri_list_uv(draw_state, color, texture, |f| {
+                    for (vs, uvs) in vertices.chunks(CHUNK).zip(uvs.chunks(CHUNK)) {
+                        f(transformed(m, vs, &mut buffer), uvs);
+                    }
+                }),
+                Command::TriListUvC {
+                    draw_state,
+                    texture,
+                    vertices,
+                    uvs,
+                    colors,
+                } => g.tri_list_uv_c(draw_state, texture, |f| {
+                    for ((vs, uvs), cs) in vertices
+                        .chunks(CHUNK)
+                        .zip(uvs.chunks(CHUNK))
+                        .zip(colors.chunks(CHUNK))
+                    {
+                        f(transformed(m, vs, &mut buffer), uvs, cs);
+                    }
+                }),
+                Command::Text {
+                    text: t,
+                    string,
+                    draw_state,
+                    transform,
+                } => text(t, string, draw_state, multiply(m, *transform), g),
+            }
+        }
+    }
+}
+
+// Appends the triangles of a command to the previous one with the same settings.
+//
+// Returns the command when it can not be merged.
+fn merge<T>(prev: &mut Command<T>, next: Command<T>) -> Option<Command<T>> {
+    match (prev, next) {
+        (
+            Command::TriList {
+                draw_state,
+                color,
+                vertices,
+            },
+            Command::TriList {
+                draw_state: ds,
+                color: c,
+                vertices: vs,
+            },
+        ) if *draw_state == ds && *color == c => vertices.extend(vs),
+        (
+            Command::TriListC {
+                draw_state,
+                vertices,
+                colors,
+            },
+            Command::TriListC {
+                draw_state: ds,
+                vertices: vs,
+                colors: cs,
+            },
+        ) if *draw_state == ds => {
+            vertices.extend(vs);
+            colors.extend(cs);
+        }
+        (
+            Command::TriListUv {
+                draw_state,
+                color,
+                texture,
+                vertices,
+                uvs,
+            },
+            Command::TriListUv {
+                draw_state: ds,
+                color: c,
+                texture: t,
+                vertices: vs,
+                uvs: ts,
+            },
+        ) if *draw_state == ds && *color == c && Rc::ptr_eq(texture, &t) => {
+            vertices.extend(vs);
+            uvs.extend(ts);
+        }
+        (
+            Command::TriListUvC {
+                draw_state,
+                texture,
+                vertices,
+                uvs,
+                colors,
+            },
+            Command::TriListUvC {
+                draw_state: ds,
+                texture: t,
+                vertices: vs,
+                uvs: ts,
+                colors: cs,
+            },
+        ) if *draw_state == ds && Rc::ptr_eq(texture, &t) => {
+            vertices.extend(vs);
+            uvs.extend(ts);
+            colors.extend(cs);
+        }
+        (_, next) => return Some(next),
+    }
+    None
+}
+
+// Transforms vertices into a buffer.
+fn transformed<'a>(
+    m: Affine2,
+    vertices: &[[f32; 2]],
+    buffer: &'a mut [[f32; 2]; BUFFER_SIZE],
+) -> &'a [[f32; 2]] {
+    for (b, v) in buffer.iter_mut().zip(vertices) {
+        let (x, y) = (v[0] as f64, v[1] as f64);
+        *b = [tx(m, x, y), ty(m, x, y)];
+    }
+    &buffer[..vertices.len()]
+}
+
+impl<T: ImageSize> Graphics for DisplayList<T> {
+    type Texture = Rc<T>;
+
+    fn clear_color(&mut self, color: Color) {
+        self.commands.push(Command::ClearColor(color));
+    }
+
+    fn clear_stencil(&mut self, value: u8) {
+        self.commands.push(Command::ClearStencil(value));
+    }
+
+    fn tri_list<F>(&mut self, draw_state: &DrawState, color: &[f32; 4], mut f: F)
+    where
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]])),
+    {
+        // Merge with the previous command when possible.
+        match self.commands.last_mut() {
+            Some(Command::TriList {
+                draw_state: ds,
+                color: c,
+                ..
+            }) if ds == draw_state && c == color => {}
+            _ => self.commands.push(Command::TriList {
+                draw_state: *draw_state,
+                color: *color,
+                vertices: vec![],
+            }),
+        }
+        if let Some(Command::TriList { vertices, .. }) = self.commands.last_mut() {
+            f(&mut |vs| vertices.extend_from_slice(vs));
+        }
+    }
+
+    fn tri_list_c<F>(&mut self, draw_state: &DrawState, mut f: F)
+    where
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]], &[[f32; 4]])),
+    {
+        // Merge with the previous command when possible.
+        match self.commands.last_mut() {
+            Some(Command::TriListC { draw_state: ds, .. }) if ds == draw_state => {}
+            _ => self.commands.push(Command::TriListC {
+                draw_state: *draw_state,
+                vertices: vec![],
+                colors: vec![],
+            }),
+        }
+        if let Some(Command::TriListC {
+            vertices, colors, ..
+        }) = self.commands.last_mut()
+        {
+            f(&mut |vs, cs| {
+                vertices.extend_from_slice(vs);
+                colors.extend_from_slice(cs);
+            });
+        }
+    }
+
+    fn tri_list_uv<F>(
+        &mut self,
+        draw_state: &DrawState,
+        color: &[f32; 4],
+        texture: &Rc<T>,
+        mut f: F,
+    ) where
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]], &[[f32; 2]])),
+    {
+        // Merge with the previous command when possible.
+        match self.commands.last_mut() {
+            Some(Command::TriListUv {
+                draw_state: ds,
+                color: c,
+                texture: t,
+                ..
+            }) if ds == draw_state && c == color && Rc::ptr_eq(t, texture) => {}
+            _ => self.commands.push(Command::TriListUv {
+                draw_state: *draw_state,
+                color: *color,
+                texture: texture.clone(),
+                vertices: vec![],
+                uvs: vec![],
+            }),
+        }
+        if let Some(Command::TriListUv { vertices, uvs, .. }) = self.commands.last_mut() {
+            f(&mut |vs, ts| {
+                vertices.extend_from_slice(vs);
+                uvs.extend_from_slice(ts);
+            });
+        }
+    }
+
+    fn tri_list_uv_c<F>(&mut self, draw_state: &DrawState, texture: &Rc<T>, mut f: F)
+    where
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]], &[[f32; 2]], &[[f32; 4]])),
+    {
+        // Merge with the previous command when possible.
+        match self.commands.last_mut() {
+            Some(Command::TriListUvC {
+                draw_state: ds,
+                texture: t,
+                ..
+            }) if ds == draw_state && Rc::ptr_eq(t, texture) => {}
+            _ => self.commands.push(Command::TriListUvC {
+                draw_state: *draw_state,
+                texture: texture.clone(),
+                vertices: vec![],
+                uvs: vec![],
+                colors: vec![],
+            }),
+        }
+        if let Some(Command::TriListUvC {
+            vertices,
+            uvs,
+            colors,
+            ..
+        }) = self.commands.last_mut()
+        {
+            f(&mut |vs, ts, cs| {
+                vertices.extend_from_slice(vs);
+                uvs.extend_from_slice(ts);
+                colors.extend_from_slice(cs);
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        graphics::{rectangle, Context, Transformed},
+        software_graphics::{SoftwareGraphics, Texture},
+    };
+
+    #[test]
+    fn test_display_list() {
+        let mut list: DisplayList<Texture> = DisplayList::new();
+        let c = Context::new();
+        rectangle([1.0; 4], [0.0, 0.0, 2.0, 2.0], c.transform, &mut list);
+        rectangle([1.0; 4], [2.0, 2.0, 2.0, 2.0], c.transform, &mut list);
+        assert_eq!(list.len(), 1);
+
+        let mut g = SoftwareGraphics::new(8, 8);
+        g.draw(|c, g| {
+            list.draw(c.transform, g);
+            list.draw(c.transform.trans(4.0, 0.0), g);
+        });
+        assert_eq!(g.get_pixel(1, 1), [255; 4]);
+        assert_eq!(g.get_pixel(3, 3), [255; 4]);
+        assert_eq!(g.get_pixel(5, 1), [255; 4]);
+        assert_eq!(g.get_pixel(7, 3), [255; 4]);
+        assert_eq!(g.get_pixel(1, 3), [0; 4]);
+    }
+}
@@ -5,11 +5,22 @@
 pub struct DrawState {
     /// Scissor mask to use. If set, no pixel outside of this
     /// rectangle (in screen space) will be written to as a result of rendering.
+    ///
+    /// The rectangle is `[x, y, w, h]` in frame buffer pixels,
+    /// with `y` measured from the top of the viewport.
+    /// Unlike stencil clipping, this is cheap and needs no extra draw calls,
+    /// so it is preferred for rectangular areas such as scrollable panels.
+    /// Use `Context::clip` to compute it from a rectangle in current coordinates.
     pub scissor: Option<[u32; 4]>,
     /// Stencil test to use. If None, no stencil testing is done.
     pub stencil: Option<Stencil>,
     /// Blend function to use. If None, blending is disabled.
     pub blend: Option<Blend>,
+    /// Whether to write the red, green, blue and alpha channels.
+    ///
+    /// Disable channels to e.g. render alpha-only masks,
+    /// or disable all channels to only change the stencil buffer.
+    pub color_mask: [bool; 4],
 }
 
 impl Default for DrawState {
@@ -25,18 +36,33 @@ impl DrawState {
             blend: Some(Blend::Alpha),
             stencil: None,
             scissor: None,
+            color_mask: [true; 4],
+        }
+    }
+
+    /// Uses blending for premultiplied alpha,
+    /// where colors and textures are already multiplied by alpha.
+    ///
+    /// See `color::premultiply` and `TextureSettings::premultiply_alpha`.
+    pub fn new_premultiplied() -> DrawState {
+        DrawState {
+            blend: Some(Blend::PremultipliedAlpha),
+            stencil: None,
+            scissor: None,
+            color_mask: [true; 4],
         }
     }
 
     /// Draws to stencil buffer with value 255.
     /// This can be used for clipping.
     ///
-    /// For nested clipping, clear the stencil buffer and use `DrawState::new_increment`.
+    /// For nested clipping, use `clip_stack::ClipStack`.
     pub fn new_clip() -> DrawState {
         DrawState {
             blend: Some(Blend::Alpha),
             stencil: Some(Stencil::Clip(255)),
             scissor: None,
+            color_mask: [true; 4],
         }
     }
 
@@ -47,6 +73,18 @@ impl DrawState {
             blend: Some(Blend::Alpha),
             stencil: Some(Stencil::Increment),
             scissor: None,
+            color_mask: [true; 4],
+        }
+    }
+
+    /// Decreases stencil buffer.
+    /// This is used to undo `DrawState::new_increment`, see `ClipStack`.
+    pub fn new_decrement() -> DrawState {
+        DrawState {
+            blend: Some(Blend::Alpha),
+            stencil: Some(Stencil::Decrement),
+            scissor: None,
+            color_mask: [true; 4],
         }
     }
 
@@ -57,6 +95,7 @@ impl DrawState {
             blend: Some(Blend::Alpha),
             stencil: Some(Stencil::Inside(255)),
             scissor: None,
+            color_mask: [true; 4],
         }
     }
 
@@ -67,6 +106,7 @@ impl DrawState {
             blend: Some(Blend::Alpha),
             stencil: Some(Stencil::Outside(255)),
             scissor: None,
+            color_mask: [true; 4],
         }
     }
 
@@ -76,6 +116,17 @@ impl DrawState {
         self
     }
 
+    /// Sets which color channels to write, in order red, green, blue and alpha.
+    pub fn color_mask(mut self, mask: [bool; 4]) -> DrawState {
+        self.color_mask = mask;
+        self
+    }
+
+    /// Writes only the alpha channel.
+    pub fn alpha_only(self) -> DrawState {
+        self.color_mask([false, false, false, true])
+    }
+
     /// Sets scissor `[x, y, w, h]`.
     pub fn scissor(mut self, scissor: [u32; 4]) -> DrawState {
         self.scissor = Some(scissor);
@@ -130,6 +181,48 @@ pub enum Blend {
     /// A white source color and a white value results in plain invert.
     /// The output alpha is same as destination alpha.
     Invert,
+    /// Screen blending, which brightens like projecting two images on a screen.
+    ///
+    /// ```not_rust
+    /// new_dest_color = src_color + dest_color * (1 - src_color)
+    /// new_dest_alpha = src_alpha + dest_alpha * (1 - src_alpha)
+    /// ```
+    Screen,
+    /// Subtracts source color weighted by alpha.
+    ///
+    /// ```not_rust
+    /// new_dest_color = dest_color - src_color * src_alpha
+    /// new_dest_alpha = dest_alpha
+    /// ```
+    Subtract,
+    /// Keeps the minimum of each color component.
+    ///
+    /// ```not_rust
+    /// new_dest_color = min(src_color, dest_color)
+    /// new_dest_alpha = src_alpha + dest_alpha * (1 - src_alpha)
+    /// ```
+    Darken,
+    /// Keeps the maximum of each color component.
+    ///
+    /// ```not_rust
+    /// new_dest_color = max(src_color, dest_color)
+    /// new_dest_alpha = src_alpha + dest_alpha * (1 - src_alpha)
+    /// ```
+    Lighten,
+    /// Alpha blending for colors that are already multiplied by alpha.
+    ///
+    /// ```not_rust
+    /// new_dest_color = src_color + dest_color * (1 - src_alpha)
+    /// new_dest_alpha = src_alpha + dest_alpha * (1 - src_alpha)
+    /// ```
+    PremultipliedAlpha,
+    /// Multiplies destination by source alpha, used for soft masks.
+    ///
+    /// ```not_rust
+    /// new_dest_color = dest_color * src_alpha
+    /// new_dest_alpha = dest_alpha * src_alpha
+    /// ```
+    Mask,
 }
 
 /// Stencil buffer settings.
@@ -143,4 +236,6 @@ pub enum Stencil {
     Outside(u8),
     /// Increment stencil buffer.
     Increment,
+    /// Decrement stencil buffer.
+    Decrement,
 }
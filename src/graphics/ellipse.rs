@@ -2,9 +2,11 @@
 
 pub use super::rectangle::{centered, centered_square as circle};
 use super::{
-    math::Affine2,
+    fill::Fill,
+    math::{identity, Affine2, Scalar},
+    radians::Radians,
     triangulation,
-    types::{Color, Radius, Rectangle, Resolution},
+    types::{Color, Radius, Rectangle, Resolution, Vec2d},
     DrawState, Graphics,
 };
 
@@ -26,6 +28,29 @@ pub struct Ellipse {
     pub border: Option<Border>,
     /// The resolution for the shape, 360 degrees.
     pub resolution: Resolution,
+    /// Optional tolerance in pixels for automatic resolution.
+    ///
+    /// When set, the resolution is computed from the radius on the screen,
+    /// if the back-end knows its viewport.
+    pub tolerance: Option<Scalar>,
+    /// Optional radial gradient, used instead of color.
+    pub gradient: Option<RadialGradient>,
+    /// Optional width of a feathered rim for anti-aliasing.
+    ///
+    /// The rim fades out outside the edge of the filled shape.
+    /// A width of about one pixel gives smooth edges without multisampling.
+    pub feather: Option<Radius>,
+}
+
+/// Radial gradient from center color to edge color of an ellipse.
+#[derive(Copy, Clone, Debug)]
+pub struct RadialGradient {
+    /// The colors at focal point and edge.
+    pub colors: [Color; 2],
+    /// The offset of the focal point from the center, relative to the radius.
+    ///
+    /// For example, `[-0.5, -0.5]` is half way to the upper left.
+    pub focal: Vec2d,
 }
 
 impl Ellipse {
@@ -35,6 +60,9 @@ impl Ellipse {
             color,
             border: None,
             resolution: 128,
+            tolerance: None,
+            gradient: None,
+            feather: None,
         }
     }
 
@@ -44,6 +72,9 @@ impl Ellipse {
             color: [0.0; 4],
             border: Some(Border { color, radius }),
             resolution: 128,
+            tolerance: None,
+            gradient: None,
+            feather: None,
         }
     }
 
@@ -65,12 +96,39 @@ impl Ellipse {
         self
     }
 
+    /// Sets radial gradient from center color to edge color.
+    pub fn radial_gradient(mut self, colors: [Color; 2]) -> Self {
+        self.gradient = Some(RadialGradient {
+            colors,
+            focal: [0.0, 0.0],
+        });
+        self
+    }
+
+    /// Sets optional radial gradient.
+    pub fn maybe_gradient(mut self, value: Option<RadialGradient>) -> Self {
+        self.gradient = value;
+        self
+    }
+
+    /// Sets width of a feathered rim for anti-aliasing.
+    pub fn feather(mut self, value: Radius) -> Self {
+        self.feather = Some(value);
+        self
+    }
+
     /// Sets resolution of the ellipse smoothness.
     pub fn resolution(mut self, value: Resolution) -> Self {
         self.resolution = value;
         self
     }
 
+    /// Enables automatic resolution with a tolerance in pixels.
+    pub fn tolerance(mut self, value: Scalar) -> Self {
+        self.tolerance = Some(value);
+        self
+    }
+
     /// Draws ellipse by corners using default method.
     #[inline(always)]
     pub fn draw_from_to<P: Into<super::types::Vec2d>, G>(
@@ -120,14 +178,62 @@ impl Ellipse {
         G: Graphics,
     {
         let rectangle = rectangle.into();
-        g.tri_list(draw_state, &self.color, |f| {
-            triangulation::with_ellipse_tri_list(
-                self.resolution,
+        let resolution = match (self.tolerance, g.current_viewport()) {
+            (Some(tolerance), Some(viewport)) => triangulation::auto_resolution(
                 transform,
-                rectangle,
-                |vertices| f(vertices),
-            )
+                &viewport,
+                0.5 * rectangle[2].abs().max(rectangle[3].abs()),
+                tolerance,
+            ),
+            _ => self.resolution,
+        };
+        let fill = self.gradient.map(|RadialGradient { colors, focal }| {
+            Fill::radial_gradient(rectangle, colors)
+                .focal([0.5 * rectangle[2] * focal[0], 0.5 * rectangle[3] * focal[1]])
         });
+        if let Some(fill) = fill {
+            // Rays from the focal point are linear in the gradient.
+            let center = fill.focal_point().unwrap();
+            fill.draw_tri_list(draw_state, transform, g, |f| {
+                triangulation::with_ellipse_fan_tri_list(
+                    resolution,
+                    identity(),
+                    rectangle,
+                    center,
+                    |vertices| f(vertices),
+                )
+            });
+        } else {
+            g.tri_list(draw_state, &self.color, |f| {
+                triangulation::with_ellipse_tri_list(resolution, transform, rectangle, |vertices| {
+                    f(vertices)
+                })
+            });
+        }
+
+        if let Some(width) = self.feather {
+            if fill.is_some() || self.color[3] != 0.0 {
+                let (x, y, w, h) = (rectangle[0], rectangle[1], rectangle[2], rectangle[3]);
+                let (cw, ch) = (0.5 * w, 0.5 * h);
+                let n = resolution;
+                let outline: Vec<Vec2d> = (0..n)
+                    .map(|i| {
+                        let angle = i as Scalar / n as Scalar * <Scalar as Radians>::_360();
+                        [x + cw + angle.cos() * cw, y + ch + angle.sin() * ch]
+                    })
+                    .collect();
+                let color = self.color;
+                g.tri_list_c(draw_state, |f| {
+                    triangulation::with_feather_tri_list_c(
+                        transform,
+                        &outline,
+                        width,
+                        |p| fill.map(|fill| fill.color_at(p)).unwrap_or(color),
+                        |vertices, colors| f(vertices, colors),
+                    )
+                });
+            }
+        }
 
         if let Some(Border {
             color,
@@ -136,7 +242,7 @@ impl Ellipse {
         {
             g.tri_list(draw_state, &color, |f| {
                 triangulation::with_ellipse_border_tri_list(
-                    self.resolution,
+                    resolution,
                     transform,
                     rectangle,
                     border_radius,
@@ -158,4 +264,38 @@ mod test {
             radius: 3.0,
         });
     }
+
+    #[test]
+    fn test_radial_gradient() {
+        use crate::software_graphics::SoftwareGraphics;
+
+        let mut g = SoftwareGraphics::new(16, 16);
+        g.draw(|c, g| {
+            Ellipse::new([1.0; 4])
+                .radial_gradient([[1.0, 0.0, 0.0, 1.0], [0.0, 0.0, 1.0, 1.0]])
+                .draw([0.0, 0.0, 16.0, 16.0], &c.draw_state, c.transform, g)
+        });
+        // Red in the center and blue towards the edge.
+        let center = g.get_pixel(8, 8);
+        let edge = g.get_pixel(8, 0);
+        assert!(center[0] > 200 && center[2] < 100);
+        assert!(edge[0] < 100 && edge[2] > 200);
+        assert_eq!(g.get_pixel(0, 0), [0; 4]);
+    }
+
+    #[test]
+    fn test_auto_resolution() {
+        use crate::{graphics::Transformed, software_graphics::SoftwareGraphics};
+
+        let g = SoftwareGraphics::new(100, 100);
+        let c = crate::graphics::Context::new_viewport(g.viewport());
+        let resolution = |c: crate::graphics::Context, radius| {
+            triangulation::auto_resolution(c.transform, &g.viewport(), radius, 0.25)
+        };
+        // Zooming in gives the same resolution as a larger radius.
+        assert_eq!(resolution(c.zoom(4.0), 10.0), resolution(c, 40.0));
+        assert!(resolution(c, 40.0) > resolution(c, 10.0));
+        assert_eq!(resolution(c, 0.1), 8);
+        assert_eq!(resolution(c.zoom(1e9), 10.0), 1024);
+    }
 }
@@ -0,0 +1,307 @@
+//! Gradient fills for shapes.
+//!
+//! Gradients are specified in shape coordinates, before transformation,
+//! and are rendered with per-vertex colors through `Graphics::tri_list_c`.
+
+use super::{
+    math::{Affine2, Scalar, Vec2d},
+    triangulation::{tx, ty},
+    types::{Color, Rectangle},
+    DrawState, Graphics, BACK_END_MAX_VERTEX_COUNT as BUFFER_SIZE,
+};
+
+/// Fills a shape with varying colors.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Fill {
+    /// Linear gradient between two points.
+    ///
+    /// The colors are extended beyond the end points.
+    LinearGradient {
+        /// The point with the first color.
+        start: Vec2d,
+        /// The point with the second color.
+        end: Vec2d,
+        /// The colors at start and end.
+        colors: [Color; 2],
+    },
+    /// Radial gradient from a focal point to the edge of an ellipse.
+    ///
+    /// The colors are extended beyond the edge.
+    RadialGradient {
+        /// The center of the ellipse.
+        center: Vec2d,
+        /// The horizontal and vertical radius of the ellipse.
+        radius: Vec2d,
+        /// The offset of the focal point with the first color from center.
+        ///
+        /// The focal point is kept inside the ellipse.
+        focal: Vec2d,
+        /// The colors at focal point and edge.
+        colors: [Color; 2],
+    },
+}
+
+impl Fill {
+    /// Creates a radial gradient filling an ellipse inside rectangle.
+    pub fn radial_gradient(rect: Rectangle, colors: [Color; 2]) -> Fill {
+        let radius = [0.5 * rect[2], 0.5 * rect[3]];
+        Fill::RadialGradient {
+            center: [rect[0] + radius[0], rect[1] + radius[1]],
+            radius,
+            focal: [0.0, 0.0],
+            colors,
+        }
+    }
+
+    /// Sets the focal offset of a radial gradient.
+    pub fn focal(mut self, value: Vec2d) -> Self {
+        if let Fill::RadialGradient { ref mut focal, .. } = self {
+            *focal = value;
+        }
+        self
+    }
+
+    /// Returns the focal point of a radial gradient, kept inside the ellipse.
+    pub fn focal_point(&self) -> Option<Vec2d> {
+        match *self {
+            Fill::LinearGradient { .. } => None,
+            Fill::RadialGradient {
+                center,
+                radius,
+                focal,
+                ..
+            } => {
+                let f = normalized_focal(radius, focal);
+                Some([center[0] + f[0] * radius[0], center[1] + f[1] * radius[1]])
+            }
+        }
+    }
+
+    /// Returns the color at a position in shape coordinates.
+    pub fn color_at(&self, pos: Vec2d) -> Color {
+        let t = self.param(pos).clamp(0.0, 1.0) as f32;
+        match *self {
+            Fill::LinearGradient { colors, .. } | Fill::RadialGradient { colors, .. } => {
+                lerp_color(colors, t)
+            }
+        }
+    }
+
+    // Returns the unclamped gradient parameter at a position.
+    fn param(&self, pos: Vec2d) -> Scalar {
+        match *self {
+            Fill::LinearGradient { start, end, .. } => {
+                let d = [end[0] - start[0], end[1] - start[1]];
+                let len_sq = d[0] * d[0] + d[1] * d[1];
+                if len_sq == 0.0 {
+                    return 0.0;
+                }
+                ((pos[0] - start[0]) * d[0] + (pos[1] - start[1]) * d[1]) / len_sq
+            }
+            Fill::RadialGradient {
+                center,
+                radius,
+                focal,
+                ..
+            } => {
+                if radius[0] == 0.0 || radius[1] == 0.0 {
+                    return 1.0;
+                }
+                // Solve `|q - t * d| = t` in coordinates where the ellipse is a unit circle,
+                // where `q` is relative to focal point and `d` points from focal point to center.
+                let f = normalized_focal(radius, focal);
+                let d = [-f[0], -f[1]];
+                let q = [
+                    (pos[0] - center[0]) / radius[0] - f[0],
+                    (pos[1] - center[1]) / radius[1] - f[1],
+                ];
+                let qd = q[0] * d[0] + q[1] * d[1];
+                let qq = q[0] * q[0] + q[1] * q[1];
+                let a = 1.0 - (d[0] * d[0] + d[1] * d[1]);
+                (-qd + (qd * qd + a * qq).sqrt()) / a
+            }
+        }
+    }
+
+    /// Draws triangles in shape coordinates with the fill.
+    ///
+    /// Triangles are split where the gradient is clamped,
+    /// such that interpolating vertex colors gives the exact gradient.
+    pub fn draw_tri_list<G, F>(
+        &self,
+        draw_state: &DrawState,
+        transform: Affine2,
+        g: &mut G,
+        mut f: F,
+    ) where
+        G: Graphics,
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]])),
+    {
+        g.tri_list_c(draw_state, |out| {
+            let mut vertices = [[0.0; 2]; BUFFER_SIZE];
+            let mut colors = [[0.0; 4]; BUFFER_SIZE];
+            let mut k = 0;
+            let mut emit = |tri: [Vec2d; 3]| {
+                // Buffer is full.
+                if k + 3 > BUFFER_SIZE {
+                    // Send chunk and start over.
+                    out(&vertices[..k], &colors[..k]);
+                    k = 0;
+                }
+                for p in tri {
+                    vertices[k] = [tx(transform, p[0], p[1]), ty(transform, p[0], p[1])];
+                    colors[k] = self.color_at(p);
+                    k += 1;
+                }
+            };
+            f(&mut |tris| {
+                for tri in tris.chunks_exact(3) {
+                    let to_vec2d = |v: [f32; 2]| [v[0] as Scalar, v[1] as Scalar];
+                    let tri = [to_vec2d(tri[0]), to_vec2d(tri[1]), to_vec2d(tri[2])];
+                    self.split_triangle(tri, &mut emit);
+                }
+            });
+            if k > 0 {
+                out(&vertices[..k], &colors[..k]);
+            }
+        });
+    }
+
+    // Splits a triangle where the gradient parameter crosses 0 and 1,
+    // or subdivides it where the gradient is not linear.
+    fn split_triangle(&self, tri: [Vec2d; 3], emit: &mut dyn FnMut([Vec2d; 3])) {
+        if let Fill::RadialGradient { .. } = *self {
+            self.subdivide(tri, 0, emit);
+            return;
+        }
+        let t = [self.param(tri[0]), self.param(tri[1]), self.param(tri[2])];
+        let (min, max) = (t[0].min(t[1]).min(t[2]), t[0].max(t[1]).max(t[2]));
+        if max <= 0.0 || min >= 1.0 || (min >= 0.0 && max <= 1.0) {
+            emit(tri);
+            return;
+        }
+        let param = |p: Vec2d| self.param(p);
+        let pieces = [
+            clip(&tri, |p| -param(p)),
+            clip(&clip(&tri, param), |p| 1.0 - param(p)),
+            clip(&tri, |p| param(p) - 1.0),
+        ];
+        for piece in &pieces {
+            for i in 2..piece.len() {
+                emit([piece[0], piece[i - 1], piece[i]]);
+            }
+        }
+    }
+
+    // Subdivides a triangle until interpolation is close to the gradient.
+    fn subdivide(&self, tri: [Vec2d; 3], depth: u32, emit: &mut dyn FnMut([Vec2d; 3])) {
+        const MAX_DEPTH: u32 = 5;
+        const TOLERANCE: Scalar = 1.0 / 128.0;
+
+        let t = |p: Vec2d| self.param(p).clamp(0.0, 1.0);
+        let mid = |a: Vec2d, b: Vec2d| [0.5 * (a[0] + b[0]), 0.5 * (a[1] + b[1])];
+        let [a, b, c] = tri;
+        let (ab, bc, ca) = (mid(a, b), mid(b, c), mid(c, a));
+        let (ta, tb, tc) = (t(a), t(b), t(c));
+        let error = (t(ab) - 0.5 * (ta + tb))
+            .abs()
+            .max((t(bc) - 0.5 * (tb + tc)).abs())
+            .max((t(ca) - 0.5 * (tc + ta)).abs());
+        if depth >= MAX_DEPTH || error <= TOLERANCE {
+            emit(tri);
+            return;
+        }
+        for tri in [[a, ab, ca], [ab, b, bc], [ca, bc, c], [ab, bc, ca]] {
+            self.subdivide(tri, depth + 1, emit);
+        }
+    }
+}
+
+// Returns focal offset relative to radius, kept inside the unit circle.
+fn normalized_focal(radius: Vec2d, focal: Vec2d) -> Vec2d {
+    const MAX_LEN: Scalar = 0.99;
+
+    if radius[0] == 0.0 || radius[1] == 0.0 {
+        return [0.0, 0.0];
+    }
+    let f = [focal[0] / radius[0], focal[1] / radius[1]];
+    let len = (f[0] * f[0] + f[1] * f[1]).sqrt();
+    if len > MAX_LEN {
+        [f[0] * MAX_LEN / len, f[1] * MAX_LEN / len]
+    } else {
+        f
+    }
+}
+
+// Interpolates between two colors.
+fn lerp_color(colors: [Color; 2], t: f32) -> Color {
+    let [a, b] = colors;
+    [
+        a[0] + (b[0] - a[0]) * t,
+        a[1] + (b[1] - a[1]) * t,
+        a[2] + (b[2] - a[2]) * t,
+        a[3] + (b[3] - a[3]) * t,
+    ]
+}
+
+// Clips a convex polygon, keeping the part where `dist` is non-negative.
+fn clip<F: Fn(Vec2d) -> Scalar>(polygon: &[Vec2d], dist: F) -> Vec<Vec2d> {
+    let mut res = vec![];
+    let n = polygon.len();
+    for i in 0..n {
+        let (a, b) = (polygon[i], polygon[(i + 1) % n]);
+        let (da, db) = (dist(a), dist(b));
+        if da >= 0.0 {
+            res.push(a);
+        }
+        if (da < 0.0) != (db < 0.0) {
+            let s = da / (da - db);
+            res.push([a[0] + (b[0] - a[0]) * s, a[1] + (b[1] - a[1]) * s]);
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_linear_gradient() {
+        let fill = Fill::LinearGradient {
+            start: [0.0, 0.0],
+            end: [10.0, 0.0],
+            colors: [[0.0; 4], [1.0; 4]],
+        };
+        assert_eq!(fill.color_at([5.0, 3.0]), [0.5; 4]);
+        assert_eq!(fill.color_at([-5.0, 0.0]), [0.0; 4]);
+        assert_eq!(fill.color_at([20.0, 0.0]), [1.0; 4]);
+
+        // A triangle across both ends is split into pieces with same area.
+        let mut area = 0.0;
+        fill.split_triangle(
+            [[-10.0, 0.0], [20.0, 0.0], [20.0, 10.0]],
+            &mut |[a, b, c]| {
+                area += ((b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0])) / 2.0;
+            },
+        );
+        assert!((area - 150.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_radial_gradient() {
+        let fill = Fill::radial_gradient([0.0, 0.0, 20.0, 10.0], [[0.0; 4], [1.0; 4]]);
+        assert_eq!(fill.color_at([10.0, 5.0]), [0.0; 4]);
+        assert_eq!(fill.color_at([15.0, 5.0]), [0.5; 4]);
+        assert_eq!(fill.color_at([10.0, 0.0]), [1.0; 4]);
+        assert_eq!(fill.color_at([30.0, 5.0]), [1.0; 4]);
+
+        // The edge stays at the edge with a focal offset.
+        let fill = fill.focal([5.0, 0.0]);
+        assert_eq!(fill.focal_point(), Some([15.0, 5.0]));
+        assert_eq!(fill.color_at([15.0, 5.0]), [0.0; 4]);
+        assert!((fill.param([0.0, 5.0]) - 1.0).abs() < 1e-9);
+        assert!((fill.param([20.0, 5.0]) - 1.0).abs() < 1e-9);
+        assert!((fill.param([17.5, 5.0]) - 0.5).abs() < 1e-9);
+    }
+}
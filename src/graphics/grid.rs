@@ -1,8 +1,10 @@
 //! A flat grid with square cells.
+//!
+//! Also includes an isometric grid with diamond cells.
 
 use super::{
-    math::{Affine2, Scalar, Vec2d},
-    DrawState, Graphics, Line,
+    math::{get_scale, invert, transform_pos, Affine2, Scalar, Vec2d},
+    Context, DrawState, Graphics, Line, Polygon,
 };
 
 /// Represents a flat grid with square cells.
@@ -47,6 +49,44 @@ impl Grid {
         }
     }
 
+    /// Draws the border of each cell, with a line style supplied per cell.
+    ///
+    /// Cells are skipped when the closure returns `None`.
+    /// Edges shared by neighbour cells are drawn by both,
+    /// so later cells in row order cover earlier ones.
+    pub fn draw_cells<G, F>(&self, draw_state: &DrawState, transform: Affine2, g: &mut G, mut f: F)
+    where
+        G: Graphics,
+        F: FnMut((u32, u32)) -> Option<Line>,
+    {
+        let units = self.units;
+        for (cell, [x, y]) in self.cell_positions() {
+            if let Some(line) = f(cell) {
+                line.draw([x, y, x + units, y], draw_state, transform, g);
+                line.draw(
+                    [x + units, y, x + units, y + units],
+                    draw_state,
+                    transform,
+                    g,
+                );
+                line.draw(
+                    [x, y + units, x + units, y + units],
+                    draw_state,
+                    transform,
+                    g,
+                );
+                line.draw([x, y, x, y + units], draw_state, transform, g);
+            }
+        }
+    }
+
+    /// Get an iterator over the cells with their on-screen positions.
+    pub fn cell_positions(&self) -> impl Iterator<Item = ((u32, u32), Vec2d)> {
+        let grid = *self;
+        self.cells()
+            .map(move |cell| (cell, grid.cell_position(cell)))
+    }
+
     /// Get a `GridIterator` for the grid
     pub fn cells(&self) -> GridCells {
         GridCells {
@@ -72,6 +112,221 @@ impl Grid {
     }
 }
 
+/// Represents a grid covering the whole view, with level of detail.
+///
+/// Only visible lines are drawn, and the spacing adapts to the zoom level,
+/// such that lines never get closer than the minimum spacing on screen.
+#[derive(Debug, Copy, Clone)]
+pub struct InfiniteGrid {
+    /// The width and height of the finest grid cells.
+    pub units: Scalar,
+    /// The number of minor cells along each side of a major cell.
+    pub subdivisions: u32,
+    /// The minimum distance between minor lines in view coordinates.
+    pub min_spacing: Scalar,
+}
+
+impl InfiniteGrid {
+    /// Creates a new infinite grid.
+    pub fn new(units: Scalar) -> InfiniteGrid {
+        InfiniteGrid {
+            units,
+            subdivisions: 10,
+            min_spacing: 8.0,
+        }
+    }
+
+    /// Sets the number of minor cells along each side of a major cell.
+    pub fn subdivisions(mut self, value: u32) -> Self {
+        self.subdivisions = value;
+        self
+    }
+
+    /// Sets the minimum distance between minor lines in view coordinates.
+    pub fn min_spacing(mut self, value: Scalar) -> Self {
+        self.min_spacing = value;
+        self
+    }
+
+    /// Computes the spacing of minor lines at a scale.
+    ///
+    /// The scale is the length in view coordinates of one unit.
+    /// The spacing is `units` multiplied with a power of `subdivisions`.
+    pub fn spacing(&self, scale: Scalar) -> Scalar {
+        let sub = Scalar::from(self.subdivisions.max(2));
+        let level = (self.min_spacing / (self.units * scale)).ln() / sub.ln();
+        self.units * sub.powi(level.ceil() as i32)
+    }
+
+    /// Draws the visible part of the grid, using the context's view.
+    ///
+    /// Every `subdivisions` line is drawn as a major line.
+    /// The radius of the lines is in view coordinates,
+    /// so lines keep their width when zooming.
+    pub fn draw<G>(&self, minor: &Line, major: &Line, c: &Context, g: &mut G)
+    where
+        G: Graphics,
+    {
+        let t = c.transform;
+        let view_size = c.get_view_size();
+        // Length of one unit in view coordinates.
+        let [sx, sy] = get_scale(t);
+        let scale = Scalar::min(sx * view_size[0], sy * view_size[1]) * 0.5;
+        if !(scale > 0.0 && self.units > 0.0) {
+            return;
+        }
+        let to_view = |line: &Line| Line {
+            radius: line.radius / scale,
+            feather: line.feather.map(|f| f / scale),
+            ..*line
+        };
+        let (minor, major) = (&to_view(minor), &to_view(major));
+        let spacing = self.spacing(scale);
+        // Find the visible area by transforming the normalized corners back.
+        let inv = invert(t);
+        let corners =
+            [[-1.0, -1.0], [1.0, -1.0], [1.0, 1.0], [-1.0, 1.0]].map(|p| transform_pos(inv, p));
+        let (mut min, mut max) = (corners[0], corners[0]);
+        for p in &corners[1..] {
+            min = [min[0].min(p[0]), min[1].min(p[1])];
+            max = [max[0].max(p[0]), max[1].max(p[1])];
+        }
+        let sub = i64::from(self.subdivisions.max(2));
+        let (x0, x1) = (
+            (min[0] / spacing).ceil() as i64,
+            (max[0] / spacing).floor() as i64,
+        );
+        let (y0, y1) = (
+            (min[1] / spacing).ceil() as i64,
+            (max[1] / spacing).floor() as i64,
+        );
+        for x in x0..=x1 {
+            let line = if x % sub == 0 { major } else { minor };
+            let x = x as Scalar * spacing;
+            line.draw([x, min[1], x, max[1]], &c.draw_state, t, g);
+        }
+        for y in y0..=y1 {
+            let line = if y % sub == 0 { major } else { minor };
+            let y = y as Scalar * spacing;
+            line.draw([min[0], y, max[0], y], &c.draw_state, t, g);
+        }
+    }
+}
+
+/// Represents an isometric grid with diamond cells.
+///
+/// The columns run down to the right and the rows run down to the left,
+/// with the top corner of cell `(0, 0)` at the origin.
+#[derive(Debug, Copy, Clone)]
+pub struct IsoGrid {
+    /// Number of columns.
+    pub cols: u32,
+    /// Number of rows.
+    pub rows: u32,
+    /// The width and height of each diamond, usually twice as wide as high.
+    pub tile_size: Vec2d,
+}
+
+impl IsoGrid {
+    /// Creates a new isometric grid.
+    pub fn new(cols: u32, rows: u32, tile_size: Vec2d) -> IsoGrid {
+        IsoGrid {
+            cols,
+            rows,
+            tile_size,
+        }
+    }
+
+    /// Draws the grid.
+    pub fn draw<G>(&self, line: &Line, draw_state: &DrawState, transform: Affine2, g: &mut G)
+    where
+        G: Graphics,
+    {
+        let (cols, rows) = (Scalar::from(self.cols), Scalar::from(self.rows));
+        for x in 0..self.cols + 1 {
+            let x = Scalar::from(x);
+            let [x1, y1] = self.grid_to_world([x, 0.0]);
+            let [x2, y2] = self.grid_to_world([x, rows]);
+            line.draw([x1, y1, x2, y2], draw_state, transform, g);
+        }
+        for y in 0..self.rows + 1 {
+            let y = Scalar::from(y);
+            let [x1, y1] = self.grid_to_world([0.0, y]);
+            let [x2, y2] = self.grid_to_world([cols, y]);
+            line.draw([x1, y1, x2, y2], draw_state, transform, g);
+        }
+    }
+
+    /// Fills the diamond of a cell, e.g. to highlight it.
+    pub fn draw_cell<G>(
+        &self,
+        polygon: &Polygon,
+        cell: (u32, u32),
+        draw_state: &DrawState,
+        transform: Affine2,
+        g: &mut G,
+    ) where
+        G: Graphics,
+    {
+        polygon.draw(&self.cell_corners(cell), draw_state, transform, g);
+    }
+
+    /// Get a `GridIterator` for the grid, in drawing order from back to front.
+    pub fn cells(&self) -> GridCells {
+        GridCells {
+            cols: self.cols,
+            rows: self.rows,
+            state: 0,
+        }
+    }
+
+    /// Converts a position in cell units to world coordinates.
+    pub fn grid_to_world(&self, pos: Vec2d) -> Vec2d {
+        let [w, h] = self.tile_size;
+        [0.5 * w * (pos[0] - pos[1]), 0.5 * h * (pos[0] + pos[1])]
+    }
+
+    /// Converts a position in world coordinates to cell units.
+    pub fn world_to_grid(&self, pos: Vec2d) -> Vec2d {
+        let [w, h] = self.tile_size;
+        let (u, v) = (pos[0] / w, pos[1] / h);
+        [v + u, v - u]
+    }
+
+    /// Get the cell containing a position in world coordinates.
+    ///
+    /// Returns `None` outside the grid.
+    pub fn world_to_cell(&self, pos: Vec2d) -> Option<(u32, u32)> {
+        let [x, y] = self.world_to_grid(pos);
+        let (x, y) = (x.floor(), y.floor());
+        if x < 0.0 || y < 0.0 || x >= Scalar::from(self.cols) || y >= Scalar::from(self.rows) {
+            return None;
+        }
+        Some((x as u32, y as u32))
+    }
+
+    /// Get on-screen position of the top corner of a grid cell.
+    pub fn cell_position(&self, cell: (u32, u32)) -> Vec2d {
+        self.grid_to_world([Scalar::from(cell.0), Scalar::from(cell.1)])
+    }
+
+    /// Get on-screen position of the center of a grid cell.
+    pub fn cell_center(&self, cell: (u32, u32)) -> Vec2d {
+        self.grid_to_world([Scalar::from(cell.0) + 0.5, Scalar::from(cell.1) + 0.5])
+    }
+
+    /// Get the corners of a cell's diamond, clockwise from the top.
+    pub fn cell_corners(&self, cell: (u32, u32)) -> [Vec2d; 4] {
+        let (x, y) = (Scalar::from(cell.0), Scalar::from(cell.1));
+        [
+            self.grid_to_world([x, y]),
+            self.grid_to_world([x + 1.0, y]),
+            self.grid_to_world([x + 1.0, y + 1.0]),
+            self.grid_to_world([x, y + 1.0]),
+        ]
+    }
+}
+
 impl Iterator for GridCells {
     type Item = (u32, u32);
 
@@ -119,6 +374,17 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_infinite_grid_spacing() {
+        let grid = InfiniteGrid::new(1.0).subdivisions(10).min_spacing(8.0);
+        // One unit is 10 pixels, so unit spacing is visible.
+        assert_eq!(grid.spacing(10.0), 1.0);
+        // Zoomed out, lines merge into coarser levels.
+        assert_eq!(grid.spacing(0.5), 100.0);
+        // Zoomed in, finer levels appear.
+        assert!((grid.spacing(100.0) - 0.1).abs() < 1e-9);
+    }
+
     #[test]
     fn test_cell_positions() {
         let g: Grid = Grid {
@@ -129,4 +395,38 @@ mod tests {
         assert_eq!(4.0, g.x_pos((2, 3)));
         assert_eq!(6.0, g.y_pos((2, 3)));
     }
+
+    #[test]
+    fn test_iso_grid() {
+        let grid = IsoGrid::new(4, 3, [64.0, 32.0]);
+        assert_eq!(grid.cell_position((1, 0)), [32.0, 16.0]);
+        assert_eq!(grid.cell_center((0, 0)), [0.0, 16.0]);
+        assert_eq!(grid.world_to_cell([0.0, 16.0]), Some((0, 0)));
+        assert_eq!(grid.world_to_cell(grid.cell_center((3, 2))), Some((3, 2)));
+        assert_eq!(grid.world_to_cell([0.0, -1.0]), None);
+        assert_eq!(grid.world_to_cell([-40.0, 8.0]), None);
+    }
+
+    #[test]
+    fn test_draw_cells() {
+        use crate::software_graphics::SoftwareGraphics;
+
+        let grid = Grid {
+            cols: 2,
+            rows: 1,
+            units: 4.0,
+        };
+        let positions: Vec<_> = grid.cell_positions().collect();
+        assert_eq!(positions, [((0, 0), [0.0, 0.0]), ((1, 0), [4.0, 0.0])]);
+
+        let mut g = SoftwareGraphics::new(10, 6);
+        g.draw(|c, g| {
+            // Only the right cell has a border.
+            grid.draw_cells(&c.draw_state, c.transform, g, |(x, _)| {
+                (x == 1).then(|| Line::new([1.0; 4], 0.5))
+            });
+        });
+        assert_eq!(g.get_pixel(6, 0), [255; 4]);
+        assert_eq!(g.get_pixel(2, 0), [0; 4]);
+    }
 }
@@ -1,10 +1,10 @@
 //! Draw an image
 
 use super::{
-    math::Affine2,
+    math::{Affine2, Scalar},
     triangulation,
     types::{Color, Rectangle, SourceRectangle},
-    DrawState, Graphics, ImageSize,
+    DrawState, Graphics, ImageSize, Transformed,
 };
 
 /// An image
@@ -61,6 +61,16 @@ pub struct Image {
     pub rectangle: Option<Rectangle>,
     /// The image source rectangle
     pub source_rectangle: Option<SourceRectangle>,
+    /// Whether to mirror the image horizontally
+    pub flip_h: bool,
+    /// Whether to mirror the image vertically
+    pub flip_v: bool,
+    /// The rotation around the center of the rectangle, in radians
+    pub rotation: Scalar,
+    /// The colors of the corners, multiplied with the color
+    ///
+    /// In order upper left, upper right, lower right and lower left.
+    pub corner_colors: Option<[Color; 4]>,
 }
 
 impl Image {
@@ -70,6 +80,10 @@ impl Image {
             color: None,
             source_rectangle: None,
             rectangle: None,
+            flip_h: false,
+            flip_v: false,
+            rotation: 0.0,
+            corner_colors: None,
         }
     }
 
@@ -79,6 +93,10 @@ impl Image {
             color: Some(color),
             source_rectangle: None,
             rectangle: None,
+            flip_h: false,
+            flip_v: false,
+            rotation: 0.0,
+            corner_colors: None,
         }
     }
 
@@ -118,6 +136,33 @@ impl Image {
         self
     }
 
+    /// Sets whether to mirror the image horizontally.
+    pub fn flip_h(mut self, value: bool) -> Self {
+        self.flip_h = value;
+        self
+    }
+
+    /// Sets whether to mirror the image vertically.
+    pub fn flip_v(mut self, value: bool) -> Self {
+        self.flip_v = value;
+        self
+    }
+
+    /// Sets rotation around the center of the rectangle (in radians).
+    pub fn rotation_around_center(mut self, angle: Scalar) -> Self {
+        self.rotation = angle;
+        self
+    }
+
+    /// Sets colors of the corners,
+    /// in order upper left, upper right, lower right and lower left.
+    ///
+    /// The colors are interpolated across the image, e.g. for fading out.
+    pub fn corner_colors(mut self, value: [Color; 4]) -> Self {
+        self.corner_colors = Some(value);
+        self
+    }
+
     /// Draws image using default method.
     #[inline(always)]
     pub fn draw<G>(
@@ -142,8 +187,6 @@ impl Image {
     ) where
         G: Graphics,
     {
-        use super::math::Scalar;
-
         let color = self.color.unwrap_or([1.0; 4]);
         let source_rectangle = self.source_rectangle.unwrap_or({
             let (w, h) = texture.get_size();
@@ -155,6 +198,27 @@ impl Image {
             source_rectangle[2] as Scalar,
             source_rectangle[3] as Scalar,
         ]);
+        let transform = self.center_transform(transform, rectangle);
+        if let Some([ul, ur, lr, ll]) = self.corner_colors {
+            let tint = |c: Color| {
+                [
+                    c[0] * color[0],
+                    c[1] * color[1],
+                    c[2] * color[2],
+                    c[3] * color[3],
+                ]
+            };
+            let (ul, ur, lr, ll) = (tint(ul), tint(ur), tint(lr), tint(ll));
+            g.tri_list_uv_c(draw_state, texture, |f| {
+                f(
+                    &triangulation::rect_tri_list_xy(transform, rectangle),
+                    &triangulation::rect_tri_list_uv(texture, source_rectangle),
+                    // Same corners as the vertices.
+                    &[ul, ur, ll, ur, lr, ll],
+                )
+            });
+            return;
+        }
         g.tri_list_uv(draw_state, &color, texture, |f| {
             f(
                 &triangulation::rect_tri_list_xy(transform, rectangle),
@@ -162,6 +226,22 @@ impl Image {
             )
         });
     }
+
+    // Applies flipping and rotation around the center of the rectangle.
+    pub(crate) fn center_transform(&self, transform: Affine2, rectangle: Rectangle) -> Affine2 {
+        if !self.flip_h && !self.flip_v && self.rotation == 0.0 {
+            return transform;
+        }
+        let cx = rectangle[0] + 0.5 * rectangle[2];
+        let cy = rectangle[1] + 0.5 * rectangle[3];
+        let sx = if self.flip_h { -1.0 } else { 1.0 };
+        let sy = if self.flip_v { -1.0 } else { 1.0 };
+        transform
+            .trans(cx, cy)
+            .rot_rad(self.rotation)
+            .scale(sx, sy)
+            .trans(-cx, -cy)
+    }
 }
 
 impl Default for Image {
@@ -170,6 +250,21 @@ impl Default for Image {
     }
 }
 
+/// Describes how a texture covers the screen, see `clear_texture`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BackgroundMode {
+    /// Stretches the texture to the size of the screen.
+    Stretch,
+    /// Scales the texture to cover the screen, keeping the aspect ratio.
+    ///
+    /// The texture is centered and the parts outside the screen are cropped.
+    Cover,
+    /// Repeats the texture from the upper left corner, one pixel per point.
+    ///
+    /// The texture must be created with `Wrap::Repeat`.
+    Tile,
+}
+
 /// Draws many images.
 pub fn draw_many<G>(
     rects: &[(Rectangle, SourceRectangle)],
@@ -191,9 +286,56 @@ pub fn draw_many<G>(
     });
 }
 
+/// An instance of a texture drawn by `Graphics::sprite_batch`.
+#[derive(Copy, Clone, Debug)]
+pub struct SpriteInstance {
+    /// The transform, mapping the source rectangle size to the screen.
+    pub transform: Affine2,
+    /// The source rectangle in the texture.
+    pub source_rectangle: SourceRectangle,
+    /// The color multiplied with the texture.
+    pub color: Color,
+}
+
+/// Draws sprite instances using triangulation.
+///
+/// Each sprite covers a rectangle of the source rectangle size from the origin,
+/// transformed by the instance transform.
+/// Consecutive instances with the same color share a call to `Graphics::tri_list_uv`.
+pub fn sprite_batch_tri<G>(
+    instances: &[SpriteInstance],
+    texture: &<G as Graphics>::Texture,
+    draw_state: &DrawState,
+    g: &mut G,
+) where
+    G: Graphics,
+{
+    let mut start = 0;
+    while start < instances.len() {
+        let color = instances[start].color;
+        let end = instances[start..]
+            .iter()
+            .position(|s| s.color != color)
+            .map(|n| start + n)
+            .unwrap_or(instances.len());
+        if color[3] != 0.0 {
+            g.tri_list_uv(draw_state, &color, texture, |f| {
+                for s in &instances[start..end] {
+                    let src = s.source_rectangle;
+                    f(
+                        &triangulation::rect_tri_list_xy(s.transform, [0.0, 0.0, src[2], src[3]]),
+                        &triangulation::rect_tri_list_uv(texture, src),
+                    )
+                }
+            });
+        }
+        start = end;
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use super::Image;
+    use super::*;
 
     #[test]
     fn test_image() {
@@ -202,4 +344,89 @@ mod test {
             .rect([0.0, 0.0, 100.0, 100.0])
             .src_rect([0.0, 0.0, 32.0, 32.0]);
     }
+
+    #[test]
+    fn test_image_flip() {
+        use crate::software_graphics::{SoftwareGraphics, Texture};
+
+        // Red and black on the top row, green and blue on the bottom row.
+        let texture = Texture::new(image::RgbaImage::from_fn(2, 2, |x, y| match (x, y) {
+            (0, 0) => image::Rgba([255, 0, 0, 255]),
+            (1, 0) => image::Rgba([0, 0, 0, 255]),
+            (0, _) => image::Rgba([0, 255, 0, 255]),
+            _ => image::Rgba([0, 0, 255, 255]),
+        }));
+        let draw = |img: Image| {
+            let mut g = SoftwareGraphics::new(4, 4);
+            g.draw(|c, g| {
+                img.rect([0.0, 0.0, 4.0, 4.0])
+                    .draw(&texture, &c.draw_state, c.transform, g)
+            });
+            g.get_pixel(0, 0)
+        };
+        assert_eq!(draw(Image::new()), [255, 0, 0, 255]);
+        assert_eq!(draw(Image::new().flip_h(true)), [0, 0, 0, 255]);
+        assert_eq!(draw(Image::new().flip_v(true)), [0, 255, 0, 255]);
+        assert_eq!(
+            draw(Image::new().flip_h(true).flip_v(true)),
+            [0, 0, 255, 255]
+        );
+        assert_eq!(
+            draw(Image::new().rotation_around_center(std::f64::consts::PI)),
+            [0, 0, 255, 255]
+        );
+    }
+
+    #[test]
+    fn test_image_corner_colors() {
+        use crate::software_graphics::{SoftwareGraphics, Texture};
+
+        let texture = Texture::new(image::RgbaImage::from_pixel(1, 1, image::Rgba([255; 4])));
+        let (white, black) = ([1.0; 4], [0.0, 0.0, 0.0, 1.0]);
+        let mut g = SoftwareGraphics::new(8, 8);
+        g.draw(|c, g| {
+            Image::new()
+                .rect([0.0, 0.0, 8.0, 8.0])
+                .corner_colors([white, white, black, black])
+                .draw(&texture, &c.draw_state, c.transform, g)
+        });
+        // Fades from white at the top to black at the bottom.
+        assert!(g.get_pixel(4, 0)[0] > 200);
+        assert!(g.get_pixel(4, 7)[0] < 100);
+        assert!(g.get_pixel(4, 4)[0] < g.get_pixel(4, 1)[0]);
+    }
+
+    #[test]
+    fn test_sprite_batch() {
+        use crate::{
+            graphics::Transformed,
+            software_graphics::{SoftwareGraphics, Texture},
+        };
+
+        // Red on the left, blue on the right.
+        let texture = Texture::new(image::RgbaImage::from_fn(2, 1, |x, _| {
+            image::Rgba(if x == 0 {
+                [255, 0, 0, 255]
+            } else {
+                [0, 0, 255, 255]
+            })
+        }));
+        let mut g = SoftwareGraphics::new(8, 8);
+        g.draw(|c, g| {
+            let sprite = |x: Scalar, y: Scalar, src_x: Scalar, color: Color| SpriteInstance {
+                transform: c.transform.trans(x, y).scale(4.0, 4.0),
+                source_rectangle: [src_x, 0.0, 1.0, 1.0],
+                color,
+            };
+            let instances = [
+                sprite(0.0, 0.0, 0.0, [1.0; 4]),
+                sprite(4.0, 0.0, 1.0, [1.0; 4]),
+                sprite(0.0, 4.0, 1.0, [0.0; 4]),
+            ];
+            g.sprite_batch(&texture, &instances, &c.draw_state);
+        });
+        assert_eq!(g.get_pixel(1, 1), [255, 0, 0, 255]);
+        assert_eq!(g.get_pixel(5, 1), [0, 0, 255, 255]);
+        assert_eq!(g.get_pixel(1, 5), [0; 4]);
+    }
 }
@@ -0,0 +1,165 @@
+//! Deferred drawing sorted by layers
+//!
+//! Games often interleave drawing of the world, shadows and user interface,
+//! which requires careful ordering of the draw calls.
+//! A `LayeredRenderer` records drawing into layers during the frame,
+//! and draws the layers sorted by their key at the end.
+//!
+//! ```ignore
+//! const SHADOWS: i32 = 0;
+//! const WORLD: i32 = 1;
+//! const UI: i32 = 2;
+//!
+//! let c = Context::new();
+//! for unit in &units {
+//!     image.draw(&unit.texture, &c.draw_state, unit.transform, layers.layer(WORLD));
+//!     ellipse.draw(unit.shadow, &c.draw_state, unit.transform, layers.layer(SHADOWS));
+//! }
+//! layers.layer(UI).text(&Text::new(16), "Score", &c.draw_state, c.transform);
+//!
+//! // At the end of the frame.
+//! layers.flush_with_cache(c.transform, &mut glyphs, g)?;
+//! ```
+//!
+//! Like with display lists, record with a context without transform,
+//! and pass the transform to the screen when flushing.
+
+use std::collections::BTreeMap;
+
+use super::{display_list::DisplayList, math::Affine2, CharacterCache, Graphics};
+
+/// Records drawing into layers, drawn in order of their keys.
+///
+/// Each layer is a `DisplayList`, which implements `Graphics`.
+/// Drawing in the same layer keeps the order of the draw calls.
+#[derive(Clone, Debug)]
+pub struct LayeredRenderer<T> {
+    layers: BTreeMap<i32, DisplayList<T>>,
+    batch_textures: bool,
+}
+
+impl<T> Default for LayeredRenderer<T> {
+    fn default() -> Self {
+        LayeredRenderer::new()
+    }
+}
+
+impl<T> LayeredRenderer<T> {
+    /// Creates a new renderer without layers.
+    pub fn new() -> LayeredRenderer<T> {
+        LayeredRenderer {
+            layers: BTreeMap::new(),
+            batch_textures: false,
+        }
+    }
+
+    /// Sets whether textured drawing in each layer is grouped by texture,
+    /// see `DisplayList::sort_by_texture`.
+    ///
+    /// This reduces texture switches, but is only correct
+    /// when shapes in the same layer do not overlap.
+    pub fn batch_textures(mut self, value: bool) -> Self {
+        self.batch_textures = value;
+        self
+    }
+
+    /// Returns the layer with a key for recording, creating it if needed.
+    ///
+    /// Layers with lower keys are drawn first.
+    pub fn layer(&mut self, key: i32) -> &mut DisplayList<T> {
+        self.layers.entry(key).or_default()
+    }
+
+    /// Returns `true` if nothing is recorded.
+    pub fn is_empty(&self) -> bool {
+        self.layers.values().all(DisplayList::is_empty)
+    }
+
+    /// Removes all recorded drawing.
+    pub fn clear(&mut self) {
+        self.layers.clear();
+    }
+
+    /// Draws the layers with a transform, skipping text, and clears them.
+    pub fn flush<G>(&mut self, transform: Affine2, g: &mut G)
+    where
+        G: Graphics<Texture = T>,
+    {
+        for layer in self.layers.values_mut() {
+            if self.batch_textures {
+                layer.sort_by_texture();
+            }
+            layer.draw(transform, g);
+        }
+        self.layers.clear();
+    }
+
+    /// Draws the layers with a transform, using a character cache to draw text,
+    /// and clears them.
+    pub fn flush_with_cache<C, G>(
+        &mut self,
+        transform: Affine2,
+        cache: &mut C,
+        g: &mut G,
+    ) -> Result<(), C::Error>
+    where
+        C: CharacterCache<Texture = T>,
+        G: Graphics<Texture = T>,
+    {
+        let layers = std::mem::take(&mut self.layers);
+        for mut layer in layers.into_values() {
+            if self.batch_textures {
+                layer.sort_by_texture();
+            }
+            layer.draw_with_cache(transform, cache, g)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::{
+        graphics::{rectangle, Context, Image},
+        software_graphics::{SoftwareGraphics, Texture},
+    };
+
+    #[test]
+    fn test_layered_renderer() {
+        let mut layers = LayeredRenderer::new().batch_textures(true);
+        let c = Context::new();
+        // Drawn in reverse order.
+        rectangle(
+            [1.0, 0.0, 0.0, 1.0],
+            [0.0, 0.0, 4.0, 4.0],
+            c.transform,
+            layers.layer(2),
+        );
+        rectangle(
+            [0.0, 0.0, 1.0, 1.0],
+            [0.0, 0.0, 8.0, 8.0],
+            c.transform,
+            layers.layer(1),
+        );
+
+        let a = Rc::new(Texture::new(image::RgbaImage::new(1, 1)));
+        let b = Rc::new(Texture::new(image::RgbaImage::new(1, 1)));
+        let layer = layers.layer(0);
+        for (i, texture) in [&a, &b, &a, &b].iter().enumerate() {
+            let image = Image::new().rect([i as f64, 0.0, 1.0, 1.0]);
+            image.draw(*texture, &c.draw_state, c.transform, layer);
+        }
+        assert_eq!(layer.len(), 4);
+        layer.sort_by_texture();
+        assert_eq!(layer.len(), 2);
+
+        let mut g = SoftwareGraphics::new(8, 8);
+        g.draw(|c, g| layers.flush(c.transform, g));
+        assert!(layers.is_empty());
+        assert_eq!(g.get_pixel(1, 1), [255, 0, 0, 255]);
+        assert_eq!(g.get_pixel(6, 6), [0, 0, 255, 255]);
+    }
+}
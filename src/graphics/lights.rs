@@ -0,0 +1,290 @@
+//! 2D lighting with shadows
+//!
+//! Lights shine in all directions up to a radius and are blocked by occluders,
+//! which are line segments such as the walls of a top-down level.
+//! The lit area of each light is a visibility polygon,
+//! drawn as a mesh fading out toward the radius using additive blending.
+//!
+//! With a render target, lights are added to an ambient color offscreen,
+//! and the result is multiplied with the scene:
+//!
+//! ```ignore
+//! let lighting = Lighting::new([0.1, 0.1, 0.2, 1.0])
+//!     .light(Light::new([200.0, 150.0], 300.0, [1.0, 0.9, 0.7, 1.0]))
+//!     .polygon_occluder(&wall);
+//!
+//! draw_scene(&c, g);
+//! lighting.draw_with_target(&mut light_map, &c, g);
+//! ```
+//!
+//! Without a render target, `Lighting::draw` darkens the scene with the ambient color
+//! and adds the lights on top, which looks more like glow than light.
+
+use super::{
+    draw_state::Blend,
+    math::{geom::ray_segment_intersection, Affine2, Scalar, Vec2d},
+    triangulation::{tx, ty},
+    types::{Color, Line},
+    Context, DrawState, Graphics, Image, Rectangle, RenderToTexture,
+    BACK_END_MAX_VERTEX_COUNT as BUFFER_SIZE,
+};
+
+/// A point light.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Light {
+    /// The position.
+    pub pos: Vec2d,
+    /// The distance where the light fades out.
+    pub radius: Scalar,
+    /// The color at the position.
+    pub color: Color,
+}
+
+impl Light {
+    /// Creates a new light.
+    pub fn new(pos: Vec2d, radius: Scalar, color: Color) -> Light {
+        Light { pos, radius, color }
+    }
+
+    /// Draws the lit area, blocked by occluders, using triangulation.
+    ///
+    /// Use additive blending to combine several lights.
+    pub fn draw_tri<G>(
+        &self,
+        occluders: &[Line],
+        resolution: usize,
+        draw_state: &DrawState,
+        transform: Affine2,
+        g: &mut G,
+    ) where
+        G: Graphics,
+    {
+        let polygon = visibility_polygon(self.pos, self.radius, occluders, resolution);
+        if polygon.len() < 2 {
+            return;
+        }
+        let [cx, cy] = self.pos;
+        let color_at = |[x, y]: Vec2d| {
+            let d = ((x - cx) * (x - cx) + (y - cy) * (y - cy)).sqrt();
+            let f = (1.0 - d / self.radius).max(0.0) as f32;
+            [
+                self.color[0] * f,
+                self.color[1] * f,
+                self.color[2] * f,
+                self.color[3] * f,
+            ]
+        };
+        // Split long edges, since colors are interpolated linearly between vertices.
+        let max_len = self.radius / 8.0;
+        let (mut vertices, mut colors) = (vec![], vec![]);
+        for (i, &a) in polygon.iter().enumerate() {
+            let b = polygon[(i + 1) % polygon.len()];
+            let len = ((b[0] - a[0]).powi(2) + (b[1] - a[1]).powi(2)).sqrt();
+            let n = (len / max_len).ceil().max(1.0) as usize;
+            for j in 0..n {
+                // Neighbour triangles must share the exact corners to avoid cracks.
+                let p = |k: usize| {
+                    let t = k as Scalar / n as Scalar;
+                    [a[0] * (1.0 - t) + b[0] * t, a[1] * (1.0 - t) + b[1] * t]
+                };
+                for q in [self.pos, p(j), p(j + 1)] {
+                    vertices.push([tx(transform, q[0], q[1]), ty(transform, q[0], q[1])]);
+                    colors.push(color_at(q));
+                }
+            }
+        }
+        // Keep whole triangles in each chunk.
+        let chunk = BUFFER_SIZE - BUFFER_SIZE % 3;
+        g.tri_list_c(draw_state, |f| {
+            for (vs, cs) in vertices.chunks(chunk).zip(colors.chunks(chunk)) {
+                f(vs, cs);
+            }
+        });
+    }
+}
+
+/// Computes the area visible from a point within a radius, blocked by occluders.
+///
+/// The radius is approximated by a polygon with `resolution` sides.
+/// Returns the corners of the visibility polygon, sorted by angle.
+pub fn visibility_polygon(
+    origin: Vec2d,
+    radius: Scalar,
+    occluders: &[Line],
+    resolution: usize,
+) -> Vec<Vec2d> {
+    use std::f64::consts::PI;
+
+    let [ox, oy] = origin;
+    let resolution = resolution.max(3);
+    // Bound the visible area by a polygon around the circle.
+    let r = radius / (PI / resolution as Scalar).cos();
+    let corner = |i: usize| {
+        let a = 2.0 * PI * i as Scalar / resolution as Scalar;
+        [ox + r * a.cos(), oy + r * a.sin()]
+    };
+    let mut segments: Vec<Line> = (0..resolution)
+        .map(|i| {
+            let (a, b) = (corner(i), corner(i + 1));
+            [a[0], a[1], b[0], b[1]]
+        })
+        .collect();
+    segments.extend_from_slice(occluders);
+
+    // Cast rays at every segment end, and slightly beside it to see past corners.
+    const EPS: Scalar = 1e-5;
+    let mut angles = vec![];
+    for s in &segments {
+        for [x, y] in [[s[0], s[1]], [s[2], s[3]]] {
+            let a = (y - oy).atan2(x - ox);
+            angles.extend_from_slice(&[a - EPS, a, a + EPS]);
+        }
+    }
+    angles.sort_by(|a, b| a.total_cmp(b));
+    angles.dedup();
+
+    let mut res = Vec::with_capacity(angles.len());
+    for a in angles {
+        let ray = [ox, oy, a.cos(), a.sin()];
+        let nearest = segments
+            .iter()
+            .filter_map(|&s| ray_segment_intersection(ray, s))
+            .fold(Scalar::INFINITY, Scalar::min);
+        if nearest.is_finite() {
+            res.push([ox + ray[2] * nearest, oy + ray[3] * nearest]);
+        }
+    }
+    res
+}
+
+/// Lights and occluders with an ambient color.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Lighting {
+    /// The color of unlit areas.
+    pub ambient: Color,
+    /// The lights.
+    pub lights: Vec<Light>,
+    /// The segments blocking light.
+    pub occluders: Vec<Line>,
+    /// The number of sides of the polygon approximating the light radius.
+    pub resolution: usize,
+}
+
+impl Lighting {
+    /// Creates new lighting without lights.
+    pub fn new(ambient: Color) -> Lighting {
+        Lighting {
+            ambient,
+            lights: vec![],
+            occluders: vec![],
+            resolution: 32,
+        }
+    }
+
+    /// Adds a light.
+    pub fn light(mut self, light: Light) -> Self {
+        self.lights.push(light);
+        self
+    }
+
+    /// Adds a segment blocking light.
+    pub fn occluder(mut self, line: Line) -> Self {
+        self.occluders.push(line);
+        self
+    }
+
+    /// Adds the edges of a closed polygon as occluders.
+    pub fn polygon_occluder(mut self, polygon: &[Vec2d]) -> Self {
+        for (i, &a) in polygon.iter().enumerate() {
+            let b = polygon[(i + 1) % polygon.len()];
+            self.occluders.push([a[0], a[1], b[0], b[1]]);
+        }
+        self
+    }
+
+    /// Sets the number of sides of the polygon approximating the light radius.
+    pub fn resolution(mut self, value: usize) -> Self {
+        self.resolution = value;
+        self
+    }
+
+    /// Draws all lights with additive blending.
+    pub fn draw_lights<G>(&self, draw_state: &DrawState, transform: Affine2, g: &mut G)
+    where
+        G: Graphics,
+    {
+        let draw_state = draw_state.blend(Blend::Add);
+        for light in &self.lights {
+            light.draw_tri(&self.occluders, self.resolution, &draw_state, transform, g);
+        }
+    }
+
+    /// Darkens the view with the ambient color, and adds the lights on top.
+    ///
+    /// This works with every back-end, but the lights do not brighten
+    /// the colors of the scene, see `draw_with_target`.
+    pub fn draw<G>(&self, c: &Context, g: &mut G)
+    where
+        G: Graphics,
+    {
+        let [w, h] = c.get_view_size();
+        let ambient = [self.ambient[0], self.ambient[1], self.ambient[2], 1.0];
+        Rectangle::new(ambient).draw(
+            [0.0, 0.0, w, h],
+            &c.draw_state.blend(Blend::Multiply),
+            c.view,
+            g,
+        );
+        self.draw_lights(&c.draw_state, c.transform, g);
+    }
+
+    /// Renders the light map into a render target, and multiplies it with the view.
+    ///
+    /// The render target should have the size of the view,
+    /// such that one point of the view is one pixel of the light map.
+    pub fn draw_with_target<G>(&self, target: &mut G::RenderTarget, c: &Context, g: &mut G)
+    where
+        G: RenderToTexture,
+    {
+        let relative = c.relative_transform();
+        g.draw_to_texture(target, |tc, g| {
+            g.clear_color(self.ambient);
+            let transform = super::math::multiply(tc.transform, relative);
+            self.draw_lights(&tc.draw_state, transform, g);
+        });
+        let [w, h] = c.get_view_size();
+        Image::new().rect([0.0, 0.0, w, h]).draw(
+            G::render_target_texture(target),
+            &c.draw_state.blend(Blend::Multiply),
+            c.view,
+            g,
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::software_graphics::SoftwareGraphics;
+
+    #[test]
+    fn test_lighting() {
+        let polygon = visibility_polygon([0.0, 0.0], 2.0, &[], 16);
+        assert!(polygon.len() >= 16);
+        for [x, y] in polygon {
+            let d = (x * x + y * y).sqrt();
+            assert!((2.0 - 1e-9..2.2).contains(&d));
+        }
+
+        let lighting = Lighting::new([0.0, 0.0, 0.0, 1.0])
+            .light(Light::new([4.0, 4.0], 4.0, [1.0; 4]))
+            .occluder([5.0, 0.0, 5.0, 8.0]);
+        let mut g = SoftwareGraphics::new(8, 8);
+        g.draw(|c, g| {
+            g.clear_color([0.5, 0.5, 0.5, 1.0]);
+            lighting.draw(&c, g);
+        });
+        assert!(g.get_pixel(2, 4)[0] > 64);
+        assert_eq!(g.get_pixel(6, 4)[0], 0);
+    }
+}
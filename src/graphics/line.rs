@@ -2,6 +2,7 @@
 
 use super::{
     math::{Affine2, Scalar},
+    radians::Radians,
     triangulation, types,
     types::{Color, Radius, Resolution},
     DrawState, Graphics,
@@ -43,6 +44,11 @@ pub struct Line {
     pub radius: Radius,
     /// The line shape
     pub shape: Shape,
+    /// Optional width of a feathered rim for anti-aliasing.
+    ///
+    /// The rim fades out outside the edge of the line.
+    /// A width of about one pixel gives smooth edges without multisampling.
+    pub feather: Option<Radius>,
 }
 
 impl Line {
@@ -52,6 +58,7 @@ impl Line {
             color,
             radius,
             shape: Shape::Square,
+            feather: None,
         }
     }
 
@@ -61,6 +68,7 @@ impl Line {
             color,
             radius,
             shape: Shape::Round,
+            feather: None,
         }
     }
 
@@ -88,6 +96,12 @@ impl Line {
         self
     }
 
+    /// Sets width of a feathered rim for anti-aliasing.
+    pub fn feather(mut self, value: Radius) -> Self {
+        self.feather = Some(value);
+        self
+    }
+
     /// Draws line using default method between points.
     #[inline(always)]
     pub fn draw_from_to<P: Into<types::Vec2d>, G>(
@@ -144,6 +158,41 @@ impl Line {
                 |vertices| f(vertices),
             )
         });
+        if let Some(width) = self.feather {
+            let outline = self.outline(line);
+            g.tri_list_c(draw_state, |f| {
+                triangulation::with_feather_tri_list_c(
+                    transform,
+                    &outline,
+                    width,
+                    |_| self.color,
+                    |vertices, colors| f(vertices, colors),
+                )
+            });
+        }
+    }
+
+    // Returns the outline of the line with end caps.
+    fn outline(&self, line: types::Line) -> Vec<types::Vec2d> {
+        let (x1, y1, x2, y2) = (line[0], line[1], line[2], line[3]);
+        let (dx, dy) = (x2 - x1, y2 - y1);
+        let len = (dx * dx + dy * dy).sqrt();
+        if len == 0.0 {
+            return vec![];
+        }
+        let (ux, uy) = (dx / len, dy / len);
+        let n = self.shape.resolution();
+        let mut res = Vec::with_capacity(2 * n as usize);
+        // Half circles at both ends, starting at the start of the line.
+        for (i, &(x, y)) in [(x1, y1), (x2, y2)].iter().enumerate() {
+            for j in 0..n {
+                let angle = (j as Scalar / (n - 1) as Scalar + i as Scalar + 0.5)
+                    * <Scalar as Radians>::_180();
+                let (a, b) = (angle.cos() * self.radius, angle.sin() * self.radius);
+                res.push([x + ux * a - uy * b, y + uy * a + ux * b]);
+            }
+        }
+        res
     }
 
     /// Draws an arrow
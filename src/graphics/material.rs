@@ -0,0 +1,32 @@
+//! Custom materials
+//!
+//! A material replaces the shaders of a back-end while drawing,
+//! e.g. for grayscale, outline or CRT effects.
+//! Materials are registered with the back-end and referred to by id,
+//! such that generic code can request them by name.
+//!
+//! ```ignore
+//! let grayscale = g.find_material("grayscale");
+//! match grayscale {
+//!     Some(id) => g.with_material(id, |g| image.draw(&texture, &c.draw_state, c.transform, g)),
+//!     None => image.draw(&texture, &c.draw_state, c.transform, g),
+//! }
+//! ```
+
+/// Identifies a material registered with a back-end.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MaterialId(pub usize);
+
+/// Implemented by back-ends that support custom materials.
+///
+/// Use `Graphics::with_material` to draw with a registered material.
+pub trait MaterialRegistry {
+    /// The back-end specific description of a material, e.g. compiled shaders.
+    type Material;
+
+    /// Registers a material with a name, replacing any material with the same name.
+    fn register_material(&mut self, name: &str, material: Self::Material) -> MaterialId;
+
+    /// Finds a material by name.
+    fn find_material(&self, name: &str) -> Option<MaterialId>;
+}
@@ -11,10 +11,14 @@ pub use vecmath::{
 };
 
 use super::{
-    modular_index::previous,
-    types::{Area, Color, Line, Polygon, Ray, Rectangle, SourceRectangle, Triangle},
+    modular_index::{next, previous},
+    types::{
+        Area, Color, Line, Polygon, Polygons, Ray, Rectangle, Resolution, SourceRectangle, Triangle,
+    },
 };
 
+pub mod geom;
+
 /// The type used for scalars.
 pub type Scalar = f64;
 
@@ -151,6 +155,59 @@ where
     [[_1, _0, _0], [_0, _1, _0]]
 }
 
+/// Computes the determinant of the linear part of a matrix.
+///
+/// The determinant is the factor that areas are scaled with,
+/// negative when the transform flips orientation.
+#[inline(always)]
+pub fn determinant<T>(m: Affine2<T>) -> T
+where
+    T: Float,
+{
+    m[0][0] * m[1][1] - m[0][1] * m[1][0]
+}
+
+/// Returns `true` if the matrix has an inverse.
+///
+/// A matrix with zero scale along some axis collapses the plane,
+/// so it can not be inverted.
+#[inline(always)]
+pub fn is_invertible<T>(m: Affine2<T>) -> bool
+where
+    T: Float,
+{
+    use vecmath::traits::Zero;
+
+    determinant(m) != Zero::zero()
+}
+
+/// Computes the inverse of a matrix, if it exists.
+///
+/// Unlike `invert`, this does not return infinite or NaN values
+/// for matrices without inverse.
+#[inline(always)]
+pub fn try_invert<T>(m: Affine2<T>) -> Option<Affine2<T>>
+where
+    T: Float,
+{
+    if is_invertible(m) {
+        Some(invert(m))
+    } else {
+        None
+    }
+}
+
+/// Multiplies a chain of matrices from left to right.
+///
+/// The last matrix is applied first to positions,
+/// like nested transforms in a scene.
+pub fn multiply_all<T>(matrices: &[Affine2<T>]) -> Affine2<T>
+where
+    T: Float,
+{
+    matrices.iter().fold(identity(), |acc, &m| multiply(acc, m))
+}
+
 /// Extract scale information from matrix.
 #[inline(always)]
 pub fn get_scale<T>(m: Affine2<T>) -> Vec2d<T>
@@ -163,6 +220,50 @@ where
     ]
 }
 
+/// The components of an affine transform.
+///
+/// Composing `translation`, `rotation`, `shear` and `scale` in that order
+/// gives back the transform, e.g.
+/// `identity().trans_pos(translation).rot_rad(rotation).shear(shear, 0.0).scale_pos(scale)`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Decomposed<T = Scalar> {
+    /// The translation.
+    pub translation: Vec2d<T>,
+    /// The rotation in radians.
+    pub rotation: T,
+    /// The scale along the x and y axes.
+    ///
+    /// The y scale is negative when the transform flips.
+    pub scale: Vec2d<T>,
+    /// The shear of the x axis along the y axis.
+    pub shear: T,
+}
+
+/// Extracts translation, rotation, scale and shear from a matrix.
+///
+/// The rotation and scale are undefined when the x axis collapses to zero,
+/// and the shear when the y axis collapses to zero.
+#[inline(always)]
+pub fn decompose<T>(m: Affine2<T>) -> Decomposed<T>
+where
+    T: Float,
+{
+    let (a, b) = (m[0][0], m[1][0]);
+    let (c, d) = (m[0][1], m[1][1]);
+    let sx = (a * a + b * b).sqrt();
+    let rotation = b.atan2(a);
+    let (cos, sin) = (rotation.cos(), rotation.sin());
+    // Remove rotation to get shear and scale of the y axis.
+    let sy = d * cos - c * sin;
+    let shear = (c * cos + d * sin) / sy;
+    Decomposed {
+        translation: [m[0][2], m[1][2]],
+        rotation,
+        scale: [sx, sy],
+        shear,
+    }
+}
+
 /// Compute the shortest vector from point to ray.
 /// A ray stores starting point and directional vector.
 #[inline(always)]
@@ -306,6 +407,92 @@ where
     }
 }
 
+/// Computes the smallest rectangle containing two rectangles.
+#[inline(always)]
+pub fn union_rectangle<T>(a: Rectangle<T>, b: Rectangle<T>) -> Rectangle<T>
+where
+    T: Float,
+{
+    let x = a[0].min(b[0]);
+    let y = a[1].min(b[1]);
+    let w = (a[0] + a[2]).max(b[0] + b[2]) - x;
+    let h = (a[1] + a[3]).max(b[1] + b[3]) - y;
+    [x, y, w, h]
+}
+
+/// Returns true if point is inside rectangle.
+///
+/// Includes the upper left edges but not the lower right edges,
+/// such that neighbour rectangles do not share points.
+#[inline(always)]
+pub fn inside_rectangle<T>(rect: Rectangle<T>, v: Vec2d<T>) -> bool
+where
+    T: Float,
+{
+    v[0] >= rect[0] && v[1] >= rect[1] && v[0] < rect[0] + rect[2] && v[1] < rect[1] + rect[3]
+}
+
+/// Grows a rectangle by a horizontal and vertical distance on each side.
+///
+/// Negative distances shrink the rectangle, see `margin_rectangle`.
+#[allow(clippy::just_underscores_and_digits)] // Naming convention.
+#[inline(always)]
+pub fn inflate_rectangle<T>(rect: Rectangle<T>, v: Vec2d<T>) -> Rectangle<T>
+where
+    T: Float,
+{
+    use vecmath::traits::FromPrimitive;
+
+    let _2: T = FromPrimitive::from_f64(2.0);
+    [
+        rect[0] - v[0],
+        rect[1] - v[1],
+        rect[2] + _2 * v[0],
+        rect[3] + _2 * v[1],
+    ]
+}
+
+/// Creates a rectangle from two opposite corners, in any order.
+#[inline(always)]
+pub fn rectangle_from_corners<T>(a: Vec2d<T>, b: Vec2d<T>) -> Rectangle<T>
+where
+    T: Float,
+{
+    let (x, y) = (a[0].min(b[0]), a[1].min(b[1]));
+    [x, y, a[0].max(b[0]) - x, a[1].max(b[1]) - y]
+}
+
+/// Returns the upper left and lower right corners of a rectangle.
+#[inline(always)]
+pub fn rectangle_corners<T>(rect: Rectangle<T>) -> [Vec2d<T>; 2]
+where
+    T: Float,
+{
+    [[rect[0], rect[1]], [rect[0] + rect[2], rect[1] + rect[3]]]
+}
+
+/// Computes the axis aligned bounding box of a transformed rectangle.
+#[inline(always)]
+pub fn transform_rectangle<T>(m: Affine2<T>, rect: Rectangle<T>) -> Rectangle<T>
+where
+    T: Float,
+{
+    let [x, y, w, h] = rect;
+    let a = transform_pos(m, [x, y]);
+    let b = transform_pos(m, [x + w, y]);
+    let c = transform_pos(m, [x, y + h]);
+    let d = transform_pos(m, [x + w, y + h]);
+    let (x0, y0) = (
+        a[0].min(b[0]).min(c[0]).min(d[0]),
+        a[1].min(b[1]).min(c[1]).min(d[1]),
+    );
+    let (x1, y1) = (
+        a[0].max(b[0]).max(c[0]).max(d[0]),
+        a[1].max(b[1]).max(c[1]).max(d[1]),
+    );
+    [x0, y0, x1 - x0, y1 - y0]
+}
+
 #[cfg(test)]
 mod test_overlap {
     use super::overlap_rectangle;
@@ -322,6 +509,19 @@ mod test_overlap {
         assert_eq!(e, c);
     }
 
+    #[test]
+    fn union_contains() {
+        use super::{inflate_rectangle, inside_rectangle, rectangle_from_corners, union_rectangle};
+
+        let a = [0.0, 0.0, 2.0, 2.0];
+        let b = rectangle_from_corners([5.0, 4.0], [3.0, 1.0]);
+        assert_eq!(b, [3.0, 1.0, 2.0, 3.0]);
+        assert_eq!(union_rectangle(a, b), [0.0, 0.0, 5.0, 4.0]);
+        assert!(inside_rectangle(a, [0.0, 1.0]));
+        assert!(!inside_rectangle(a, [2.0, 1.0]));
+        assert_eq!(inflate_rectangle(a, [1.0, 0.5]), [-1.0, -0.5, 4.0, 3.0]);
+    }
+
     #[test]
     fn edge() {
         let a = [0.0, 0.0, 100.0, 100.0];
@@ -349,6 +549,37 @@ pub fn modular_offset<T: Add<Output = T> + Rem<Output = T> + Copy>(n: &T, i: &T,
     (*i + (*off % *n + *n)) % *n
 }
 
+#[cfg(test)]
+mod test_decompose {
+    use super::*;
+    use crate::graphics::Transformed;
+
+    #[test]
+    fn test_decompose() {
+        let m = identity()
+            .trans(3.0, 4.0)
+            .rot_rad(0.5)
+            .shear(0.25, 0.0)
+            .scale(2.0, -3.0);
+        let d = decompose(m);
+        let eps = 1e-9;
+        assert!((d.translation[0] - 3.0).abs() < eps && (d.translation[1] - 4.0).abs() < eps);
+        assert!((d.rotation - 0.5).abs() < eps);
+        assert!((d.scale[0] - 2.0).abs() < eps && (d.scale[1] + 3.0).abs() < eps);
+        assert!((d.shear - 0.25).abs() < eps);
+    }
+
+    #[test]
+    fn test_invert() {
+        let m = identity().trans(3.0, 4.0).scale(2.0, -0.5);
+        assert_eq!(determinant(m), -1.0);
+        assert_eq!(try_invert(scale(0.0, 1.0)), None);
+        let inv = try_invert(m).unwrap();
+        assert_eq!(multiply_all(&[m, inv, m]), m);
+        assert_eq!(transform_pos(inv, transform_pos(m, [1.0, 2.0])), [1.0, 2.0]);
+    }
+}
+
 #[cfg(test)]
 mod test_modular_offset {
     use super::*;
@@ -600,3 +831,764 @@ pub fn hsv(color: Color, h_rad: f32, s: f32, v: f32) -> Color {
         color[3],
     ]
 }
+
+/// Computes the squared distance from a point to a line segment.
+#[allow(clippy::just_underscores_and_digits)] // Naming convention.
+#[inline(always)]
+pub fn square_distance_to_segment<T>(line: Line<T>, v: Vec2d<T>) -> T
+where
+    T: Float,
+{
+    use vecmath::traits::{One, Zero};
+
+    let _0: T = Zero::zero();
+    let _1: T = One::one();
+    let a = [line[0], line[1]];
+    let ab = [line[2] - line[0], line[3] - line[1]];
+    let av = sub(v, a);
+    let len = square_len(ab);
+    let t = if len == _0 {
+        _0
+    } else {
+        let t = dot(av, ab) / len;
+        if t < _0 {
+            _0
+        } else if t > _1 {
+            _1
+        } else {
+            t
+        }
+    };
+    square_len(sub(av, mul_scalar(ab, t)))
+}
+
+/// Simplifies a polyline using the Ramer-Douglas-Peucker algorithm.
+///
+/// Removes vertices that are closer than `tolerance` to the line segment
+/// between the vertices that are kept around them.
+/// The first and last vertex are always kept.
+///
+/// To simplify a closed polygon, repeat the first vertex at the end.
+///
+/// Source: <https://en.wikipedia.org/wiki/Ramer%E2%80%93Douglas%E2%80%93Peucker_algorithm>
+pub fn simplify<T>(polyline: Polygon<'_, T>, tolerance: T) -> Vec<Vec2d<T>>
+where
+    T: Float,
+{
+    let n = polyline.len();
+    if n < 3 {
+        return polyline.to_vec();
+    }
+
+    let square_tolerance = tolerance * tolerance;
+    let mut keep = vec![false; n];
+    keep[0] = true;
+    keep[n - 1] = true;
+    // Ranges of indices that are not yet simplified.
+    let mut stack = vec![(0, n - 1)];
+    while let Some((first, last)) = stack.pop() {
+        let a = polyline[first];
+        let b = polyline[last];
+        let line = [a[0], a[1], b[0], b[1]];
+        let mut max: Option<(usize, T)> = None;
+        for (i, &v) in polyline.iter().enumerate().take(last).skip(first + 1) {
+            let d = square_distance_to_segment(line, v);
+            if max.map(|(_, m)| d > m).unwrap_or(true) {
+                max = Some((i, d));
+            }
+        }
+        if let Some((i, d)) = max {
+            if d > square_tolerance {
+                keep[i] = true;
+                stack.push((first, i));
+                stack.push((i, last));
+            }
+        }
+    }
+
+    polyline
+        .iter()
+        .zip(keep)
+        .filter(|&(_, k)| k)
+        .map(|(&v, _)| v)
+        .collect()
+}
+
+/// Computes the convex hull of points using Andrew's monotone chain algorithm.
+///
+/// Returns the vertices of the hull counter-clockwise in a coordinate system
+/// where the y axis points up, starting with the lowest x coordinate.
+/// Collinear points on the edges of the hull are removed.
+///
+/// Source: <https://en.wikibooks.org/wiki/Algorithm_Implementation/Geometry/Convex_hull/Monotone_chain>
+pub fn convex_hull<T>(points: &[Vec2d<T>]) -> Vec<Vec2d<T>>
+where
+    T: Float,
+{
+    use vecmath::traits::Zero;
+
+    let mut points = points.to_vec();
+    points.sort_by(|a, b| {
+        a[0].partial_cmp(&b[0])
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then(a[1].partial_cmp(&b[1]).unwrap_or(std::cmp::Ordering::Equal))
+    });
+    points.dedup();
+    if points.len() < 3 {
+        return points;
+    }
+
+    let zero: T = Zero::zero();
+    let turns_left = |hull: &[Vec2d<T>], p: Vec2d<T>| {
+        let n = hull.len();
+        let (a, b) = (hull[n - 2], hull[n - 1]);
+        line_side([a[0], a[1], b[0], b[1]], p) > zero
+    };
+    let mut hull: Vec<Vec2d<T>> = Vec::with_capacity(2 * points.len());
+    // Lower hull.
+    for &p in &points {
+        while hull.len() >= 2 && !turns_left(&hull, p) {
+            hull.pop();
+        }
+        hull.push(p);
+    }
+    // Upper hull.
+    let lower = hull.len() + 1;
+    for &p in points.iter().rev().skip(1) {
+        while hull.len() >= lower && !turns_left(&hull, p) {
+            hull.pop();
+        }
+        hull.push(p);
+    }
+    // The last point is the same as the first.
+    hull.pop();
+    hull
+}
+
+/// Redistributes `n` vertices evenly along the perimeter of a closed polygon.
+///
+/// The first vertex is kept, and the rest follow the winding order.
+/// Used to interpolate between polygons with different vertex counts.
+#[allow(clippy::just_underscores_and_digits)] // Naming convention.
+pub fn resample_polygon<T>(polygon: Polygon<'_, T>, n: usize) -> Vec<Vec2d<T>>
+where
+    T: Float,
+{
+    use vecmath::traits::Zero;
+
+    let _0: T = Zero::zero();
+    let len = polygon.len();
+    if len == 0 {
+        return vec![];
+    }
+    let edge = |i: usize| (polygon[i], polygon[(i + 1) % len]);
+    let edge_len = |i: usize| {
+        let (a, b) = edge(i);
+        square_len(sub(b, a)).sqrt()
+    };
+    let perimeter = (0..len).fold(_0, |sum, i| sum + edge_len(i));
+    if perimeter == _0 {
+        return vec![polygon[0]; n];
+    }
+    let step = perimeter / T::from_f64(n as f64);
+    let mut res = Vec::with_capacity(n);
+    // The current edge and the distance along the perimeter where it starts.
+    let mut i = 0;
+    let mut start = _0;
+    for k in 0..n {
+        let d = step * T::from_f64(k as f64);
+        while i + 1 < len && start + edge_len(i) <= d {
+            start += edge_len(i);
+            i += 1;
+        }
+        let (a, b) = edge(i);
+        let l = edge_len(i);
+        let t = if l == _0 { _0 } else { (d - start) / l };
+        res.push(add(a, mul_scalar(sub(b, a), t)));
+    }
+    res
+}
+
+/// Describes how corners are joined when offsetting a polygon.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Join<T = Scalar> {
+    /// Extends the edges until they meet.
+    ///
+    /// Falls back to a bevel when the miter length divided by
+    /// the offset distance exceeds the limit.
+    Miter(T),
+    /// Cuts off the corner with a straight line.
+    Bevel,
+    /// Rounds the corner, with resolution per 360 degrees.
+    Round(Resolution),
+}
+
+/// Describes how the ends of an open stroke are drawn.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Cap {
+    /// Ends exactly at the end point.
+    Butt,
+    /// Extends past the end point by half the stroke width.
+    Square,
+    /// Ends with a half circle, with resolution per 360 degrees.
+    Round(Resolution),
+}
+
+/// Describes which areas of a shape with multiple or self-intersecting contours are inside.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum FillRule {
+    /// Inside where a ray crosses an odd number of edges.
+    #[default]
+    EvenOdd,
+    /// Inside where edges going up and down do not cancel out.
+    ///
+    /// Contours with opposite winding order become holes.
+    NonZero,
+}
+
+/// Offsets a simple polygon by a distance, calling `f` for each new vertex.
+///
+/// The closure receives the corner of the original polygon
+/// and an offset vertex belonging to that corner.
+/// A corner gets more than one vertex when it is beveled or rounded.
+///
+/// A positive distance grows the polygon and a negative distance shrinks it,
+/// independent of the winding order.
+/// Corners that turn away from the offset direction are joined using `join`,
+/// while the others use the intersection of the offset edges.
+/// Offsetting by a large negative distance might produce a self-intersecting result.
+#[allow(clippy::just_underscores_and_digits)] // Naming convention.
+pub fn offset_polygon_with<T, F>(polygon: Polygon<'_, T>, distance: T, join: Join<T>, mut f: F)
+where
+    T: Float,
+    F: FnMut(Vec2d<T>, Vec2d<T>),
+{
+    use vecmath::traits::{FromPrimitive, One, Radians, Zero};
+
+    let _0: T = Zero::zero();
+    let _1: T = One::one();
+    let _2: T = FromPrimitive::from_f64(2.0);
+    let eps: T = FromPrimitive::from_f64(1e-9);
+
+    // Remove repeated vertices, since they have no edge normal.
+    let mut points: Vec<Vec2d<T>> = Vec::with_capacity(polygon.len());
+    for &p in polygon {
+        if points.last() != Some(&p) {
+            points.push(p);
+        }
+    }
+    while points.len() > 1 && points.first() == points.last() {
+        points.pop();
+    }
+    let n = points.len();
+    if n < 3 {
+        return;
+    }
+    let (area, _) = area_centroid(&points);
+    if area == _0 {
+        return;
+    }
+    // Flip the normals of clockwise polygons such that they point outwards.
+    let sign = if area > _0 { _1 } else { -_1 };
+    let normal = |a: Vec2d<T>, b: Vec2d<T>| {
+        let d = sub(b, a);
+        let len = square_len(d).sqrt();
+        [sign * d[1] / len, -sign * d[0] / len]
+    };
+    let offset = |v: Vec2d<T>, normal: Vec2d<T>| add(v, mul_scalar(normal, distance));
+
+    for i in 0..n {
+        let prev = points[previous(n, i)];
+        let v = points[i];
+        let nxt = points[next(n, i)];
+        let n0 = normal(prev, v);
+        let n1 = normal(v, nxt);
+        let cos = dot(n0, n1);
+        let turn = sign * cross(sub(v, prev), sub(nxt, v));
+        let outer = if distance > _0 { turn > _0 } else { turn < _0 };
+        if !outer && _1 + cos > eps {
+            // The offset edges intersect at the miter point.
+            f(v, add(v, mul_scalar(add(n0, n1), distance / (_1 + cos))));
+            continue;
+        }
+
+        match join {
+            // The miter length divided by distance is `sqrt(2 / (1 + cos))`.
+            Join::Miter(limit) if _1 + cos > eps && _2 <= limit * limit * (_1 + cos) => {
+                f(v, add(v, mul_scalar(add(n0, n1), distance / (_1 + cos))));
+            }
+            Join::Miter(_) | Join::Bevel => {
+                f(v, offset(v, n0));
+                f(v, offset(v, n1));
+            }
+            Join::Round(resolution) => {
+                let angle = cross(n0, n1).atan2(cos);
+                let (dir, angle) = if angle < _0 {
+                    (-_1, -angle)
+                } else {
+                    (_1, angle)
+                };
+                let step = <T as Radians>::_360() / FromPrimitive::from_u32(resolution.max(1));
+                f(v, offset(v, n0));
+                let mut a = step;
+                while a < angle {
+                    let (sin, cos) = ((dir * a).sin(), (dir * a).cos());
+                    let r = [n0[0] * cos - n0[1] * sin, n0[0] * sin + n0[1] * cos];
+                    f(v, offset(v, r));
+                    a += step;
+                }
+                f(v, offset(v, n1));
+            }
+        }
+    }
+}
+
+/// Offsets a simple polygon by a distance.
+///
+/// See `offset_polygon_with` for more information.
+pub fn offset_polygon<T>(polygon: Polygon<'_, T>, distance: T, join: Join<T>) -> Vec<Vec2d<T>>
+where
+    T: Float,
+{
+    let mut res = vec![];
+    offset_polygon_with(polygon, distance, join, |_, v| res.push(v));
+    res
+}
+
+/// Offsets a shape with holes by a distance.
+///
+/// A shape consists of polygons, where polygons inside other polygons are holes.
+/// A positive distance grows the shape and shrinks its holes,
+/// e.g. for outlines or collision margins,
+/// while a negative distance insets the shape.
+/// Offset polygons that overlap are merged, see `boolean`.
+pub fn offset_shape<T>(shape: Polygons<'_, T>, distance: T, join: Join<T>) -> Vec<Vec<Vec2d<T>>>
+where
+    T: Float,
+{
+    let mut outer: Vec<Vec<Vec2d<T>>> = vec![];
+    let mut holes: Vec<Vec<Vec2d<T>>> = vec![];
+    for (i, polygon) in shape.iter().enumerate() {
+        let v = match polygon.first() {
+            Some(&v) => v,
+            None => continue,
+        };
+        // A polygon is a hole when inside an odd number of other polygons.
+        let others: Vec<Polygon<'_, T>> = shape
+            .iter()
+            .enumerate()
+            .filter(|&(j, _)| j != i)
+            .map(|(_, &p)| p)
+            .collect();
+        if inside_even_odd(&others, v) {
+            holes.push(offset_polygon(polygon, -distance, join));
+        } else {
+            let grown = offset_polygon(polygon, distance, join);
+            outer = boolean(&polygon_refs(&outer), &[&grown], BooleanOp::Union);
+        }
+    }
+    for hole in holes {
+        outer = boolean(&polygon_refs(&outer), &[&hole], BooleanOp::Difference);
+    }
+    outer
+}
+
+// Borrows a list of polygons as a shape.
+fn polygon_refs<T>(polygons: &[Vec<Vec2d<T>>]) -> Vec<Polygon<'_, T>> {
+    polygons.iter().map(|p| &p[..]).collect()
+}
+
+/// Boolean operation on shapes, see `boolean`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BooleanOp {
+    /// Area covered by either shape.
+    Union,
+    /// Area covered by both shapes.
+    Intersection,
+    /// Area covered by the first shape but not the second.
+    Difference,
+    /// Area covered by exactly one of the shapes.
+    Xor,
+}
+
+impl BooleanOp {
+    /// Returns whether a point is in the result, given whether it is in each shape.
+    pub fn apply(self, a: bool, b: bool) -> bool {
+        match self {
+            BooleanOp::Union => a || b,
+            BooleanOp::Intersection => a && b,
+            BooleanOp::Difference => a && !b,
+            BooleanOp::Xor => a != b,
+        }
+    }
+}
+
+/// Returns true if point is inside a shape, using the even-odd rule.
+///
+/// A shape consists of polygons, where polygons inside other polygons are holes.
+pub fn inside_even_odd<T>(shape: Polygons<'_, T>, v: Vec2d<T>) -> bool
+where
+    T: Float,
+{
+    let mut inside = false;
+    for polygon in shape {
+        let n = polygon.len();
+        for i in 0..n {
+            let a = polygon[i];
+            let b = polygon[(i + 1) % n];
+            if (a[1] > v[1]) != (b[1] > v[1]) {
+                let x = a[0] + (v[1] - a[1]) / (b[1] - a[1]) * (b[0] - a[0]);
+                if v[0] < x {
+                    inside = !inside;
+                }
+            }
+        }
+    }
+    inside
+}
+
+/// Computes a boolean operation on two shapes.
+///
+/// A shape consists of polygons filled using the even-odd rule,
+/// so polygons inside other polygons are holes, independent of winding order.
+/// Returns the polygons of the resulting shape, which might contain holes.
+///
+/// The result is to the left of each edge,
+/// in a coordinate system where the y axis points up.
+#[allow(clippy::just_underscores_and_digits)] // Naming convention.
+pub fn boolean<T>(a: Polygons<'_, T>, b: Polygons<'_, T>, op: BooleanOp) -> Vec<Vec<Vec2d<T>>>
+where
+    T: Float,
+{
+    use vecmath::traits::FromPrimitive;
+
+    let _05: T = FromPrimitive::from_f64(0.5);
+
+    // Use tolerances relative to the size of shapes.
+    let mut min = [None::<T>; 2];
+    let mut max = [None::<T>; 2];
+    for v in a.iter().chain(b.iter()).flat_map(|p| p.iter()) {
+        for i in 0..2 {
+            if min[i].map(|m| v[i] < m).unwrap_or(true) {
+                min[i] = Some(v[i]);
+            }
+            if max[i].map(|m| v[i] > m).unwrap_or(true) {
+                max[i] = Some(v[i]);
+            }
+        }
+    }
+    let diag = match (min, max) {
+        ([Some(x0), Some(y0)], [Some(x1), Some(y1)]) => square_len([x1 - x0, y1 - y0]).sqrt(),
+        _ => return vec![],
+    };
+    let eps = diag * FromPrimitive::from_f64(1e-9);
+    let side_eps = diag * FromPrimitive::from_f64(1e-7);
+
+    let on_boundary = |shape: Polygons<'_, T>, v: Vec2d<T>| {
+        shape.iter().any(|polygon| {
+            let n = polygon.len();
+            (0..n).any(|i| {
+                let (p, q) = (polygon[i], polygon[(i + 1) % n]);
+                square_distance_to_segment([p[0], p[1], q[0], q[1]], v) <= side_eps * side_eps
+            })
+        })
+    };
+
+    // Keep the edges where the result changes across,
+    // oriented with the result on the left side.
+    let mut edges: Vec<(Vec2d<T>, Vec2d<T>)> = vec![];
+    for (shape, other, first) in [(a, b, true), (b, a, false)] {
+        for (p, q) in split_edges(shape, other, eps) {
+            let d = sub(q, p);
+            let len = square_len(d).sqrt();
+            if len <= eps {
+                continue;
+            }
+            let mid = mul_scalar(add(p, q), _05);
+            // Edges shared by both shapes are taken from the first one.
+            if !first && on_boundary(a, mid) {
+                continue;
+            }
+            let n = mul_scalar(perp(d), side_eps / len);
+            let (left, right) = (add(mid, n), sub(mid, n));
+            let inside_left = op.apply(inside_even_odd(a, left), inside_even_odd(b, left));
+            let inside_right = op.apply(inside_even_odd(a, right), inside_even_odd(b, right));
+            if inside_left != inside_right {
+                edges.push(if inside_left { (p, q) } else { (q, p) });
+            }
+        }
+    }
+
+    // Link edges into polygons.
+    let close = |p: Vec2d<T>, q: Vec2d<T>| square_len(sub(p, q)) <= side_eps * side_eps;
+    let mut used = vec![false; edges.len()];
+    let mut res = vec![];
+    for i in 0..edges.len() {
+        if used[i] {
+            continue;
+        }
+        used[i] = true;
+        let (start, mut end) = edges[i];
+        let mut polygon = vec![start];
+        while !close(end, start) {
+            let next = (0..edges.len()).find(|&j| !used[j] && close(edges[j].0, end));
+            match next {
+                None => break,
+                Some(j) => {
+                    used[j] = true;
+                    polygon.push(end);
+                    end = edges[j].1;
+                }
+            }
+        }
+        // Remove vertices on straight lines left by splitting.
+        let mut j = 0;
+        while polygon.len() > 2 && j < polygon.len() {
+            let n = polygon.len();
+            let (u, v, w) = (polygon[previous(n, j)], polygon[j], polygon[next(n, j)]);
+            let c = cross(sub(v, u), sub(w, v));
+            if c.max(-c) <= eps * diag {
+                polygon.remove(j);
+            } else {
+                j += 1;
+            }
+        }
+        if polygon.len() > 2 {
+            res.push(polygon);
+        }
+    }
+    res
+}
+
+// Splits edges of a shape where they cross edges or touch vertices of another shape.
+#[allow(clippy::just_underscores_and_digits)] // Naming convention.
+fn split_edges<T>(
+    shape: Polygons<'_, T>,
+    other: Polygons<'_, T>,
+    eps: T,
+) -> Vec<(Vec2d<T>, Vec2d<T>)>
+where
+    T: Float,
+{
+    use vecmath::traits::{One, Zero};
+
+    let _0: T = Zero::zero();
+    let _1: T = One::one();
+    let mut res = vec![];
+    for polygon in shape {
+        let n = polygon.len();
+        for i in 0..n {
+            let (p0, p1) = (polygon[i], polygon[(i + 1) % n]);
+            let r = sub(p1, p0);
+            let r_len = square_len(r);
+            if r_len <= eps * eps {
+                continue;
+            }
+            let mut ts = vec![];
+            for other_polygon in other {
+                let m = other_polygon.len();
+                for j in 0..m {
+                    let (q0, q1) = (other_polygon[j], other_polygon[(j + 1) % m]);
+                    let s = sub(q1, q0);
+                    // Split at vertices of the other shape lying on the edge.
+                    if square_distance_to_segment([p0[0], p0[1], p1[0], p1[1]], q0) <= eps * eps {
+                        ts.push(dot(sub(q0, p0), r) / r_len);
+                    }
+                    let denom = cross(r, s);
+                    if denom == _0 {
+                        continue;
+                    }
+                    let t = cross(sub(q0, p0), s) / denom;
+                    let u = cross(sub(q0, p0), r) / denom;
+                    if u >= _0 && u <= _1 {
+                        ts.push(t);
+                    }
+                }
+            }
+            ts.retain(|&t| t > _0 && t < _1);
+            ts.sort_by(|a, b| a.partial_cmp(b).unwrap_or(core::cmp::Ordering::Equal));
+            let mut last = p0;
+            for t in ts {
+                let v = add(p0, mul_scalar(r, t));
+                if square_len(sub(v, last)) > eps * eps {
+                    res.push((last, v));
+                    last = v;
+                }
+            }
+            res.push((last, p1));
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod test_offset_polygon {
+    use super::*;
+
+    #[test]
+    fn test_offset_polygon() {
+        let square = [[0.0, 0.0], [2.0, 0.0], [2.0, 2.0], [0.0, 2.0]];
+        let grown = [[-1.0, -1.0], [3.0, -1.0], [3.0, 3.0], [-1.0, 3.0]];
+        assert_eq!(offset_polygon(&square, 1.0, Join::Miter(2.0)), grown);
+        assert_eq!(
+            offset_polygon(&square, -0.5, Join::Bevel),
+            [[0.5, 0.5], [1.5, 0.5], [1.5, 1.5], [0.5, 1.5]]
+        );
+        // Winding order does not change the offset direction.
+        let mut clockwise = square;
+        clockwise.reverse();
+        let mut grown_clockwise = grown;
+        grown_clockwise.reverse();
+        assert_eq!(
+            offset_polygon(&clockwise, 1.0, Join::Miter(2.0)),
+            grown_clockwise
+        );
+        // The miter limit is exceeded for square corners.
+        assert_eq!(offset_polygon(&square, 1.0, Join::Miter(1.0)).len(), 8);
+        assert_eq!(offset_polygon(&square, 1.0, Join::Bevel).len(), 8);
+        assert_eq!(offset_polygon(&square, 1.0, Join::Round(16)).len(), 4 * 5);
+    }
+
+    #[test]
+    fn test_offset_shape() {
+        // A frame with a hole, and a square overlapping after growing.
+        let outer = [[0.0, 0.0], [6.0, 0.0], [6.0, 6.0], [0.0, 6.0]];
+        let hole = [[2.0, 2.0], [4.0, 2.0], [4.0, 4.0], [2.0, 4.0]];
+        let other = [[7.0, 0.0], [9.0, 0.0], [9.0, 6.0], [7.0, 6.0]];
+        let shape: [Polygon<'_>; 3] = [&outer, &hole, &other];
+        let grown = offset_shape(&shape, 0.5, Join::Miter(2.0));
+        let total: Scalar = grown.iter().map(|p| area(p)).sum();
+        // The hole shrinks to 1x1 and the squares merge into 10x7.
+        assert_eq!(total, 70.0 - 1.0);
+        assert!(!inside_even_odd(&polygon_refs(&grown), [3.0, 3.0]));
+        assert!(inside_even_odd(&polygon_refs(&grown), [6.5, 3.0]));
+    }
+}
+
+#[cfg(test)]
+mod test_convex_hull {
+    use super::*;
+
+    #[test]
+    fn test_convex_hull() {
+        let points = [
+            [1.0, 1.0],
+            [0.0, 0.0],
+            [2.0, 0.0],
+            [1.0, 0.0],
+            [2.0, 2.0],
+            [0.0, 2.0],
+            [1.0, 1.5],
+            [0.0, 0.0],
+        ];
+        assert_eq!(
+            convex_hull(&points),
+            vec![[0.0, 0.0], [2.0, 0.0], [2.0, 2.0], [0.0, 2.0]]
+        );
+        assert_eq!(convex_hull(&[[1.0, 1.0]]), vec![[1.0, 1.0]]);
+    }
+}
+
+#[cfg(test)]
+mod test_simplify {
+    use super::*;
+
+    #[test]
+    fn test_simplify() {
+        let polyline = [
+            [0.0, 0.0],
+            [1.0, 0.1],
+            [2.0, -0.1],
+            [3.0, 5.0],
+            [4.0, 6.0],
+            [5.0, 7.0],
+        ];
+        assert_eq!(
+            simplify(&polyline, 1.0),
+            vec![[0.0, 0.0], [2.0, -0.1], [3.0, 5.0], [5.0, 7.0]]
+        );
+        assert_eq!(simplify(&polyline, 100.0), vec![[0.0, 0.0], [5.0, 7.0]]);
+        // Collinear vertices are removed even with zero tolerance.
+        assert_eq!(
+            simplify(&polyline, 0.0),
+            vec![[0.0, 0.0], [1.0, 0.1], [2.0, -0.1], [3.0, 5.0], [5.0, 7.0]]
+        );
+    }
+
+    #[test]
+    fn test_square_distance_to_segment() {
+        let line = [0.0, 0.0, 10.0, 0.0];
+        assert_eq!(square_distance_to_segment(line, [5.0, 3.0]), 9.0);
+        assert_eq!(square_distance_to_segment(line, [-3.0, 4.0]), 25.0);
+        assert_eq!(square_distance_to_segment(line, [13.0, 4.0]), 25.0);
+    }
+}
+
+#[cfg(test)]
+mod test_resample_polygon {
+    use super::*;
+
+    #[test]
+    fn test_resample_polygon() {
+        let square = [[0.0, 0.0], [4.0, 0.0], [4.0, 4.0], [0.0, 4.0]];
+        assert_eq!(
+            resample_polygon(&square, 8),
+            vec![
+                [0.0, 0.0],
+                [2.0, 0.0],
+                [4.0, 0.0],
+                [4.0, 2.0],
+                [4.0, 4.0],
+                [2.0, 4.0],
+                [0.0, 4.0],
+                [0.0, 2.0]
+            ]
+        );
+        assert_eq!(resample_polygon(&square, 2), vec![[0.0, 0.0], [4.0, 4.0]]);
+    }
+}
+
+#[cfg(test)]
+mod test_boolean {
+    use super::*;
+
+    fn area_sum(shape: &[Vec<Vec2d>]) -> Scalar {
+        shape.iter().map(|p| area(p)).sum()
+    }
+
+    #[test]
+    fn test_boolean() {
+        let a: &[Vec2d] = &[[0.0, 0.0], [2.0, 0.0], [2.0, 2.0], [0.0, 2.0]];
+        let b: &[Vec2d] = &[[1.0, 1.0], [3.0, 1.0], [3.0, 3.0], [1.0, 3.0]];
+        let (a, b) = (&[a][..], &[b][..]);
+
+        let union = boolean(a, b, BooleanOp::Union);
+        assert_eq!(union.len(), 1);
+        assert_eq!(union[0].len(), 8);
+        assert!((area_sum(&union) - 7.0).abs() < 1e-9);
+
+        let intersection = boolean(a, b, BooleanOp::Intersection);
+        assert_eq!(intersection.len(), 1);
+        assert_eq!(intersection[0].len(), 4);
+        assert!((area_sum(&intersection) - 1.0).abs() < 1e-9);
+
+        let difference = boolean(a, b, BooleanOp::Difference);
+        assert!((area_sum(&difference) - 3.0).abs() < 1e-9);
+
+        let xor = boolean(a, b, BooleanOp::Xor);
+        assert!((area_sum(&xor) - 6.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_boolean_hole() {
+        let outer: &[Vec2d] = &[[0.0, 0.0], [4.0, 0.0], [4.0, 4.0], [0.0, 4.0]];
+        let inner: &[Vec2d] = &[[1.0, 1.0], [3.0, 1.0], [3.0, 3.0], [1.0, 3.0]];
+        let frame = boolean(&[outer], &[inner], BooleanOp::Difference);
+        assert_eq!(frame.len(), 2);
+        // The hole has opposite winding order.
+        assert!((area_sum(&frame) - 12.0).abs() < 1e-9);
+        let frame: Vec<&[Vec2d]> = frame.iter().map(|p| &p[..]).collect();
+        assert!(!inside_even_odd(&frame, [2.0, 2.0]));
+        assert!(inside_even_odd(&frame, [0.5, 2.0]));
+    }
+}
@@ -0,0 +1,231 @@
+//! Geometry utilities for hit-testing and clipping
+//!
+//! Tests points against polygons, computes intersections of segments and rays,
+//! and combines shapes with boolean operations.
+
+use vecmath::traits::{Float, One, Zero};
+
+use super::{
+    super::types::{Line, Polygon, Polygons, Ray, Rectangle},
+    Vec2d,
+};
+pub use super::{area, area_centroid, boolean, centroid, BooleanOp, FillRule};
+
+/// Computes the winding number of a polygon around a point.
+///
+/// The winding number counts how many times the polygon goes around the point,
+/// positive for edges going counter-clockwise with y axis pointing up.
+/// It is zero for points outside.
+pub fn winding_number<T>(polygon: Polygon<'_, T>, v: Vec2d<T>) -> i32
+where
+    T: Float,
+{
+    let zero: T = Zero::zero();
+    let n = polygon.len();
+    let mut winding = 0;
+    for i in 0..n {
+        let a = polygon[i];
+        let b = polygon[(i + 1) % n];
+        let side = (b[0] - a[0]) * (v[1] - a[1]) - (b[1] - a[1]) * (v[0] - a[0]);
+        if a[1] <= v[1] {
+            if b[1] > v[1] && side > zero {
+                winding += 1;
+            }
+        } else if b[1] <= v[1] && side < zero {
+            winding -= 1;
+        }
+    }
+    winding
+}
+
+/// Returns true if point is inside a polygon, using a fill rule.
+///
+/// The polygon may intersect itself.
+pub fn inside_polygon<T>(polygon: Polygon<'_, T>, v: Vec2d<T>, rule: FillRule) -> bool
+where
+    T: Float,
+{
+    let winding = winding_number(polygon, v);
+    match rule {
+        FillRule::EvenOdd => winding % 2 != 0,
+        FillRule::NonZero => winding != 0,
+    }
+}
+
+/// Computes the intersection point of two line segments.
+///
+/// Returns `None` when the segments do not intersect or are parallel.
+pub fn segment_intersection<T>(a: Line<T>, b: Line<T>) -> Option<Vec2d<T>>
+where
+    T: Float,
+{
+    let (zero, one): (T, T) = (Zero::zero(), One::one());
+    let d1 = [a[2] - a[0], a[3] - a[1]];
+    let d2 = [b[2] - b[0], b[3] - b[1]];
+    let denom = d1[0] * d2[1] - d1[1] * d2[0];
+    if denom == zero {
+        return None;
+    }
+    let (dx, dy) = (b[0] - a[0], b[1] - a[1]);
+    let s = (dx * d2[1] - dy * d2[0]) / denom;
+    let t = (dx * d1[1] - dy * d1[0]) / denom;
+    if s < zero || s > one || t < zero || t > one {
+        return None;
+    }
+    Some([a[0] + s * d1[0], a[1] + s * d1[1]])
+}
+
+/// Computes where a ray hits a line segment.
+///
+/// A ray stores starting point and directional vector.
+/// Returns the distance along the ray in units of the directional vector,
+/// or `None` when the ray misses the segment or is parallel to it.
+pub fn ray_segment_intersection<T>(ray: Ray<T>, segment: Line<T>) -> Option<T>
+where
+    T: Float,
+{
+    let (zero, one): (T, T) = (Zero::zero(), One::one());
+    let d = [segment[2] - segment[0], segment[3] - segment[1]];
+    let denom = ray[2] * d[1] - ray[3] * d[0];
+    if denom == zero {
+        return None;
+    }
+    let (dx, dy) = (segment[0] - ray[0], segment[1] - ray[1]);
+    let t = (dx * d[1] - dy * d[0]) / denom;
+    let s = (dx * ray[3] - dy * ray[2]) / denom;
+    if t < zero || s < zero || s > one {
+        return None;
+    }
+    Some(t)
+}
+
+/// Computes the nearest hit of a ray with the edges of a polygon.
+///
+/// Returns the distance along the ray in units of the directional vector.
+pub fn ray_polygon_intersection<T>(ray: Ray<T>, polygon: Polygon<'_, T>) -> Option<T>
+where
+    T: Float,
+{
+    let n = polygon.len();
+    (0..n)
+        .filter_map(|i| {
+            let (a, b) = (polygon[i], polygon[(i + 1) % n]);
+            ray_segment_intersection(ray, [a[0], a[1], b[0], b[1]])
+        })
+        .fold(None, |min, t| match min {
+            Some(min) if min <= t => Some(min),
+            _ => Some(t),
+        })
+}
+
+/// Computes the area covered by either shape.
+///
+/// See `boolean` for more information.
+pub fn union<T>(a: Polygons<'_, T>, b: Polygons<'_, T>) -> Vec<Vec<Vec2d<T>>>
+where
+    T: Float,
+{
+    boolean(a, b, BooleanOp::Union)
+}
+
+/// Computes the area covered by both shapes, e.g. to clip a shape by a mask.
+///
+/// See `boolean` for more information.
+pub fn intersection<T>(a: Polygons<'_, T>, b: Polygons<'_, T>) -> Vec<Vec<Vec2d<T>>>
+where
+    T: Float,
+{
+    boolean(a, b, BooleanOp::Intersection)
+}
+
+/// Computes the area covered by the first shape but not the second,
+/// e.g. to cut holes into destructible terrain.
+///
+/// See `boolean` for more information.
+pub fn difference<T>(a: Polygons<'_, T>, b: Polygons<'_, T>) -> Vec<Vec<Vec2d<T>>>
+where
+    T: Float,
+{
+    boolean(a, b, BooleanOp::Difference)
+}
+
+/// Computes the axis aligned bounding box `[x, y, w, h]` of points.
+///
+/// Returns `None` when there are no points.
+pub fn bounding_box<T>(points: &[Vec2d<T>]) -> Option<Rectangle<T>>
+where
+    T: Float,
+{
+    let (first, rest) = points.split_first()?;
+    let (mut min, mut max) = (*first, *first);
+    for p in rest {
+        min = [min[0].min(p[0]), min[1].min(p[1])];
+        max = [max[0].max(p[0]), max[1].max(p[1])];
+    }
+    Some([min[0], min[1], max[0] - min[0], max[1] - min[1]])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_inside_polygon() {
+        // A square going around twice, counter-clockwise with y axis up.
+        let square = [[0.0, 0.0], [2.0, 0.0], [2.0, 2.0], [0.0, 2.0]];
+        let twice: Vec<_> = square.iter().chain(&square).copied().collect();
+        assert_eq!(winding_number(&square, [1.0, 1.0]), 1);
+        assert_eq!(winding_number(&twice, [1.0, 1.0]), 2);
+        assert!(inside_polygon(&twice, [1.0, 1.0], FillRule::NonZero));
+        assert!(!inside_polygon(&twice, [1.0, 1.0], FillRule::EvenOdd));
+        assert!(!inside_polygon(&square, [3.0, 1.0], FillRule::NonZero));
+        assert_eq!(bounding_box(&square), Some([0.0, 0.0, 2.0, 2.0]));
+    }
+
+    #[test]
+    fn test_clipping() {
+        // Squares with y axis up, the inner one given clockwise.
+        let outer = [[0.0, 0.0], [4.0, 0.0], [4.0, 4.0], [0.0, 4.0]];
+        let inner = [[1.0, 1.0], [1.0, 3.0], [3.0, 3.0], [3.0, 1.0]];
+        let apart = [[5.0, 0.0], [6.0, 0.0], [6.0, 1.0], [5.0, 1.0]];
+        let (outer, inner, apart): (&[_], &[_], &[_]) = (&outer, &inner, &apart);
+        let areas = |shape: Vec<Vec<Vec2d>>| {
+            let mut areas: Vec<f64> = shape.iter().map(|p| area(p)).collect();
+            areas.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            areas
+        };
+
+        // Disjoint shapes.
+        assert_eq!(areas(union(&[outer], &[apart])), [1.0, 16.0]);
+        assert!(intersection(&[outer], &[apart]).is_empty());
+        assert_eq!(areas(difference(&[outer], &[apart])), [16.0]);
+
+        // One shape containing the other.
+        assert_eq!(areas(union(&[outer], &[inner])), [16.0]);
+        assert_eq!(areas(union(&[inner], &[outer])), [16.0]);
+        assert_eq!(areas(intersection(&[outer], &[inner])), [4.0]);
+        assert!(difference(&[inner], &[outer]).is_empty());
+        // Results are counter-clockwise, holes clockwise.
+        assert_eq!(areas(difference(&[outer], &[inner])), [-4.0, 16.0]);
+    }
+
+    #[test]
+    fn test_intersection() {
+        let a = [0.0, 0.0, 2.0, 2.0];
+        assert_eq!(
+            segment_intersection(a, [0.0, 2.0, 2.0, 0.0]),
+            Some([1.0, 1.0])
+        );
+        assert_eq!(segment_intersection(a, [3.0, 0.0, 3.0, 4.0]), None);
+        assert_eq!(segment_intersection(a, [1.0, 0.0, 3.0, 2.0]), None);
+
+        let ray = [0.0, 1.0, 2.0, 0.0];
+        assert_eq!(
+            ray_segment_intersection(ray, [3.0, 0.0, 3.0, 2.0]),
+            Some(1.5)
+        );
+        assert_eq!(ray_segment_intersection(ray, [-1.0, 0.0, -1.0, 2.0]), None);
+        let square = [[2.0, 0.0], [4.0, 0.0], [4.0, 2.0], [2.0, 2.0]];
+        assert_eq!(ray_polygon_intersection(ray, &square), Some(1.0));
+    }
+}
@@ -0,0 +1,189 @@
+//! Draw triangle meshes
+//!
+//! A mesh gives direct access to the triangles sent to the back-end,
+//! with optional texture coordinates and colors per vertex.
+//!
+//! ```ignore
+//! let quad = Mesh::new(
+//!     vec![[0.0, 0.0], [100.0, 0.0], [100.0, 100.0], [0.0, 100.0]],
+//!     vec![0, 1, 2, 0, 2, 3],
+//! )
+//! .uvs(vec![[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]]);
+//! quad.draw_textured(&texture, &c.draw_state, c.transform, g);
+//! ```
+
+use super::{
+    math::{Affine2, Vec2d},
+    triangulation::{tx, ty},
+    types::Color,
+    DrawState, Graphics, BACK_END_MAX_VERTEX_COUNT as BUFFER_SIZE,
+};
+
+/// Indexed triangles with optional texture coordinates and colors.
+///
+/// Every three indices make a triangle.
+/// Triangles referring to vertices that do not exist,
+/// or that lack texture coordinates or colors, are skipped when drawing.
+#[derive(Clone, Debug)]
+pub struct Mesh {
+    /// The color, multiplied with vertex colors and textures.
+    pub color: Color,
+    /// The vertex positions.
+    pub positions: Vec<Vec2d>,
+    /// The normalized texture coordinates per vertex.
+    pub uvs: Option<Vec<[f32; 2]>>,
+    /// The colors per vertex.
+    pub colors: Option<Vec<Color>>,
+    /// The vertex indices of the triangles.
+    pub indices: Vec<u32>,
+}
+
+impl Mesh {
+    /// Creates a new white mesh.
+    pub fn new(positions: Vec<Vec2d>, indices: Vec<u32>) -> Mesh {
+        Mesh {
+            color: [1.0; 4],
+            positions,
+            uvs: None,
+            colors: None,
+            indices,
+        }
+    }
+
+    /// Sets color.
+    pub fn color(mut self, value: Color) -> Self {
+        self.color = value;
+        self
+    }
+
+    /// Sets texture coordinates per vertex.
+    pub fn uvs(mut self, value: Vec<[f32; 2]>) -> Self {
+        self.uvs = Some(value);
+        self
+    }
+
+    /// Sets colors per vertex.
+    pub fn colors(mut self, value: Vec<Color>) -> Self {
+        self.colors = Some(value);
+        self
+    }
+
+    /// Draws mesh without texture.
+    pub fn draw<G>(&self, draw_state: &DrawState, transform: Affine2, g: &mut G)
+    where
+        G: Graphics,
+    {
+        if self.colors.is_some() {
+            g.tri_list_c(draw_state, |f| {
+                self.stream(transform, false, |vertices, _, colors| f(vertices, colors))
+            });
+        } else {
+            g.tri_list(draw_state, &self.color, |f| {
+                self.stream(transform, false, |vertices, _, _| f(vertices))
+            });
+        }
+    }
+
+    /// Draws mesh with texture.
+    ///
+    /// Draws nothing when the mesh has no texture coordinates.
+    pub fn draw_textured<G>(
+        &self,
+        texture: &<G as Graphics>::Texture,
+        draw_state: &DrawState,
+        transform: Affine2,
+        g: &mut G,
+    ) where
+        G: Graphics,
+    {
+        if self.uvs.is_none() {
+            return;
+        }
+        if self.colors.is_some() {
+            g.tri_list_uv_c(draw_state, texture, |f| {
+                self.stream(transform, true, |vertices, uvs, colors| {
+                    f(vertices, uvs, colors)
+                })
+            });
+        } else {
+            g.tri_list_uv(draw_state, &self.color, texture, |f| {
+                self.stream(transform, true, |vertices, uvs, _| f(vertices, uvs))
+            });
+        }
+    }
+
+    // Streams triangles with texture coordinates and colors.
+    fn stream<F>(&self, m: Affine2, textured: bool, mut f: F)
+    where
+        F: FnMut(&[[f32; 2]], &[[f32; 2]], &[[f32; 4]]),
+    {
+        let mut vertices: [[f32; 2]; BUFFER_SIZE] = [[0.0; 2]; BUFFER_SIZE];
+        let mut uvs: [[f32; 2]; BUFFER_SIZE] = [[0.0; 2]; BUFFER_SIZE];
+        let mut colors: [[f32; 4]; BUFFER_SIZE] = [[0.0; 4]; BUFFER_SIZE];
+        let c = self.color;
+        let uv_at = |i: usize| match &self.uvs {
+            Some(uvs) => uvs.get(i).copied(),
+            None if textured => None,
+            None => Some([0.0; 2]),
+        };
+        let color_at = |i: usize| match &self.colors {
+            Some(colors) => colors
+                .get(i)
+                .map(|v| [v[0] * c[0], v[1] * c[1], v[2] * c[2], v[3] * c[3]]),
+            None => Some(c),
+        };
+        let mut k = 0;
+        for tri in self.indices.chunks_exact(3) {
+            let mut corners = [([0.0; 2], [0.0; 2], [0.0; 4]); 3];
+            let valid = tri.iter().zip(&mut corners).all(|(&i, corner)| {
+                let i = i as usize;
+                match (self.positions.get(i), uv_at(i), color_at(i)) {
+                    (Some(p), Some(uv), Some(color)) => {
+                        *corner = ([tx(m, p[0], p[1]), ty(m, p[0], p[1])], uv, color);
+                        true
+                    }
+                    _ => false,
+                }
+            });
+            if !valid {
+                continue;
+            }
+            // Buffer is full.
+            if k + 3 > BUFFER_SIZE {
+                // Send chunk and start over.
+                f(&vertices[0..k], &uvs[0..k], &colors[0..k]);
+                k = 0;
+            }
+            for (pos, uv, color) in corners {
+                vertices[k] = pos;
+                uvs[k] = uv;
+                colors[k] = color;
+                k += 1;
+            }
+        }
+        if k > 0 {
+            f(&vertices[0..k], &uvs[0..k], &colors[0..k]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::software_graphics::SoftwareGraphics;
+
+    #[test]
+    fn test_mesh() {
+        let red = [1.0, 0.0, 0.0, 1.0];
+        let mesh = Mesh::new(
+            vec![[0.0, 0.0], [8.0, 0.0], [8.0, 8.0], [0.0, 8.0]],
+            // The last triangle refers to a missing vertex.
+            vec![0, 1, 2, 0, 2, 3, 0, 2, 9],
+        )
+        .colors(vec![red; 4]);
+        let mut g = SoftwareGraphics::new(8, 8);
+        g.draw(|c, g| mesh.draw(&c.draw_state, c.transform, g));
+        assert_eq!(g.get_pixel(6, 1), [255, 0, 0, 255]);
+        assert_eq!(g.get_pixel(1, 6), [255, 0, 0, 255]);
+    }
+}
@@ -0,0 +1,163 @@
+//! Draw mesh gradient
+
+use super::{
+    math::{Affine2, Vec2d},
+    triangulation,
+    types::{Color, CubicBezier, Resolution},
+    DrawState, Graphics,
+};
+
+/// A Coons patch with a color in each corner.
+///
+/// See `curve::coons_patch` for the order of edges.
+/// The color of corner `i` is used at the start of `edges[i]`.
+#[derive(Copy, Clone, Debug)]
+pub struct Patch {
+    /// The edges going around the patch.
+    pub edges: [CubicBezier; 4],
+    /// The corner colors.
+    pub colors: [Color; 4],
+}
+
+impl Patch {
+    /// Creates a patch with straight edges between corners.
+    pub fn from_corners(corners: [Vec2d; 4], colors: [Color; 4]) -> Patch {
+        let line = |a: Vec2d, b: Vec2d| {
+            let at = |t: f64| [a[0] + (b[0] - a[0]) * t, a[1] + (b[1] - a[1]) * t];
+            [a, at(1.0 / 3.0), at(2.0 / 3.0), b]
+        };
+        Patch {
+            edges: [
+                line(corners[0], corners[1]),
+                line(corners[1], corners[2]),
+                line(corners[2], corners[3]),
+                line(corners[3], corners[0]),
+            ],
+            colors,
+        }
+    }
+}
+
+/// A mesh gradient, filling patches with smoothly interpolated colors.
+#[derive(Copy, Clone, Debug)]
+pub struct MeshGradient {
+    /// The number of subdivisions along each side of a patch.
+    pub resolution: Resolution,
+}
+
+impl MeshGradient {
+    /// Creates a new mesh gradient.
+    pub fn new() -> MeshGradient {
+        MeshGradient { resolution: 16 }
+    }
+
+    /// Sets the number of subdivisions along each side of a patch.
+    pub fn resolution(mut self, value: Resolution) -> Self {
+        self.resolution = value;
+        self
+    }
+
+    /// Draws patches.
+    pub fn draw<G>(&self, patches: &[Patch], draw_state: &DrawState, transform: Affine2, g: &mut G)
+    where
+        G: Graphics,
+    {
+        g.tri_list_c(draw_state, |f| {
+            for patch in patches {
+                triangulation::with_coons_patch_tri_list_c(
+                    transform,
+                    patch.edges,
+                    patch.colors,
+                    self.resolution,
+                    |vertices, colors| f(vertices, colors),
+                );
+            }
+        });
+    }
+
+    /// Draws a grid of points with colors, stored row by row.
+    ///
+    /// Each cell of the grid becomes a patch with straight edges.
+    pub fn draw_grid<G>(
+        &self,
+        points: &[Vec2d],
+        colors: &[Color],
+        columns: usize,
+        draw_state: &DrawState,
+        transform: Affine2,
+        g: &mut G,
+    ) where
+        G: Graphics,
+    {
+        if columns < 2 {
+            return;
+        }
+        let rows = points.len().min(colors.len()) / columns;
+        let mut patches = Vec::with_capacity((rows.max(1) - 1) * (columns - 1));
+        for y in 1..rows {
+            for x in 1..columns {
+                let ind = [
+                    (y - 1) * columns + x - 1,
+                    (y - 1) * columns + x,
+                    y * columns + x,
+                    y * columns + x - 1,
+                ];
+                patches.push(Patch::from_corners(
+                    ind.map(|i| points[i]),
+                    ind.map(|i| colors[i]),
+                ));
+            }
+        }
+        self.draw(&patches, draw_state, transform, g);
+    }
+}
+
+impl Default for MeshGradient {
+    fn default() -> Self {
+        MeshGradient::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_mesh_gradient() {
+        use crate::software_graphics::SoftwareGraphics;
+
+        let mut g = SoftwareGraphics::new(16, 16);
+        g.draw(|c, g| {
+            MeshGradient::new().resolution(4).draw_grid(
+                &[[0.0, 0.0], [16.0, 0.0], [0.0, 16.0], [16.0, 16.0]],
+                &[
+                    [1.0, 0.0, 0.0, 1.0],
+                    [0.0, 1.0, 0.0, 1.0],
+                    [0.0, 0.0, 1.0, 1.0],
+                    [1.0, 1.0, 1.0, 1.0],
+                ],
+                2,
+                &c.draw_state,
+                c.transform,
+                g,
+            )
+        });
+        // Corners have the corner colors, and the center is a mix of them.
+        let near = |x, y, expected: [u8; 4]| {
+            let pixel = g.get_pixel(x, y);
+            (0..4).all(|i| (i32::from(pixel[i]) - i32::from(expected[i])).abs() <= 32)
+        };
+        assert!(near(0, 0, [255, 0, 0, 255]));
+        assert!(near(15, 0, [0, 255, 0, 255]));
+        assert!(near(0, 15, [0, 0, 255, 255]));
+        assert!(near(15, 15, [255; 4]));
+        assert!(near(8, 8, [128, 128, 128, 255]));
+
+        let patch = Patch::from_corners(
+            [[0.0, 0.0], [3.0, 0.0], [3.0, 3.0], [0.0, 3.0]],
+            [[1.0; 4]; 4],
+        );
+        assert_eq!(patch.edges[0][1], [1.0, 0.0]);
+        assert_eq!(patch.edges[3][3], [0.0, 0.0]);
+    }
+}
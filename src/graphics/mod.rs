@@ -24,6 +24,10 @@
 //! At top level, there are some shortcut methods for common operations.
 //! For example, `ellipse` is a simplified version of `Ellipse`.
 
+pub use arrow::Arrow;
+pub use bounded::Bounded;
+pub use camera::Camera2d;
+pub use capsule::Capsule;
 pub use character::{Character, CharacterCache};
 pub use circle_arc::CircleArc;
 pub use colored::Colored;
@@ -33,12 +37,19 @@ pub use ellipse::Ellipse;
 pub use image::Image;
 pub use line::Line;
 use math::{Affine2, Scalar};
+pub use mesh::Mesh;
+pub use mesh_gradient::MeshGradient;
 pub use polygon::Polygon;
+pub use polyline::PolyLine;
 pub use radians::Radians;
 pub use rectangle::Rectangle;
 pub use rectangled::Rectangled;
+pub use render_texture::RenderToTexture;
+pub use ring::Ring;
+pub use sector::Sector;
 pub use source_rectangled::SourceRectangled;
-pub use text::Text;
+pub use text::{RichText, Text, TextBlob, TextLayout};
+pub use textured_polygon::TexturedPolygon;
 pub use transformed::Transformed;
 
 pub use crate::{texture::ImageSize, viewport::Viewport};
@@ -51,26 +62,54 @@ pub use crate::{texture::ImageSize, viewport::Viewport};
 /// in a triangle list.
 pub const BACK_END_MAX_VERTEX_COUNT: usize = 1023;
 
+mod bounded;
 mod colored;
 mod rectangled;
 mod source_rectangled;
 mod transformed;
 
+pub mod arrow;
+pub mod atlas;
+pub mod batch;
+pub mod bezier;
+pub mod camera;
+pub mod capabilities;
+pub mod capsule;
 pub mod character;
 pub mod circle_arc;
+pub mod clip_stack;
 pub mod color;
 pub mod context;
+pub mod cull;
+pub mod curve;
+pub mod damage;
+pub mod display_list;
 pub mod draw_state;
 pub mod ellipse;
+pub mod fill;
 pub mod grid;
 pub mod image;
+pub mod layered;
+pub mod lights;
 pub mod line;
+pub mod material;
 pub mod math;
+pub mod mesh;
+pub mod mesh_gradient;
 pub mod modular_index;
+pub mod opacity;
+pub mod particles;
+pub mod path;
 pub mod polygon;
+pub mod polyline;
 pub mod rectangle;
+pub mod render_texture;
+pub mod ring;
+pub mod scene;
+pub mod sector;
 pub mod text;
 pub mod texture_packer;
+pub mod textured_polygon;
 pub mod triangulation;
 pub mod types;
 
@@ -89,6 +128,66 @@ where
     g.clear_stencil(0);
 }
 
+/// Clears the screen with a vertical gradient.
+///
+/// The color is interpolated from `top` to `bottom` of the screen.
+/// Alpha replaces the values in the buffer, like `clear`.
+pub fn clear_gradient<G>(top: types::Color, bottom: types::Color, g: &mut G)
+where
+    G: Graphics,
+{
+    let draw_state = DrawState {
+        scissor: None,
+        stencil: None,
+        blend: None,
+        color_mask: [true; 4],
+    };
+    let vertices = triangulation::rect_tri_list_xy(math::identity(), [-1.0, -1.0, 2.0, 2.0]);
+    // The y axis points up in normalized coordinates.
+    let colors = vertices.map(|v| if v[1] > 0.0 { top } else { bottom });
+    g.tri_list_c(&draw_state, |f| f(&vertices, &colors));
+    g.clear_stencil(0);
+}
+
+/// Clears the screen with a texture.
+///
+/// The context is used to compute the size of the screen.
+/// Alpha replaces the values in the buffer, like `clear`.
+pub fn clear_texture<G>(
+    texture: &<G as Graphics>::Texture,
+    mode: image::BackgroundMode,
+    c: &Context,
+    g: &mut G,
+) where
+    G: Graphics,
+{
+    use image::BackgroundMode;
+
+    let draw_state = DrawState {
+        scissor: None,
+        stencil: None,
+        blend: None,
+        color_mask: [true; 4],
+    };
+    let [vw, vh] = c.get_view_size();
+    let (tw, th) = texture.get_size();
+    let (tw, th) = (tw as Scalar, th as Scalar);
+    let src_rect = match mode {
+        BackgroundMode::Stretch => [0.0, 0.0, tw, th],
+        BackgroundMode::Cover => {
+            let scale = (vw / tw).max(vh / th);
+            let (w, h) = (vw / scale, vh / scale);
+            [0.5 * (tw - w), 0.5 * (th - h), w, h]
+        }
+        BackgroundMode::Tile => [0.0, 0.0, vw, vh],
+    };
+    Image::new()
+        .rect([0.0, 0.0, vw, vh])
+        .src_rect(src_rect)
+        .draw(texture, &draw_state, math::abs_transform(vw, vh), g);
+    g.clear_stencil(0);
+}
+
 /// Draws image.
 pub fn image<G>(image: &<G as Graphics>::Texture, transform: math::Affine2, g: &mut G)
 where
@@ -238,11 +337,16 @@ where
 ///
 /// Color space is sRGB.
 ///
+/// Back-ends may let the embedder configure how colors are interpreted,
+/// using `color::ColorSpace`, e.g. when colors are already linear
+/// or when composing with another renderer that handles conversion.
+///
 /// ### Notice for back-end authors
 ///
 /// When sRGB is enabled for a back-end shader, the gamma must be converted
 /// to linear space when used as vertex color or uniform parameter.
-/// To convert gamma, use `color::gamma_srgb_to_linear`.
+/// To convert gamma, use `color::gamma_srgb_to_linear`,
+/// or `color::ColorSpace::to_shader` to respect the configured color space.
 ///
 /// For more information, see
 /// <https://github.com/PistonDevelopers/piston/issues/1014>.
@@ -414,6 +518,22 @@ pub trait Graphics: Sized {
         image.draw_tri(texture, draw_state, transform, self);
     }
 
+    /// Draws many instances of a texture.
+    ///
+    /// Can be overridden in the back-end for higher performance,
+    /// e.g. using instancing.
+    ///
+    /// The default implementation uses `image::sprite_batch_tri`.
+    #[inline(always)]
+    fn sprite_batch(
+        &mut self,
+        texture: &Self::Texture,
+        instances: &[image::SpriteInstance],
+        draw_state: &DrawState,
+    ) {
+        image::sprite_batch_tri(instances, texture, draw_state, self);
+    }
+
     /// Draws ellipse.
     ///
     /// Can be overridden in the back-end for higher performance.
@@ -461,4 +581,79 @@ pub trait Graphics: Sized {
     ) {
         c.draw_tri(rectangle, draw_state, transform, self);
     }
+
+    /// Draws a list of line segments with a solid color.
+    ///
+    /// Each line is `[x0, y0, x1, y1]` and drawn with butt ends,
+    /// where the radius is half the width.
+    /// Useful for debug overlays with many lines.
+    ///
+    /// Can be overridden in the back-end for higher performance,
+    /// e.g. using native wide lines.
+    fn line_list(
+        &mut self,
+        draw_state: &DrawState,
+        color: &types::Color,
+        radius: types::Radius,
+        lines: &[types::Line],
+        transform: Affine2,
+    ) {
+        if color[3] == 0.0 {
+            return;
+        }
+        self.tri_list(draw_state, color, |f| {
+            triangulation::with_lines_tri_list(transform, radius, lines, |vertices| f(vertices))
+        });
+    }
+
+    /// Draws a list of points as squares with a solid color.
+    ///
+    /// The radius is half the side length of a square.
+    /// Useful for particles and debug overlays.
+    ///
+    /// Can be overridden in the back-end for higher performance,
+    /// e.g. using point sprites.
+    fn point_list(
+        &mut self,
+        draw_state: &DrawState,
+        color: &types::Color,
+        radius: types::Radius,
+        points: &[types::Vec2d],
+        transform: Affine2,
+    ) {
+        if color[3] == 0.0 {
+            return;
+        }
+        self.tri_list(draw_state, color, |f| {
+            triangulation::with_points_tri_list(transform, radius, points, |vertices| f(vertices))
+        });
+    }
+
+    /// Draws with a custom material registered with the back-end.
+    ///
+    /// Back-ends that support custom materials implement `material::MaterialRegistry`.
+    /// The default implementation ignores the material and draws as usual,
+    /// so generic code works with all back-ends.
+    fn with_material<F, U>(&mut self, material: material::MaterialId, f: F) -> U
+    where
+        F: FnOnce(&mut Self) -> U,
+    {
+        let _ = material;
+        f(self)
+    }
+
+    /// Returns what the back-end supports.
+    ///
+    /// The default implementation reports no optional features.
+    fn capabilities(&self) -> capabilities::Capabilities {
+        Default::default()
+    }
+
+    /// Returns the viewport that is drawn to, if known.
+    ///
+    /// Shapes use this to adapt tessellation to their size on the screen.
+    /// The default implementation returns `None`.
+    fn current_viewport(&self) -> Option<Viewport> {
+        None
+    }
 }
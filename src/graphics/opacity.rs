@@ -0,0 +1,111 @@
+//! Group opacity by multiplying colors
+
+use super::{
+    capabilities::Capabilities, types::Color, DrawState, Graphics, Viewport,
+    BACK_END_MAX_VERTEX_COUNT as BUFFER_SIZE,
+};
+
+/// Wraps a back-end, multiplying the alpha of all colors by an opacity.
+///
+/// This fades a group of draw calls without changing the colors passed to them.
+/// Overlapping shapes within the group are blended with each other,
+/// so they show through where they overlap.
+/// For correct group opacity, use `Context::with_alpha_layer`.
+///
+/// Usually created by `Context::with_alpha`.
+pub struct Opacity<'a, G> {
+    /// The wrapped back-end.
+    pub g: &'a mut G,
+    /// The opacity from 0 to 1.
+    pub alpha: f32,
+}
+
+impl<'a, G> Opacity<'a, G> {
+    /// Creates a new wrapper with opacity.
+    pub fn new(g: &'a mut G, alpha: f32) -> Opacity<'a, G> {
+        Opacity { g, alpha }
+    }
+}
+
+fn apply(alpha: f32, color: &Color) -> Color {
+    [color[0], color[1], color[2], color[3] * alpha]
+}
+
+// Multiplies the alpha of colors into a buffer.
+fn apply_all<'a>(
+    alpha: f32,
+    colors: &[Color],
+    buffer: &'a mut [Color; BUFFER_SIZE],
+) -> &'a [Color] {
+    for (b, c) in buffer.iter_mut().zip(colors) {
+        *b = apply(alpha, c);
+    }
+    &buffer[..colors.len()]
+}
+
+impl<G: Graphics> Graphics for Opacity<'_, G> {
+    type Texture = G::Texture;
+
+    fn clear_color(&mut self, color: Color) {
+        self.g.clear_color(color);
+    }
+
+    fn clear_stencil(&mut self, value: u8) {
+        self.g.clear_stencil(value);
+    }
+
+    fn tri_list<F>(&mut self, draw_state: &DrawState, color: &[f32; 4], f: F)
+    where
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]])),
+    {
+        let color = apply(self.alpha, color);
+        self.g.tri_list(draw_state, &color, f);
+    }
+
+    fn tri_list_c<F>(&mut self, draw_state: &DrawState, mut f: F)
+    where
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]], &[[f32; 4]])),
+    {
+        let alpha = self.alpha;
+        let mut buffer = [[0.0; 4]; BUFFER_SIZE];
+        self.g.tri_list_c(draw_state, |g| {
+            f(&mut |vertices, colors| g(vertices, apply_all(alpha, colors, &mut buffer)))
+        });
+    }
+
+    fn tri_list_uv<F>(
+        &mut self,
+        draw_state: &DrawState,
+        color: &[f32; 4],
+        texture: &Self::Texture,
+        f: F,
+    ) where
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]], &[[f32; 2]])),
+    {
+        let color = apply(self.alpha, color);
+        self.g.tri_list_uv(draw_state, &color, texture, f);
+    }
+
+    fn tri_list_uv_c<F>(&mut self, draw_state: &DrawState, texture: &Self::Texture, mut f: F)
+    where
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]], &[[f32; 2]], &[[f32; 4]])),
+    {
+        let alpha = self.alpha;
+        let mut buffer = [[0.0; 4]; BUFFER_SIZE];
+        self.g.tri_list_uv_c(draw_state, texture, |g| {
+            f(
+                &mut |vertices, uvs, colors| {
+                    g(vertices, uvs, apply_all(alpha, colors, &mut buffer))
+                },
+            )
+        });
+    }
+
+    fn current_viewport(&self) -> Option<Viewport> {
+        self.g.current_viewport()
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        self.g.capabilities()
+    }
+}
@@ -0,0 +1,413 @@
+//! Particle systems
+//!
+//! An emitter spawns particles at a rate, moves them on the CPU,
+//! and draws all of them with a texture in a few calls to `Graphics::tri_list_uv_c`.
+//! Color, size and speed of particles change over their lifetime following curves.
+//!
+//! ```ignore
+//! let settings = EmitterSettings::new()
+//!     .rate(200.0)
+//!     .lifetime([0.5, 1.0])
+//!     .speed([50.0, 100.0])
+//!     .spread(0.3)
+//!     .color(Curve::linear([1.0, 0.8, 0.2, 1.0], [1.0, 0.0, 0.0, 0.0]))
+//!     .size(Curve::linear(8.0, 2.0));
+//! let mut fire = ParticleEmitter::new(settings);
+//!
+//! // Each update.
+//! fire.position = torch.pos;
+//! fire.update(args.dt);
+//!
+//! // Each frame.
+//! fire.draw(&spark_texture, &c.draw_state, c.transform, g);
+//! ```
+
+use interpolation::Lerp;
+use vecmath::traits::FromPrimitive;
+
+use super::{
+    math::{Affine2, Scalar, Vec2d},
+    triangulation,
+    types::{Color, SourceRectangle},
+    DrawState, Graphics, ImageSize, BACK_END_MAX_VERTEX_COUNT as BUFFER_SIZE,
+};
+
+/// A value changing over the lifetime of a particle.
+///
+/// The value is linearly interpolated between keys,
+/// where time goes from 0 at birth to 1 at death.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Curve<T> {
+    keys: Vec<(Scalar, T)>,
+}
+
+impl<T: Lerp + Copy> Curve<T>
+where
+    T::Scalar: FromPrimitive,
+{
+    /// Creates a curve with the same value over the lifetime.
+    pub fn constant(value: T) -> Curve<T> {
+        Curve {
+            keys: vec![(0.0, value)],
+        }
+    }
+
+    /// Creates a curve going from one value at birth to another at death.
+    pub fn linear(start: T, end: T) -> Curve<T> {
+        Curve {
+            keys: vec![(0.0, start), (1.0, end)],
+        }
+    }
+
+    /// Adds a key, keeping the keys sorted by time.
+    pub fn key(mut self, time: Scalar, value: T) -> Self {
+        let i = self.keys.partition_point(|&(t, _)| t <= time);
+        self.keys.insert(i, (time, value));
+        self
+    }
+
+    /// Computes the value at a time from 0 to 1.
+    pub fn eval(&self, time: Scalar) -> T {
+        let i = self.keys.partition_point(|&(t, _)| t <= time);
+        if i == 0 {
+            return self.keys[0].1;
+        }
+        if i == self.keys.len() {
+            return self.keys[i - 1].1;
+        }
+        let (t0, a) = self.keys[i - 1];
+        let (t1, b) = self.keys[i];
+        a.lerp(&b, &FromPrimitive::from_f64((time - t0) / (t1 - t0)))
+    }
+}
+
+/// Describes how particles are spawned and how they change.
+///
+/// Ranges `[min, max]` are sampled uniformly for each particle.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EmitterSettings {
+    /// The number of particles spawned per second.
+    pub rate: Scalar,
+    /// The lifetime of particles in seconds.
+    pub lifetime: [Scalar; 2],
+    /// The initial speed of particles.
+    pub speed: [Scalar; 2],
+    /// The direction particles move in, in radians.
+    pub direction: Scalar,
+    /// The angle on both sides of the direction particles spread within, in radians.
+    pub spread: Scalar,
+    /// The acceleration of particles, e.g. gravity.
+    pub acceleration: Vec2d,
+    /// The factor multiplied with the velocity over the lifetime.
+    pub speed_curve: Curve<Scalar>,
+    /// The color multiplied with the texture over the lifetime.
+    pub color: Curve<Color>,
+    /// The width and height of particles over the lifetime.
+    pub size: Curve<Scalar>,
+    /// The maximum number of living particles.
+    pub max_particles: usize,
+    /// The part of the texture drawn for each particle, or the whole texture.
+    pub source_rectangle: Option<SourceRectangle>,
+}
+
+impl Default for EmitterSettings {
+    fn default() -> Self {
+        EmitterSettings::new()
+    }
+}
+
+impl EmitterSettings {
+    /// Creates new settings, spawning 10 white particles per second living 1 second.
+    pub fn new() -> EmitterSettings {
+        EmitterSettings {
+            rate: 10.0,
+            lifetime: [1.0, 1.0],
+            speed: [0.0, 0.0],
+            direction: 0.0,
+            spread: std::f64::consts::PI,
+            acceleration: [0.0, 0.0],
+            speed_curve: Curve::constant(1.0),
+            color: Curve::constant([1.0; 4]),
+            size: Curve::constant(1.0),
+            max_particles: 1000,
+            source_rectangle: None,
+        }
+    }
+
+    /// Sets the number of particles spawned per second.
+    pub fn rate(mut self, value: Scalar) -> Self {
+        self.rate = value;
+        self
+    }
+
+    /// Sets the range of lifetimes in seconds.
+    pub fn lifetime(mut self, value: [Scalar; 2]) -> Self {
+        self.lifetime = value;
+        self
+    }
+
+    /// Sets the range of initial speeds.
+    pub fn speed(mut self, value: [Scalar; 2]) -> Self {
+        self.speed = value;
+        self
+    }
+
+    /// Sets the direction in radians.
+    pub fn direction(mut self, value: Scalar) -> Self {
+        self.direction = value;
+        self
+    }
+
+    /// Sets the spread angle on both sides of the direction, in radians.
+    pub fn spread(mut self, value: Scalar) -> Self {
+        self.spread = value;
+        self
+    }
+
+    /// Sets the acceleration.
+    pub fn acceleration(mut self, value: Vec2d) -> Self {
+        self.acceleration = value;
+        self
+    }
+
+    /// Sets the velocity factor over the lifetime.
+    pub fn speed_curve(mut self, value: Curve<Scalar>) -> Self {
+        self.speed_curve = value;
+        self
+    }
+
+    /// Sets the color over the lifetime.
+    pub fn color(mut self, value: Curve<Color>) -> Self {
+        self.color = value;
+        self
+    }
+
+    /// Sets the size over the lifetime.
+    pub fn size(mut self, value: Curve<Scalar>) -> Self {
+        self.size = value;
+        self
+    }
+
+    /// Sets the maximum number of living particles.
+    pub fn max_particles(mut self, value: usize) -> Self {
+        self.max_particles = value;
+        self
+    }
+
+    /// Sets the part of the texture drawn for each particle.
+    pub fn src_rect(mut self, value: SourceRectangle) -> Self {
+        self.source_rectangle = Some(value);
+        self
+    }
+}
+
+/// A living particle.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Particle {
+    /// The position.
+    pub pos: Vec2d,
+    /// The velocity, before applying the speed curve.
+    pub velocity: Vec2d,
+    /// The time since spawning in seconds.
+    pub age: Scalar,
+    /// The lifetime in seconds.
+    pub lifetime: Scalar,
+}
+
+impl Particle {
+    /// Returns the time of life from 0 to 1.
+    pub fn time(&self) -> Scalar {
+        (self.age / self.lifetime).min(1.0)
+    }
+}
+
+/// Spawns, simulates and draws particles.
+#[derive(Clone, Debug)]
+pub struct ParticleEmitter {
+    /// The settings.
+    pub settings: EmitterSettings,
+    /// The position where particles are spawned.
+    pub position: Vec2d,
+    /// Whether new particles are spawned by `update`.
+    pub active: bool,
+    particles: Vec<Particle>,
+    // Fraction of a particle to spawn in the next update.
+    pending: Scalar,
+    rng: u64,
+}
+
+impl ParticleEmitter {
+    /// Creates a new active emitter at the origin.
+    pub fn new(settings: EmitterSettings) -> ParticleEmitter {
+        ParticleEmitter {
+            settings,
+            position: [0.0, 0.0],
+            active: true,
+            particles: vec![],
+            pending: 0.0,
+            rng: 0x2545_f491_4f6c_dd1d,
+        }
+    }
+
+    /// Sets the seed of the random numbers, to repeat the same effect.
+    pub fn seed(mut self, value: u64) -> Self {
+        // Xorshift requires a state different from zero.
+        self.rng = value.max(1);
+        self
+    }
+
+    /// Returns the living particles.
+    pub fn particles(&self) -> &[Particle] {
+        &self.particles
+    }
+
+    /// Returns the number of living particles.
+    pub fn len(&self) -> usize {
+        self.particles.len()
+    }
+
+    /// Returns `true` if there are no living particles.
+    pub fn is_empty(&self) -> bool {
+        self.particles.is_empty()
+    }
+
+    /// Removes all particles.
+    pub fn clear(&mut self) {
+        self.particles.clear();
+        self.pending = 0.0;
+    }
+
+    /// Spawns a number of particles at once, e.g. for explosions.
+    pub fn burst(&mut self, n: usize) {
+        for _ in 0..n {
+            if self.particles.len() >= self.settings.max_particles {
+                break;
+            }
+            let particle = self.spawn();
+            self.particles.push(particle);
+        }
+    }
+
+    /// Moves particles forward in time, removing dead particles and spawning new ones.
+    pub fn update(&mut self, dt: Scalar) {
+        let settings = &self.settings;
+        self.particles.retain_mut(|p| {
+            p.age += dt;
+            if p.age >= p.lifetime {
+                return false;
+            }
+            let speed = settings.speed_curve.eval(p.time());
+            p.velocity[0] += settings.acceleration[0] * dt;
+            p.velocity[1] += settings.acceleration[1] * dt;
+            p.pos[0] += p.velocity[0] * speed * dt;
+            p.pos[1] += p.velocity[1] * speed * dt;
+            true
+        });
+        if self.active {
+            self.pending += self.settings.rate * dt;
+            let n = self.pending as usize;
+            self.pending -= n as Scalar;
+            self.burst(n);
+        }
+    }
+
+    /// Draws the particles centered at their positions.
+    pub fn draw<G>(
+        &self,
+        texture: &<G as Graphics>::Texture,
+        draw_state: &DrawState,
+        transform: Affine2,
+        g: &mut G,
+    ) where
+        G: Graphics,
+    {
+        if self.particles.is_empty() {
+            return;
+        }
+        let source_rectangle = self.settings.source_rectangle.unwrap_or({
+            let (w, h) = texture.get_size();
+            [0.0, 0.0, w as Scalar, h as Scalar]
+        });
+        let uv = triangulation::rect_tri_list_uv(texture, source_rectangle);
+        // Keep whole quads in each chunk.
+        const CHUNK: usize = BUFFER_SIZE - BUFFER_SIZE % 6;
+        let mut vertices = [[0.0; 2]; CHUNK];
+        let mut uvs = [[0.0; 2]; CHUNK];
+        let mut colors = [[0.0; 4]; CHUNK];
+        g.tri_list_uv_c(draw_state, texture, |f| {
+            for chunk in self.particles.chunks(CHUNK / 6) {
+                for (i, p) in chunk.iter().enumerate() {
+                    let t = p.time();
+                    let size = self.settings.size.eval(t);
+                    let color = self.settings.color.eval(t);
+                    let rect = [p.pos[0] - 0.5 * size, p.pos[1] - 0.5 * size, size, size];
+                    let quad = triangulation::rect_tri_list_xy(transform, rect);
+                    vertices[6 * i..6 * i + 6].copy_from_slice(&quad);
+                    uvs[6 * i..6 * i + 6].copy_from_slice(&uv);
+                    colors[6 * i..6 * i + 6].fill(color);
+                }
+                let n = 6 * chunk.len();
+                f(&vertices[..n], &uvs[..n], &colors[..n]);
+            }
+        });
+    }
+
+    fn spawn(&mut self) -> Particle {
+        let s = &self.settings;
+        let (lifetime, speed, spread) = (s.lifetime, s.speed, s.spread);
+        let lifetime = self.random(lifetime);
+        let speed = self.random(speed);
+        let angle = self.settings.direction + self.random([-spread, spread]);
+        Particle {
+            pos: self.position,
+            velocity: [angle.cos() * speed, angle.sin() * speed],
+            age: 0.0,
+            lifetime,
+        }
+    }
+
+    // Samples a range uniformly, using xorshift.
+    fn random(&mut self, [min, max]: [Scalar; 2]) -> Scalar {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        let t = (self.rng >> 11) as Scalar / (1u64 << 53) as Scalar;
+        min + (max - min) * t
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::software_graphics::{SoftwareGraphics, Texture};
+
+    #[test]
+    fn test_particles() {
+        let curve = Curve::linear(0.0, 1.0).key(0.5, 4.0);
+        assert_eq!(curve.eval(0.25), 2.0);
+        assert_eq!(curve.eval(0.75), 2.5);
+        assert_eq!(curve.eval(2.0), 1.0);
+
+        let settings = EmitterSettings::new()
+            .rate(10.0)
+            .lifetime([1.0, 1.0])
+            .size(Curve::constant(4.0))
+            .color(Curve::linear([1.0; 4], [0.0, 0.0, 0.0, 1.0]));
+        let mut emitter = ParticleEmitter::new(settings).seed(7);
+        emitter.position = [4.0, 4.0];
+        emitter.update(0.5);
+        assert_eq!(emitter.len(), 5);
+        emitter.update(0.8);
+        assert_eq!(emitter.len(), 13);
+        emitter.active = false;
+        emitter.update(1.0);
+        assert!(emitter.is_empty());
+
+        emitter.burst(1);
+        let texture = Texture::new(image::RgbaImage::from_pixel(1, 1, image::Rgba([255; 4])));
+        let mut g = SoftwareGraphics::new(8, 8);
+        g.draw(|c, g| emitter.draw(&texture, &c.draw_state, c.transform, g));
+        assert_eq!(g.get_pixel(2, 2), [255; 4]);
+        assert_eq!(g.get_pixel(6, 6), [0; 4]);
+    }
+}
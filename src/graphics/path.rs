@@ -0,0 +1,255 @@
+//! Draw paths
+//!
+//! A path is a sequence of contours built from lines and Bézier curves,
+//! which can be filled and stroked.
+//!
+//! ```ignore
+//! let path = Path::new()
+//!     .move_to([0.0, 0.0])
+//!     .line_to([100.0, 0.0])
+//!     .curve_to([150.0, 0.0], [150.0, 100.0], [100.0, 100.0])
+//!     .close();
+//! path.fill([1.0, 0.0, 0.0, 1.0], FillRule::NonZero, &c, g);
+//! path.stroke(&PolyLine::new([0.0, 0.0, 0.0, 1.0], 1.0), &c, g);
+//! ```
+
+pub use super::math::FillRule;
+use super::{
+    bezier::{curve_tolerance, DEFAULT_TOLERANCE},
+    curve::{flatten_cubic_bezier, flatten_quadratic_bezier},
+    math::{Cap, Scalar, Vec2d},
+    triangulation,
+    types::Color,
+    Context, Graphics, PolyLine,
+};
+
+/// A segment of a path.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PathCommand {
+    /// Starts a new contour at a point.
+    MoveTo(Vec2d),
+    /// Adds a line to a point.
+    LineTo(Vec2d),
+    /// Adds a quadratic Bézier curve with a control point and end point.
+    QuadTo(Vec2d, Vec2d),
+    /// Adds a cubic Bézier curve with two control points and end point.
+    CurveTo(Vec2d, Vec2d, Vec2d),
+    /// Closes the current contour with a line back to its start.
+    Close,
+}
+
+/// A shape made of lines and Bézier curves.
+///
+/// Curves are flattened when drawing,
+/// using the tolerance in view coordinates and the current transform scale.
+#[derive(Clone, Debug)]
+pub struct Path {
+    /// The segments of the path.
+    pub commands: Vec<PathCommand>,
+    /// The maximum distance between curves and their line segments,
+    /// in view coordinates.
+    pub tolerance: Scalar,
+}
+
+impl Default for Path {
+    fn default() -> Self {
+        Path::new()
+    }
+}
+
+impl Path {
+    /// Creates a new empty path.
+    pub fn new() -> Path {
+        Path {
+            commands: vec![],
+            tolerance: DEFAULT_TOLERANCE,
+        }
+    }
+
+    /// Sets flattening tolerance in view coordinates.
+    pub fn tolerance(mut self, value: Scalar) -> Self {
+        self.tolerance = value;
+        self
+    }
+
+    /// Starts a new contour at a point.
+    pub fn move_to(mut self, pos: Vec2d) -> Self {
+        self.commands.push(PathCommand::MoveTo(pos));
+        self
+    }
+
+    /// Adds a line to a point.
+    ///
+    /// Starts at the origin when there is no current point.
+    pub fn line_to(mut self, pos: Vec2d) -> Self {
+        self.commands.push(PathCommand::LineTo(pos));
+        self
+    }
+
+    /// Adds a quadratic Bézier curve with a control point.
+    pub fn quad_to(mut self, control: Vec2d, pos: Vec2d) -> Self {
+        self.commands.push(PathCommand::QuadTo(control, pos));
+        self
+    }
+
+    /// Adds a cubic Bézier curve with two control points.
+    pub fn curve_to(mut self, control1: Vec2d, control2: Vec2d, pos: Vec2d) -> Self {
+        self.commands
+            .push(PathCommand::CurveTo(control1, control2, pos));
+        self
+    }
+
+    /// Closes the current contour.
+    pub fn close(mut self) -> Self {
+        self.commands.push(PathCommand::Close);
+        self
+    }
+
+    /// Flattens the path into contours of points.
+    ///
+    /// Calls `f` with the points of each contour and whether it is closed.
+    /// Closed contours do not repeat the first point at the end.
+    pub fn flatten<F>(&self, tolerance: Scalar, mut f: F)
+    where
+        F: FnMut(&[Vec2d], bool),
+    {
+        let mut points: Vec<Vec2d> = vec![];
+        let mut flush = |points: &mut Vec<Vec2d>, closed: bool| {
+            if closed && points.len() > 1 && points[0] == points[points.len() - 1] {
+                points.pop();
+            }
+            if points.len() > 1 {
+                f(points, closed);
+            }
+            points.clear();
+        };
+        for &command in &self.commands {
+            let last = points.last().copied().unwrap_or([0.0, 0.0]);
+            if points.is_empty() {
+                points.push(last);
+            }
+            match command {
+                PathCommand::MoveTo(pos) => {
+                    flush(&mut points, false);
+                    points.push(pos);
+                }
+                PathCommand::LineTo(pos) => points.push(pos),
+                PathCommand::QuadTo(control, pos) => {
+                    flatten_quadratic_bezier([last, control, pos], tolerance, |p| {
+                        if p != last {
+                            points.push(p)
+                        }
+                    });
+                }
+                PathCommand::CurveTo(control1, control2, pos) => {
+                    flatten_cubic_bezier([last, control1, control2, pos], tolerance, |p| {
+                        if p != last {
+                            points.push(p)
+                        }
+                    });
+                }
+                PathCommand::Close => {
+                    let start = points[0];
+                    flush(&mut points, true);
+                    // A new contour continues from the start of the closed one.
+                    points.push(start);
+                }
+            }
+        }
+        flush(&mut points, false);
+    }
+
+    /// Fills the path using a fill rule.
+    ///
+    /// Open contours are closed implicitly.
+    pub fn fill<G>(&self, color: Color, rule: FillRule, c: &Context, g: &mut G)
+    where
+        G: Graphics,
+    {
+        if color[3] == 0.0 {
+            return;
+        }
+        let mut contours: Vec<Vec<Vec2d>> = vec![];
+        self.flatten(curve_tolerance(self.tolerance, c), |points, _| {
+            contours.push(points.to_vec())
+        });
+        let shape: Vec<&[Vec2d]> = contours.iter().map(|c| &c[..]).collect();
+        g.tri_list(&c.draw_state, &color, |f| {
+            triangulation::with_fill_rule_tri_list(c.transform, &shape, rule, |vertices| {
+                f(vertices)
+            })
+        });
+    }
+
+    /// Strokes the path with the color, radius, joins and caps of a polyline.
+    ///
+    /// Closed contours are joined at the start instead of using caps.
+    pub fn stroke<G>(&self, line: &PolyLine, c: &Context, g: &mut G)
+    where
+        G: Graphics,
+    {
+        let tolerance = curve_tolerance(self.tolerance, c);
+        self.flatten(tolerance, |points, closed| {
+            if !closed {
+                line.draw(points, &c.draw_state, c.transform, g);
+                return;
+            }
+            // Start and end in the middle of the first segment,
+            // such that every corner gets a join.
+            let (a, b) = (points[0], points[1]);
+            let mid = [0.5 * (a[0] + b[0]), 0.5 * (a[1] + b[1])];
+            let mut ring = Vec::with_capacity(points.len() + 2);
+            ring.push(mid);
+            ring.extend_from_slice(&points[1..]);
+            ring.push(a);
+            ring.push(mid);
+            line.cap(Cap::Butt)
+                .draw(&ring, &c.draw_state, c.transform, g);
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::software_graphics::SoftwareGraphics;
+
+    #[test]
+    fn test_path() {
+        let mut n = 0;
+        let path = Path::new()
+            .move_to([0.0, 0.0])
+            .line_to([4.0, 0.0])
+            .quad_to([4.0, 4.0], [0.0, 4.0])
+            .close()
+            .line_to([8.0, 8.0]);
+        path.flatten(0.1, |points, closed| {
+            if n == 0 {
+                assert!(closed && points.len() > 3);
+                assert_eq!(points[0], [0.0, 0.0]);
+                assert_eq!(points[points.len() - 1], [0.0, 4.0]);
+            } else {
+                assert_eq!((points, closed), (&[[0.0, 0.0], [8.0, 8.0]][..], false));
+            }
+            n += 1;
+        });
+        assert_eq!(n, 2);
+
+        // Two overlapping squares with same winding.
+        let square = |path: Path, x: Scalar| {
+            path.move_to([x, 2.0])
+                .line_to([x + 8.0, 2.0])
+                .line_to([x + 8.0, 10.0])
+                .line_to([x, 10.0])
+                .close()
+        };
+        let path = square(square(Path::new(), 2.0), 6.0);
+        for &(rule, overlap) in &[(FillRule::EvenOdd, [0; 4]), (FillRule::NonZero, [255; 4])] {
+            let mut g = SoftwareGraphics::new(16, 12);
+            g.draw(|c, g| path.fill([1.0; 4], rule, &c, g));
+            assert_eq!(g.get_pixel(3, 5), [255; 4]);
+            assert_eq!(g.get_pixel(8, 5), overlap);
+            assert_eq!(g.get_pixel(12, 5), [255; 4]);
+        }
+    }
+}
@@ -1,9 +1,10 @@
 //! Draw polygon
 
 use super::{
-    math::{Affine2, Scalar},
+    fill::Fill,
+    math::{identity, Affine2, Scalar},
     triangulation, types,
-    types::Color,
+    types::{Color, Radius},
     DrawState, Graphics,
 };
 
@@ -12,12 +13,29 @@ use super::{
 pub struct Polygon {
     /// The color of the polygon
     pub color: Color,
+    /// Optional gradient fill, used instead of color.
+    pub fill: Option<Fill>,
+    /// Whether the polygon is known to be convex.
+    ///
+    /// Convex polygons are triangulated as a fan, which is faster,
+    /// but gives wrong results for concave polygons.
+    pub convex: bool,
+    /// Optional width of a feathered rim for anti-aliasing.
+    ///
+    /// The rim fades out outside the edge of the filled shape.
+    /// A width of about one pixel gives smooth edges without multisampling.
+    pub feather: Option<Radius>,
 }
 
 impl Polygon {
     /// Creates new polygon
     pub fn new(color: Color) -> Polygon {
-        Polygon { color }
+        Polygon {
+            color,
+            fill: None,
+            convex: false,
+            feather: None,
+        }
     }
 
     /// Sets color.
@@ -26,6 +44,24 @@ impl Polygon {
         self
     }
 
+    /// Sets gradient fill, used instead of color.
+    pub fn fill(mut self, value: Fill) -> Self {
+        self.fill = Some(value);
+        self
+    }
+
+    /// Sets whether the polygon is known to be convex.
+    pub fn convex(mut self, value: bool) -> Self {
+        self.convex = value;
+        self
+    }
+
+    /// Sets width of a feathered rim for anti-aliasing.
+    pub fn feather(mut self, value: Radius) -> Self {
+        self.feather = Some(value);
+        self
+    }
+
     /// Draws polygon using the default method.
     #[inline(always)]
     pub fn draw<G>(
@@ -41,6 +77,8 @@ impl Polygon {
     }
 
     /// Draws polygon using triangulation.
+    ///
+    /// Uses ear clipping, unless the polygon is set as convex.
     pub fn draw_tri<G>(
         &self,
         polygon: types::Polygon<'_>,
@@ -50,8 +88,54 @@ impl Polygon {
     ) where
         G: Graphics,
     {
+        let convex = self.convex;
+        let tri_list = |m: Affine2, f: &mut dyn FnMut(&[[f32; 2]])| {
+            if convex {
+                triangulation::with_polygon_tri_list(m, polygon, f)
+            } else {
+                triangulation::with_ear_clipping_tri_list(m, polygon, f)
+            }
+        };
+        if let Some(ref fill) = self.fill {
+            fill.draw_tri_list(draw_state, transform, g, |f| tri_list(identity(), f));
+        } else {
+            g.tri_list(draw_state, &self.color, |f| tri_list(transform, f));
+        }
+        if let Some(width) = self.feather {
+            let (fill, color) = (self.fill, self.color);
+            g.tri_list_c(draw_state, |f| {
+                triangulation::with_feather_tri_list_c(
+                    transform,
+                    polygon,
+                    width,
+                    |p| fill.map(|fill| fill.color_at(p)).unwrap_or(color),
+                    |vertices, colors| f(vertices, colors),
+                )
+            });
+        }
+    }
+
+    /// Draws a shape of polygons using the even-odd rule.
+    ///
+    /// Polygons inside other polygons become holes.
+    /// See `triangulation::with_even_odd_tri_list` for details.
+    pub fn draw_even_odd<G>(
+        &self,
+        shape: types::Polygons<'_>,
+        draw_state: &DrawState,
+        transform: Affine2,
+        g: &mut G,
+    ) where
+        G: Graphics,
+    {
+        if let Some(ref fill) = self.fill {
+            fill.draw_tri_list(draw_state, transform, g, |f| {
+                triangulation::with_even_odd_tri_list(identity(), shape, |vertices| f(vertices))
+            });
+            return;
+        }
         g.tri_list(draw_state, &self.color, |f| {
-            triangulation::with_polygon_tri_list(transform, polygon, |vertices| f(vertices))
+            triangulation::with_even_odd_tri_list(transform, shape, |vertices| f(vertices))
         });
     }
 
@@ -93,6 +177,35 @@ impl Polygon {
             )
         });
     }
+
+    /// Draws tweened polygon with linear interpolation,
+    /// resampling the polygons to the same number of vertices.
+    ///
+    /// Use this to morph between polygons with different vertex counts.
+    pub fn draw_tween_lerp_resampled<G>(
+        &self,
+        polygons: types::Polygons<'_>,
+        tween_factor: Scalar,
+        samples: usize,
+        draw_state: &DrawState,
+        transform: Affine2,
+        g: &mut G,
+    ) where
+        G: Graphics,
+    {
+        if self.color[3] == 0.0 {
+            return;
+        }
+        g.tri_list(draw_state, &self.color, |f| {
+            triangulation::with_resampled_lerp_polygons_tri_list(
+                transform,
+                polygons,
+                tween_factor,
+                samples,
+                |vertices| f(vertices),
+            )
+        });
+    }
 }
 
 #[cfg(test)]
@@ -103,4 +216,24 @@ mod test {
     fn test_polygon() {
         let _polygon = Polygon::new([1.0; 4]).color([0.0; 4]);
     }
+
+    #[test]
+    fn test_concave_polygon() {
+        use crate::software_graphics::SoftwareGraphics;
+
+        // An arrow pointing right, with a notch at the back.
+        let arrow = [[0.0, 0.0], [16.0, 8.0], [0.0, 16.0], [4.0, 8.0]];
+        let mut g = SoftwareGraphics::new(16, 16);
+        g.draw(|c, g| Polygon::new([1.0; 4]).draw(&arrow, &c.draw_state, c.transform, g));
+        assert_eq!(g.get_pixel(1, 8), [0; 4]);
+        assert_eq!(g.get_pixel(8, 8), [255; 4]);
+        let mut g = SoftwareGraphics::new(16, 16);
+        g.draw(|c, g| {
+            Polygon::new([1.0; 4])
+                .convex(true)
+                .draw(&arrow, &c.draw_state, c.transform, g)
+        });
+        // A fan from the first vertex fills the notch.
+        assert_ne!(g.get_pixel(1, 8), [0; 4]);
+    }
 }
@@ -0,0 +1,120 @@
+//! Draw polyline
+
+pub use super::math::{Cap, Join};
+use super::{
+    math::Affine2,
+    triangulation, types,
+    types::{Color, Radius},
+    DrawState, Graphics,
+};
+
+/// A stroke along connected points.
+///
+/// Unlike drawing each segment with `Line`,
+/// corners are joined without seams or overlapping triangles.
+#[derive(Copy, Clone, Debug)]
+pub struct PolyLine {
+    /// The line color.
+    pub color: Color,
+    /// The line radius, half the width.
+    pub radius: Radius,
+    /// How corners are joined.
+    pub join: Join,
+    /// How the ends are drawn.
+    pub cap: Cap,
+}
+
+impl PolyLine {
+    /// Creates a new polyline with miter joins and butt caps.
+    pub fn new(color: Color, radius: Radius) -> PolyLine {
+        PolyLine {
+            color,
+            radius,
+            join: Join::Miter(4.0),
+            cap: Cap::Butt,
+        }
+    }
+
+    /// Sets color.
+    pub fn color(mut self, value: Color) -> Self {
+        self.color = value;
+        self
+    }
+
+    /// Sets radius.
+    pub fn radius(mut self, value: Radius) -> Self {
+        self.radius = value;
+        self
+    }
+
+    /// Sets width.
+    pub fn width(mut self, value: types::Width) -> Self {
+        self.radius = 0.5 * value;
+        self
+    }
+
+    /// Sets how corners are joined.
+    pub fn join(mut self, value: Join) -> Self {
+        self.join = value;
+        self
+    }
+
+    /// Sets how the ends are drawn.
+    pub fn cap(mut self, value: Cap) -> Self {
+        self.cap = value;
+        self
+    }
+
+    /// Draws polyline through points.
+    pub fn draw<G>(
+        &self,
+        points: types::Polygon<'_>,
+        draw_state: &DrawState,
+        transform: Affine2,
+        g: &mut G,
+    ) where
+        G: Graphics,
+    {
+        if self.color[3] == 0.0 {
+            return;
+        }
+        g.tri_list(draw_state, &self.color, |f| {
+            triangulation::with_polyline_tri_list(
+                transform,
+                points,
+                self.radius,
+                self.join,
+                self.cap,
+                |vertices| f(vertices),
+            )
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::software_graphics::SoftwareGraphics;
+
+    #[test]
+    fn test_polyline_joins() {
+        for &join in &[Join::Miter(4.0), Join::Bevel, Join::Round(32)] {
+            let mut g = SoftwareGraphics::new(16, 16);
+            g.draw(|c, g| {
+                PolyLine::new([1.0, 1.0, 1.0, 0.5], 2.0)
+                    .join(join)
+                    .cap(Cap::Square)
+                    .draw(
+                        &[[4.0, 12.0], [4.0, 4.0], [12.0, 4.0]],
+                        &c.draw_state,
+                        c.transform,
+                        g,
+                    );
+            });
+            // The corner is not blended twice.
+            assert_eq!(g.get_pixel(4, 4), g.get_pixel(4, 8));
+            assert_eq!(g.get_pixel(5, 5), g.get_pixel(8, 4));
+            assert_eq!(g.get_pixel(8, 8), [0; 4]);
+        }
+    }
+}
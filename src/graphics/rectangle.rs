@@ -15,7 +15,8 @@
 
 pub use super::math::margin_rectangle as margin;
 use super::{
-    math::{Affine2, Scalar},
+    fill::Fill,
+    math::{identity, Affine2, Scalar},
     triangulation, types,
     types::{Color, Radius, Resolution},
     DrawState, Graphics,
@@ -86,6 +87,14 @@ pub enum Shape {
     Round(Radius, Resolution),
     /// Bevel corners
     Bevel(Radius),
+    /// Round corners with radius per corner, with resolution per corner.
+    ///
+    /// Radii are ordered top-left, top-right, bottom-right, bottom-left.
+    RoundCorners([Radius; 4], Resolution),
+    /// Bevel corners with radius per corner.
+    ///
+    /// Radii are ordered top-left, top-right, bottom-right, bottom-left.
+    BevelCorners([Radius; 4]),
 }
 
 /// The border of the rectangle
@@ -107,6 +116,13 @@ pub struct Rectangle {
     pub shape: Shape,
     /// The border
     pub border: Option<Border>,
+    /// Optional gradient fill, used instead of color.
+    pub fill: Option<Fill>,
+    /// Optional width of a feathered rim for anti-aliasing.
+    ///
+    /// The rim fades out outside the edge of the filled shape.
+    /// A width of about one pixel gives smooth edges without multisampling.
+    pub feather: Option<Radius>,
 }
 
 impl Rectangle {
@@ -116,6 +132,8 @@ impl Rectangle {
             color,
             shape: Shape::Square,
             border: None,
+            fill: None,
+            feather: None,
         }
     }
 
@@ -125,6 +143,21 @@ impl Rectangle {
             color,
             shape: Shape::Round(round_radius, 32),
             border: None,
+            fill: None,
+            feather: None,
+        }
+    }
+
+    /// Creates a new rectangle with a round radius per corner.
+    ///
+    /// Radii are ordered top-left, top-right, bottom-right, bottom-left.
+    pub fn new_round_corners(color: Color, radii: [Radius; 4]) -> Rectangle {
+        Rectangle {
+            color,
+            shape: Shape::RoundCorners(radii, 32),
+            border: None,
+            fill: None,
+            feather: None,
         }
     }
 
@@ -134,6 +167,8 @@ impl Rectangle {
             color: [0.0; 4],
             shape: Shape::Square,
             border: Some(Border { color, radius }),
+            fill: None,
+            feather: None,
         }
     }
 
@@ -150,6 +185,8 @@ impl Rectangle {
                 color,
                 radius: border_radius,
             }),
+            fill: None,
+            feather: None,
         }
     }
 
@@ -177,6 +214,18 @@ impl Rectangle {
         self
     }
 
+    /// Sets gradient fill, used instead of color.
+    pub fn fill(mut self, value: Fill) -> Self {
+        self.fill = Some(value);
+        self
+    }
+
+    /// Sets width of a feathered rim for anti-aliasing.
+    pub fn feather(mut self, value: Radius) -> Self {
+        self.feather = Some(value);
+        self
+    }
+
     /// Draws the rectangle by corners using the default method.
     #[inline(always)]
     pub fn draw_from_to<P: Into<types::Vec2d>, G>(
@@ -232,35 +281,66 @@ impl Rectangle {
         G: Graphics,
     {
         let rectangle = rectangle.into();
-        if self.color[3] != 0.0 {
-            match self.shape {
-                Shape::Square => {
-                    g.tri_list(draw_state, &self.color, |f| {
-                        f(&triangulation::rect_tri_list_xy(transform, rectangle))
-                    });
-                }
-                Shape::Round(round_radius, resolution) => {
-                    g.tri_list(draw_state, &self.color, |f| {
-                        triangulation::with_round_rectangle_tri_list(
-                            resolution,
-                            transform,
-                            rectangle,
-                            round_radius,
-                            |vertices| f(vertices),
-                        )
-                    });
-                }
-                Shape::Bevel(bevel_radius) => {
-                    g.tri_list(draw_state, &self.color, |f| {
-                        triangulation::with_round_rectangle_tri_list(
-                            2,
-                            transform,
-                            rectangle,
-                            bevel_radius,
-                            |vertices| f(vertices),
-                        )
-                    });
-                }
+        let tri_list = |m: Affine2, f: &mut dyn FnMut(&[[f32; 2]])| match self.shape {
+            Shape::Square => f(&triangulation::rect_tri_list_xy(m, rectangle)),
+            Shape::Round(round_radius, resolution) => triangulation::with_round_rectangle_tri_list(
+                resolution,
+                m,
+                rectangle,
+                round_radius,
+                |vertices| f(vertices),
+            ),
+            Shape::Bevel(bevel_radius) => triangulation::with_round_rectangle_tri_list(
+                2,
+                m,
+                rectangle,
+                bevel_radius,
+                |vertices| f(vertices),
+            ),
+            Shape::RoundCorners(radii, resolution) => {
+                triangulation::with_round_corners_rectangle_tri_list(
+                    resolution,
+                    m,
+                    rectangle,
+                    radii,
+                    |vertices| f(vertices),
+                )
+            }
+            Shape::BevelCorners(radii) => triangulation::with_round_corners_rectangle_tri_list(
+                2,
+                m,
+                rectangle,
+                radii,
+                |vertices| f(vertices),
+            ),
+        };
+        if let Some(ref fill) = self.fill {
+            fill.draw_tri_list(draw_state, transform, g, |f| tri_list(identity(), f));
+        } else if self.color[3] != 0.0 {
+            g.tri_list(draw_state, &self.color, |f| tri_list(transform, f));
+        }
+        if let Some(width) = self.feather {
+            if self.fill.is_some() || self.color[3] != 0.0 {
+                let (resolution, radii) = match self.shape {
+                    Shape::Square => (2, [0.0; 4]),
+                    Shape::Round(radius, resolution) => (resolution, [radius; 4]),
+                    Shape::Bevel(radius) => (2, [radius; 4]),
+                    Shape::RoundCorners(radii, resolution) => (resolution, radii),
+                    Shape::BevelCorners(radii) => (2, radii),
+                };
+                let outline =
+                    triangulation::round_corners_rectangle_outline(resolution, rectangle, radii);
+                let fill = self.fill;
+                let color = self.color;
+                g.tri_list_c(draw_state, |f| {
+                    triangulation::with_feather_tri_list_c(
+                        transform,
+                        &outline,
+                        width,
+                        |p| fill.map(|fill| fill.color_at(p)).unwrap_or(color),
+                        |vertices, colors| f(vertices, colors),
+                    )
+                });
             }
         }
 
@@ -306,6 +386,30 @@ impl Rectangle {
                         )
                     });
                 }
+                Shape::RoundCorners(radii, resolution) => {
+                    g.tri_list(draw_state, &color, |f| {
+                        triangulation::with_round_corners_rectangle_border_tri_list(
+                            resolution,
+                            transform,
+                            rectangle,
+                            radii,
+                            border_radius,
+                            |vertices| f(vertices),
+                        )
+                    });
+                }
+                Shape::BevelCorners(radii) => {
+                    g.tri_list(draw_state, &color, |f| {
+                        triangulation::with_round_corners_rectangle_border_tri_list(
+                            2,
+                            transform,
+                            rectangle,
+                            radii,
+                            border_radius,
+                            |vertices| f(vertices),
+                        )
+                    });
+                }
             }
         }
     }
@@ -326,6 +430,45 @@ mod test {
             });
     }
 
+    #[test]
+    fn test_round_corners() {
+        use crate::software_graphics::SoftwareGraphics;
+
+        let mut g = SoftwareGraphics::new(16, 16);
+        g.draw(|c, g| {
+            Rectangle::new_round_corners([1.0; 4], [8.0, 0.0, 0.0, 0.0]).draw(
+                [0.0, 0.0, 16.0, 16.0],
+                &c.draw_state,
+                c.transform,
+                g,
+            );
+        });
+        assert_eq!(g.get_pixel(0, 0), [0; 4]);
+        assert_eq!(g.get_pixel(15, 0), [255; 4]);
+        assert_eq!(g.get_pixel(0, 15), [255; 4]);
+        assert_eq!(g.get_pixel(15, 15), [255; 4]);
+    }
+
+    #[test]
+    fn test_feather() {
+        use crate::software_graphics::SoftwareGraphics;
+
+        let mut g = SoftwareGraphics::new(16, 16);
+        g.draw(|c, g| {
+            Rectangle::new([1.0; 4]).feather(2.0).draw(
+                [4.0, 4.0, 8.0, 8.0],
+                &c.draw_state,
+                c.transform,
+                g,
+            );
+        });
+        assert_eq!(g.get_pixel(8, 8), [255; 4]);
+        let [_, _, _, a] = g.get_pixel(3, 8);
+        let [_, _, _, b] = g.get_pixel(2, 8);
+        assert!(255 > a && a > b && b > 0);
+        assert_eq!(g.get_pixel(1, 8), [0; 4]);
+    }
+
     #[test]
     fn test_rectangle_by_corners() {
         assert_eq!(
@@ -1,5 +1,9 @@
 use super::{
-    math::{margin_rectangle, relative_rectangle, Scalar},
+    math::{
+        inflate_rectangle, inside_rectangle, margin_rectangle, overlap_rectangle,
+        rectangle_corners, rectangle_from_corners, relative_rectangle, union_rectangle, Scalar,
+        Vec2d,
+    },
     types::Rectangle,
 };
 
@@ -16,6 +20,27 @@ pub trait Rectangled: Sized {
 
     /// Moves to a relative rectangle using the current rectangle as tile.
     fn rel(self, x: Scalar, y: Scalar) -> Self;
+
+    /// Grows the current rectangle by a horizontal and vertical distance on each side.
+    fn inflate(self, dx: Scalar, dy: Scalar) -> Self;
+
+    /// Computes the smallest rectangle containing both rectangles.
+    fn union(self, other: Self) -> Self;
+
+    /// Computes the overlap of both rectangles, if any.
+    fn intersect(self, other: Self) -> Option<Self>;
+
+    /// Returns `true` if the rectangle contains a point.
+    fn contains(self, pos: Vec2d) -> bool;
+
+    /// Returns the center of the rectangle.
+    fn center(self) -> Vec2d;
+
+    /// Returns the upper left and lower right corners.
+    fn corners(self) -> [Vec2d; 2];
+
+    /// Creates a rectangle from two opposite corners, in any order.
+    fn from_corners(a: Vec2d, b: Vec2d) -> Self;
 }
 
 impl Rectangled for Rectangle {
@@ -28,4 +53,59 @@ impl Rectangled for Rectangle {
     fn rel(self, x: Scalar, y: Scalar) -> Self {
         relative_rectangle(self, [x, y])
     }
+
+    #[inline(always)]
+    fn inflate(self, dx: Scalar, dy: Scalar) -> Self {
+        inflate_rectangle(self, [dx, dy])
+    }
+
+    #[inline(always)]
+    fn union(self, other: Self) -> Self {
+        union_rectangle(self, other)
+    }
+
+    #[inline(always)]
+    fn intersect(self, other: Self) -> Option<Self> {
+        overlap_rectangle(self, other)
+    }
+
+    #[inline(always)]
+    fn contains(self, pos: Vec2d) -> bool {
+        inside_rectangle(self, pos)
+    }
+
+    #[inline(always)]
+    fn center(self) -> Vec2d {
+        [self[0] + 0.5 * self[2], self[1] + 0.5 * self[3]]
+    }
+
+    #[inline(always)]
+    fn corners(self) -> [Vec2d; 2] {
+        rectangle_corners(self)
+    }
+
+    #[inline(always)]
+    fn from_corners(a: Vec2d, b: Vec2d) -> Self {
+        rectangle_from_corners(a, b)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_rectangled() {
+        let a: Rectangle = [0.0, 0.0, 2.0, 2.0];
+        assert!(a.contains([1.0, 1.0]));
+        assert!(!a.contains([3.0, 1.0]));
+        assert_eq!(a.union([1.0, 1.0, 2.0, 2.0]), [0.0, 0.0, 3.0, 3.0]);
+        assert_eq!(
+            a.intersect([1.0, 1.0, 2.0, 2.0]),
+            Some([1.0, 1.0, 1.0, 1.0])
+        );
+        assert_eq!(Rectangle::from_corners([2.0, 2.0], [0.0, 0.0]), a);
+        assert_eq!(a.inflate(1.0, 1.0).corners(), [[-1.0, -1.0], [3.0, 3.0]]);
+        assert_eq!(a.center(), [1.0, 1.0]);
+    }
 }
@@ -0,0 +1,41 @@
+//! Rendering to textures
+
+use super::{Context, Graphics, ImageSize};
+
+/// Implemented by back-ends that can render into an offscreen texture.
+///
+/// This is separate from `Graphics`, since not every back-end can render offscreen.
+/// Generic code can require both traits to render e.g. minimaps or cached layers,
+/// and then draw the texture with `Image`.
+///
+/// ```ignore
+/// fn draw_minimap<G: RenderToTexture>(target: &mut G::RenderTarget, c: &Context, g: &mut G) {
+///     g.draw_to_texture(target, |c, g| {
+///         clear([0.0; 4], g);
+///         // ...
+///     });
+///     image(G::render_target_texture(target), c.transform, g);
+/// }
+/// ```
+pub trait RenderToTexture: Graphics {
+    /// The offscreen target that is rendered to.
+    type RenderTarget: ImageSize;
+
+    /// Creates a new render target of size in pixels.
+    fn create_render_target(
+        &mut self,
+        width: u32,
+        height: u32,
+    ) -> Result<Self::RenderTarget, String>;
+
+    /// Gets the texture of a render target, for drawing it as an image.
+    fn render_target_texture(target: &Self::RenderTarget) -> &Self::Texture;
+
+    /// Draws to a render target instead of the current output.
+    ///
+    /// The context covers the render target, with the origin in the upper left corner,
+    /// such that the texture appears upright when drawn as an image.
+    fn draw_to_texture<F, U>(&mut self, target: &mut Self::RenderTarget, f: F) -> U
+    where
+        F: FnOnce(Context, &mut Self) -> U;
+}
@@ -0,0 +1,312 @@
+//! Scene graph
+//!
+//! A minimal retained layer on top of the immediate drawing API.
+//! A scene is a tree of nodes with local transforms,
+//! where each node can have a shape, image or text attached.
+//! The whole tree is drawn with a single call.
+//!
+//! ```ignore
+//! let mut scene = Scene::new();
+//! let player = scene.add(scene.root(), Node::new(Drawable::Image(Image::new(), texture))
+//!     .transform(identity().trans(100.0, 50.0)));
+//! scene.add(player, Node::new(Drawable::Text(Text::new(12), "Player".into()))
+//!     .transform(identity().trans(0.0, -4.0))
+//!     .z(1));
+//!
+//! // Each frame.
+//! scene.node_mut(player).unwrap().transform = identity().trans(x, y);
+//! scene.draw_with_cache(&c.draw_state, c.transform, &mut glyphs, g)?;
+//! ```
+
+use std::rc::Rc;
+
+use super::{
+    math::{identity, multiply, Affine2, Vec2d},
+    types, CharacterCache, DrawState, Ellipse, Graphics, Image, Line, Polygon, Rectangle, Text,
+};
+
+/// Identifies a node in a scene.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct NodeId(usize);
+
+/// Something attached to a node that is drawn.
+#[derive(Clone, Debug)]
+pub enum Drawable<T> {
+    /// Nothing is drawn, e.g. for nodes that group other nodes.
+    None,
+    /// A rectangle.
+    Rectangle(Rectangle, types::Rectangle),
+    /// An ellipse inside a rectangle.
+    Ellipse(Ellipse, types::Rectangle),
+    /// A line.
+    Line(Line, types::Line),
+    /// A polygon.
+    Polygon(Polygon, Vec<Vec2d>),
+    /// An image with a texture.
+    Image(Image, Rc<T>),
+    /// A text, which requires a character cache to draw.
+    Text(Text, String),
+}
+
+/// A node in a scene.
+#[derive(Clone, Debug)]
+pub struct Node<T> {
+    /// The transform relative to the parent.
+    pub transform: Affine2,
+    /// The drawing order among the siblings, lower first.
+    ///
+    /// Siblings with the same value are drawn in the order they were added.
+    pub z: i32,
+    /// Whether the node and its children are drawn.
+    pub visible: bool,
+    /// The attached drawable.
+    pub drawable: Drawable<T>,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+}
+
+impl<T> Node<T> {
+    /// Creates a new visible node with a drawable.
+    pub fn new(drawable: Drawable<T>) -> Node<T> {
+        Node {
+            transform: identity(),
+            z: 0,
+            visible: true,
+            drawable,
+            parent: None,
+            children: vec![],
+        }
+    }
+
+    /// Creates a new node without drawable, for grouping other nodes.
+    pub fn group() -> Node<T> {
+        Node::new(Drawable::None)
+    }
+
+    /// Sets the transform relative to the parent.
+    pub fn transform(mut self, value: Affine2) -> Self {
+        self.transform = value;
+        self
+    }
+
+    /// Sets the drawing order among the siblings.
+    pub fn z(mut self, value: i32) -> Self {
+        self.z = value;
+        self
+    }
+
+    /// Sets whether the node and its children are drawn.
+    pub fn visible(mut self, value: bool) -> Self {
+        self.visible = value;
+        self
+    }
+
+    /// Returns the parent, or `None` for the root.
+    pub fn parent(&self) -> Option<NodeId> {
+        self.parent
+    }
+
+    /// Returns the children, in the order they were added.
+    pub fn children(&self) -> &[NodeId] {
+        &self.children
+    }
+}
+
+/// A tree of nodes.
+///
+/// Nodes are drawn before their children.
+#[derive(Clone, Debug)]
+pub struct Scene<T> {
+    nodes: Vec<Option<Node<T>>>,
+}
+
+impl<T> Default for Scene<T> {
+    fn default() -> Self {
+        Scene::new()
+    }
+}
+
+impl<T> Scene<T> {
+    /// Creates a new scene with an empty root node.
+    pub fn new() -> Scene<T> {
+        Scene {
+            nodes: vec![Some(Node::group())],
+        }
+    }
+
+    /// Returns the root node.
+    pub fn root(&self) -> NodeId {
+        NodeId(0)
+    }
+
+    /// Adds a node as the last child of a parent.
+    ///
+    /// Panics if the parent was removed.
+    pub fn add(&mut self, parent: NodeId, mut node: Node<T>) -> NodeId {
+        let id = NodeId(self.nodes.len());
+        self.node_mut(parent)
+            .expect("Parent node does not exist")
+            .children
+            .push(id);
+        node.parent = Some(parent);
+        node.children.clear();
+        self.nodes.push(Some(node));
+        id
+    }
+
+    /// Removes a node with all its children.
+    ///
+    /// The root can not be removed, only its children.
+    pub fn remove(&mut self, id: NodeId) {
+        if id == self.root() {
+            return;
+        }
+        let node = match self.nodes.get_mut(id.0).and_then(Option::take) {
+            Some(node) => node,
+            None => return,
+        };
+        if let Some(parent) = node.parent.and_then(|p| self.node_mut(p)) {
+            parent.children.retain(|&ch| ch != id);
+        }
+        let mut stack = node.children;
+        while let Some(id) = stack.pop() {
+            if let Some(node) = self.nodes[id.0].take() {
+                stack.extend(node.children);
+            }
+        }
+    }
+
+    /// Returns a node, or `None` if it was removed.
+    pub fn node(&self, id: NodeId) -> Option<&Node<T>> {
+        self.nodes.get(id.0)?.as_ref()
+    }
+
+    /// Returns a mutable node, or `None` if it was removed.
+    pub fn node_mut(&mut self, id: NodeId) -> Option<&mut Node<T>> {
+        self.nodes.get_mut(id.0)?.as_mut()
+    }
+
+    /// Computes the transform of a node relative to the scene.
+    pub fn world_transform(&self, id: NodeId) -> Affine2 {
+        let mut m = identity();
+        let mut next = Some(id);
+        while let Some(node) = next.and_then(|id| self.node(id)) {
+            m = multiply(node.transform, m);
+            next = node.parent;
+        }
+        m
+    }
+
+    /// Draws the scene with a transform, skipping text.
+    pub fn draw<G>(&self, draw_state: &DrawState, transform: Affine2, g: &mut G)
+    where
+        G: Graphics<Texture = T>,
+    {
+        self.draw_node(
+            self.root(),
+            draw_state,
+            transform,
+            g,
+            &mut |_, _, _, _, _| {},
+        );
+    }
+
+    /// Draws the scene with a transform,
+    /// using a character cache to draw text.
+    pub fn draw_with_cache<C, G>(
+        &self,
+        draw_state: &DrawState,
+        transform: Affine2,
+        cache: &mut C,
+        g: &mut G,
+    ) -> Result<(), C::Error>
+    where
+        C: CharacterCache<Texture = T>,
+        G: Graphics<Texture = T>,
+    {
+        let mut res = Ok(());
+        self.draw_node(
+            self.root(),
+            draw_state,
+            transform,
+            g,
+            &mut |text, string, draw_state, transform, g| {
+                if res.is_ok() {
+                    res = text.draw(string, cache, draw_state, transform, g);
+                }
+            },
+        );
+        res
+    }
+
+    fn draw_node<G>(
+        &self,
+        id: NodeId,
+        draw_state: &DrawState,
+        transform: Affine2,
+        g: &mut G,
+        text: &mut dyn FnMut(&Text, &str, &DrawState, Affine2, &mut G),
+    ) where
+        G: Graphics<Texture = T>,
+    {
+        let node = match self.node(id) {
+            Some(node) if node.visible => node,
+            _ => return,
+        };
+        let m = multiply(transform, node.transform);
+        match &node.drawable {
+            Drawable::None => {}
+            Drawable::Rectangle(r, rect) => r.draw(*rect, draw_state, m, g),
+            Drawable::Ellipse(e, rect) => e.draw(*rect, draw_state, m, g),
+            Drawable::Line(l, line) => l.draw(*line, draw_state, m, g),
+            Drawable::Polygon(p, polygon) => p.draw(polygon, draw_state, m, g),
+            Drawable::Image(image, texture) => image.draw(&**texture, draw_state, m, g),
+            Drawable::Text(t, string) => text(t, string, draw_state, m, g),
+        }
+        let mut children = node.children.clone();
+        children.sort_by_key(|&ch| self.node(ch).map(|n| n.z).unwrap_or(0));
+        for ch in children {
+            self.draw_node(ch, draw_state, m, g, text);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        graphics::Transformed,
+        software_graphics::{SoftwareGraphics, Texture},
+    };
+
+    #[test]
+    fn test_scene() {
+        let red = Rectangle::new([1.0, 0.0, 0.0, 1.0]);
+        let blue = Rectangle::new([0.0, 0.0, 1.0, 1.0]);
+        let mut scene: Scene<Texture> = Scene::new();
+        let root = scene.root();
+        let group = scene.add(root, Node::group().transform(identity().trans(2.0, 2.0)));
+        let a = Drawable::Rectangle(red, [0.0, 0.0, 4.0, 4.0]);
+        let b = Drawable::Rectangle(blue, [2.0, 2.0, 4.0, 4.0]);
+        // Added first, but drawn last.
+        scene.add(group, Node::new(a).z(1));
+        scene.add(group, Node::new(b));
+        let hidden = scene.add(
+            root,
+            Node::new(Drawable::Rectangle(red, [0.0, 0.0, 8.0, 8.0])),
+        );
+        scene.node_mut(hidden).unwrap().visible = false;
+        assert_eq!(scene.world_transform(group), identity().trans(2.0, 2.0));
+
+        let mut g = SoftwareGraphics::new(8, 8);
+        g.draw(|c, g| scene.draw(&c.draw_state, c.transform, g));
+        assert_eq!(g.get_pixel(0, 0), [0; 4]);
+        assert_eq!(g.get_pixel(2, 2), [255, 0, 0, 255]);
+        assert_eq!(g.get_pixel(4, 4), [255, 0, 0, 255]);
+        assert_eq!(g.get_pixel(7, 7), [0, 0, 255, 255]);
+
+        scene.remove(group);
+        assert!(scene.node(group).is_none());
+        assert!(scene.node(root).unwrap().children() == [hidden]);
+    }
+}
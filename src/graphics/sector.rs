@@ -0,0 +1,114 @@
+//! Draw sector
+
+use super::{
+    math::{Affine2, Scalar, Vec2d},
+    triangulation,
+    types::{Color, Radius, Resolution},
+    DrawState, Graphics,
+};
+
+/// A filled slice of a circle, like a piece of a pie chart.
+///
+/// The sector goes from the start angle to the end angle,
+/// which complements the stroke of `CircleArc`.
+#[derive(Copy, Clone, Debug)]
+pub struct Sector {
+    /// The sector color.
+    pub color: Color,
+    /// The radius of the circle.
+    pub radius: Radius,
+    /// The start angle in radians.
+    pub start: Scalar,
+    /// The end angle in radians.
+    pub end: Scalar,
+    /// The resolution per 360 degrees.
+    pub resolution: Resolution,
+}
+
+impl Sector {
+    /// Creates a new sector.
+    pub fn new(color: Color, radius: Radius, start: Scalar, end: Scalar) -> Sector {
+        Sector {
+            color,
+            radius,
+            start,
+            end,
+            resolution: 128,
+        }
+    }
+
+    /// Sets color.
+    pub fn color(mut self, value: Color) -> Self {
+        self.color = value;
+        self
+    }
+
+    /// Sets the radius of the circle.
+    pub fn radius(mut self, value: Radius) -> Self {
+        self.radius = value;
+        self
+    }
+
+    /// Sets the start angle (in radians).
+    pub fn start(mut self, value: Scalar) -> Self {
+        self.start = value;
+        self
+    }
+
+    /// Sets the end angle (in radians).
+    pub fn end(mut self, value: Scalar) -> Self {
+        self.end = value;
+        self
+    }
+
+    /// Sets the resolution per 360 degrees.
+    pub fn resolution(mut self, value: Resolution) -> Self {
+        self.resolution = value;
+        self
+    }
+
+    /// Draws sector around center.
+    pub fn draw<G>(&self, center: Vec2d, draw_state: &DrawState, transform: Affine2, g: &mut G)
+    where
+        G: Graphics,
+    {
+        if self.color[3] == 0.0 {
+            return;
+        }
+        g.tri_list(draw_state, &self.color, |f| {
+            triangulation::with_sector_tri_list(
+                self.start,
+                self.end,
+                self.resolution,
+                transform,
+                center,
+                self.radius,
+                |vertices| f(vertices),
+            )
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{graphics::radians::Radians, software_graphics::SoftwareGraphics};
+
+    #[test]
+    fn test_sector() {
+        let mut g = SoftwareGraphics::new(32, 32);
+        g.draw(|c, g| {
+            // A quarter from right to down, with y axis pointing down.
+            Sector::new([1.0; 4], 12.0, 0.0, <Scalar as Radians>::_90()).draw(
+                [16.0, 16.0],
+                &c.draw_state,
+                c.transform,
+                g,
+            );
+        });
+        assert_eq!(g.get_pixel(20, 20), [255; 4]);
+        assert_eq!(g.get_pixel(12, 20), [0; 4]);
+        assert_eq!(g.get_pixel(20, 12), [0; 4]);
+        assert_eq!(g.get_pixel(26, 26), [0; 4]);
+    }
+}
@@ -1,11 +1,14 @@
 //! Draw text
 
+use std::ops::Range;
+
 use super::{
-    character::CharacterCache,
+    character::{Character, CharacterCache, FontMetrics},
     color,
     math::{Affine2, Vec2d},
-    types::{Color, FontSize},
-    DrawState, Graphics, Image, Transformed,
+    triangulation,
+    types::{Color, FontSize, Rectangle, Scalar},
+    DrawState, Graphics, Transformed, BACK_END_MAX_VERTEX_COUNT as BUFFER_SIZE,
 };
 
 /// Renders text
@@ -17,6 +20,47 @@ pub struct Text {
     pub font_size: FontSize,
     /// Whether or not the text's position should be rounded (to a signed distance field).
     pub round: bool,
+    /// The number of subpixel glyph variants, `0` or `1` disables subpixel positioning.
+    pub subpixel: u32,
+    /// The hinting applied to glyph positions.
+    pub hinting: Hinting,
+    /// The outline drawn around glyphs.
+    pub outline: Option<TextOutline>,
+    /// The shadow drawn below glyphs.
+    pub shadow: Option<TextShadow>,
+}
+
+/// An outline around text, see `Text::outline`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TextOutline {
+    /// The width of the outline.
+    pub width: Scalar,
+    /// The color of the outline.
+    pub color: Color,
+}
+
+/// A drop shadow below text, see `Text::shadow`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TextShadow {
+    /// The offset of the shadow from the text.
+    pub offset: Vec2d,
+    /// The radius of the blur, `0` for sharp edges.
+    pub blur: Scalar,
+    /// The color of the shadow.
+    pub color: Color,
+}
+
+/// Hinting of glyph positions to the pixel grid.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Hinting {
+    /// Glyph positions are not changed.
+    None,
+    /// Snaps the baseline to whole pixels.
+    ///
+    /// Works well with subpixel positioning, which handles horizontal placement.
+    Vertical,
+    /// Snaps the baseline and advances to whole pixels.
+    Full,
 }
 
 impl Text {
@@ -26,6 +70,10 @@ impl Text {
             color: color::BLACK,
             font_size,
             round: false,
+            subpixel: 0,
+            hinting: Hinting::None,
+            outline: None,
+            shadow: None,
         }
     }
 
@@ -35,6 +83,10 @@ impl Text {
             color,
             font_size,
             round: false,
+            subpixel: 0,
+            hinting: Hinting::None,
+            outline: None,
+            shadow: None,
         }
     }
 
@@ -44,6 +96,49 @@ impl Text {
         self
     }
 
+    /// Sets the number of subpixel glyph variants.
+    ///
+    /// Glyphs are rendered at fractional pixel offsets, quantized to
+    /// this many variants per pixel, which keeps small text evenly spaced.
+    /// See `CharacterCache::character_at`.
+    ///
+    /// Like hinting, this assumes text coordinates map to whole device pixels.
+    pub fn subpixel(mut self, variants: u32) -> Text {
+        self.subpixel = variants;
+        self
+    }
+
+    /// Sets the hinting of glyph positions.
+    ///
+    /// Positions are snapped in text coordinates, before the transform is applied.
+    /// This only aligns glyphs to device pixels when the transform
+    /// is a translation by whole pixels without scaling or rotation.
+    pub fn hinting(mut self, hinting: Hinting) -> Text {
+        self.hinting = hinting;
+        self
+    }
+
+    /// Draws an outline around glyphs.
+    ///
+    /// The outline is drawn as copies of the glyphs around their position,
+    /// which keeps text readable over arbitrary backgrounds.
+    pub fn outline(mut self, width: Scalar, color: Color) -> Text {
+        self.outline = Some(TextOutline { width, color });
+        self
+    }
+
+    /// Draws a shadow below glyphs.
+    ///
+    /// The blur is approximated by spreading translucent copies of the glyphs.
+    pub fn shadow(mut self, offset: Vec2d, blur: Scalar, color: Color) -> Text {
+        self.shadow = Some(TextShadow {
+            offset,
+            blur,
+            color,
+        });
+        self
+    }
+
     /// Draws text at position with a character cache
     pub fn draw_pos<C, G>(
         &self,
@@ -74,34 +169,1170 @@ impl Text {
         C: CharacterCache,
         G: Graphics<Texture = <C as CharacterCache>::Texture>,
     {
-        let mut image = Image::new_color(self.color);
+        let mut glyphs = vec![];
+        self.layout(text, cache, |ch, subpixel_offset, _, pos| {
+            glyphs.push(Glyph {
+                ch,
+                subpixel_offset,
+                pos,
+            })
+        })?;
+        self.draw_glyphs(&glyphs, self.color, cache, draw_state, transform, g)
+    }
+
+    /// Draws text laid out in a box with a character cache.
+    ///
+    /// Returns the bounding box of the text, see `TextLayout::bounds`.
+    pub fn draw_layout<C, G>(
+        &self,
+        text: &str,
+        layout: &TextLayout,
+        cache: &mut C,
+        draw_state: &DrawState,
+        transform: Affine2,
+        g: &mut G,
+    ) -> Result<Rectangle, C::Error>
+    where
+        C: CharacterCache,
+        G: Graphics<Texture = <C as CharacterCache>::Texture>,
+    {
+        let lines = layout.lines(self, text, cache)?;
+        for line in &lines {
+            let string = &text[line.range.clone()];
+            if line.word_spacing == 0.0 {
+                self.draw_pos(string, line.pos, cache, draw_state, transform, g)?;
+                continue;
+            }
+            // Draw words separately to stretch the spaces between them.
+            let space = cache.width(self.font_size, " ")? + line.word_spacing;
+            let mut x = line.pos[0];
+            for word in string.split(' ') {
+                self.draw_pos(word, [x, line.pos[1]], cache, draw_state, transform, g)?;
+                x += cache.width(self.font_size, word)? + space;
+            }
+        }
+        let metrics = cache.metrics(self.font_size)?;
+        Ok(layout.bounds(&metrics, &lines))
+    }
 
+    // Draws placed glyphs with shadow and outline.
+    fn draw_glyphs<C, G>(
+        &self,
+        glyphs: &[Glyph],
+        color: Color,
+        cache: &mut C,
+        draw_state: &DrawState,
+        transform: Affine2,
+        g: &mut G,
+    ) -> Result<(), C::Error>
+    where
+        C: CharacterCache,
+        G: Graphics<Texture = <C as CharacterCache>::Texture>,
+    {
+        let outline_width = self.outline.map(|o| o.width).unwrap_or(0.0);
+        if let Some(shadow) = self.shadow {
+            let radius = outline_width + shadow.blur;
+            let mut color = shadow.color;
+            if shadow.blur > 0.0 {
+                // Overlapping copies add up to the shadow opacity.
+                let passes = spread_offsets(radius).len() + 1;
+                color[3] = 1.0 - (1.0 - color[3]).powf(1.0 / passes as f32);
+            }
+            let transform = transform.trans_pos(shadow.offset);
+            self.draw_spread(glyphs, color, radius, true, cache, draw_state, transform, g)?;
+        }
+        if let Some(outline) = self.outline {
+            let (width, color) = (outline.width, outline.color);
+            self.draw_spread(glyphs, color, width, false, cache, draw_state, transform, g)?;
+        }
+        self.draw_pass(glyphs, color, false, cache, draw_state, transform, g)
+    }
+
+    // Draws copies of glyphs around their position.
+    //
+    // Includes the copy at the center when `center` is true.
+    #[allow(clippy::too_many_arguments)]
+    fn draw_spread<C, G>(
+        &self,
+        glyphs: &[Glyph],
+        color: Color,
+        radius: Scalar,
+        center: bool,
+        cache: &mut C,
+        draw_state: &DrawState,
+        transform: Affine2,
+        g: &mut G,
+    ) -> Result<(), C::Error>
+    where
+        C: CharacterCache,
+        G: Graphics<Texture = <C as CharacterCache>::Texture>,
+    {
+        if center || radius <= 0.0 {
+            self.draw_pass(glyphs, color, true, cache, draw_state, transform, g)?;
+        }
+        if radius > 0.0 {
+            for offset in spread_offsets(radius) {
+                let transform = transform.trans_pos(offset);
+                self.draw_pass(glyphs, color, true, cache, draw_state, transform, g)?;
+            }
+        }
+        Ok(())
+    }
+
+    // Draws placed glyphs.
+    //
+    // Glyphs sharing a texture, e.g. a page of a glyph atlas,
+    // are drawn with a single call to `Graphics::tri_list_uv`.
+    // Color glyphs are skipped in effect passes, since they can not be tinted.
+    #[allow(clippy::too_many_arguments)]
+    fn draw_pass<C, G>(
+        &self,
+        glyphs: &[Glyph],
+        color: Color,
+        effect: bool,
+        cache: &mut C,
+        draw_state: &DrawState,
+        transform: Affine2,
+        g: &mut G,
+    ) -> Result<(), C::Error>
+    where
+        C: CharacterCache,
+        G: Graphics<Texture = <C as CharacterCache>::Texture>,
+    {
+        // Cache all glyphs first, since caching a glyph can move the textures
+        // of glyphs cached before, e.g. when a glyph atlas adds a page.
+        for glyph in glyphs {
+            self.character(cache, glyph.ch, glyph.subpixel_offset)?;
+        }
+        // The texture of each glyph, only used for comparing,
+        // the rectangle of the glyph within the texture and whether it has colors.
+        let mut quads: Vec<(*const C::Texture, Rectangle, bool)> = Vec::with_capacity(glyphs.len());
+        for glyph in glyphs {
+            let character = self.character(cache, glyph.ch, glyph.subpixel_offset)?;
+            quads.push((
+                character.texture,
+                [
+                    character.atlas_offset[0],
+                    character.atlas_offset[1],
+                    character.atlas_size[0],
+                    character.atlas_size[1],
+                ],
+                character.is_color,
+            ));
+        }
+        // No glyphs are cached while drawing, so textures stay in place.
+        let mut drawn: Vec<bool> = quads.iter().map(|q| effect && q.2).collect();
+        for i in 0..glyphs.len() {
+            if drawn[i] {
+                continue;
+            }
+            let (page, _, is_color) = quads[i];
+            let color = if is_color {
+                [1.0, 1.0, 1.0, color[3]]
+            } else {
+                color
+            };
+            let character = self.character(cache, glyphs[i].ch, glyphs[i].subpixel_offset)?;
+            let texture = character.texture;
+            g.tri_list_uv(draw_state, &color, texture, |f| {
+                let mut vertices = [[0.0; 2]; BUFFER_SIZE];
+                let mut uvs = [[0.0; 2]; BUFFER_SIZE];
+                let mut n = 0;
+                for j in i..glyphs.len() {
+                    let (texture_j, rect, is_color_j) = quads[j];
+                    if drawn[j] || is_color_j != is_color || !std::ptr::eq(texture_j, page) {
+                        continue;
+                    }
+                    drawn[j] = true;
+                    if n + 6 > BUFFER_SIZE {
+                        // Buffer is full.
+                        // Send chunk and start over.
+                        f(&vertices[..n], &uvs[..n]);
+                        n = 0;
+                    }
+                    let pos = glyphs[j].pos;
+                    let m = transform.trans(pos[0], pos[1]);
+                    vertices[n..n + 6].copy_from_slice(&triangulation::rect_tri_list_xy(
+                        m,
+                        [0.0, 0.0, rect[2], rect[3]],
+                    ));
+                    uvs[n..n + 6].copy_from_slice(&triangulation::rect_tri_list_uv(texture, rect));
+                    n += 6;
+                }
+                if n > 0 {
+                    f(&vertices[..n], &uvs[..n]);
+                }
+            });
+        }
+        Ok(())
+    }
+
+    // Gets character from cache, rendered at subpixel offset when enabled.
+    fn character<'a, C: CharacterCache>(
+        &self,
+        cache: &'a mut C,
+        ch: char,
+        subpixel_offset: Vec2d,
+    ) -> Result<Character<'a, C::Texture>, C::Error> {
+        if self.subpixel > 1 {
+            cache.character_at(self.font_size, ch, subpixel_offset)
+        } else {
+            cache.character(self.font_size, ch)
+        }
+    }
+
+    // Computes glyph positions.
+    //
+    // Calls closure with character, subpixel offset, glyph and draw position.
+    pub(crate) fn layout<C, F>(&self, text: &str, cache: &mut C, mut f: F) -> Result<(), C::Error>
+    where
+        C: CharacterCache,
+        F: FnMut(char, Vec2d, &Character<'_, C::Texture>, Vec2d),
+    {
         let mut x = 0.0;
         let mut y = 0.0;
+        let mut prev = None;
         for ch in text.chars() {
-            let character = cache.character(self.font_size, ch)?;
-            let mut ch_x = x + character.left();
-            let mut ch_y = y - character.top();
+            if let Some(prev) = prev {
+                x += cache.kerning(self.font_size, prev, ch)?;
+            }
+            prev = Some(ch);
+            let pen_y = if self.hinting == Hinting::None {
+                y
+            } else {
+                Scalar::round(y)
+            };
+            let (pen_x, subpixel_offset) = if self.subpixel > 1 {
+                // Draw at whole pixels and render the fraction into the glyph.
+                let pen_x = Scalar::floor(x);
+                let variants = Scalar::from(self.subpixel);
+                (pen_x, [((x - pen_x) * variants).floor() / variants, 0.0])
+            } else {
+                (x, [0.0; 2])
+            };
+            let character = self.character(cache, ch, subpixel_offset)?;
+            let mut ch_x = pen_x + character.left();
+            let mut ch_y = pen_y - character.top();
             if self.round {
                 ch_x = ch_x.round();
                 ch_y = ch_y.round();
             }
-            image = image.src_rect([
-                character.atlas_offset[0],
-                character.atlas_offset[1],
-                character.atlas_size[0],
-                character.atlas_size[1],
-            ]);
-            image.draw(
-                character.texture,
+            f(ch, subpixel_offset, &character, [ch_x, ch_y]);
+            if self.hinting == Hinting::Full {
+                x += character.advance_width().round();
+                y += character.advance_height().round();
+            } else {
+                x += character.advance_width();
+                y += character.advance_height();
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// Computes offsets on a circle, with about one pixel between neighbours.
+fn spread_offsets(radius: Scalar) -> Vec<Vec2d> {
+    use std::f64::consts::TAU;
+
+    let n = ((TAU * radius).ceil() as usize).clamp(8, 32);
+    (0..n)
+        .map(|i| {
+            let angle = TAU * i as Scalar / n as Scalar;
+            [radius * angle.cos(), radius * angle.sin()]
+        })
+        .collect()
+}
+
+/// Horizontal alignment of text lines.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TextAlign {
+    /// Aligns lines to the left.
+    Left,
+    /// Centers lines.
+    Center,
+    /// Aligns lines to the right.
+    Right,
+    /// Stretches the spaces of wrapped lines to fill the width.
+    ///
+    /// The last line of each paragraph is aligned to the left.
+    Justify,
+}
+
+/// Vertical alignment of text in a box.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum VerticalAlign {
+    /// Aligns text to the top.
+    Top,
+    /// Centers text.
+    Middle,
+    /// Aligns text to the bottom.
+    Bottom,
+}
+
+/// Settings for laying out text in a box,
+/// with the upper left corner at the origin.
+///
+/// Lines are broken at new lines, and wrapped at spaces when the width is limited.
+/// Words wider than the box are not broken.
+/// The height of lines is computed from the font metrics, see `CharacterCache::metrics`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TextLayout {
+    /// The width of the box, which wraps text.
+    pub max_width: Option<Scalar>,
+    /// The height of the box, used for vertical alignment.
+    pub max_height: Option<Scalar>,
+    /// The horizontal alignment.
+    pub align: TextAlign,
+    /// The vertical alignment.
+    pub vertical_align: VerticalAlign,
+    /// The distance between baselines, in multiples of the line height of the font.
+    pub line_spacing: Scalar,
+}
+
+/// A line of text placed by `TextLayout`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextLine {
+    /// The byte range of the line within the string.
+    pub range: Range<usize>,
+    /// The start of the baseline.
+    pub pos: Vec2d,
+    /// The width of the line, including justification.
+    pub width: Scalar,
+    /// Extra space added to each space character when justified.
+    pub word_spacing: Scalar,
+}
+
+impl Default for TextLayout {
+    fn default() -> Self {
+        TextLayout::new()
+    }
+}
+
+impl TextLayout {
+    /// Creates a new layout without wrapping, aligned to the top left.
+    pub fn new() -> TextLayout {
+        TextLayout {
+            max_width: None,
+            max_height: None,
+            align: TextAlign::Left,
+            vertical_align: VerticalAlign::Top,
+            line_spacing: 1.2,
+        }
+    }
+
+    /// Sets the width of the box, which wraps text.
+    pub fn max_width(mut self, value: Scalar) -> Self {
+        self.max_width = Some(value);
+        self
+    }
+
+    /// Sets the height of the box, used for vertical alignment.
+    pub fn max_height(mut self, value: Scalar) -> Self {
+        self.max_height = Some(value);
+        self
+    }
+
+    /// Sets horizontal alignment.
+    pub fn align(mut self, value: TextAlign) -> Self {
+        self.align = value;
+        self
+    }
+
+    /// Sets vertical alignment.
+    pub fn vertical_align(mut self, value: VerticalAlign) -> Self {
+        self.vertical_align = value;
+        self
+    }
+
+    /// Sets the distance between baselines, in multiples of the line height of the font.
+    pub fn line_spacing(mut self, value: Scalar) -> Self {
+        self.line_spacing = value;
+        self
+    }
+
+    /// Breaks text into lines and places them.
+    pub fn lines<C>(
+        &self,
+        text: &Text,
+        string: &str,
+        cache: &mut C,
+    ) -> Result<Vec<TextLine>, C::Error>
+    where
+        C: CharacterCache,
+    {
+        let size = text.font_size;
+        let space = cache.width(size, " ")?;
+        // Lines with byte range, width and whether they end a paragraph.
+        let mut breaks: Vec<(Range<usize>, Scalar, bool)> = vec![];
+        let mut start = 0;
+        for paragraph in string.split('\n') {
+            let mut line = start..start;
+            let mut width = 0.0;
+            let mut word_start = start;
+            for (i, word) in paragraph.split(' ').enumerate() {
+                let w = cache.width(size, word)?;
+                let wraps = self.max_width.is_some_and(|m| width + space + w > m);
+                if i == 0 {
+                    width = w;
+                } else if wraps {
+                    breaks.push((line, width, false));
+                    line = word_start..word_start;
+                    width = w;
+                } else {
+                    width += space + w;
+                }
+                line.end = word_start + word.len();
+                word_start = line.end + 1;
+            }
+            breaks.push((line, width, true));
+            start += paragraph.len() + 1;
+        }
+
+        let box_width = self
+            .max_width
+            .unwrap_or_else(|| breaks.iter().map(|b| b.1).fold(0.0, Scalar::max));
+        let metrics = cache.metrics(size)?;
+        let line_height = metrics.line_height() * self.line_spacing;
+        let height = self.height(&metrics, breaks.len());
+        let top = match (self.vertical_align, self.max_height) {
+            (VerticalAlign::Middle, Some(h)) => 0.5 * (h - height),
+            (VerticalAlign::Bottom, Some(h)) => h - height,
+            _ => 0.0,
+        };
+        Ok(breaks
+            .into_iter()
+            .enumerate()
+            .map(|(i, (range, width, last))| {
+                let spaces = string[range.clone()].matches(' ').count();
+                let justify = self.align == TextAlign::Justify && !last && spaces > 0;
+                let word_spacing = if justify {
+                    (box_width - width) / spaces as Scalar
+                } else {
+                    0.0
+                };
+                let x = match self.align {
+                    TextAlign::Left | TextAlign::Justify => 0.0,
+                    TextAlign::Center => 0.5 * (box_width - width),
+                    TextAlign::Right => box_width - width,
+                };
+                TextLine {
+                    range,
+                    pos: [x, top + metrics.ascent + i as Scalar * line_height],
+                    width: width + word_spacing * spaces as Scalar,
+                    word_spacing,
+                }
+            })
+            .collect())
+    }
+
+    /// Computes the bounding box `[x, y, w, h]` of laid out lines.
+    pub fn bounds(&self, metrics: &FontMetrics, lines: &[TextLine]) -> Rectangle {
+        let (first, last) = match (lines.first(), lines.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return [0.0; 4],
+        };
+        let x0 = lines
+            .iter()
+            .map(|l| l.pos[0])
+            .fold(Scalar::INFINITY, Scalar::min);
+        let x1 = lines
+            .iter()
+            .map(|l| l.pos[0] + l.width)
+            .fold(Scalar::NEG_INFINITY, Scalar::max);
+        let y0 = first.pos[1] - metrics.ascent;
+        [x0, y0, x1 - x0, last.pos[1] + metrics.descent - y0]
+    }
+
+    /// Computes the caret rectangle `[x, y, w, h]` at a byte offset of laid out lines.
+    ///
+    /// The caret is one unit wide and spans the ascent and descent of the font.
+    /// Offsets at a line break place the caret at the end of the line.
+    /// Offsets within a character are rounded down to its start.
+    pub fn caret<C>(
+        &self,
+        text: &Text,
+        string: &str,
+        lines: &[TextLine],
+        offset: usize,
+        cache: &mut C,
+    ) -> Result<Rectangle, C::Error>
+    where
+        C: CharacterCache,
+    {
+        let line = match lines.iter().rev().find(|l| l.range.start <= offset) {
+            Some(line) => line,
+            None => match lines.first() {
+                Some(line) => line,
+                None => return Ok([0.0; 4]),
+            },
+        };
+        let metrics = cache.metrics(text.font_size)?;
+        let x = line_x(text, string, line, offset, cache)?;
+        let y = line.pos[1] - metrics.ascent;
+        Ok([x, y, 1.0, metrics.ascent + metrics.descent])
+    }
+
+    /// Computes the selection rectangles `[x, y, w, h]` of a byte range of laid out lines.
+    ///
+    /// Returns one rectangle for each line with selected characters.
+    /// Offsets within a character are rounded down to its start.
+    pub fn selection<C>(
+        &self,
+        text: &Text,
+        string: &str,
+        lines: &[TextLine],
+        range: Range<usize>,
+        cache: &mut C,
+    ) -> Result<Vec<Rectangle>, C::Error>
+    where
+        C: CharacterCache,
+    {
+        let metrics = cache.metrics(text.font_size)?;
+        let mut rects = vec![];
+        for line in lines {
+            let start = range.start.max(line.range.start);
+            let end = range.end.min(line.range.end);
+            if start >= end {
+                continue;
+            }
+            let x0 = line_x(text, string, line, start, cache)?;
+            let x1 = line_x(text, string, line, end, cache)?;
+            let y = line.pos[1] - metrics.ascent;
+            rects.push([x0, y, x1 - x0, metrics.ascent + metrics.descent]);
+        }
+        Ok(rects)
+    }
+
+    // Computes the height of a number of lines.
+    fn height(&self, metrics: &FontMetrics, lines: usize) -> Scalar {
+        if lines == 0 {
+            return 0.0;
+        }
+        let line_height = metrics.line_height() * self.line_spacing;
+        metrics.ascent + metrics.descent + (lines - 1) as Scalar * line_height
+    }
+}
+
+// Computes the horizontal position of a byte offset within a line.
+//
+// The offset is clamped to the line and rounded down to a character boundary.
+fn line_x<C: CharacterCache>(
+    text: &Text,
+    string: &str,
+    line: &TextLine,
+    offset: usize,
+    cache: &mut C,
+) -> Result<Scalar, C::Error> {
+    let mut offset = offset
+        .clamp(line.range.start, line.range.end)
+        .min(string.len());
+    while !string.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    let offset = offset.max(line.range.start);
+    let prefix = &string[line.range.start..offset];
+    let spaces = prefix.matches(' ').count() as Scalar;
+    Ok(line.pos[0] + cache.width(text.font_size, prefix)? + spaces * line.word_spacing)
+}
+
+// A glyph placed by text layout.
+#[derive(Copy, Clone, Debug)]
+struct Glyph {
+    ch: char,
+    subpixel_offset: Vec2d,
+    pos: Vec2d,
+}
+
+/// Text that is laid out once and drawn many times.
+///
+/// Stores glyph positions, so static labels do not need layout every frame.
+/// The layout is computed on first draw and reused until invalidated,
+/// e.g. after changing the string or clearing the character cache.
+#[derive(Clone, Debug)]
+pub struct TextBlob {
+    /// The text settings used for layout.
+    ///
+    /// The color is used as default when drawing.
+    pub text: Text,
+    string: String,
+    glyphs: Vec<Glyph>,
+    width: Scalar,
+    dirty: bool,
+}
+
+impl TextBlob {
+    /// Creates a new text blob.
+    pub fn new<S: Into<String>>(text: Text, string: S) -> TextBlob {
+        TextBlob {
+            text,
+            string: string.into(),
+            glyphs: vec![],
+            width: 0.0,
+            dirty: true,
+        }
+    }
+
+    /// Gets the string.
+    pub fn get_string(&self) -> &str {
+        &self.string
+    }
+
+    /// Sets the string, invalidating the layout.
+    pub fn set_string<S: Into<String>>(&mut self, string: S) {
+        self.string = string.into();
+        self.invalidate();
+    }
+
+    /// Forces layout on next draw.
+    pub fn invalidate(&mut self) {
+        self.dirty = true;
+    }
+
+    /// Returns `true` if layout is needed before drawing.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Computes glyph positions, if the layout is invalid.
+    pub fn layout<C: CharacterCache>(&mut self, cache: &mut C) -> Result<(), C::Error> {
+        if !self.dirty {
+            return Ok(());
+        }
+        let glyphs = &mut self.glyphs;
+        let mut width: Scalar = 0.0;
+        glyphs.clear();
+        self.text.layout(
+            &self.string,
+            cache,
+            |ch, subpixel_offset, character, pos| {
+                glyphs.push(Glyph {
+                    ch,
+                    subpixel_offset,
+                    pos,
+                });
+                width = width.max(pos[0] - character.left() + character.advance_width());
+            },
+        )?;
+        self.width = width;
+        self.dirty = false;
+        Ok(())
+    }
+
+    /// Returns the width of the laid out text.
+    ///
+    /// This is zero until the layout is computed.
+    pub fn width(&self) -> Scalar {
+        self.width
+    }
+
+    /// Draws text blob using the color of the text settings.
+    pub fn draw<C, G>(
+        &mut self,
+        cache: &mut C,
+        draw_state: &DrawState,
+        transform: Affine2,
+        g: &mut G,
+    ) -> Result<(), C::Error>
+    where
+        C: CharacterCache,
+        G: Graphics<Texture = <C as CharacterCache>::Texture>,
+    {
+        let color = self.text.color;
+        self.draw_color(color, cache, draw_state, transform, g)
+    }
+
+    /// Draws text blob with a color.
+    pub fn draw_color<C, G>(
+        &mut self,
+        color: Color,
+        cache: &mut C,
+        draw_state: &DrawState,
+        transform: Affine2,
+        g: &mut G,
+    ) -> Result<(), C::Error>
+    where
+        C: CharacterCache,
+        G: Graphics<Texture = <C as CharacterCache>::Texture>,
+    {
+        self.layout(cache)?;
+        self.text
+            .draw_glyphs(&self.glyphs, color, cache, draw_state, transform, g)
+    }
+}
+
+/// A run of text with its own style, see `RichText`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Span {
+    /// The text of the span, which may contain line breaks.
+    pub string: String,
+    /// The color.
+    pub color: Color,
+    /// The font size.
+    pub font_size: FontSize,
+    /// The index of the character cache used to draw the span.
+    pub font: usize,
+    /// Whether to draw a line below the text.
+    pub underline: bool,
+    /// Whether to draw a line through the text.
+    pub strikethrough: bool,
+}
+
+impl Span {
+    /// Creates a new span using the first font.
+    pub fn new<S: Into<String>>(string: S, color: Color, font_size: FontSize) -> Span {
+        Span {
+            string: string.into(),
+            color,
+            font_size,
+            font: 0,
+            underline: false,
+            strikethrough: false,
+        }
+    }
+
+    /// Sets the index of the character cache.
+    ///
+    /// Measuring or drawing panics when the index is outside the caches,
+    /// since the error type of the caches can not describe it.
+    pub fn font(mut self, value: usize) -> Self {
+        self.font = value;
+        self
+    }
+
+    /// Draws a line below the text.
+    pub fn underline(mut self) -> Self {
+        self.underline = true;
+        self
+    }
+
+    /// Draws a line through the text.
+    pub fn strikethrough(mut self) -> Self {
+        self.strikethrough = true;
+        self
+    }
+}
+
+/// Text made of styled spans, laid out and drawn together.
+///
+/// Spans follow each other on a common baseline,
+/// which is placed below the highest ascent of each line.
+/// Line breaks within spans start new lines.
+///
+/// Fonts are selected by index into a slice of character caches
+/// when measuring or drawing.
+///
+/// ```ignore
+/// let text = RichText::new()
+///     .span(Span::new("player: ", [0.5, 0.5, 1.0, 1.0], 14).font(1))
+///     .span(Span::new("hello", [1.0; 4], 14).underline());
+/// text.draw(&mut [regular, bold], &c.draw_state, c.transform, g)?;
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct RichText {
+    /// The spans.
+    pub spans: Vec<Span>,
+    /// The line spacing, multiplied with the line height of each line.
+    pub line_spacing: Scalar,
+}
+
+impl Default for RichText {
+    fn default() -> Self {
+        RichText::new()
+    }
+}
+
+// A part of a span on a single line.
+struct Run {
+    span: usize,
+    range: Range<usize>,
+    pos: Vec2d,
+    width: Scalar,
+    metrics: FontMetrics,
+}
+
+impl RichText {
+    /// Creates a new empty rich text.
+    pub fn new() -> RichText {
+        RichText {
+            spans: vec![],
+            line_spacing: 1.0,
+        }
+    }
+
+    /// Adds a span.
+    pub fn span(mut self, span: Span) -> Self {
+        self.spans.push(span);
+        self
+    }
+
+    /// Sets line spacing.
+    pub fn line_spacing(mut self, value: Scalar) -> Self {
+        self.line_spacing = value;
+        self
+    }
+
+    /// Adds a span to the end.
+    pub fn push(&mut self, span: Span) {
+        self.spans.push(span);
+    }
+
+    /// Computes the bounding box `[x, y, w, h]` of the text.
+    ///
+    /// Panics if a span uses a font index outside `caches`.
+    pub fn bounds<C: CharacterCache>(&self, caches: &mut [C]) -> Result<Rectangle, C::Error> {
+        Ok(self.layout(caches)?.1)
+    }
+
+    /// Draws the spans with character caches selected by font index.
+    ///
+    /// Returns the bounding box of the text.
+    /// Panics if a span uses a font index outside `caches`.
+    pub fn draw<C, G>(
+        &self,
+        caches: &mut [C],
+        draw_state: &DrawState,
+        transform: Affine2,
+        g: &mut G,
+    ) -> Result<Rectangle, C::Error>
+    where
+        C: CharacterCache,
+        G: Graphics<Texture = <C as CharacterCache>::Texture>,
+    {
+        let (runs, bounds) = self.layout(caches)?;
+        for run in &runs {
+            let span = &self.spans[run.span];
+            let string = &span.string[run.range.clone()];
+            let text = Text::new_color(span.color, span.font_size);
+            text.draw_pos(
+                string,
+                run.pos,
+                &mut caches[span.font],
                 draw_state,
-                transform.trans(ch_x, ch_y),
+                transform,
                 g,
+            )?;
+            // Decorations are placed relative to the font of the span.
+            let thickness = (run.metrics.ascent / 14.0).max(1.0);
+            let [x, y] = run.pos;
+            if span.underline {
+                let y = y + 0.5 * run.metrics.descent;
+                let rect = [x, y - 0.5 * thickness, run.width, thickness];
+                super::rectangle(span.color, rect, transform, g);
+            }
+            if span.strikethrough {
+                let y = y - 0.3 * run.metrics.ascent;
+                let rect = [x, y - 0.5 * thickness, run.width, thickness];
+                super::rectangle(span.color, rect, transform, g);
+            }
+        }
+        Ok(bounds)
+    }
+
+    // Places the parts of spans on lines.
+    //
+    // Returns the runs with baseline positions and the bounding box.
+    fn layout<C: CharacterCache>(
+        &self,
+        caches: &mut [C],
+    ) -> Result<(Vec<Run>, Rectangle), C::Error> {
+        if let Some(span) = self.spans.iter().find(|s| s.font >= caches.len()) {
+            panic!(
+                "Span font index {} is outside {} character caches",
+                span.font,
+                caches.len()
             );
-            x += character.advance_width();
-            y += character.advance_height();
+        }
+        let mut runs: Vec<Run> = vec![];
+        let mut line_start = 0;
+        let (mut top, mut width, mut height) = (0.0, 0.0, 0.0);
+        let mut x = 0.0;
+        for (i, span) in self.spans.iter().enumerate() {
+            let cache = &mut caches[span.font];
+            let metrics = cache.metrics(span.font_size)?;
+            let mut start = 0;
+            for (j, part) in span.string.split('\n').enumerate() {
+                if j > 0 {
+                    let (line_height, bottom) = place_line(&mut runs[line_start..], top);
+                    width = Scalar::max(width, x);
+                    height = bottom;
+                    top += line_height * self.line_spacing;
+                    line_start = runs.len();
+                    x = 0.0;
+                }
+                let w = cache.width(span.font_size, part)?;
+                runs.push(Run {
+                    span: i,
+                    range: start..start + part.len(),
+                    pos: [x, 0.0],
+                    width: w,
+                    metrics,
+                });
+                x += w;
+                start += part.len() + 1;
+            }
+        }
+        if line_start < runs.len() {
+            let (_, bottom) = place_line(&mut runs[line_start..], top);
+            width = Scalar::max(width, x);
+            height = bottom;
+        }
+        Ok((runs, [0.0, 0.0, width, height]))
+    }
+}
+
+// Moves runs of a line to a common baseline below the top.
+//
+// Returns the line height and the bottom of the line.
+fn place_line(runs: &mut [Run], top: Scalar) -> (Scalar, Scalar) {
+    let max =
+        |f: fn(&FontMetrics) -> Scalar| runs.iter().map(|r| f(&r.metrics)).fold(0.0, Scalar::max);
+    let ascent = max(|m| m.ascent);
+    let descent = max(|m| m.descent);
+    let line_height = max(FontMetrics::line_height);
+    for run in runs.iter_mut() {
+        run.pos[1] = top + ascent;
+    }
+    (line_height, top + ascent + descent)
+}
+
+#[cfg(test)]
+mod test {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::{
+        graphics::{display_list::DisplayList, Context},
+        software_graphics::Texture,
+    };
+
+    // A monospace cache with glyphs half as wide as the font size,
+    // all in the same texture.
+    struct MonoCache(Rc<Texture>);
+
+    impl CharacterCache for MonoCache {
+        type Texture = Rc<Texture>;
+        type Error = ();
+
+        fn character(
+            &mut self,
+            font_size: FontSize,
+            ch: char,
+        ) -> Result<Character<'_, Rc<Texture>>, ()> {
+            Ok(Character {
+                offset: [0.0, Scalar::from(font_size)],
+                advance_size: [0.5 * Scalar::from(font_size), 0.0],
+                atlas_offset: [0.0; 2],
+                atlas_size: [1.0; 2],
+                texture: &self.0,
+                is_invalid: false,
+                is_color: ch == '*',
+            })
         }
 
-        Ok(())
+        fn kerning(&mut self, _font_size: FontSize, left: char, right: char) -> Result<Scalar, ()> {
+            Ok(if (left, right) == ('A', 'V') {
+                -2.0
+            } else {
+                0.0
+            })
+        }
+
+        fn metrics(&mut self, font_size: FontSize) -> Result<FontMetrics, ()> {
+            let size = Scalar::from(font_size);
+            Ok(FontMetrics {
+                ascent: 0.8 * size,
+                descent: 0.2 * size,
+                line_gap: 0.2 * size,
+            })
+        }
+    }
+
+    #[test]
+    fn test_text_layout() {
+        let mut cache = MonoCache(Rc::new(Texture::new(image::RgbaImage::new(1, 1))));
+        let text = Text::new(10);
+        let string = "aaa bb cc\ndd";
+        let layout = TextLayout::new().max_width(40.0).line_spacing(2.0);
+        let lines = layout.lines(&text, string, &mut cache).unwrap();
+        let ranges: Vec<&str> = lines.iter().map(|l| &string[l.range.clone()]).collect();
+        assert_eq!(ranges, ["aaa bb", "cc", "dd"]);
+        assert_eq!(lines[1].pos, [0.0, 32.0]);
+        let metrics = cache.metrics(10).unwrap();
+        assert_eq!(layout.bounds(&metrics, &lines), [0.0, 0.0, 30.0, 58.0]);
+
+        let layout = layout
+            .align(TextAlign::Right)
+            .vertical_align(VerticalAlign::Bottom);
+        let lines = layout
+            .max_height(60.0)
+            .lines(&text, string, &mut cache)
+            .unwrap();
+        assert_eq!(lines[0].pos, [10.0, 10.0]);
+
+        let lines = layout
+            .align(TextAlign::Justify)
+            .lines(&text, string, &mut cache)
+            .unwrap();
+        assert_eq!((lines[0].word_spacing, lines[0].width), (10.0, 40.0));
+        assert_eq!(lines[1].word_spacing, 0.0);
+    }
+
+    #[test]
+    fn test_draw_batched() {
+        let mut cache = MonoCache(Rc::new(Texture::new(image::RgbaImage::new(1, 1))));
+        let mut list = DisplayList::new();
+        let c = Context::new();
+        Text::new(10)
+            .draw("Hello", &mut cache, &c.draw_state, c.transform, &mut list)
+            .unwrap();
+        assert_eq!(list.len(), 1);
+    }
+
+    #[test]
+    fn test_text_blob() {
+        use crate::{graphics::Transformed, software_graphics::SoftwareGraphics};
+
+        let white = image::RgbaImage::from_pixel(1, 1, image::Rgba([255; 4]));
+        let mut cache = MonoCache(Rc::new(Texture::new(white)));
+        let mut blob = TextBlob::new(Text::new_color([1.0, 0.0, 0.0, 1.0], 10), "aa");
+        assert!(blob.is_dirty());
+        let mut list = DisplayList::new();
+        let c = Context::new();
+        let transform = c.transform.trans(0.0, 10.0);
+        blob.draw(&mut cache, &c.draw_state, transform, &mut list)
+            .unwrap();
+        assert!(!blob.is_dirty());
+        let (color, transform) = ([0.0, 0.0, 1.0, 1.0], transform.trans(1.0, 1.0));
+        blob.draw_color(color, &mut cache, &c.draw_state, transform, &mut list)
+            .unwrap();
+        let mut g = SoftwareGraphics::new(8, 2);
+        g.draw(|c, g| list.draw(c.transform, g));
+        assert_eq!(blob.width(), 10.0);
+        assert_eq!(g.get_pixel(0, 0), [255, 0, 0, 255]);
+        assert_eq!(g.get_pixel(5, 0), [255, 0, 0, 255]);
+        assert_eq!(g.get_pixel(1, 1), [0, 0, 255, 255]);
+        assert_eq!(g.get_pixel(6, 1), [0, 0, 255, 255]);
+        assert_eq!(g.get_pixel(0, 1), [0; 4]);
+    }
+
+    #[test]
+    fn test_kerning() {
+        let mut cache = MonoCache(Rc::new(Texture::new(image::RgbaImage::new(1, 1))));
+        assert_eq!(cache.width(10, "AVA").unwrap(), 13.0);
+        let mut blob = TextBlob::new(Text::new(10), "AVA");
+        blob.layout(&mut cache).unwrap();
+        assert_eq!(blob.width(), 13.0);
+    }
+
+    #[test]
+    fn test_subpixel_fallback() {
+        // The cache does not render glyph variants, so glyphs keep fractional positions.
+        let mut cache = MonoCache(Rc::new(Texture::new(image::RgbaImage::new(1, 1))));
+        let mut xs = vec![];
+        Text::new(5)
+            .subpixel(4)
+            .layout("aaa", &mut cache, |_, _, _, pos| xs.push(pos[0]))
+            .unwrap();
+        assert_eq!(xs, [0.0, 2.5, 5.0]);
+    }
+
+    #[test]
+    fn test_rich_text() {
+        let texture = Rc::new(Texture::new(image::RgbaImage::new(1, 1)));
+        let mut caches = [MonoCache(texture.clone()), MonoCache(texture)];
+        let text = RichText::new()
+            .span(Span::new("ab", [1.0; 4], 10))
+            .span(Span::new("c\nd", [1.0; 4], 20).font(1).underline());
+        assert_eq!(text.bounds(&mut caches).unwrap(), [0.0, 0.0, 20.0, 44.0]);
+        let (runs, _) = text.layout(&mut caches).unwrap();
+        assert_eq!(runs[1].pos, [10.0, 16.0]);
+        assert_eq!(runs[2].pos, [0.0, 40.0]);
+
+        let mut list = DisplayList::new();
+        let c = Context::new();
+        text.draw(&mut caches, &c.draw_state, c.transform, &mut list)
+            .unwrap();
+        // Three runs of text and two underlines,
+        // where the first two runs are merged since they share the texture.
+        assert_eq!(list.len(), 4);
+    }
+
+    #[test]
+    fn test_outline_shadow() {
+        use crate::{graphics::Transformed, software_graphics::SoftwareGraphics};
+
+        // Glyphs are single white pixels.
+        let white = image::RgbaImage::from_pixel(1, 1, image::Rgba([255; 4]));
+        let mut cache = MonoCache(Rc::new(Texture::new(white)));
+        let mut list = DisplayList::new();
+        let c = Context::new();
+        Text::new_color([1.0; 4], 10)
+            .outline(1.0, [1.0, 0.0, 0.0, 1.0])
+            .shadow([3.0, 3.0], 0.0, [0.0, 0.0, 1.0, 1.0])
+            .draw("a", &mut cache, &c.draw_state, c.transform, &mut list)
+            .unwrap();
+        let mut g = SoftwareGraphics::new(16, 16);
+        g.draw(|c, g| list.draw(c.transform.trans(5.0, 15.0), g));
+        assert_eq!(g.get_pixel(5, 5), [255; 4]);
+        assert_eq!(g.get_pixel(6, 5), [255, 0, 0, 255]);
+        assert_eq!(g.get_pixel(8, 8), [0, 0, 255, 255]);
+        assert_eq!(g.get_pixel(12, 12), [0; 4]);
+    }
+
+    #[test]
+    fn test_color_glyphs() {
+        use crate::{graphics::Transformed, software_graphics::SoftwareGraphics};
+
+        let white = image::RgbaImage::from_pixel(1, 1, image::Rgba([255; 4]));
+        let mut cache = MonoCache(Rc::new(Texture::new(white)));
+        let mut list = DisplayList::new();
+        let c = Context::new();
+        // The mock cache treats '*' as color glyph.
+        Text::new_color([1.0, 0.0, 0.0, 1.0], 10)
+            .draw("a*a", &mut cache, &c.draw_state, c.transform, &mut list)
+            .unwrap();
+        assert_eq!(list.len(), 2);
+        let mut g = SoftwareGraphics::new(16, 4);
+        g.draw(|c, g| list.draw(c.transform.trans(0.0, 10.0), g));
+        assert_eq!(g.get_pixel(0, 0), [255, 0, 0, 255]);
+        assert_eq!(g.get_pixel(5, 0), [255; 4]);
+        assert_eq!(g.get_pixel(10, 0), [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_caret_selection() {
+        let mut cache = MonoCache(Rc::new(Texture::new(image::RgbaImage::new(1, 1))));
+        let text = Text::new(10);
+        let string = "aaa bb cc";
+        let layout = TextLayout::new().max_width(40.0).line_spacing(1.0);
+        let lines = layout.lines(&text, string, &mut cache).unwrap();
+        assert_eq!(
+            layout.caret(&text, string, &lines, 2, &mut cache).unwrap(),
+            [10.0, 0.0, 1.0, 10.0]
+        );
+        // The caret at the line break stays on the first line.
+        assert_eq!(
+            layout.caret(&text, string, &lines, 6, &mut cache).unwrap()[..2],
+            [30.0, 0.0]
+        );
+        assert_eq!(
+            layout.caret(&text, string, &lines, 8, &mut cache).unwrap()[..2],
+            [5.0, 12.0]
+        );
+        assert_eq!(
+            layout
+                .selection(&text, string, &lines, 4..8, &mut cache)
+                .unwrap(),
+            [[20.0, 0.0, 10.0, 10.0], [0.0, 12.0, 5.0, 10.0]]
+        );
+
+        // Offsets within a character are rounded down.
+        let string = "aé b";
+        let lines = layout.lines(&text, string, &mut cache).unwrap();
+        assert_eq!(
+            layout.caret(&text, string, &lines, 2, &mut cache).unwrap()[..2],
+            [5.0, 0.0]
+        );
     }
 }
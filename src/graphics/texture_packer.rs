@@ -1,9 +1,467 @@
 //! Texture packing.
+//!
+//! `TexturePacker` adds tiles one by one to texture atlases,
+//! creating new atlases by need.
+//! By default it uses a fast skyline heuristic that is good at packing tiles of similar sizes.
+//! For tiles of varying sizes, such as glyphs and sprites,
+//! pick a `PackingHeuristic` for better atlas utilization:
+//!
+//! ```ignore
+//! let mut packer = TexturePacker::with_heuristic(PackingHeuristic::MaxRectsBestShortSideFit);
+//! let (texture, offset) = match packer.insert(size) {
+//!     Some(res) => res,
+//!     None => (packer.create(size, new_atlas()?), [0, 0]),
+//! };
+//! ```
+//!
+//! `RectPacker` packs rectangles into a single bin without storing textures.
+//!
+//! For streaming sprites in long-running applications, `TextureAtlas` keeps
+//! stable handles to regions in several pages, and evicts regions not used recently
+//! when running out of space.
+
+use std::collections::HashMap;
 
 use super::ImageSize;
 
+/// A strategy for placing rectangles in a bin.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum PackingHeuristic {
+    /// Keeps a skyline of segments and places each rectangle
+    /// where its top edge is lowest.
+    ///
+    /// Fast, but space below the skyline is lost.
+    SkylineBottomLeft,
+    /// Keeps maximal free rectangles and picks the one
+    /// where the shorter leftover side is smallest.
+    ///
+    /// Usually gives the best utilization.
+    MaxRectsBestShortSideFit,
+    /// Keeps maximal free rectangles and picks the one with smallest leftover area.
+    MaxRectsBestAreaFit,
+    /// Keeps maximal free rectangles and picks the one
+    /// where the top edge of the rectangle is lowest.
+    MaxRectsBottomLeft,
+}
+
+/// Packs rectangles into a bin of fixed size.
+///
+/// Rectangles are placed one by one, without knowledge about future rectangles.
+/// Sorting rectangles by decreasing height or area before inserting
+/// improves utilization when all rectangles are known in advance.
+#[derive(Clone, Debug)]
+pub struct RectPacker {
+    size: [u32; 2],
+    heuristic: PackingHeuristic,
+    // Segments `[x, y, w]` from left to right, for skyline heuristics.
+    skyline: Vec<[u32; 3]>,
+    // Maximal free rectangles `[x, y, w, h]`, for max rects heuristics.
+    free: Vec<[u32; 4]>,
+    used: u64,
+}
+
+impl RectPacker {
+    /// Creates a new empty bin.
+    pub fn new(size: [u32; 2], heuristic: PackingHeuristic) -> RectPacker {
+        RectPacker {
+            size,
+            heuristic,
+            skyline: vec![[0, 0, size[0]]],
+            free: vec![[0, 0, size[0], size[1]]],
+            used: 0,
+        }
+    }
+
+    /// Returns the size of the bin.
+    pub fn size(&self) -> [u32; 2] {
+        self.size
+    }
+
+    /// Returns the heuristic.
+    pub fn heuristic(&self) -> PackingHeuristic {
+        self.heuristic
+    }
+
+    /// Returns the fraction of the bin area covered by rectangles.
+    pub fn occupancy(&self) -> f64 {
+        let area = u64::from(self.size[0]) * u64::from(self.size[1]);
+        if area == 0 {
+            0.0
+        } else {
+            self.used as f64 / area as f64
+        }
+    }
+
+    /// Places a rectangle of some size.
+    ///
+    /// Returns the offset of the rectangle,
+    /// or `None` if there is no room for it.
+    pub fn insert(&mut self, size: [u32; 2]) -> Option<[u32; 2]> {
+        if size[0] > self.size[0] || size[1] > self.size[1] {
+            return None;
+        }
+        if size[0] == 0 || size[1] == 0 {
+            return Some([0, 0]);
+        }
+        let offset = match self.heuristic {
+            PackingHeuristic::SkylineBottomLeft => self.insert_skyline(size)?,
+            _ => self.insert_max_rects(size)?,
+        };
+        self.used += u64::from(size[0]) * u64::from(size[1]);
+        Some(offset)
+    }
+
+    fn insert_skyline(&mut self, [w, h]: [u32; 2]) -> Option<[u32; 2]> {
+        // Finds the lowest y-value for a rectangle starting at a segment.
+        let fit = |i: usize| {
+            let x = self.skyline[i][0];
+            if x + w > self.size[0] {
+                return None;
+            }
+            let mut y = 0;
+            for s in &self.skyline[i..] {
+                if s[0] >= x + w {
+                    break;
+                }
+                y = y.max(s[1]);
+            }
+            if y + h > self.size[1] {
+                None
+            } else {
+                Some(y)
+            }
+        };
+        let mut best: Option<(usize, u32, u32)> = None;
+        for i in 0..self.skyline.len() {
+            if let Some(y) = fit(i) {
+                let x = self.skyline[i][0];
+                if best.is_none_or(|(_, bx, by)| (y, x) < (by, bx)) {
+                    best = Some((i, x, y));
+                }
+            }
+        }
+        let (i, x, y) = best?;
+
+        // Raise the skyline where the rectangle is placed.
+        self.skyline.insert(i, [x, y + h, w]);
+        let end = x + w;
+        while i + 1 < self.skyline.len() && self.skyline[i + 1][0] < end {
+            let s = &mut self.skyline[i + 1];
+            let s_end = s[0] + s[2];
+            if s_end <= end {
+                self.skyline.remove(i + 1);
+            } else {
+                *s = [end, s[1], s_end - end];
+                break;
+            }
+        }
+        // Merge neighbour segments at the same height.
+        self.skyline.dedup_by(|b, a| {
+            if a[1] == b[1] {
+                a[2] += b[2];
+                true
+            } else {
+                false
+            }
+        });
+        Some([x, y])
+    }
+
+    fn insert_max_rects(&mut self, [w, h]: [u32; 2]) -> Option<[u32; 2]> {
+        let mut best: Option<([u32; 2], (u64, u64))> = None;
+        for f in &self.free {
+            if f[2] < w || f[3] < h {
+                continue;
+            }
+            let (dw, dh) = (u64::from(f[2] - w), u64::from(f[3] - h));
+            let score = match self.heuristic {
+                PackingHeuristic::MaxRectsBestAreaFit => (
+                    u64::from(f[2]) * u64::from(f[3]) - u64::from(w) * u64::from(h),
+                    dw.min(dh),
+                ),
+                PackingHeuristic::MaxRectsBottomLeft => (u64::from(f[1] + h), u64::from(f[0])),
+                _ => (dw.min(dh), dw.max(dh)),
+            };
+            if best.is_none_or(|(_, b)| score < b) {
+                best = Some(([f[0], f[1]], score));
+            }
+        }
+        let ([x, y], _) = best?;
+
+        // Split every free rectangle overlapping the placed one.
+        let used = [x, y, x + w, y + h];
+        let mut split = vec![];
+        self.free.retain(|f| {
+            let [fx0, fy0, fx1, fy1] = [f[0], f[1], f[0] + f[2], f[1] + f[3]];
+            if used[0] >= fx1 || used[2] <= fx0 || used[1] >= fy1 || used[3] <= fy0 {
+                return true;
+            }
+            if used[0] > fx0 {
+                split.push([fx0, fy0, used[0] - fx0, f[3]]);
+            }
+            if used[2] < fx1 {
+                split.push([used[2], fy0, fx1 - used[2], f[3]]);
+            }
+            if used[1] > fy0 {
+                split.push([fx0, fy0, f[2], used[1] - fy0]);
+            }
+            if used[3] < fy1 {
+                split.push([fx0, used[3], f[2], fy1 - used[3]]);
+            }
+            false
+        });
+        self.free.extend(split);
+
+        self.prune();
+        Some([x, y])
+    }
+
+    /// Frees the space of a rectangle placed earlier.
+    ///
+    /// With skyline heuristics, the space is only reused after `clear`.
+    pub fn free(&mut self, offset: [u32; 2], size: [u32; 2]) {
+        if size[0] == 0 || size[1] == 0 {
+            return;
+        }
+        self.used = self
+            .used
+            .saturating_sub(u64::from(size[0]) * u64::from(size[1]));
+        if self.heuristic == PackingHeuristic::SkylineBottomLeft {
+            return;
+        }
+        let mut rect = [offset[0], offset[1], size[0], size[1]];
+        // Join with free neighbours sharing a whole edge, to reduce fragmentation.
+        while let Some(i) = self.free.iter().position(|f| {
+            (f[0] == rect[0]
+                && f[2] == rect[2]
+                && (f[1] + f[3] == rect[1] || rect[1] + rect[3] == f[1]))
+                || (f[1] == rect[1]
+                    && f[3] == rect[3]
+                    && (f[0] + f[2] == rect[0] || rect[0] + rect[2] == f[0]))
+        }) {
+            let f = self.free.swap_remove(i);
+            let (x, y) = (f[0].min(rect[0]), f[1].min(rect[1]));
+            rect = [
+                x,
+                y,
+                (f[0] + f[2]).max(rect[0] + rect[2]) - x,
+                (f[1] + f[3]).max(rect[1] + rect[3]) - y,
+            ];
+        }
+        self.free.push(rect);
+        self.prune();
+    }
+
+    /// Removes all rectangles.
+    pub fn clear(&mut self) {
+        *self = RectPacker::new(self.size, self.heuristic);
+    }
+
+    // Removes free rectangles contained in others.
+    fn prune(&mut self) {
+        let contains = |a: &[u32; 4], b: &[u32; 4]| {
+            a[0] <= b[0] && a[1] <= b[1] && a[0] + a[2] >= b[0] + b[2] && a[1] + a[3] >= b[1] + b[3]
+        };
+        let mut i = 0;
+        while i < self.free.len() {
+            let f = self.free[i];
+            let redundant = self
+                .free
+                .iter()
+                .enumerate()
+                .any(|(j, g)| j != i && contains(g, &f) && (g != &f || j < i));
+            if redundant {
+                self.free.swap_remove(i);
+            } else {
+                i += 1;
+            }
+        }
+    }
+}
+
+/// Identifies a region in a `TextureAtlas`.
+///
+/// Handles stay valid until the region is removed or evicted.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct AtlasHandle(u64);
+
+/// A region in a page of a texture atlas.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct AtlasRegion {
+    /// The index of the page.
+    pub page: usize,
+    /// The offset in the page.
+    pub offset: [u32; 2],
+    /// The size.
+    pub size: [u32; 2],
+}
+
+#[derive(Debug)]
+struct Page<T> {
+    texture: T,
+    packer: RectPacker,
+    regions: usize,
+}
+
+/// A texture atlas with several pages, evicting the least recently used regions.
+///
+/// New pages are created by need, up to a maximum number of pages.
+/// When all pages are full, the regions used longest ago are evicted
+/// to make room, which invalidates their handles.
+/// Call `get` when using a region to keep it from being evicted.
+#[derive(Debug)]
+pub struct TextureAtlas<T> {
+    pages: Vec<Page<T>>,
+    regions: HashMap<AtlasHandle, (AtlasRegion, u64)>,
+    page_size: [u32; 2],
+    heuristic: PackingHeuristic,
+    max_pages: usize,
+    time: u64,
+    next_handle: u64,
+}
+
+impl<T> TextureAtlas<T> {
+    /// Creates a new atlas without pages.
+    pub fn new(page_size: [u32; 2]) -> TextureAtlas<T> {
+        TextureAtlas {
+            pages: vec![],
+            regions: HashMap::new(),
+            page_size,
+            heuristic: PackingHeuristic::MaxRectsBestShortSideFit,
+            max_pages: usize::MAX,
+            time: 0,
+            next_handle: 0,
+        }
+    }
+
+    /// Sets the heuristic for packing new pages.
+    pub fn heuristic(mut self, value: PackingHeuristic) -> Self {
+        self.heuristic = value;
+        self
+    }
+
+    /// Sets the maximum number of pages before regions are evicted.
+    pub fn max_pages(mut self, value: usize) -> Self {
+        self.max_pages = value.max(1);
+        self
+    }
+
+    /// Adds a region of some size.
+    ///
+    /// Calls `new_page` with the page size when a new page is needed.
+    /// Regions larger than the page size get their own page, ignoring the maximum.
+    /// Returns the handle and the region, where the caller should upload the pixels.
+    pub fn insert<F, E>(
+        &mut self,
+        size: [u32; 2],
+        mut new_page: F,
+    ) -> Result<(AtlasHandle, AtlasRegion), E>
+    where
+        F: FnMut([u32; 2]) -> Result<T, E>,
+    {
+        let region = loop {
+            if let Some(region) = self.allocate(size) {
+                break region;
+            }
+            let oversized = size[0] > self.page_size[0] || size[1] > self.page_size[1];
+            if self.pages.len() < self.max_pages || self.regions.is_empty() || oversized {
+                let page_size = [
+                    size[0].max(self.page_size[0]),
+                    size[1].max(self.page_size[1]),
+                ];
+                self.pages.push(Page {
+                    texture: new_page(page_size)?,
+                    packer: RectPacker::new(page_size, self.heuristic),
+                    regions: 0,
+                });
+            } else {
+                let lru = self
+                    .regions
+                    .iter()
+                    .min_by_key(|(_, &(_, time))| time)
+                    .map(|(&handle, _)| handle);
+                if let Some(handle) = lru {
+                    self.remove(handle);
+                }
+            }
+        };
+        let handle = AtlasHandle(self.next_handle);
+        self.next_handle += 1;
+        self.time += 1;
+        self.regions.insert(handle, (region, self.time));
+        Ok((handle, region))
+    }
+
+    fn allocate(&mut self, size: [u32; 2]) -> Option<AtlasRegion> {
+        self.pages.iter_mut().enumerate().find_map(|(page, p)| {
+            let offset = p.packer.insert(size)?;
+            p.regions += 1;
+            Some(AtlasRegion { page, offset, size })
+        })
+    }
+
+    /// Returns a region and marks it as recently used,
+    /// or `None` if it was removed or evicted.
+    pub fn get(&mut self, handle: AtlasHandle) -> Option<AtlasRegion> {
+        self.time += 1;
+        let (region, time) = self.regions.get_mut(&handle)?;
+        *time = self.time;
+        Some(*region)
+    }
+
+    /// Returns `true` if a region was neither removed nor evicted.
+    pub fn contains(&self, handle: AtlasHandle) -> bool {
+        self.regions.contains_key(&handle)
+    }
+
+    /// Removes a region, freeing its space.
+    ///
+    /// Returns `false` if it was already removed or evicted.
+    pub fn remove(&mut self, handle: AtlasHandle) -> bool {
+        let (region, _) = match self.regions.remove(&handle) {
+            Some(entry) => entry,
+            None => return false,
+        };
+        let page = &mut self.pages[region.page];
+        page.regions -= 1;
+        if page.regions == 0 {
+            page.packer.clear();
+        } else {
+            page.packer.free(region.offset, region.size);
+        }
+        true
+    }
+
+    /// Returns the number of regions.
+    pub fn len(&self) -> usize {
+        self.regions.len()
+    }
+
+    /// Returns `true` if there are no regions.
+    pub fn is_empty(&self) -> bool {
+        self.regions.is_empty()
+    }
+
+    /// Returns the number of pages.
+    pub fn pages(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// Returns the texture of a page.
+    pub fn texture(&self, page: usize) -> Option<&T> {
+        self.pages.get(page).map(|p| &p.texture)
+    }
+
+    /// Returns the mutable texture of a page, for uploading pixels.
+    pub fn texture_mut(&mut self, page: usize) -> Option<&mut T> {
+        self.pages.get_mut(page).map(|p| &mut p.texture)
+    }
+}
+
 /// A texture packer using a skyline heuristic.
 ///
+/// Use `with_heuristic` to pack with a `RectPacker` instead.
+///
 /// For offline texture packing, see [texture_packer](https://github.com/pistondevelopers/texture_packer).
 ///
 /// Designed for adding textures one by one to current texture atlas.
@@ -42,6 +500,8 @@ pub struct TexturePacker<T> {
     /// it updates the atlas offsets that it overlaps.
     /// This means that "holes" get filled in over time.
     pub skyline: Vec<[u32; 2]>,
+    heuristic: Option<PackingHeuristic>,
+    packer: Option<RectPacker>,
 }
 
 impl<T: ImageSize> TexturePacker<T> {
@@ -51,6 +511,19 @@ impl<T: ImageSize> TexturePacker<T> {
             textures: vec![],
             atlas: 0,
             skyline: vec![],
+            heuristic: None,
+            packer: None,
+        }
+    }
+
+    /// Returns a new `TexturePacker` that packs tiles using a heuristic.
+    ///
+    /// Use `insert` to add tiles, since `find_space` and `update`
+    /// only work with the default skyline.
+    pub fn with_heuristic(heuristic: PackingHeuristic) -> TexturePacker<T> {
+        TexturePacker {
+            heuristic: Some(heuristic),
+            ..TexturePacker::new()
         }
     }
 
@@ -63,10 +536,30 @@ impl<T: ImageSize> TexturePacker<T> {
             self.atlas += 1;
         }
         self.skyline = vec![[0, size[1]], [size[0], 0]];
+        self.packer = self.heuristic.map(|heuristic| {
+            let mut packer =
+                RectPacker::new([texture.get_width(), texture.get_height()], heuristic);
+            packer.insert(size);
+            packer
+        });
         self.textures.push(texture);
         id
     }
 
+    /// Adds a tile to the current texture atlas.
+    ///
+    /// Returns the index of the current texture atlas and
+    /// the atlas offset of the new tile,
+    /// or `None` if there is no room, in which case a new atlas should be created.
+    pub fn insert(&mut self, size: [u32; 2]) -> Option<(usize, [u32; 2])> {
+        let atlas = self.atlas;
+        match self.packer {
+            Some(ref mut packer) => packer.insert(size).map(|offset| (atlas, offset)),
+            None if self.heuristic.is_some() => None,
+            None => self.find_space(size).map(|ind| self.update(ind, size)),
+        }
+    }
+
     /// Update current texture atlas.
     ///
     /// - ind: index of atlas offset in the skyline
@@ -141,3 +634,80 @@ impl<T: ImageSize> Default for TexturePacker<T> {
         TexturePacker::new()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::software_graphics::Texture;
+
+    #[test]
+    fn test_rect_packer() {
+        let heuristics = [
+            PackingHeuristic::SkylineBottomLeft,
+            PackingHeuristic::MaxRectsBestShortSideFit,
+            PackingHeuristic::MaxRectsBestAreaFit,
+            PackingHeuristic::MaxRectsBottomLeft,
+        ];
+        for heuristic in heuristics {
+            let mut packer = RectPacker::new([64, 64], heuristic);
+            let mut placed: Vec<[u32; 4]> = vec![];
+            for i in 0..40 {
+                let size = [4 + (i * 7) % 13, 4 + (i * 5) % 11];
+                if let Some([x, y]) = packer.insert(size) {
+                    let r = [x, y, x + size[0], y + size[1]];
+                    assert!(r[2] <= 64 && r[3] <= 64);
+                    for p in &placed {
+                        assert!(r[0] >= p[2] || r[2] <= p[0] || r[1] >= p[3] || r[3] <= p[1]);
+                    }
+                    placed.push(r);
+                }
+            }
+            assert!(packer.occupancy() > 0.6, "{:?}", heuristic);
+        }
+
+        // Tiles of same size fill the bin perfectly.
+        let mut packer = RectPacker::new([64, 64], PackingHeuristic::MaxRectsBestShortSideFit);
+        for _ in 0..16 {
+            assert!(packer.insert([16, 16]).is_some());
+        }
+        assert_eq!(packer.insert([1, 1]), None);
+        assert_eq!(packer.occupancy(), 1.0);
+    }
+
+    #[test]
+    fn test_texture_packer_heuristic() {
+        let atlas = || Texture::new(image::RgbaImage::new(32, 32));
+        let mut packer = TexturePacker::with_heuristic(PackingHeuristic::MaxRectsBestAreaFit);
+        assert_eq!(packer.insert([8, 8]), None);
+        assert_eq!(packer.create([8, 8], atlas()), 0);
+        assert_eq!(packer.insert([24, 32]), Some((0, [8, 0])));
+        assert_eq!(packer.insert([8, 24]), Some((0, [0, 8])));
+        assert_eq!(packer.insert([1, 1]), None);
+        assert_eq!(packer.create([8, 8], atlas()), 1);
+        assert_eq!(packer.insert([8, 8]).map(|n| n.0), Some(1));
+    }
+
+    #[test]
+    fn test_texture_atlas() {
+        let mut atlas = TextureAtlas::new([4, 4]).max_pages(1);
+        let new_page = |size| Ok::<_, ()>(size);
+        let handles: Vec<_> = (0..4)
+            .map(|_| atlas.insert([2, 2], new_page).unwrap().0)
+            .collect();
+        assert_eq!(atlas.pages(), 1);
+        assert!(atlas.get(handles[0]).is_some());
+
+        // Evicts the least recently used region.
+        let (handle, region) = atlas.insert([2, 2], new_page).unwrap();
+        assert!(!atlas.contains(handles[1]));
+        assert!(atlas.contains(handles[0]));
+        assert_eq!(region.page, 0);
+        assert_eq!(atlas.len(), 4);
+
+        assert!(atlas.remove(handle));
+        assert!(!atlas.remove(handle));
+        let (_, region) = atlas.insert([8, 2], new_page).unwrap();
+        assert_eq!(region.page, 1);
+        assert_eq!(atlas.texture(1), Some(&[8, 4]));
+    }
+}
@@ -0,0 +1,161 @@
+//! Draw polygon filled with a texture
+
+use super::{
+    math::{Affine2, Scalar},
+    triangulation,
+    types::{self, Color, Rectangle, SourceRectangle},
+    DrawState, Graphics, ImageSize,
+};
+
+/// A polygon filled with a texture.
+///
+/// The polygon may be concave, see `triangulation::with_ear_clipping_tri_list`.
+/// By default, the texture is stretched over the bounding box of the polygon.
+#[derive(Copy, Clone, Debug)]
+pub struct TexturedPolygon {
+    /// The color multiplied with the texture.
+    pub color: Color,
+    /// The rectangle in polygon coordinates covered by the source rectangle.
+    ///
+    /// Uses the bounding box of the polygon when `None`.
+    pub mapping: Option<Rectangle>,
+    /// The source rectangle in the texture.
+    ///
+    /// Uses the whole texture when `None`.
+    pub source_rectangle: Option<SourceRectangle>,
+}
+
+impl TexturedPolygon {
+    /// Creates a new textured polygon.
+    pub fn new() -> TexturedPolygon {
+        TexturedPolygon {
+            color: [1.0; 4],
+            mapping: None,
+            source_rectangle: None,
+        }
+    }
+
+    /// Sets color.
+    pub fn color(mut self, value: Color) -> Self {
+        self.color = value;
+        self
+    }
+
+    /// Sets the rectangle in polygon coordinates covered by the source rectangle.
+    ///
+    /// With `Wrap::Repeat` textures, a mapping smaller than the polygon tiles the texture.
+    pub fn mapping<R: Into<Rectangle>>(mut self, value: R) -> Self {
+        self.mapping = Some(value.into());
+        self
+    }
+
+    /// Sets source rectangle.
+    pub fn src_rect(mut self, value: SourceRectangle) -> Self {
+        self.source_rectangle = Some(value);
+        self
+    }
+
+    /// Draws polygon, generating texture coordinates from the mapping.
+    pub fn draw<G>(
+        &self,
+        polygon: types::Polygon<'_>,
+        texture: &<G as Graphics>::Texture,
+        draw_state: &DrawState,
+        transform: Affine2,
+        g: &mut G,
+    ) where
+        G: Graphics,
+    {
+        if polygon.is_empty() {
+            return;
+        }
+        let mapping = self.mapping.unwrap_or_else(|| {
+            let (mut min, mut max) = (polygon[0], polygon[0]);
+            for p in polygon {
+                min = [min[0].min(p[0]), min[1].min(p[1])];
+                max = [max[0].max(p[0]), max[1].max(p[1])];
+            }
+            [min[0], min[1], max[0] - min[0], max[1] - min[1]]
+        });
+        let (w, h) = texture.get_size();
+        let src = self
+            .source_rectangle
+            .unwrap_or([0.0, 0.0, w as Scalar, h as Scalar]);
+        // Maps a coordinate from the mapping to the texture.
+        let uv = |x: Scalar,
+                  start: Scalar,
+                  size: Scalar,
+                  src_start: Scalar,
+                  src_size: Scalar,
+                  tex_size: u32| {
+            let t = if size == 0.0 { 0.0 } else { (x - start) / size };
+            ((src_start + t * src_size) / tex_size.max(1) as Scalar) as f32
+        };
+        let uvs: Vec<[f32; 2]> = polygon
+            .iter()
+            .map(|p| {
+                [
+                    uv(p[0], mapping[0], mapping[2], src[0], src[2], w),
+                    uv(p[1], mapping[1], mapping[3], src[1], src[3], h),
+                ]
+            })
+            .collect();
+        self.draw_uv(polygon, &uvs, texture, draw_state, transform, g);
+    }
+
+    /// Draws polygon with texture coordinates per vertex.
+    ///
+    /// The texture coordinates are normalized, between 0 and 1 across the texture.
+    pub fn draw_uv<G>(
+        &self,
+        polygon: types::Polygon<'_>,
+        uvs: &[[f32; 2]],
+        texture: &<G as Graphics>::Texture,
+        draw_state: &DrawState,
+        transform: Affine2,
+        g: &mut G,
+    ) where
+        G: Graphics,
+    {
+        if self.color[3] == 0.0 {
+            return;
+        }
+        g.tri_list_uv(draw_state, &self.color, texture, |f| {
+            triangulation::with_polygon_uv_tri_list(transform, polygon, uvs, |vertices, uvs| {
+                f(vertices, uvs)
+            })
+        });
+    }
+}
+
+impl Default for TexturedPolygon {
+    fn default() -> Self {
+        TexturedPolygon::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::software_graphics::{SoftwareGraphics, Texture};
+
+    #[test]
+    fn test_textured_polygon() {
+        // Red on the left, blue on the right.
+        let texture = Texture::new(image::RgbaImage::from_fn(2, 1, |x, _| {
+            image::Rgba(if x == 0 {
+                [255, 0, 0, 255]
+            } else {
+                [0, 0, 255, 255]
+            })
+        }));
+        let triangle = [[0.0, 0.0], [16.0, 0.0], [0.0, 16.0]];
+        let mut g = SoftwareGraphics::new(16, 16);
+        g.draw(|c, g| {
+            TexturedPolygon::new().draw(&triangle, &texture, &c.draw_state, c.transform, g)
+        });
+        assert_eq!(g.get_pixel(2, 2), [255, 0, 0, 255]);
+        assert_eq!(g.get_pixel(12, 1), [0, 0, 255, 255]);
+        assert_eq!(g.get_pixel(12, 12), [0; 4]);
+    }
+}
@@ -60,6 +60,18 @@ pub trait Transformed: Sized {
         self.scale(s, s)
     }
 
+    /// Rotates radians around a pivot point in local coordinates.
+    #[inline(always)]
+    fn rot_around(self, x: Scalar, y: Scalar, angle: Scalar) -> Self {
+        self.trans(x, y).rot_rad(angle).trans(-x, -y)
+    }
+
+    /// Scales around a pivot point in local coordinates.
+    #[inline(always)]
+    fn scale_around(self, x: Scalar, y: Scalar, sx: Scalar, sy: Scalar) -> Self {
+        self.trans(x, y).scale(sx, sy).trans(-x, -y)
+    }
+
     /// Flips vertically in local coordinates.
     #[inline(always)]
     fn flip_v(self) -> Self {
@@ -87,6 +99,21 @@ pub trait Transformed: Sized {
         let pos = pos.into();
         self.shear(pos[0], pos[1])
     }
+
+    /// Skews the axes by angles in radians in local coordinates.
+    ///
+    /// The x angle tilts the y axis towards the x axis, and vice versa.
+    #[inline(always)]
+    fn skew_rad(self, x: Scalar, y: Scalar) -> Self {
+        self.shear(x.tan(), y.tan())
+    }
+
+    /// Skews the axes by angles in degrees in local coordinates.
+    #[inline(always)]
+    fn skew_deg(self, x: Scalar, y: Scalar) -> Self {
+        let pi: Scalar = Radians::_180();
+        self.skew_rad(x * pi / 180.0, y * pi / 180.0)
+    }
 }
 
 impl Transformed for Affine2 {
@@ -174,3 +201,22 @@ impl Transformed for Context {
         self
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graphics::math::{identity, transform_pos};
+
+    #[test]
+    fn test_around_pivot() {
+        let m = identity().rot_around(2.0, 3.0, <Scalar as Radians>::_90());
+        let p = transform_pos(m, [2.0, 3.0]);
+        assert!((p[0] - 2.0).abs() < 1e-9 && (p[1] - 3.0).abs() < 1e-9);
+        let p = transform_pos(m, [3.0, 3.0]);
+        assert!((p[0] - 2.0).abs() < 1e-9 && (p[1] - 4.0).abs() < 1e-9);
+
+        let m = identity().scale_around(2.0, 3.0, 2.0, 3.0);
+        assert_eq!(transform_pos(m, [2.0, 3.0]), [2.0, 3.0]);
+        assert_eq!(transform_pos(m, [3.0, 4.0]), [4.0, 6.0]);
+    }
+}
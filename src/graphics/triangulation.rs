@@ -2,10 +2,16 @@
 use interpolation::lerp;
 
 use super::{
-    math::{multiply, orient, translate, Affine2, Scalar, Vec2d},
+    curve::coons_patch,
+    math::{
+        multiply, offset_polygon_with, orient, resample_polygon, translate, Affine2, Cap, FillRule,
+        Join, Scalar, Vec2d,
+    },
     radians::Radians,
-    types::{Line, Polygon, Polygons, Radius, Rectangle, Resolution, SourceRectangle},
-    ImageSize, BACK_END_MAX_VERTEX_COUNT as BUFFER_SIZE,
+    types::{
+        Color, CubicBezier, Line, Polygon, Polygons, Radius, Rectangle, Resolution, SourceRectangle,
+    },
+    ImageSize, Viewport, BACK_END_MAX_VERTEX_COUNT as BUFFER_SIZE,
 };
 
 /// Transformed x coordinate as f32.
@@ -30,7 +36,37 @@ pub fn with_lerp_polygons_tri_list<F>(
 ) where
     F: FnMut(&[[f32; 2]]),
 {
-    let poly_len = polygons.len() as Scalar;
+    let (frame, next_frame, tw) = tween_frames(polygons.len(), tween_factor);
+    let p0 = polygons[frame];
+    let p1 = polygons[next_frame];
+    let n = polygons[0].len();
+    stream_polygon_tri_list(m, (0..n).map(|j| lerp(&p0[j], &p1[j], &tw)), f);
+}
+
+/// Streams tweened polygons using linear interpolation,
+/// after resampling the two current frames to `samples` vertices each.
+///
+/// Unlike `with_lerp_polygons_tri_list`, the polygons can have different vertex counts.
+/// Vertices are distributed evenly along each perimeter, starting at the first vertex,
+/// so corners are preserved only approximately unless `samples` is large.
+pub fn with_resampled_lerp_polygons_tri_list<F>(
+    m: Affine2,
+    polygons: Polygons<'_>,
+    tween_factor: Scalar,
+    samples: usize,
+    f: F,
+) where
+    F: FnMut(&[[f32; 2]]),
+{
+    let (frame, next_frame, tw) = tween_frames(polygons.len(), tween_factor);
+    let p0 = resample_polygon(polygons[frame], samples);
+    let p1 = resample_polygon(polygons[next_frame], samples);
+    stream_polygon_tri_list(m, p0.iter().zip(&p1).map(|(a, b)| lerp(a, b, &tw)), f);
+}
+
+// Returns the current frame, the next frame and the factor between them.
+fn tween_frames(frames: usize, tween_factor: Scalar) -> (usize, usize, Scalar) {
+    let poly_len = frames as Scalar;
     // Map to interval between 0 and 1.
     let tw = tween_factor % 1.0;
     // Map negative values to positive.
@@ -40,13 +76,9 @@ pub fn with_lerp_polygons_tri_list<F>(
     // Get the current frame.
     let frame = tw as usize;
     // Get the next frame.
-    let next_frame = (frame + 1) % polygons.len();
-    let p0 = polygons[frame];
-    let p1 = polygons[next_frame];
+    let next_frame = (frame + 1) % frames;
     // Get factor between frames.
-    let tw = tw - frame as Scalar;
-    let n = polygons[0].len();
-    stream_polygon_tri_list(m, (0..n).map(|j| lerp(&p0[j], &p1[j], &tw)), f);
+    (frame, next_frame, tw - frame as Scalar)
 }
 
 /// Streams an ellipse specified by a resolution.
@@ -69,6 +101,84 @@ where
     );
 }
 
+/// Returns the resolution per 360 degrees for a circle,
+/// such that segments deviate at most `tolerance` pixels from the circle on the screen.
+///
+/// The on-screen radius uses the largest scale of the transform in the viewport.
+pub fn auto_resolution(
+    m: Affine2,
+    viewport: &Viewport,
+    radius: Radius,
+    tolerance: Scalar,
+) -> Resolution {
+    const MIN_RESOLUTION: Resolution = 8;
+    const MAX_RESOLUTION: Resolution = 1024;
+
+    let (hw, hh) = (
+        0.5 * viewport.rect[2] as Scalar,
+        0.5 * viewport.rect[3] as Scalar,
+    );
+    let len = |x: Scalar, y: Scalar| ((x * hw) * (x * hw) + (y * hh) * (y * hh)).sqrt();
+    let scale = len(m[0][0], m[1][0]).max(len(m[0][1], m[1][1]));
+    let radius = radius.abs() * scale;
+    if tolerance.is_nan() || tolerance <= 0.0 || !radius.is_finite() {
+        return MAX_RESOLUTION;
+    }
+    if radius <= tolerance {
+        return MIN_RESOLUTION;
+    }
+    // A chord spanning angle `a` deviates `r * (1 - cos(a / 2))` from the circle.
+    let angle = 2.0 * (1.0 - tolerance / radius).acos();
+    let n = (<Scalar as Radians>::_360() / angle).ceil();
+    (n as Resolution).clamp(MIN_RESOLUTION, MAX_RESOLUTION)
+}
+
+/// Streams an ellipse as a fan of triangles around a point inside.
+///
+/// This is useful when vertex attributes vary along rays from the point,
+/// e.g. for radial gradients with a focal point.
+pub fn with_ellipse_fan_tri_list<F>(
+    resolution: Resolution,
+    m: Affine2,
+    rect: Rectangle,
+    center: Vec2d,
+    mut f: F,
+) where
+    F: FnMut(&[[f32; 2]]),
+{
+    let (x, y, w, h) = (rect[0], rect[1], rect[2], rect[3]);
+    let (cw, ch) = (0.5 * w, 0.5 * h);
+    let (cx, cy) = (x + cw, y + ch);
+    let n = resolution.max(3);
+    let point = |i: Resolution| {
+        let angle = i as Scalar / n as Scalar * <Scalar as Radians>::_360();
+        let (px, py) = (cx + angle.cos() * cw, cy + angle.sin() * ch);
+        [tx(m, px, py), ty(m, px, py)]
+    };
+    let c = [tx(m, center[0], center[1]), ty(m, center[0], center[1])];
+    let mut vertices: [[f32; 2]; BUFFER_SIZE] = [[0.0; 2]; BUFFER_SIZE];
+    let mut k = 0;
+    let mut prev = point(0);
+    for i in 1..=n {
+        let next = point(i % n);
+        // Buffer is full.
+        if k + 3 > BUFFER_SIZE {
+            // Send chunk and start over.
+            f(&vertices[0..k]);
+            k = 0;
+        }
+        vertices[k] = c;
+        vertices[k + 1] = prev;
+        vertices[k + 2] = next;
+        k += 3;
+        prev = next;
+    }
+
+    if k > 0 {
+        f(&vertices[0..k]);
+    }
+}
+
 /// Streams a round border line.
 #[inline(always)]
 pub fn with_round_border_line_tri_list<F>(
@@ -309,26 +419,12 @@ pub fn with_ellipse_border_tri_list<F>(
     );
 }
 
-/// Streams an arc between the two radian boundaries.
-#[inline(always)]
-pub fn with_arc_tri_list<F>(
+// Returns start angle, segment size and number of segments of an arc.
+fn arc_segments(
     start_radians: Scalar,
     end_radians: Scalar,
     resolution: Resolution,
-    m: Affine2,
-    rect: Rectangle,
-    border_radius: Radius,
-    f: F,
-) where
-    F: FnMut(&[[f32; 2]]),
-{
-    let (x, y, w, h) = (rect[0], rect[1], rect[2], rect[3]);
-    let (cw, ch) = (0.5 * w, 0.5 * h);
-    let (cw1, ch1) = (cw + border_radius, ch + border_radius);
-    let (cw2, ch2) = (cw - border_radius, ch - border_radius);
-    let (cx, cy) = (x + cw, y + ch);
-    let mut i = 0;
-
+) -> (Scalar, Scalar, u64) {
     let twopi = <Scalar as Radians>::_360();
     let max_seg_size = twopi / resolution as Scalar;
 
@@ -345,10 +441,34 @@ pub fn with_arc_tri_list<F>(
 
     // Taking ceiling here implies that the resolution parameter provides a
     // lower bound on the drawn resolution.
-    let n_quads = (delta / max_seg_size).ceil() as u64;
+    let n = (delta / max_seg_size).ceil() as u64;
+
+    // n * seg_size exactly spans the included angle.
+    let seg_size = delta / n as Scalar;
+    (start_radians, seg_size, n)
+}
+
+/// Streams an arc between the two radian boundaries.
+#[inline(always)]
+pub fn with_arc_tri_list<F>(
+    start_radians: Scalar,
+    end_radians: Scalar,
+    resolution: Resolution,
+    m: Affine2,
+    rect: Rectangle,
+    border_radius: Radius,
+    f: F,
+) where
+    F: FnMut(&[[f32; 2]]),
+{
+    let (x, y, w, h) = (rect[0], rect[1], rect[2], rect[3]);
+    let (cw, ch) = (0.5 * w, 0.5 * h);
+    let (cw1, ch1) = (cw + border_radius, ch + border_radius);
+    let (cw2, ch2) = (cw - border_radius, ch - border_radius);
+    let (cx, cy) = (x + cw, y + ch);
+    let mut i = 0;
 
-    // n_quads * seg_size exactly spans the included angle.
-    let seg_size = delta / n_quads as Scalar;
+    let (start_radians, seg_size, n_quads) = arc_segments(start_radians, end_radians, resolution);
     stream_quad_tri_list(
         m,
         || {
@@ -370,6 +490,32 @@ pub fn with_arc_tri_list<F>(
     );
 }
 
+/// Streams a filled sector of a circle between the two radian boundaries.
+pub fn with_sector_tri_list<F>(
+    start_radians: Scalar,
+    end_radians: Scalar,
+    resolution: Resolution,
+    m: Affine2,
+    center: Vec2d,
+    radius: Radius,
+    f: F,
+) where
+    F: FnMut(&[[f32; 2]]),
+{
+    let (start_radians, seg_size, n) = arc_segments(start_radians, end_radians, resolution);
+    if n == 0 {
+        return;
+    }
+    let arc = (0..=n).map(|i| {
+        let angle = start_radians + (i as Scalar * seg_size);
+        [
+            center[0] + angle.cos() * radius,
+            center[1] + angle.sin() * radius,
+        ]
+    });
+    stream_polygon_tri_list(m, core::iter::once(center).chain(arc), f);
+}
+
 /// Streams a round rectangle border.
 #[allow(clippy::identity_op)] // Identity operations are used for readibility.
 #[inline(always)]
@@ -483,6 +629,311 @@ pub fn with_round_rectangle_border_tri_list<F>(
     );
 }
 
+// Returns the radius, arc center and outward diagonal of corner `k`
+// of a rectangle with radii ordered top-left, top-right, bottom-right, bottom-left.
+//
+// Corners are visited clockwise starting at bottom-right,
+// such that corner `k` covers angles from `k * 90` to `(k + 1) * 90` degrees.
+fn rectangle_corner(rect: Rectangle, radii: [Radius; 4], k: usize) -> (Radius, Vec2d, Vec2d) {
+    let (x, y, w, h) = (rect[0], rect[1], rect[2], rect[3]);
+    let (r, corner, diag) = match k {
+        0 => (radii[2], [x + w, y + h], [1.0, 1.0]),
+        1 => (radii[3], [x, y + h], [-1.0, 1.0]),
+        2 => (radii[0], [x, y], [-1.0, -1.0]),
+        _ => (radii[1], [x + w, y], [1.0, -1.0]),
+    };
+    let center = [corner[0] - diag[0] * r, corner[1] - diag[1] * r];
+    (r, center, diag)
+}
+
+// Returns the angle of point `i` of a rectangle corner `k`.
+fn rectangle_corner_angle(resolution_corner: Resolution, k: usize, i: Resolution) -> Scalar {
+    (i as Scalar / (resolution_corner - 1) as Scalar + k as Scalar) * <Scalar as Radians>::_90()
+}
+
+/// Returns the outline of a rectangle with a round radius per corner.
+///
+/// Radii are ordered top-left, top-right, bottom-right, bottom-left.
+/// A corner with zero radius gets a single point.
+pub fn round_corners_rectangle_outline(
+    resolution_corner: Resolution,
+    rect: Rectangle,
+    radii: [Radius; 4],
+) -> Vec<Vec2d> {
+    let n = resolution_corner.max(2);
+    let mut res = Vec::with_capacity(4 * n as usize);
+    for k in 0..4 {
+        let (r, c, _) = rectangle_corner(rect, radii, k);
+        if r == 0.0 {
+            res.push(c);
+            continue;
+        }
+        for i in 0..n {
+            let angle = rectangle_corner_angle(n, k, i);
+            res.push([c[0] + angle.cos() * r, c[1] + angle.sin() * r]);
+        }
+    }
+    res
+}
+
+/// Streams a rectangle with a round radius per corner.
+///
+/// Radii are ordered top-left, top-right, bottom-right, bottom-left.
+/// A corner with zero radius is square.
+pub fn with_round_corners_rectangle_tri_list<F>(
+    resolution_corner: Resolution,
+    m: Affine2,
+    rect: Rectangle,
+    radii: [Radius; 4],
+    f: F,
+) where
+    F: FnMut(&[[f32; 2]]),
+{
+    let n = resolution_corner.max(2);
+    stream_polygon_tri_list(
+        m,
+        (0..4 * n).map(|j| {
+            let (k, i) = ((j / n) as usize, j % n);
+            let (r, c, _) = rectangle_corner(rect, radii, k);
+            let angle = rectangle_corner_angle(n, k, i);
+            [c[0] + angle.cos() * r, c[1] + angle.sin() * r]
+        }),
+        f,
+    );
+}
+
+/// Streams a border of rectangle with a round radius per corner.
+///
+/// Radii are ordered top-left, top-right, bottom-right, bottom-left.
+/// A corner with zero radius gets a square border.
+pub fn with_round_corners_rectangle_border_tri_list<F>(
+    resolution_corner: Resolution,
+    m: Affine2,
+    rect: Rectangle,
+    radii: [Radius; 4],
+    border_radius: Radius,
+    f: F,
+) where
+    F: FnMut(&[[f32; 2]]),
+{
+    let n = resolution_corner.max(2);
+    let mut j = 0;
+    stream_quad_tri_list(
+        m,
+        || {
+            if j > 4 * n {
+                return None;
+            }
+            // Repeat the first point to close the border.
+            let (k, i) = (((j % (4 * n)) / n) as usize, j % n);
+            j += 1;
+            let (r, c, diag) = rectangle_corner(rect, radii, k);
+            let angle = rectangle_corner_angle(n, k, i);
+            let (cos, sin) = (angle.cos(), angle.sin());
+            let outer = if r > 0.0 {
+                [
+                    c[0] + cos * (r + border_radius),
+                    c[1] + sin * (r + border_radius),
+                ]
+            } else {
+                [
+                    c[0] + diag[0] * border_radius,
+                    c[1] + diag[1] * border_radius,
+                ]
+            };
+            let inner = if r > border_radius {
+                [
+                    c[0] + cos * (r - border_radius),
+                    c[1] + sin * (r - border_radius),
+                ]
+            } else {
+                // Keep the inner corner square when the border is wider than the radius.
+                let inset = border_radius - r;
+                [c[0] - diag[0] * inset, c[1] - diag[1] * inset]
+            };
+            Some((outer, inner))
+        },
+        f,
+    );
+}
+
+/// Streams a stroke along connected points.
+///
+/// Corners are joined with `join` on the outer side,
+/// while the inner side uses the intersection of the offset edges,
+/// such that triangles do not overlap except at very sharp corners.
+/// Repeated points are ignored.
+pub fn with_polyline_tri_list<F>(
+    m: Affine2,
+    points: Polygon<'_>,
+    radius: Radius,
+    join: Join,
+    cap: Cap,
+    mut f: F,
+) where
+    F: FnMut(&[[f32; 2]]),
+{
+    let mut ps: Vec<Vec2d> = Vec::with_capacity(points.len());
+    for &p in points {
+        if ps.last() != Some(&p) {
+            ps.push(p);
+        }
+    }
+    if ps.len() < 2 {
+        return;
+    }
+
+    let mut vertices: [[f32; 2]; BUFFER_SIZE] = [[0.0; 2]; BUFFER_SIZE];
+    let mut k = 0;
+    let mut tri = |a: Vec2d, b: Vec2d, c: Vec2d| {
+        // Buffer is full.
+        if k + 3 > BUFFER_SIZE {
+            // Send chunk and start over.
+            f(&vertices[0..k]);
+            k = 0;
+        }
+        for p in [a, b, c] {
+            vertices[k] = [tx(m, p[0], p[1]), ty(m, p[0], p[1])];
+            k += 1;
+        }
+    };
+    let at = |p: Vec2d, d: Vec2d, s: Scalar| [p[0] + d[0] * s, p[1] + d[1] * s];
+    // Adds a fan of triangles around center, rotating from `a` to `b` by `angle`.
+    let arc = |tri: &mut dyn FnMut(Vec2d, Vec2d, Vec2d),
+               center: Vec2d,
+               a: Vec2d,
+               angle: Scalar,
+               resolution: Resolution| {
+        let step = <Scalar as Radians>::_360() / resolution.max(1) as Scalar;
+        let n = (angle.abs() / step).ceil().max(1.0) as u32;
+        let mut prev = a;
+        for i in 1..=n {
+            let t = angle * i as Scalar / n as Scalar;
+            let (sin, cos) = t.sin_cos();
+            let v = [a[0] - center[0], a[1] - center[1]];
+            let next = [
+                center[0] + v[0] * cos - v[1] * sin,
+                center[1] + v[0] * sin + v[1] * cos,
+            ];
+            tri(center, prev, next);
+            prev = next;
+        }
+    };
+
+    let n = ps.len();
+    let dirs: Vec<Vec2d> = ps
+        .windows(2)
+        .map(|w| {
+            let d = [w[1][0] - w[0][0], w[1][1] - w[0][1]];
+            let len = (d[0] * d[0] + d[1] * d[1]).sqrt();
+            [d[0] / len, d[1] / len]
+        })
+        .collect();
+    let lens: Vec<Scalar> = ps
+        .windows(2)
+        .map(|w| ((w[1][0] - w[0][0]).powi(2) + (w[1][1] - w[0][1]).powi(2)).sqrt())
+        .collect();
+    let normal = |d: Vec2d| [-d[1], d[0]];
+
+    // Start cap.
+    let (p0, d0) = (ps[0], dirs[0]);
+    let n0 = normal(d0);
+    let (mut left, mut right) = match cap {
+        Cap::Square => {
+            let p = at(p0, d0, -radius);
+            (at(p, n0, radius), at(p, n0, -radius))
+        }
+        Cap::Butt | Cap::Round(_) => (at(p0, n0, radius), at(p0, n0, -radius)),
+    };
+    if let Cap::Round(resolution) = cap {
+        arc(&mut tri, p0, left, <Scalar as Radians>::_180(), resolution);
+    }
+
+    for i in 1..n - 1 {
+        let p = ps[i];
+        let (da, db) = (dirs[i - 1], dirs[i]);
+        let (na, nb) = (normal(da), normal(db));
+        let cross = da[0] * db[1] - da[1] * db[0];
+        let cos = na[0] * nb[0] + na[1] * nb[1];
+        if cross.abs() < 1e-9 && cos > 0.0 {
+            // Straight continuation.
+            let (l, r) = (at(p, na, radius), at(p, na, -radius));
+            tri(left, right, l);
+            tri(right, l, r);
+            left = l;
+            right = r;
+            continue;
+        }
+        // The outer side is opposite of the turn direction.
+        let s = if cross > 0.0 { -1.0 } else { 1.0 };
+        let miter = [na[0] + nb[0], na[1] + nb[1]];
+        // Limit the inner point for sharp corners between short segments.
+        let max_len = (radius * radius + lens[i - 1].min(lens[i]).powi(2)).sqrt();
+        let inner_len = if 1.0 + cos > 1e-9 {
+            (radius / ((1.0 + cos) * 0.5).sqrt()).min(max_len)
+        } else {
+            max_len
+        };
+        let miter_len = (miter[0] * miter[0] + miter[1] * miter[1]).sqrt();
+        let inner = if miter_len > 1e-9 {
+            at(p, miter, -s * inner_len / miter_len)
+        } else {
+            at(p, da, -inner_len)
+        };
+        let (oa, ob) = (at(p, na, s * radius), at(p, nb, s * radius));
+        // Segment before the corner.
+        let (l, r) = if s > 0.0 { (oa, inner) } else { (inner, oa) };
+        tri(left, right, l);
+        tri(right, l, r);
+        let outer_end = match join {
+            // The miter length divided by radius is `sqrt(2 / (1 + cos))`.
+            Join::Miter(limit) if 1.0 + cos > 1e-9 && 2.0 <= limit * limit * (1.0 + cos) => {
+                let tip = at(p, miter, s * radius / (1.0 + cos));
+                tri(inner, oa, tip);
+                tri(inner, tip, ob);
+                ob
+            }
+            Join::Miter(_) | Join::Bevel => {
+                tri(inner, oa, ob);
+                ob
+            }
+            Join::Round(resolution) => {
+                tri(inner, oa, p);
+                let angle = (na[0] * nb[1] - na[1] * nb[0]).atan2(cos);
+                arc(&mut tri, p, oa, angle, resolution);
+                tri(inner, p, ob);
+                ob
+            }
+        };
+        if s > 0.0 {
+            left = outer_end;
+            right = inner;
+        } else {
+            left = inner;
+            right = outer_end;
+        }
+    }
+
+    // End cap.
+    let (p1, d1) = (ps[n - 1], dirs[n - 2]);
+    let n1 = normal(d1);
+    let end = if let Cap::Square = cap {
+        at(p1, d1, radius)
+    } else {
+        p1
+    };
+    let (l, r) = (at(end, n1, radius), at(end, n1, -radius));
+    tri(left, right, l);
+    tri(right, l, r);
+    if let Cap::Round(resolution) = cap {
+        arc(&mut tri, p1, r, <Scalar as Radians>::_180(), resolution);
+    }
+
+    if k > 0 {
+        f(&vertices[0..k]);
+    }
+}
+
 /// Streams a quad into tri list.
 ///
 /// Uses buffers that fit inside L1 cache.
@@ -583,6 +1034,470 @@ where
     stream_polygon_tri_list(m, (0..polygon.len()).map(|i| polygon[i]), f);
 }
 
+/// Streams a simple polygon, which may be concave, using ear clipping.
+///
+/// Repeatedly cuts off a corner that contains no other vertex.
+/// Works with either winding order.
+/// Self-intersecting polygons are triangulated without panicking,
+/// but the result may not cover the expected area.
+/// For convex polygons, `with_polygon_tri_list` is faster.
+pub fn with_ear_clipping_tri_list<F>(m: Affine2, polygon: Polygon<'_>, mut f: F)
+where
+    F: FnMut(&[[f32; 2]]),
+{
+    let mut vertices: [[f32; 2]; BUFFER_SIZE] = [[0.0; 2]; BUFFER_SIZE];
+    let mut k = 0;
+    ear_clipping(polygon, |tri| {
+        // Buffer is full.
+        if k + 3 > BUFFER_SIZE {
+            // Send chunk and start over.
+            f(&vertices[0..k]);
+            k = 0;
+        }
+        for i in tri {
+            let p = polygon[i];
+            vertices[k] = [tx(m, p[0], p[1]), ty(m, p[0], p[1])];
+            k += 1;
+        }
+    });
+
+    if k > 0 {
+        f(&vertices[0..k]);
+    }
+}
+
+/// Streams a simple polygon with texture coordinates per vertex, using ear clipping.
+///
+/// The texture coordinates are normalized, between 0 and 1 across the texture.
+/// Vertices without texture coordinates are ignored.
+pub fn with_polygon_uv_tri_list<F>(m: Affine2, polygon: Polygon<'_>, uvs: &[[f32; 2]], mut f: F)
+where
+    F: FnMut(&[[f32; 2]], &[[f32; 2]]),
+{
+    let polygon = &polygon[..polygon.len().min(uvs.len())];
+    let mut vertices: [[f32; 2]; BUFFER_SIZE] = [[0.0; 2]; BUFFER_SIZE];
+    let mut tex: [[f32; 2]; BUFFER_SIZE] = [[0.0; 2]; BUFFER_SIZE];
+    let mut k = 0;
+    ear_clipping(polygon, |tri| {
+        // Buffer is full.
+        if k + 3 > BUFFER_SIZE {
+            // Send chunk and start over.
+            f(&vertices[0..k], &tex[0..k]);
+            k = 0;
+        }
+        for i in tri {
+            let p = polygon[i];
+            vertices[k] = [tx(m, p[0], p[1]), ty(m, p[0], p[1])];
+            tex[k] = uvs[i];
+            k += 1;
+        }
+    });
+
+    if k > 0 {
+        f(&vertices[0..k], &tex[0..k]);
+    }
+}
+
+/// Streams line segments as quads with butt ends.
+///
+/// Each line is `[x0, y0, x1, y1]`, and the radius is half the width.
+/// Zero length lines are skipped.
+pub fn with_lines_tri_list<F>(m: Affine2, radius: Radius, lines: &[Line], f: F)
+where
+    F: FnMut(&[[f32; 2]]),
+{
+    let quads = lines.iter().filter_map(|line| {
+        let (dx, dy) = (line[2] - line[0], line[3] - line[1]);
+        let len = (dx * dx + dy * dy).sqrt();
+        if len == 0.0 {
+            return None;
+        }
+        // Perpendicular to the line, scaled to the radius.
+        let (nx, ny) = (-dy / len * radius, dx / len * radius);
+        Some([
+            [line[0] + nx, line[1] + ny],
+            [line[2] + nx, line[3] + ny],
+            [line[2] - nx, line[3] - ny],
+            [line[0] - nx, line[1] - ny],
+        ])
+    });
+    stream_quads_tri_list(m, quads, f);
+}
+
+/// Streams points as squares with half the side length given by radius.
+pub fn with_points_tri_list<F>(m: Affine2, radius: Radius, points: &[Vec2d], f: F)
+where
+    F: FnMut(&[[f32; 2]]),
+{
+    let r = radius;
+    let quads = points.iter().map(|p| {
+        [
+            [p[0] - r, p[1] - r],
+            [p[0] + r, p[1] - r],
+            [p[0] + r, p[1] + r],
+            [p[0] - r, p[1] + r],
+        ]
+    });
+    stream_quads_tri_list(m, quads, f);
+}
+
+// Streams quads with corners in order around the edge.
+fn stream_quads_tri_list<I, F>(m: Affine2, quads: I, mut f: F)
+where
+    I: Iterator<Item = [Vec2d; 4]>,
+    F: FnMut(&[[f32; 2]]),
+{
+    let mut vertices: [[f32; 2]; BUFFER_SIZE] = [[0.0; 2]; BUFFER_SIZE];
+    let mut k = 0;
+    for q in quads {
+        // Buffer is full.
+        if k + 6 > BUFFER_SIZE {
+            // Send chunk and start over.
+            f(&vertices[0..k]);
+            k = 0;
+        }
+        for p in [q[0], q[1], q[2], q[0], q[2], q[3]] {
+            vertices[k] = [tx(m, p[0], p[1]), ty(m, p[0], p[1])];
+            k += 1;
+        }
+    }
+    if k > 0 {
+        f(&vertices[0..k]);
+    }
+}
+
+// Triangulates a polygon by ear clipping, calling `emit` with vertex indices.
+fn ear_clipping<F>(polygon: Polygon<'_>, mut emit: F)
+where
+    F: FnMut([usize; 3]),
+{
+    let n = polygon.len();
+    if n < 3 {
+        return;
+    }
+    let cross = |a: Vec2d, b: Vec2d, c: Vec2d| {
+        (b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0])
+    };
+    // Twice the signed area, used to get the same winding for all corners.
+    let area: Scalar = (0..n)
+        .map(|i| {
+            let (p, q) = (polygon[i], polygon[(i + 1) % n]);
+            p[0] * q[1] - q[0] * p[1]
+        })
+        .sum();
+    let sign = if area < 0.0 { -1.0 } else { 1.0 };
+    let is_convex = |a: Vec2d, b: Vec2d, c: Vec2d| sign * cross(a, b, c) > 0.0;
+    let contains = |a: Vec2d, b: Vec2d, c: Vec2d, p: Vec2d| {
+        sign * cross(a, b, p) >= 0.0 && sign * cross(b, c, p) >= 0.0 && sign * cross(c, a, p) >= 0.0
+    };
+
+    let mut remaining: Vec<usize> = (0..n).collect();
+    let mut i = 0;
+    // Counts corners checked since the last ear was cut off.
+    let mut checked = 0;
+    while remaining.len() > 3 {
+        let len = remaining.len();
+        let (ia, ib, ic) = (
+            remaining[(i + len - 1) % len],
+            remaining[i],
+            remaining[(i + 1) % len],
+        );
+        let (a, b, c) = (polygon[ia], polygon[ib], polygon[ic]);
+        let is_ear = is_convex(a, b, c)
+            && remaining.iter().all(|&j| {
+                let p = polygon[j];
+                j == ia || j == ib || j == ic || p == a || p == b || p == c || !contains(a, b, c, p)
+            });
+        // Cut off any corner when there are no ears left, which happens with
+        // degenerate or self-intersecting polygons, to make sure the loop ends.
+        if is_ear || checked >= len {
+            emit([ia, ib, ic]);
+            remaining.remove(i);
+            checked = 0;
+            if i >= remaining.len() {
+                i = 0;
+            }
+        } else {
+            checked += 1;
+            i = (i + 1) % len;
+        }
+    }
+    emit([remaining[0], remaining[1], remaining[2]]);
+}
+
+/// Streams the border between a polygon and the polygon offset by a distance.
+///
+/// A positive distance puts the border outside the polygon,
+/// and a negative distance puts it inside.
+/// Corners are joined using `join`, see `math::offset_polygon_with`.
+pub fn with_polygon_offset_border_tri_list<F>(
+    m: Affine2,
+    polygon: Polygon<'_>,
+    distance: Scalar,
+    join: Join,
+    mut f: F,
+) where
+    F: FnMut(&[[f32; 2]]),
+{
+    let mut corners: Vec<(Vec2d, Vec2d)> = vec![];
+    offset_polygon_with(polygon, distance, join, |corner, v| {
+        corners.push((corner, v))
+    });
+    let n = corners.len();
+    let mut vertices: [[f32; 2]; BUFFER_SIZE] = [[0.0; 2]; BUFFER_SIZE];
+    let mut i = 0;
+    for j in 0..n {
+        let (c0, p0) = corners[j];
+        let (c1, p1) = corners[(j + 1) % n];
+        // Joins add a triangle, edges add a quad.
+        let triangles: &[[Vec2d; 3]] = if c0 == c1 {
+            &[[c0, p0, p1]]
+        } else {
+            &[[c0, p0, p1], [c0, p1, c1]]
+        };
+        // Buffer is full.
+        if i + 3 * triangles.len() > BUFFER_SIZE {
+            // Send chunk and start over.
+            f(&vertices[0..i]);
+            i = 0;
+        }
+        for triangle in triangles {
+            for v in triangle {
+                vertices[i] = [tx(m, v[0], v[1]), ty(m, v[0], v[1])];
+                i += 1;
+            }
+        }
+    }
+
+    if i > 0 {
+        f(&vertices[0..i]);
+    }
+}
+
+/// Streams a feathered rim outside a polygon, with alpha fading to zero.
+///
+/// The rim is `width` wide and gives smooth edges without multisampling.
+/// The inner color is computed per vertex, e.g. to follow a gradient.
+/// Corners are mitered, or beveled when sharp.
+pub fn with_feather_tri_list_c<C, F>(
+    m: Affine2,
+    polygon: Polygon<'_>,
+    width: Radius,
+    inner_color: C,
+    mut f: F,
+) where
+    C: Fn(Vec2d) -> Color,
+    F: FnMut(&[[f32; 2]], &[[f32; 4]]),
+{
+    if polygon.len() < 3 || width <= 0.0 {
+        return;
+    }
+    let mut vertices: [[f32; 2]; BUFFER_SIZE] = [[0.0; 2]; BUFFER_SIZE];
+    let mut colors: [[f32; 4]; BUFFER_SIZE] = [[0.0; 4]; BUFFER_SIZE];
+    let mut k = 0;
+    let mut emit = |tri: [(Vec2d, bool); 3]| {
+        // Buffer is full.
+        if k + 3 > BUFFER_SIZE {
+            // Send chunk and start over.
+            f(&vertices[0..k], &colors[0..k]);
+            k = 0;
+        }
+        for (p, outer) in tri {
+            let c = inner_color(p);
+            vertices[k] = [tx(m, p[0], p[1]), ty(m, p[0], p[1])];
+            colors[k] = if outer { [c[0], c[1], c[2], 0.0] } else { c };
+            k += 1;
+        }
+    };
+    let mut first: Option<(Vec2d, Vec2d)> = None;
+    let mut last: Option<(Vec2d, Vec2d)> = None;
+    let mut quad = |(c0, o0): (Vec2d, Vec2d), (c1, o1): (Vec2d, Vec2d)| {
+        emit([(c0, false), (o0, true), (o1, true)]);
+        if c0 != c1 {
+            emit([(c0, false), (o1, true), (c1, false)]);
+        }
+    };
+    offset_polygon_with(polygon, width, Join::Miter(2.0), |corner, offset| {
+        if let Some(prev) = last {
+            quad(prev, (corner, offset));
+        } else {
+            first = Some((corner, offset));
+        }
+        last = Some((corner, offset));
+    });
+    if let (Some(first), Some(last)) = (first, last) {
+        quad(last, first);
+    }
+
+    if k > 0 {
+        f(&vertices[0..k], &colors[0..k]);
+    }
+}
+
+/// Streams a shape filled using the even-odd rule.
+///
+/// Polygons inside other polygons become holes, independent of winding order.
+/// See `with_fill_rule_tri_list` for details.
+pub fn with_even_odd_tri_list<F>(m: Affine2, shape: Polygons<'_>, f: F)
+where
+    F: FnMut(&[[f32; 2]]),
+{
+    with_fill_rule_tri_list(m, shape, FillRule::EvenOdd, f);
+}
+
+/// Streams a shape of polygons filled using a fill rule.
+///
+/// The shape is split into horizontal trapezoids between vertices
+/// and points where edges cross, so polygons may intersect themselves and each other.
+/// Finding crossings compares every pair of edges,
+/// so this is meant for shapes with moderate numbers of vertices.
+pub fn with_fill_rule_tri_list<F>(m: Affine2, shape: Polygons<'_>, rule: FillRule, mut f: F)
+where
+    F: FnMut(&[[f32; 2]]),
+{
+    // Collect non-horizontal edges pointing downwards, with winding direction.
+    let mut edges: Vec<(Vec2d, Vec2d, i32)> = vec![];
+    let mut ys: Vec<Scalar> = vec![];
+    for polygon in shape {
+        let n = polygon.len();
+        for i in 0..n {
+            let (p, q) = (polygon[i], polygon[(i + 1) % n]);
+            ys.push(p[1]);
+            if p[1] < q[1] {
+                edges.push((p, q, 1));
+            } else if p[1] > q[1] {
+                edges.push((q, p, -1));
+            }
+        }
+    }
+    // Split bands where edges cross, such that edges keep their order within a band.
+    for (i, &(a0, a1, _)) in edges.iter().enumerate() {
+        for &(b0, b1, _) in &edges[i + 1..] {
+            if a1[1] <= b0[1] || b1[1] <= a0[1] {
+                continue;
+            }
+            let (da, db) = (
+                [a1[0] - a0[0], a1[1] - a0[1]],
+                [b1[0] - b0[0], b1[1] - b0[1]],
+            );
+            let den = da[0] * db[1] - da[1] * db[0];
+            if den == 0.0 {
+                continue;
+            }
+            let w = [b0[0] - a0[0], b0[1] - a0[1]];
+            let s = (w[0] * db[1] - w[1] * db[0]) / den;
+            let t = (w[0] * da[1] - w[1] * da[0]) / den;
+            if s > 0.0 && s < 1.0 && t > 0.0 && t < 1.0 {
+                ys.push(a0[1] + s * da[1]);
+            }
+        }
+    }
+    ys.sort_by(|a, b| a.partial_cmp(b).unwrap_or(core::cmp::Ordering::Equal));
+    ys.dedup();
+
+    let x_at = |p: Vec2d, q: Vec2d, y: Scalar| p[0] + (y - p[1]) / (q[1] - p[1]) * (q[0] - p[0]);
+    let inside = |winding: i32| match rule {
+        FillRule::EvenOdd => winding % 2 != 0,
+        FillRule::NonZero => winding != 0,
+    };
+    let mut vertices: [[f32; 2]; BUFFER_SIZE] = [[0.0; 2]; BUFFER_SIZE];
+    let mut i = 0;
+    let mut crossings: Vec<(Scalar, Scalar, Scalar, i32)> = vec![];
+    for band in ys.windows(2) {
+        let (y0, y1) = (band[0], band[1]);
+        crossings.clear();
+        for &(p, q, dir) in &edges {
+            if p[1] <= y0 && q[1] >= y1 {
+                let (x0, x1) = (x_at(p, q, y0), x_at(p, q, y1));
+                crossings.push((x0 + x1, x0, x1, dir));
+            }
+        }
+        crossings.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(core::cmp::Ordering::Equal));
+        let mut winding = 0;
+        for pair in crossings.windows(2) {
+            let (_, a0, a1, dir) = pair[0];
+            let (_, b0, b1, _) = pair[1];
+            winding += dir;
+            if !inside(winding) {
+                continue;
+            }
+            // Buffer is full.
+            if i + 6 > BUFFER_SIZE {
+                // Send chunk and start over.
+                f(&vertices[0..i]);
+                i = 0;
+            }
+            for &(x, y) in &[(a0, y0), (b0, y0), (b1, y1), (a0, y0), (b1, y1), (a1, y1)] {
+                vertices[i] = [tx(m, x, y), ty(m, x, y)];
+                i += 1;
+            }
+        }
+    }
+
+    if i > 0 {
+        f(&vertices[0..i]);
+    }
+}
+
+/// Streams a Coons patch with colors interpolated between corners.
+///
+/// See `curve::coons_patch` for the order of edges.
+/// The color of corner `i` is used at the start of `edges[i]`.
+pub fn with_coons_patch_tri_list_c<F>(
+    m: Affine2,
+    edges: [CubicBezier; 4],
+    colors: [Color; 4],
+    resolution: Resolution,
+    mut f: F,
+) where
+    F: FnMut(&[[f32; 2]], &[[f32; 4]]),
+{
+    let n = resolution.max(1);
+    let point = |i: Resolution, j: Resolution| {
+        let (u, v) = (
+            Scalar::from(i) / Scalar::from(n),
+            Scalar::from(j) / Scalar::from(n),
+        );
+        let p = coons_patch(edges, u, v);
+        let (u, v) = (u as f32, v as f32);
+        let mut color = [0.0; 4];
+        for (k, c) in color.iter_mut().enumerate() {
+            *c = colors[0][k] * (1.0 - u) * (1.0 - v)
+                + colors[1][k] * u * (1.0 - v)
+                + colors[2][k] * u * v
+                + colors[3][k] * (1.0 - u) * v;
+        }
+        ([tx(m, p[0], p[1]), ty(m, p[0], p[1])], color)
+    };
+    let mut vertices: [[f32; 2]; BUFFER_SIZE] = [[0.0; 2]; BUFFER_SIZE];
+    let mut vertex_colors: [[f32; 4]; BUFFER_SIZE] = [[0.0; 4]; BUFFER_SIZE];
+    let mut k = 0;
+    // Reuse the previous row of points.
+    let mut row: Vec<([f32; 2], [f32; 4])> = (0..=n).map(|i| point(i, 0)).collect();
+    for j in 1..=n {
+        let next_row: Vec<([f32; 2], [f32; 4])> = (0..=n).map(|i| point(i, j)).collect();
+        for i in 0..n as usize {
+            let (a, b, c, d) = (row[i], row[i + 1], next_row[i + 1], next_row[i]);
+            // Buffer is full.
+            if k + 6 > BUFFER_SIZE {
+                // Send chunk and start over.
+                f(&vertices[0..k], &vertex_colors[0..k]);
+                k = 0;
+            }
+            for &(v, c) in &[a, b, c, a, c, d] {
+                vertices[k] = v;
+                vertex_colors[k] = c;
+                k += 1;
+            }
+        }
+        row = next_row;
+    }
+
+    if k > 0 {
+        f(&vertices[0..k], &vertex_colors[0..k]);
+    }
+}
+
 /// Creates triangle list vertices from rectangle.
 #[inline(always)]
 pub fn rect_tri_list_xy(m: Affine2, rect: Rectangle) -> [[f32; 2]; 6] {
@@ -17,6 +17,12 @@ pub type ColorComponent = f32;
 /// [x1, y1, x2, y2]
 pub type Line<T = Scalar> = [T; 4];
 
+/// [start, control, end]
+pub type QuadraticBezier<T = Scalar> = [Vec2d<T>; 3];
+
+/// [start, control1, control2, end]
+pub type CubicBezier<T = Scalar> = [Vec2d<T>; 4];
+
 /// [x, y, w, h]
 pub type SourceRectangle<T = Scalar> = [T; 4];
 
@@ -0,0 +1,238 @@
+//! A headless OpenGL window back-end.
+//!
+//! Creates an EGL context on a device display without any surface,
+//! so OpenGL rendering works without a display server,
+//! e.g. in continuous integration or on servers.
+//!
+//! Since there is no default frame buffer,
+//! drawing goes to an offscreen frame buffer object of the window size.
+
+use std::{error::Error, ffi::CString, time::Duration};
+
+use gl::types::GLuint;
+use glutin::{
+    api::egl::{context::PossiblyCurrentContext, device::Device, display::Display},
+    config::{ConfigSurfaceTypes, ConfigTemplateBuilder},
+    context::{ContextApi, ContextAttributesBuilder, PossiblyCurrentGlContext, Version},
+    display::GlDisplay,
+};
+
+use crate::{
+    glutin_window::graphics_api_from_settings,
+    input::Event,
+    window::{
+        AdvancedWindow, BuildFromWindowSettings, OpenGLWindow, Position, ProcAddress, Size, Window,
+        WindowSettings,
+    },
+};
+
+/// A window without user interface that renders to an offscreen buffer.
+///
+/// Like [`NoWindow`](../window/struct.NoWindow.html), it never emits input events.
+pub struct HeadlessWindow {
+    /// The OpenGL context.
+    pub ctx: PossiblyCurrentContext,
+    /// The EGL display.
+    pub display: Display,
+    // The offscreen frame buffer used as render target.
+    fbo: GLuint,
+    // Color and depth-stencil render buffers attached to the frame buffer.
+    render_buffers: [GLuint; 2],
+    title: String,
+    should_close: bool,
+    automatic_close: bool,
+    size: Size,
+}
+
+impl HeadlessWindow {
+    /// Creates a new headless window.
+    ///
+    /// Uses the first EGL device that supports creating a display.
+    pub fn new(settings: &WindowSettings) -> Result<Self, Box<dyn Error>> {
+        let display = Device::query_devices()?
+            .find_map(|device| unsafe { Display::with_device(&device, None).ok() })
+            .ok_or("No EGL device display found")?;
+
+        let template = ConfigTemplateBuilder::new()
+            .with_surface_type(ConfigSurfaceTypes::empty())
+            .build();
+        let config = unsafe { display.find_configs(template)? }
+            .next()
+            .ok_or("No EGL config found")?;
+
+        let api = graphics_api_from_settings(settings)?;
+        let context_attributes = ContextAttributesBuilder::new()
+            .with_context_api(ContextApi::OpenGl(Some(Version::new(
+                api.major as u8,
+                api.minor as u8,
+            ))))
+            .build(None);
+        let ctx = unsafe { display.create_context(&config, &context_attributes)? }
+            .make_current_surfaceless()?;
+
+        gl::load_with(|s| {
+            let s = CString::new(s).expect("CString::new failed");
+            display.get_proc_address(&s) as *const _
+        });
+
+        let size = settings.get_size();
+        let (w, h) = (size.width as i32, size.height as i32);
+        let mut fbo = 0;
+        let mut render_buffers = [0; 2];
+        unsafe {
+            gl::GenFramebuffers(1, &mut fbo);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, fbo);
+            gl::GenRenderbuffers(2, render_buffers.as_mut_ptr());
+            gl::BindRenderbuffer(gl::RENDERBUFFER, render_buffers[0]);
+            let format = if settings.get_srgb() {
+                gl::SRGB8_ALPHA8
+            } else {
+                gl::RGBA8
+            };
+            gl::RenderbufferStorage(gl::RENDERBUFFER, format, w, h);
+            gl::FramebufferRenderbuffer(
+                gl::FRAMEBUFFER,
+                gl::COLOR_ATTACHMENT0,
+                gl::RENDERBUFFER,
+                render_buffers[0],
+            );
+            gl::BindRenderbuffer(gl::RENDERBUFFER, render_buffers[1]);
+            gl::RenderbufferStorage(gl::RENDERBUFFER, gl::DEPTH24_STENCIL8, w, h);
+            gl::FramebufferRenderbuffer(
+                gl::FRAMEBUFFER,
+                gl::DEPTH_STENCIL_ATTACHMENT,
+                gl::RENDERBUFFER,
+                render_buffers[1],
+            );
+            if gl::CheckFramebufferStatus(gl::FRAMEBUFFER) != gl::FRAMEBUFFER_COMPLETE {
+                return Err("Offscreen frame buffer is incomplete".into());
+            }
+        }
+
+        Ok(HeadlessWindow {
+            ctx,
+            display,
+            fbo,
+            render_buffers,
+            title: settings.get_title(),
+            should_close: false,
+            automatic_close: settings.get_automatic_close(),
+            size,
+        })
+    }
+
+    /// Returns the offscreen frame buffer object that is rendered to.
+    pub fn get_fbo(&self) -> GLuint {
+        self.fbo
+    }
+}
+
+impl Drop for HeadlessWindow {
+    fn drop(&mut self) {
+        if self.ctx.make_current_surfaceless().is_ok() {
+            unsafe {
+                gl::DeleteFramebuffers(1, &self.fbo);
+                gl::DeleteRenderbuffers(2, self.render_buffers.as_ptr());
+            }
+        }
+    }
+}
+
+impl Window for HeadlessWindow {
+    fn set_should_close(&mut self, value: bool) {
+        self.should_close = value;
+    }
+
+    fn should_close(&self) -> bool {
+        self.should_close
+    }
+
+    fn size(&self) -> Size {
+        self.size
+    }
+
+    fn swap_buffers(&mut self) {
+        // There is no surface to present, so just finish rendering.
+        unsafe {
+            gl::Flush();
+        }
+    }
+
+    fn wait_event(&mut self) -> Event {
+        panic!("HeadlessWindow will never return an input event");
+    }
+
+    fn wait_event_timeout(&mut self, _timeout: Duration) -> Option<Event> {
+        None
+    }
+
+    fn poll_event(&mut self) -> Option<Event> {
+        None
+    }
+
+    fn draw_size(&self) -> Size {
+        self.size()
+    }
+}
+
+impl BuildFromWindowSettings for HeadlessWindow {
+    fn build_from_window_settings(settings: &WindowSettings) -> Result<Self, Box<dyn Error>> {
+        HeadlessWindow::new(settings)
+    }
+}
+
+impl AdvancedWindow for HeadlessWindow {
+    fn get_title(&self) -> String {
+        self.title.clone()
+    }
+
+    fn set_title(&mut self, value: String) {
+        self.title = value;
+    }
+
+    fn get_exit_on_esc(&self) -> bool {
+        false
+    }
+
+    fn set_exit_on_esc(&mut self, _value: bool) {}
+
+    fn get_automatic_close(&self) -> bool {
+        self.automatic_close
+    }
+
+    fn set_automatic_close(&mut self, value: bool) {
+        self.automatic_close = value;
+    }
+
+    fn set_capture_cursor(&mut self, _value: bool) {}
+
+    fn show(&mut self) {}
+
+    fn hide(&mut self) {}
+
+    fn get_position(&self) -> Option<Position> {
+        None
+    }
+
+    fn set_position<P: Into<Position>>(&mut self, _val: P) {}
+
+    fn set_size<S: Into<Size>>(&mut self, _val: S) {}
+}
+
+impl OpenGLWindow for HeadlessWindow {
+    fn get_proc_address(&mut self, proc_name: &str) -> ProcAddress {
+        let s = CString::new(proc_name).expect("CString::new failed");
+        self.display.get_proc_address(&s) as *const _
+    }
+
+    fn is_current(&self) -> bool {
+        self.ctx.is_current()
+    }
+
+    fn make_current(&mut self) {
+        let _ = self.ctx.make_current_surfaceless();
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+        }
+    }
+}
@@ -144,11 +144,14 @@ pub mod event_loop;
 pub mod glutin_window;
 pub mod graphics;
 pub mod graphics_api_version;
+#[cfg(all(any(windows, unix), not(any(target_os = "macos", target_os = "ios"))))]
+pub mod headless_window;
 pub mod input;
 pub mod opengl_graphics;
 pub mod read_color;
 pub mod shader_version;
 pub mod shaders;
+pub mod software_graphics;
 pub mod texture;
 pub mod viewport;
 pub mod window;
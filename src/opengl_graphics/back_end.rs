@@ -2,25 +2,32 @@
 
 // External crates.
 // Local crate.
-use std::ffi::CString;
+use std::{ffi::CString, path::Path};
 
 use gl::{
     self,
     types::{GLint, GLsizei, GLuint},
 };
+use image::RgbaImage;
 
 use super::{
+    deferred::{Command, Deferred},
     draw_state,
+    query::OcclusionQuery,
+    render_target::RenderTarget,
     shader_utils::{compile_shader, DynamicAttribute},
-    texture::Texture,
+    texture::{self, Texture},
 };
 use crate::{
     graphics::{
-        color::gamma_srgb_to_linear, Context, DrawState, Graphics, Viewport,
-        BACK_END_MAX_VERTEX_COUNT as BUFFER_SIZE,
+        capabilities::Capabilities,
+        color::ColorSpace,
+        material::{MaterialId, MaterialRegistry},
+        Context, DrawState, Graphics, Viewport, BACK_END_MAX_VERTEX_COUNT as BUFFER_SIZE,
     },
     shader_version::{OpenGL, Shaders, GLSL},
     shaders,
+    texture::ops,
 };
 
 // The number of chunks to fill up before rendering.
@@ -66,16 +73,7 @@ impl Colored {
         use shaders::colored;
         let src = |bytes| unsafe { ::std::str::from_utf8_unchecked(bytes) };
 
-        let mut vertex_shaders = Shaders::new();
-        if USE_WEBGL {
-            vertex_shaders
-                .set(GLSL::V1_20, src(colored::VERTEX_GLSL_120_WEBGL))
-                .set(GLSL::V1_50, src(colored::VERTEX_GLSL_150_CORE_WEBGL))
-        } else {
-            vertex_shaders
-                .set(GLSL::V1_20, src(colored::VERTEX_GLSL_120))
-                .set(GLSL::V1_50, src(colored::VERTEX_GLSL_150_CORE))
-        };
+        let vertex_shaders = VertexLayout::Colored.vertex_shaders();
 
         let mut fragment_shaders = Shaders::new();
         if USE_WEBGL {
@@ -196,16 +194,7 @@ impl Textured {
         use shaders::textured;
         let src = |bytes| unsafe { ::std::str::from_utf8_unchecked(bytes) };
 
-        let mut vertex_shaders = Shaders::new();
-        if USE_WEBGL {
-            vertex_shaders
-                .set(GLSL::V1_20, src(textured::VERTEX_GLSL_120_WEBGL))
-                .set(GLSL::V1_50, src(textured::VERTEX_GLSL_150_CORE_WEBGL))
-        } else {
-            vertex_shaders
-                .set(GLSL::V1_20, src(textured::VERTEX_GLSL_120))
-                .set(GLSL::V1_50, src(textured::VERTEX_GLSL_150_CORE))
-        };
+        let vertex_shaders = VertexLayout::Textured.vertex_shaders();
 
         let mut fragment_shaders = Shaders::new();
         if USE_WEBGL {
@@ -283,12 +272,12 @@ impl Textured {
         })
     }
 
-    fn flush(&mut self) {
+    fn flush(&mut self, texture_bindings: &mut TextureBindings) {
         let texture_id = self.last_texture_id;
         let color = self.last_color;
+        texture_bindings.bind(0, texture_id);
         unsafe {
             gl::BindVertexArray(self.vao);
-            gl::BindTexture(gl::TEXTURE_2D, texture_id);
             gl::Uniform4f(self.color, color[0], color[1], color[2], color[3]);
             // Render triangles whether they are facing
             // clockwise or counter clockwise.
@@ -339,16 +328,7 @@ impl TexturedColor {
         use shaders::textured_color;
         let src = |bytes| unsafe { ::std::str::from_utf8_unchecked(bytes) };
 
-        let mut vertex_shaders = Shaders::new();
-        if USE_WEBGL {
-            vertex_shaders
-                .set(GLSL::V1_20, src(textured_color::VERTEX_GLSL_120_WEBGL))
-                .set(GLSL::V1_50, src(textured_color::VERTEX_GLSL_150_CORE_WEBGL))
-        } else {
-            vertex_shaders
-                .set(GLSL::V1_20, src(textured_color::VERTEX_GLSL_120))
-                .set(GLSL::V1_50, src(textured_color::VERTEX_GLSL_150_CORE))
-        };
+        let vertex_shaders = VertexLayout::TexturedColor.vertex_shaders();
 
         let mut fragment_shaders = Shaders::new();
         if USE_WEBGL {
@@ -424,11 +404,11 @@ impl TexturedColor {
         })
     }
 
-    fn flush(&mut self) {
+    fn flush(&mut self, texture_bindings: &mut TextureBindings) {
         let texture_id = self.last_texture_id;
+        texture_bindings.bind(0, texture_id);
         unsafe {
             gl::BindVertexArray(self.vao);
-            gl::BindTexture(gl::TEXTURE_2D, texture_id);
             // Render triangles whether they are facing
             // clockwise or counter clockwise.
             gl::Disable(gl::CULL_FACE);
@@ -443,6 +423,48 @@ impl TexturedColor {
     }
 }
 
+// Keeps track of which textures are bound to which texture units,
+// to avoid rebinding textures shared by consecutive draws.
+#[derive(Default)]
+struct TextureBindings {
+    // Texture id per unit, or `None` when unknown.
+    units: Vec<Option<GLuint>>,
+    active_unit: Option<usize>,
+    // The number of deleted textures when bindings were last checked.
+    deleted_textures: usize,
+}
+
+impl TextureBindings {
+    // Binds texture to unit, unless it is already bound.
+    fn bind(&mut self, unit: usize, texture_id: GLuint) {
+        let deleted_textures = texture::deleted_textures();
+        if self.deleted_textures != deleted_textures {
+            self.deleted_textures = deleted_textures;
+            self.units.clear();
+        }
+        if self.units.len() <= unit {
+            self.units.resize(unit + 1, None);
+        }
+        if self.units[unit] == Some(texture_id) {
+            return;
+        }
+        unsafe {
+            if self.active_unit != Some(unit) {
+                gl::ActiveTexture(gl::TEXTURE0 + unit as GLuint);
+                self.active_unit = Some(unit);
+            }
+            gl::BindTexture(gl::TEXTURE_2D, texture_id);
+        }
+        self.units[unit] = Some(texture_id);
+    }
+
+    // Forgets all bindings, forcing textures to be bound on next use.
+    fn clear(&mut self) {
+        self.units.clear();
+        self.active_unit = None;
+    }
+}
+
 // Newlines and indents for cleaner panic message.
 const GL_FUNC_NOT_LOADED: &str = "
     OpenGL function pointers must be loaded before creating the `Gl` backend!
@@ -450,6 +472,81 @@ const GL_FUNC_NOT_LOADED: &str = "
     https://github.com/PistonDevelopers/opengl_graphics/issues/103
 ";
 
+/// The vertex layout of a built-in pipeline, used by custom fragment shaders.
+///
+/// The fragment shaders of every layout get the vertex color `v_Color`,
+/// and with textures also the texture coordinates `v_UV` and the sampler `s_texture`.
+/// They write the color to `o_Color` in GLSL 1.50, or `gl_FragColor` in GLSL 1.20.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum VertexLayout {
+    /// Colored vertices, see `Colored`.
+    Colored,
+    /// Textured vertices with a single color, see `Textured`.
+    Textured,
+    /// Textured vertices with individual colors, see `TexturedColor`.
+    TexturedColor,
+}
+
+impl VertexLayout {
+    // Returns the default vertex shaders.
+    fn vertex_shaders(self) -> Shaders<'static, GLSL, str> {
+        use shaders::{colored, textured, textured_color};
+        let src = |bytes| unsafe { ::std::str::from_utf8_unchecked(bytes) };
+
+        let (v120, v150, v120_webgl, v150_webgl) = match self {
+            VertexLayout::Colored => (
+                colored::VERTEX_GLSL_120,
+                colored::VERTEX_GLSL_150_CORE,
+                colored::VERTEX_GLSL_120_WEBGL,
+                colored::VERTEX_GLSL_150_CORE_WEBGL,
+            ),
+            VertexLayout::Textured => (
+                textured::VERTEX_GLSL_120,
+                textured::VERTEX_GLSL_150_CORE,
+                textured::VERTEX_GLSL_120_WEBGL,
+                textured::VERTEX_GLSL_150_CORE_WEBGL,
+            ),
+            VertexLayout::TexturedColor => (
+                textured_color::VERTEX_GLSL_120,
+                textured_color::VERTEX_GLSL_150_CORE,
+                textured_color::VERTEX_GLSL_120_WEBGL,
+                textured_color::VERTEX_GLSL_150_CORE_WEBGL,
+            ),
+        };
+        let mut vertex_shaders = Shaders::new();
+        if USE_WEBGL {
+            vertex_shaders
+                .set(GLSL::V1_20, src(v120_webgl))
+                .set(GLSL::V1_50, src(v150_webgl));
+        } else {
+            vertex_shaders
+                .set(GLSL::V1_20, src(v120))
+                .set(GLSL::V1_50, src(v150));
+        }
+        vertex_shaders
+    }
+}
+
+/// Shaders replacing the default ones while drawing with a material.
+///
+/// See `Graphics::with_material`.
+pub struct Material {
+    /// Describes how to render colored objects.
+    pub colored: Colored,
+    /// Describes how to render textured objects.
+    pub textured: Textured,
+    /// Describes how to render textured objects with individual vertex colors.
+    pub textured_color: TexturedColor,
+}
+
+// The programs of a registered material.
+// Layouts without a program use the default one.
+struct Programs {
+    colored: Option<Colored>,
+    textured: Option<Textured>,
+    textured_color: Option<TexturedColor>,
+}
+
 /// Contains OpenGL data.
 pub struct GlGraphics {
     colored: Colored,
@@ -461,6 +558,18 @@ pub struct GlGraphics {
     current_draw_state: Option<DrawState>,
     // Keeps track of the current viewport
     current_viewport: Option<Viewport>,
+    // Keeps track of textures bound to texture units.
+    texture_bindings: TextureBindings,
+    // Records draw calls when in deferred mode.
+    deferred: Option<Deferred>,
+    // How vertex colors are interpreted.
+    color_space: ColorSpace,
+    // Whether to merge textured draw calls with different colors.
+    auto_batch: bool,
+    // Registered materials with names.
+    materials: Vec<(String, Programs)>,
+    // The shader version for compiling custom programs.
+    glsl: GLSL,
 }
 
 impl GlGraphics {
@@ -481,6 +590,12 @@ impl GlGraphics {
             current_program: None,
             current_draw_state: None,
             current_viewport: None,
+            texture_bindings: Default::default(),
+            deferred: None,
+            color_space: ColorSpace::Srgb,
+            auto_batch: false,
+            materials: vec![],
+            glsl,
         }
     }
 
@@ -505,6 +620,105 @@ impl GlGraphics {
             current_program: None,
             current_draw_state: None,
             current_viewport: None,
+            texture_bindings: Default::default(),
+            deferred: None,
+            color_space: ColorSpace::Srgb,
+            auto_batch: false,
+            materials: vec![],
+            glsl: context_glsl(),
+        }
+    }
+
+    /// Sets the shader version for compiling custom programs.
+    ///
+    /// The default is the version of the OpenGL version passed to `new`,
+    /// or the version supported by the current context when created with `from_pieces`.
+    pub fn glsl(mut self, glsl: GLSL) -> Self {
+        self.glsl = glsl;
+        self
+    }
+
+    /// Compiles custom fragment shaders for a vertex layout,
+    /// and registers them as material with a name.
+    ///
+    /// Drawing with the other layouts uses the default shaders.
+    /// Draw with the program using `Graphics::with_material`.
+    /// For custom vertex shaders, register a `Material` instead.
+    pub fn register_program(
+        &mut self,
+        name: &str,
+        layout: VertexLayout,
+        fragment_shaders: &Shaders<GLSL, str>,
+    ) -> Result<MaterialId, String> {
+        let glsl = self.glsl;
+        let vertex_shaders = layout.vertex_shaders();
+        let mut programs = Programs {
+            colored: None,
+            textured: None,
+            textured_color: None,
+        };
+        match layout {
+            VertexLayout::Colored => {
+                programs.colored = Some(Colored::from_vs_fs(
+                    glsl,
+                    &vertex_shaders,
+                    fragment_shaders,
+                )?)
+            }
+            VertexLayout::Textured => {
+                programs.textured = Some(Textured::from_vs_fs(
+                    glsl,
+                    &vertex_shaders,
+                    fragment_shaders,
+                )?)
+            }
+            VertexLayout::TexturedColor => {
+                programs.textured_color = Some(TexturedColor::from_vs_fs(
+                    glsl,
+                    &vertex_shaders,
+                    fragment_shaders,
+                )?)
+            }
+        }
+        Ok(self.register_programs(name, programs))
+    }
+
+    /// Sets how colors are interpreted.
+    ///
+    /// The default is `ColorSpace::Srgb`.
+    /// Use `ColorSpace::Linear` or `ColorSpace::Passthrough`
+    /// when colors are already converted, e.g. by another renderer.
+    pub fn color_space(mut self, color_space: ColorSpace) -> Self {
+        self.color_space = color_space;
+        self
+    }
+
+    /// Sets how colors are interpreted.
+    ///
+    /// Pending draw calls are rendered first with the previous color space.
+    pub fn set_color_space(&mut self, color_space: ColorSpace) {
+        if self.current_viewport.is_some() {
+            self.flush();
+            self.color_space = color_space;
+            self.use_color_space();
+        } else {
+            self.color_space = color_space;
+        }
+    }
+
+    /// Returns how colors are interpreted.
+    pub fn get_color_space(&self) -> ColorSpace {
+        self.color_space
+    }
+
+    // Enables or disables sRGB frame buffer conversion for the color space.
+    fn use_color_space(&self) {
+        unsafe {
+            if self.color_space.is_srgb_framebuffer() {
+                gl::Enable(gl::FRAMEBUFFER_SRGB);
+            } else {
+                gl::Disable(gl::FRAMEBUFFER_SRGB);
+            }
         }
     }
 
@@ -551,6 +765,7 @@ impl GlGraphics {
                 draw_state::bind_scissor(draw_state.scissor, &self.current_viewport);
                 draw_state::bind_stencil(draw_state.stencil);
                 draw_state::bind_blend(draw_state.blend);
+                draw_state::bind_color_mask(draw_state.color_mask);
             }
             Some(ref old_state) => {
                 draw_state::bind_state(old_state, draw_state, &self.current_viewport);
@@ -566,6 +781,42 @@ impl GlGraphics {
         self.current_draw_state = None;
     }
 
+    /// Binds textures to texture units, in order, starting at unit 0.
+    ///
+    /// This is used by custom shaders sampling from multiple textures.
+    /// Textures that are already bound to the same unit are not rebound.
+    ///
+    /// Pending draw calls are flushed first,
+    /// since they might use textures bound to the same units.
+    pub fn bind_textures(&mut self, textures: &[&Texture]) {
+        self.flush_textured();
+        for (unit, texture) in textures.iter().enumerate() {
+            self.texture_bindings.bind(unit, texture.get_id());
+        }
+    }
+
+    /// Unsets the current texture bindings.
+    ///
+    /// This forces textures to be bound on next drawing call.
+    /// Call this after binding textures with OpenGL directly.
+    pub fn clear_texture_bindings(&mut self) {
+        self.texture_bindings.clear();
+    }
+
+    // Renders pending textured triangles.
+    fn flush_textured(&mut self) {
+        if self.textured.offset > 0 {
+            let program = self.textured.program;
+            self.use_program(program);
+            self.textured.flush(&mut self.texture_bindings);
+        }
+        if self.textured_color.offset > 0 {
+            let program = self.textured_color.program;
+            self.use_program(program);
+            self.textured_color.flush(&mut self.texture_bindings);
+        }
+    }
+
     /// Setup that should be called at the start of a frame's draw call.
     pub fn draw_begin(&mut self, viewport: Viewport) -> Context {
         let rect = viewport.rect;
@@ -573,28 +824,250 @@ impl GlGraphics {
         self.viewport(x, y, w, h);
         self.current_viewport = Some(viewport);
         self.clear_program();
-        unsafe {
-            gl::Enable(gl::FRAMEBUFFER_SRGB);
-        }
+        self.clear_texture_bindings();
+        self.use_color_space();
         Context::new_viewport(viewport)
     }
 
-    /// Finalize the frame's draw calls.
-    pub fn draw_end(&mut self) {
+    /// Sets whether to merge consecutive textured draw calls into one batch.
+    ///
+    /// Consecutive draw calls sharing draw state, color and texture
+    /// are always rendered with a single buffer upload and draw call.
+    /// With auto-batching, textured draw calls only need to share
+    /// draw state and texture, since color is stored per vertex,
+    /// e.g. when drawing many tinted sprites from the same texture.
+    ///
+    /// Pending draw calls are rendered first.
+    pub fn set_auto_batch(&mut self, value: bool) {
+        if self.auto_batch != value {
+            self.flush();
+            self.auto_batch = value;
+        }
+    }
+
+    /// Returns `true` if auto-batching is enabled.
+    pub fn is_auto_batch(&self) -> bool {
+        self.auto_batch
+    }
+
+    /// Sets whether to defer draw calls until the end of the frame.
+    ///
+    /// In deferred mode, draw calls are recorded and sorted by layer
+    /// before rendering, so that e.g. a UI layer can be drawn in between.
+    /// Draw calls keep their order within a layer, which preserves transparency,
+    /// and adjacent draw calls with the same texture and draw state are merged.
+    /// Clears and draw calls using the stencil buffer are never reordered.
+    /// Draw calls keep the material they were recorded with.
+    ///
+    /// Draw calls using textures that are dropped before flushing are skipped.
+    /// Turning deferred mode off flushes recorded draw calls.
+    pub fn set_deferred(&mut self, value: bool) {
+        if value {
+            if self.deferred.is_none() {
+                self.deferred = Some(Deferred::new());
+            }
+        } else {
+            self.flush_deferred();
+            self.deferred = None;
+        }
+    }
+
+    /// Returns `true` if draw calls are deferred.
+    pub fn is_deferred(&self) -> bool {
+        self.deferred.is_some()
+    }
+
+    /// Sets the layer of following draw calls in deferred mode.
+    ///
+    /// Lower layers are rendered first. Has no effect when not deferred.
+    pub fn set_layer(&mut self, layer: i32) {
+        if let Some(ref mut deferred) = self.deferred {
+            deferred.layer = layer;
+        }
+    }
+
+    /// Renders recorded draw calls in deferred mode.
+    ///
+    /// This is called automatically by `draw_end`.
+    pub fn flush_deferred(&mut self) {
+        let mut deferred = match self.deferred.take() {
+            Some(deferred) => deferred,
+            None => return,
+        };
+        let mut material = None;
+        for command in deferred.take_sorted() {
+            if let Command::Draw(ref draw) = command {
+                if draw.material != material {
+                    // Pending draw calls use the current shaders.
+                    self.flush_batches();
+                    if let Some(id) = material {
+                        self.swap_material(MaterialId(id));
+                    }
+                    if let Some(id) = draw.material {
+                        self.swap_material(MaterialId(id));
+                    }
+                    material = draw.material;
+                }
+            }
+            match command {
+                Command::ClearColor(color) => self.clear_color(color),
+                Command::ClearStencil(value) => self.clear_stencil(value),
+                Command::Draw(draw) => match draw.texture_id {
+                    None => self.tri_list_c(&draw.draw_state, |f| {
+                        for (vertices, colors) in draw
+                            .vertices
+                            .chunks(BUFFER_SIZE)
+                            .zip(draw.colors.chunks(BUFFER_SIZE))
+                        {
+                            f(vertices, colors)
+                        }
+                    }),
+                    Some(texture_id) => self.tri_list_uv_c_id(&draw.draw_state, texture_id, |f| {
+                        for ((vertices, uvs), colors) in draw
+                            .vertices
+                            .chunks(BUFFER_SIZE)
+                            .zip(draw.uvs.chunks(BUFFER_SIZE))
+                            .zip(draw.colors.chunks(BUFFER_SIZE))
+                        {
+                            f(vertices, uvs, colors)
+                        }
+                    }),
+                },
+            }
+        }
+        if let Some(id) = material {
+            self.flush_batches();
+            self.swap_material(MaterialId(id));
+        }
+        deferred.layer = 0;
+        self.deferred = Some(deferred);
+    }
+
+    /// Renders all pending draw calls.
+    ///
+    /// Draw calls are batched, so this is needed before
+    /// reading from the frame buffer or using OpenGL directly.
+    pub fn flush(&mut self) {
+        self.flush_deferred();
+        self.flush_batches();
+    }
+
+    // Renders pending batched triangles, without recorded draw calls.
+    fn flush_batches(&mut self) {
         if self.colored.offset > 0 {
             let program = self.colored.program;
             self.use_program(program);
             self.colored.flush();
         }
-        if self.textured.offset > 0 {
-            let program = self.textured.program;
-            self.use_program(program);
-            self.textured.flush();
+        self.flush_textured();
+    }
+
+    /// Starts counting samples passing the depth and stencil tests.
+    ///
+    /// Pending draw calls are rendered first, so only draw calls
+    /// between `begin_query` and `end_query` are counted.
+    pub fn begin_query(&mut self, query: &OcclusionQuery) {
+        self.flush();
+        unsafe {
+            gl::BeginQuery(gl::SAMPLES_PASSED, query.get_id());
         }
-        if self.textured_color.offset > 0 {
-            let program = self.textured_color.program;
-            self.use_program(program);
-            self.textured_color.flush();
+    }
+
+    /// Stops counting samples, see `begin_query`.
+    ///
+    /// The result is available later through `OcclusionQuery::result`.
+    pub fn end_query(&mut self) {
+        self.flush();
+        unsafe {
+            gl::EndQuery(gl::SAMPLES_PASSED);
+        }
+    }
+
+    /// Reads the color of a pixel, relative to the upper left corner of the viewport.
+    ///
+    /// Returns `None` when no viewport is set or the pixel is outside.
+    /// This stalls the pipeline until rendering is done,
+    /// so it should be used sparingly, e.g. for picking objects by color.
+    pub fn read_pixel(&mut self, x: u32, y: u32) -> Option<[u8; 4]> {
+        let rect = self.current_viewport?.rect;
+        if x >= rect[2] as u32 || y >= rect[3] as u32 {
+            return None;
+        }
+        self.flush();
+        let mut pixel = [0u8; 4];
+        unsafe {
+            gl::ReadPixels(
+                rect[0] + x as GLint,
+                rect[1] + rect[3] - 1 - y as GLint,
+                1,
+                1,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                pixel.as_mut_ptr() as *mut _,
+            );
+        }
+        Some(pixel)
+    }
+
+    /// Reads a rectangle `[x, y, w, h]` of pixels, relative to the upper left corner
+    /// of the viewport.
+    ///
+    /// Returns `None` when no viewport is set or the rectangle is not inside it.
+    /// Like `read_pixel`, this stalls the pipeline until rendering is done.
+    pub fn read_pixels(&mut self, rect: [u32; 4]) -> Option<RgbaImage> {
+        let vp = self.current_viewport?.rect;
+        let [x, y, w, h] = rect;
+        if u64::from(x) + u64::from(w) > vp[2] as u64 || u64::from(y) + u64::from(h) > vp[3] as u64
+        {
+            return None;
+        }
+        self.flush();
+        let mut memory = vec![0u8; w as usize * h as usize * 4];
+        unsafe {
+            gl::ReadPixels(
+                vp[0] + x as GLint,
+                vp[1] + vp[3] - (y + h) as GLint,
+                w as GLsizei,
+                h as GLsizei,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                memory.as_mut_ptr() as *mut _,
+            );
+        }
+        // OpenGL starts with the bottom row.
+        let memory = ops::flip_vertical(&memory, [w, h], 4);
+        RgbaImage::from_raw(w, h, memory)
+    }
+
+    /// Reads all pixels of the viewport, e.g. for screenshots or golden image tests.
+    pub fn screenshot(&mut self) -> Option<RgbaImage> {
+        let vp = self.current_viewport?.rect;
+        self.read_pixels([0, 0, vp[2] as u32, vp[3] as u32])
+    }
+
+    /// Saves all pixels of the viewport as PNG file.
+    ///
+    /// Encoding requires the `image` feature, which enables the PNG codec.
+    pub fn save_screenshot<P>(&mut self, path: P) -> Result<(), String>
+    where
+        P: AsRef<Path>,
+    {
+        let image = self.screenshot().ok_or("No viewport is set")?;
+        image
+            .save_with_format(path, image::ImageFormat::Png)
+            .map_err(|err| err.to_string())
+    }
+
+    /// Finalize the frame's draw calls.
+    pub fn draw_end(&mut self) {
+        self.flush();
+        // Restore scissor and color mask so they do not leak out of the frame.
+        if let Some(ref state) = self.current_draw_state {
+            if state.scissor.is_some() || state.color_mask != [true; 4] {
+                draw_state::bind_scissor(None, &self.current_viewport);
+                draw_state::bind_color_mask([true; 4]);
+                self.clear_draw_state();
+            }
         }
     }
 
@@ -612,6 +1085,103 @@ impl GlGraphics {
         res
     }
 
+    /// Draws to a render target instead of the current frame buffer.
+    ///
+    /// The context uses the size of the render target in pixels.
+    /// Pending draw calls are rendered first,
+    /// and the previous frame buffer and viewport are restored afterwards,
+    /// such that this can be called in the middle of a frame.
+    pub fn draw_to<F, U>(&mut self, target: &RenderTarget, f: F) -> U
+    where
+        F: FnOnce(Context, &mut Self) -> U,
+    {
+        use crate::texture::ImageSize;
+
+        self.flush();
+        let mut previous_fbo: GLint = 0;
+        let mut previous_rect: [GLint; 4] = [0; 4];
+        unsafe {
+            gl::GetIntegerv(gl::FRAMEBUFFER_BINDING, &mut previous_fbo);
+            gl::GetIntegerv(gl::VIEWPORT, previous_rect.as_mut_ptr());
+            gl::BindFramebuffer(gl::FRAMEBUFFER, target.get_fbo());
+        }
+        let previous_viewport = self.current_viewport;
+        let (w, h) = target.get_size();
+        let res = self.draw(
+            Viewport {
+                rect: [0, 0, w as i32, h as i32],
+                draw_size: [w, h],
+                window_size: [w as f64, h as f64],
+            },
+            f,
+        );
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, previous_fbo as GLuint);
+        }
+        let [x, y, w, h] = previous_rect;
+        self.viewport(x, y, w, h);
+        self.current_viewport = previous_viewport;
+        res
+    }
+
+    // Renders textured triangles with colors, using a texture id.
+    fn tri_list_uv_c_id<F>(&mut self, draw_state: &DrawState, texture_id: GLuint, mut f: F)
+    where
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]], &[[f32; 2]], &[[f32; 4]])),
+    {
+        if self.colored.offset > 0 {
+            let program = self.colored.program;
+            self.use_program(program);
+            self.colored.flush();
+        }
+        if self.textured.offset > 0 {
+            let program = self.textured.program;
+            self.use_program(program);
+            self.textured.flush(&mut self.texture_bindings);
+        }
+
+        // Flush when draw state changes.
+        if self.current_draw_state.is_none()
+            || self.current_draw_state.as_ref().unwrap() != draw_state
+            || self.textured_color.last_texture_id != texture_id
+        {
+            let program = self.textured_color.program;
+            if self.current_draw_state.is_none() {
+                self.use_draw_state(&Default::default());
+            }
+            if self.textured_color.offset > 0 {
+                self.use_program(program);
+                self.textured_color.flush(&mut self.texture_bindings);
+            }
+            self.use_draw_state(draw_state);
+        }
+
+        self.textured_color.last_texture_id = texture_id;
+        let color_space = self.color_space;
+        f(
+            &mut |vertices: &[[f32; 2]], texture_coords: &[[f32; 2]], colors: &[[f32; 4]]| {
+                let items = vertices.len();
+
+                // Render if there is not enough room.
+                if self.textured_color.offset + items > BUFFER_SIZE * CHUNKS {
+                    let shader_program = self.textured_color.program;
+                    self.use_program(shader_program);
+                    self.textured_color.flush(&mut self.texture_bindings);
+                }
+
+                let shader = &mut self.textured_color;
+                let color_buffer =
+                    &mut shader.color_buffer[shader.offset..shader.offset + colors.len()];
+                color_buffer.copy_from_slice(colors);
+                color_space.to_shader_batch(color_buffer);
+                shader.pos_buffer[shader.offset..shader.offset + items].copy_from_slice(vertices);
+                shader.uv_buffer[shader.offset..shader.offset + items]
+                    .copy_from_slice(texture_coords);
+                shader.offset += items;
+            },
+        );
+    }
+
     /// Assume all textures has alpha channel for now.
     pub fn has_texture_alpha(&self, _texture: &Texture) -> bool {
         true
@@ -622,7 +1192,19 @@ impl Graphics for GlGraphics {
     type Texture = Texture;
 
     fn clear_color(&mut self, color: [f32; 4]) {
-        let color = gamma_srgb_to_linear(color);
+        if let Some(ref mut deferred) = self.deferred {
+            deferred.barrier(Command::ClearColor(color));
+            return;
+        }
+        // Clearing is affected by the color mask.
+        if let Some(state) = self.current_draw_state {
+            if state.color_mask != [true; 4] {
+                self.flush();
+                draw_state::bind_color_mask([true; 4]);
+                self.clear_draw_state();
+            }
+        }
+        let color = self.color_space.to_shader(color);
         unsafe {
             let (r, g, b, a) = (color[0], color[1], color[2], color[3]);
             gl::ClearColor(r, g, b, a);
@@ -631,6 +1213,10 @@ impl Graphics for GlGraphics {
     }
 
     fn clear_stencil(&mut self, value: u8) {
+        if let Some(ref mut deferred) = self.deferred {
+            deferred.barrier(Command::ClearStencil(value));
+            return;
+        }
         unsafe {
             gl::ClearStencil(value as i32);
             gl::Clear(gl::STENCIL_BUFFER_BIT);
@@ -641,17 +1227,27 @@ impl Graphics for GlGraphics {
     where
         F: FnMut(&mut dyn FnMut(&[[f32; 2]])),
     {
-        let color = gamma_srgb_to_linear(*color);
+        if let Some(ref mut deferred) = self.deferred {
+            let color = *color;
+            deferred.record(draw_state, None, |draw| {
+                f(&mut |vertices| {
+                    draw.vertices.extend_from_slice(vertices);
+                    draw.colors.extend(vertices.iter().map(|_| color));
+                })
+            });
+            return;
+        }
+        let color = self.color_space.to_shader(*color);
 
         if self.textured.offset > 0 {
             let program = self.textured.program;
             self.use_program(program);
-            self.textured.flush();
+            self.textured.flush(&mut self.texture_bindings);
         }
         if self.textured_color.offset > 0 {
             let program = self.textured_color.program;
             self.use_program(program);
-            self.textured_color.flush();
+            self.textured_color.flush(&mut self.texture_bindings);
         }
 
         // Flush when draw state changes.
@@ -692,15 +1288,24 @@ impl Graphics for GlGraphics {
     where
         F: FnMut(&mut dyn FnMut(&[[f32; 2]], &[[f32; 4]])),
     {
+        if let Some(ref mut deferred) = self.deferred {
+            deferred.record(draw_state, None, |draw| {
+                f(&mut |vertices, colors| {
+                    draw.vertices.extend_from_slice(vertices);
+                    draw.colors.extend_from_slice(colors);
+                })
+            });
+            return;
+        }
         if self.textured.offset > 0 {
             let program = self.textured.program;
             self.use_program(program);
-            self.textured.flush();
+            self.textured.flush(&mut self.texture_bindings);
         }
         if self.textured_color.offset > 0 {
             let program = self.textured_color.program;
             self.use_program(program);
-            self.textured_color.flush();
+            self.textured_color.flush(&mut self.texture_bindings);
         }
 
         // Flush when draw state changes.
@@ -718,6 +1323,7 @@ impl Graphics for GlGraphics {
             self.use_draw_state(draw_state);
         }
 
+        let color_space = self.color_space;
         f(&mut |vertices: &[[f32; 2]], colors: &[[f32; 4]]| {
             let items = vertices.len();
 
@@ -729,9 +1335,10 @@ impl Graphics for GlGraphics {
             }
 
             let shader = &mut self.colored;
-            for (i, color) in colors.iter().enumerate() {
-                shader.color_buffer[shader.offset + i] = gamma_srgb_to_linear(*color);
-            }
+            let color_buffer =
+                &mut shader.color_buffer[shader.offset..shader.offset + colors.len()];
+            color_buffer.copy_from_slice(colors);
+            color_space.to_shader_batch(color_buffer);
             shader.pos_buffer[shader.offset..shader.offset + items].copy_from_slice(vertices);
             shader.offset += items;
         });
@@ -746,7 +1353,30 @@ impl Graphics for GlGraphics {
     ) where
         F: FnMut(&mut dyn FnMut(&[[f32; 2]], &[[f32; 2]])),
     {
-        let color = gamma_srgb_to_linear(*color);
+        if let Some(ref mut deferred) = self.deferred {
+            let color = *color;
+            deferred.record(draw_state, Some(texture.get_id()), |draw| {
+                f(&mut |vertices, uvs| {
+                    draw.vertices.extend_from_slice(vertices);
+                    draw.uvs.extend_from_slice(uvs);
+                    draw.colors.extend(vertices.iter().map(|_| color));
+                })
+            });
+            return;
+        }
+        if self.auto_batch {
+            let colors = [*color; BUFFER_SIZE];
+            self.tri_list_uv_c_id(draw_state, texture.get_id(), |g| {
+                f(&mut |vertices, uvs| {
+                    for (vertices, uvs) in vertices.chunks(BUFFER_SIZE).zip(uvs.chunks(BUFFER_SIZE))
+                    {
+                        g(vertices, uvs, &colors[..vertices.len()]);
+                    }
+                })
+            });
+            return;
+        }
+        let color = self.color_space.to_shader(*color);
 
         if self.colored.offset > 0 {
             let program = self.colored.program;
@@ -756,7 +1386,7 @@ impl Graphics for GlGraphics {
         if self.textured_color.offset > 0 {
             let program = self.textured_color.program;
             self.use_program(program);
-            self.textured_color.flush();
+            self.textured_color.flush(&mut self.texture_bindings);
         }
 
         // Flush when draw state changes.
@@ -771,7 +1401,7 @@ impl Graphics for GlGraphics {
             }
             if self.textured.offset > 0 {
                 self.use_program(program);
-                self.textured.flush();
+                self.textured.flush(&mut self.texture_bindings);
             }
             self.use_draw_state(draw_state);
         }
@@ -785,7 +1415,7 @@ impl Graphics for GlGraphics {
             if self.textured.offset + items > BUFFER_SIZE * CHUNKS {
                 let shader_program = self.textured.program;
                 self.use_program(shader_program);
-                self.textured.flush();
+                self.textured.flush(&mut self.texture_bindings);
             }
 
             let shader = &mut self.textured;
@@ -799,56 +1429,156 @@ impl Graphics for GlGraphics {
     where
         F: FnMut(&mut dyn FnMut(&[[f32; 2]], &[[f32; 2]], &[[f32; 4]])),
     {
-        if self.colored.offset > 0 {
-            let program = self.colored.program;
-            self.use_program(program);
-            self.colored.flush();
+        if let Some(ref mut deferred) = self.deferred {
+            deferred.record(draw_state, Some(texture.get_id()), |draw| {
+                f(&mut |vertices, uvs, colors| {
+                    draw.vertices.extend_from_slice(vertices);
+                    draw.uvs.extend_from_slice(uvs);
+                    draw.colors.extend_from_slice(colors);
+                })
+            });
+            return;
         }
-        if self.textured.offset > 0 {
-            let program = self.textured.program;
-            self.use_program(program);
-            self.textured.flush();
+        self.tri_list_uv_c_id(draw_state, texture.get_id(), f);
+    }
+
+    fn current_viewport(&self) -> Option<Viewport> {
+        self.current_viewport
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        let mut max_texture_size: GLint = 0;
+        unsafe {
+            gl::GetIntegerv(gl::MAX_TEXTURE_SIZE, &mut max_texture_size);
+        }
+        Capabilities {
+            max_texture_size: if max_texture_size > 0 {
+                Some(max_texture_size as u32)
+            } else {
+                None
+            },
+            stencil_clip: true,
+            instancing: false,
+            srgb: self.color_space.is_srgb_framebuffer(),
+            render_to_texture: true,
+            materials: true,
         }
+    }
 
-        // Flush when draw state changes.
-        if self.current_draw_state.is_none()
-            || self.current_draw_state.as_ref().unwrap() != draw_state
-            || self.textured_color.last_texture_id != texture.get_id()
-        {
-            let program = self.textured_color.program;
-            if self.current_draw_state.is_none() {
-                self.use_draw_state(&Default::default());
-            }
-            if self.textured_color.offset > 0 {
-                self.use_program(program);
-                self.textured_color.flush();
+    fn with_material<F, U>(&mut self, material: MaterialId, f: F) -> U
+    where
+        F: FnOnce(&mut Self) -> U,
+    {
+        if material.0 >= self.materials.len() {
+            return f(self);
+        }
+        // Recorded draw calls are drawn with their material when flushed.
+        if let Some(ref mut deferred) = self.deferred {
+            let prev = deferred.material.replace(material.0);
+            let res = f(self);
+            if let Some(ref mut deferred) = self.deferred {
+                deferred.material = prev;
             }
-            self.use_draw_state(draw_state);
+            return res;
         }
+        // Pending draw calls use the current shaders.
+        self.flush_batches();
+        self.swap_material(material);
+        let res = f(self);
+        self.flush_batches();
+        self.swap_material(material);
+        res
+    }
+}
 
-        self.textured_color.last_texture_id = texture.get_id();
-        f(
-            &mut |vertices: &[[f32; 2]], texture_coords: &[[f32; 2]], colors: &[[f32; 4]]| {
-                let items = vertices.len();
+impl GlGraphics {
+    // Registers programs with a name, replacing those of an existing material.
+    fn register_programs(&mut self, name: &str, programs: Programs) -> MaterialId {
+        if let Some(id) = self.find_material(name) {
+            self.materials[id.0].1 = programs;
+            return id;
+        }
+        self.materials.push((name.into(), programs));
+        MaterialId(self.materials.len() - 1)
+    }
 
-                // Render if there is not enough room.
-                if self.textured_color.offset + items > BUFFER_SIZE * CHUNKS {
-                    let shader_program = self.textured_color.program;
-                    self.use_program(shader_program);
-                    self.textured_color.flush();
-                }
+    // Swaps the current shaders with those of a material.
+    fn swap_material(&mut self, material: MaterialId) {
+        let m = &mut self.materials[material.0].1;
+        if let Some(colored) = &mut m.colored {
+            std::mem::swap(&mut self.colored, colored);
+        }
+        if let Some(textured) = &mut m.textured {
+            std::mem::swap(&mut self.textured, textured);
+        }
+        if let Some(textured_color) = &mut m.textured_color {
+            std::mem::swap(&mut self.textured_color, textured_color);
+        }
+        self.clear_program();
+        self.clear_draw_state();
+    }
+}
 
-                let shader = &mut self.textured_color;
-                for (i, color) in colors.iter().enumerate() {
-                    shader.color_buffer[shader.offset + i] = gamma_srgb_to_linear(*color);
-                }
-                shader.pos_buffer[shader.offset..shader.offset + items].copy_from_slice(vertices);
-                shader.uv_buffer[shader.offset..shader.offset + items]
-                    .copy_from_slice(texture_coords);
-                shader.offset += items;
+impl MaterialRegistry for GlGraphics {
+    type Material = Material;
+
+    fn register_material(&mut self, name: &str, material: Material) -> MaterialId {
+        self.register_programs(
+            name,
+            Programs {
+                colored: Some(material.colored),
+                textured: Some(material.textured),
+                textured_color: Some(material.textured_color),
             },
-        );
+        )
+    }
+
+    fn find_material(&self, name: &str) -> Option<MaterialId> {
+        self.materials
+            .iter()
+            .position(|(n, _)| n == name)
+            .map(MaterialId)
+    }
+}
+
+// Returns the highest shader version supported by the current context.
+//
+// Falls back to GLSL 1.20 when the version can not be read.
+fn context_glsl() -> GLSL {
+    let version = unsafe { gl::GetString(gl::SHADING_LANGUAGE_VERSION) };
+    if version.is_null() {
+        return GLSL::V1_20;
+    }
+    let version = unsafe { std::ffi::CStr::from_ptr(version as *const _) };
+    let version = version.to_string_lossy();
+    let es = version.starts_with("OpenGL ES");
+    let mut numbers = version
+        .split(|c: char| !c.is_ascii_digit())
+        .filter(|n| !n.is_empty())
+        .map(|n| n.parse::<u32>().unwrap_or(0));
+    let major = numbers.next().unwrap_or(0);
+    let minor = numbers.next().unwrap_or(0);
+    if es {
+        // GLSL ES 3.00 corresponds to GLSL 1.50 shaders.
+        return if major >= 3 { GLSL::V1_50 } else { GLSL::V1_20 };
     }
+    [
+        (GLSL::V4_50, (4, 50)),
+        (GLSL::V4_40, (4, 40)),
+        (GLSL::V4_30, (4, 30)),
+        (GLSL::V4_20, (4, 20)),
+        (GLSL::V4_10, (4, 10)),
+        (GLSL::V4_00, (4, 0)),
+        (GLSL::V3_30, (3, 30)),
+        (GLSL::V1_50, (1, 50)),
+        (GLSL::V1_40, (1, 40)),
+        (GLSL::V1_30, (1, 30)),
+        (GLSL::V1_20, (1, 20)),
+    ]
+    .iter()
+    .find(|&&(_, v)| (major, minor) >= v)
+    .map(|&(glsl, _)| glsl)
+    .unwrap_or(GLSL::V1_10)
 }
 
 // Might not fail if previous tests loaded functions.
@@ -0,0 +1,240 @@
+//! Deferred submission of draw calls, sorted by layer.
+
+use gl::types::GLuint;
+
+use super::texture;
+use crate::graphics::{types::Color, DrawState};
+
+/// A recorded draw call.
+pub struct Draw {
+    /// The layer set when the draw call was recorded.
+    pub layer: i32,
+    /// The draw state.
+    pub draw_state: DrawState,
+    /// The material set when the draw call was recorded.
+    pub material: Option<usize>,
+    /// The texture, or `None` for colored triangles.
+    pub texture_id: Option<GLuint>,
+    /// Vertex positions.
+    pub vertices: Vec<[f32; 2]>,
+    /// Texture coordinates, empty for colored triangles.
+    pub uvs: Vec<[f32; 2]>,
+    /// Vertex colors, in sRGB color space.
+    pub colors: Vec<Color>,
+    // The number of logged deleted texture ids when recorded.
+    deleted_ids: usize,
+}
+
+impl Draw {
+    // Returns `true` if the draw call can be appended to this one.
+    fn is_compatible(&self, other: &Draw) -> bool {
+        self.layer == other.layer
+            && self.material == other.material
+            && self.texture_id == other.texture_id
+            && self.draw_state == other.draw_state
+    }
+}
+
+/// A recorded command.
+pub enum Command {
+    /// Draws triangles.
+    Draw(Draw),
+    /// Clears color buffer.
+    ClearColor(Color),
+    /// Clears stencil buffer.
+    ClearStencil(u8),
+}
+
+/// Records commands for sorting.
+///
+/// Commands are sorted in segments separated by barriers.
+/// Clears and draw calls using the stencil buffer are barriers,
+/// since they depend on order across layers.
+///
+/// Draw calls using textures deleted before flushing are skipped,
+/// since their ids might be reused by other textures.
+pub struct Deferred {
+    /// The current layer.
+    pub layer: i32,
+    /// The current material.
+    pub material: Option<usize>,
+    // Commands with the segment they belong to.
+    commands: Vec<(usize, Command)>,
+    segment: usize,
+    // Whether deleted texture ids are watched.
+    watching: bool,
+}
+
+impl Deferred {
+    /// Creates a new empty command queue.
+    pub fn new() -> Deferred {
+        Deferred {
+            layer: 0,
+            material: None,
+            commands: vec![],
+            segment: 0,
+            watching: false,
+        }
+    }
+
+    /// Records a draw call, which is filled in by closure.
+    pub fn record<F>(&mut self, draw_state: &DrawState, texture_id: Option<GLuint>, f: F)
+    where
+        F: FnOnce(&mut Draw),
+    {
+        if texture_id.is_some() && !self.watching {
+            texture::watch_deleted_ids();
+            self.watching = true;
+        }
+        let mut draw = Draw {
+            layer: self.layer,
+            draw_state: *draw_state,
+            material: self.material,
+            texture_id,
+            vertices: vec![],
+            uvs: vec![],
+            colors: vec![],
+            deleted_ids: texture::deleted_ids_len(),
+        };
+        f(&mut draw);
+        if draw.vertices.is_empty() {
+            return;
+        }
+        if draw_state.stencil.is_some() {
+            self.barrier(Command::Draw(draw));
+        } else {
+            self.commands.push((self.segment, Command::Draw(draw)));
+        }
+    }
+
+    /// Records a command that is not reordered.
+    pub fn barrier(&mut self, command: Command) {
+        self.segment += 1;
+        self.commands.push((self.segment, command));
+        self.segment += 1;
+    }
+
+    /// Takes the recorded commands, sorted by segment and layer.
+    ///
+    /// The sort is stable, so draw calls keep their order within a layer,
+    /// which preserves blending of overlapping shapes.
+    /// Adjacent draw calls with the same texture, draw state and material are merged.
+    pub fn take_sorted(&mut self) -> Vec<Command> {
+        let mut commands = std::mem::take(&mut self.commands);
+        commands.sort_by(|(sa, a), (sb, b)| {
+            sa.cmp(sb).then_with(|| match (a, b) {
+                (Command::Draw(a), Command::Draw(b)) => a.layer.cmp(&b.layer),
+                _ => std::cmp::Ordering::Equal,
+            })
+        });
+        let mut res: Vec<Command> = Vec::with_capacity(commands.len());
+        for (_, command) in commands {
+            if let Command::Draw(ref draw) = command {
+                if let Some(id) = draw.texture_id {
+                    if texture::is_deleted_since(id, draw.deleted_ids) {
+                        continue;
+                    }
+                }
+            }
+            match (res.last_mut(), command) {
+                (Some(Command::Draw(last)), Command::Draw(draw))
+                    if draw.draw_state.stencil.is_none() && last.is_compatible(&draw) =>
+                {
+                    last.vertices.extend_from_slice(&draw.vertices);
+                    last.uvs.extend_from_slice(&draw.uvs);
+                    last.colors.extend_from_slice(&draw.colors);
+                }
+                (_, command) => res.push(command),
+            }
+        }
+        if self.watching {
+            texture::unwatch_deleted_ids();
+            self.watching = false;
+        }
+        self.segment = 0;
+        res
+    }
+}
+
+impl Drop for Deferred {
+    fn drop(&mut self) {
+        if self.watching {
+            texture::unwatch_deleted_ids();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sort() {
+        let mut deferred = Deferred::new();
+        let draw_state = DrawState::default();
+        let record = |deferred: &mut Deferred, layer, texture_id| {
+            deferred.layer = layer;
+            deferred.record(&draw_state, texture_id, |draw| {
+                draw.vertices.push([0.0; 2]);
+                draw.colors.push([1.0; 4]);
+            });
+        };
+        record(&mut deferred, 1, Some(2));
+        record(&mut deferred, 0, Some(2));
+        record(&mut deferred, 1, Some(1));
+        record(&mut deferred, 1, Some(1));
+        record(&mut deferred, 1, Some(2));
+        deferred.material = Some(0);
+        record(&mut deferred, 1, Some(2));
+        deferred.material = None;
+        deferred.barrier(Command::ClearStencil(0));
+        record(&mut deferred, 0, None);
+        let keys: Vec<_> = deferred
+            .take_sorted()
+            .iter()
+            .map(|command| match command {
+                Command::Draw(draw) => Some((draw.layer, draw.texture_id, draw.vertices.len())),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            keys,
+            [
+                Some((0, Some(2), 1)),
+                Some((1, Some(2), 1)),
+                Some((1, Some(1), 2)),
+                Some((1, Some(2), 1)),
+                Some((1, Some(2), 1)),
+                None,
+                Some((0, None, 1))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_deleted_texture() {
+        let mut deferred = Deferred::new();
+        let draw_state = DrawState::default();
+        let record = |deferred: &mut Deferred, texture_id| {
+            deferred.record(&draw_state, Some(texture_id), |draw| {
+                draw.vertices.push([0.0; 2]);
+                draw.colors.push([1.0; 4]);
+            });
+        };
+        record(&mut deferred, 1);
+        record(&mut deferred, 2);
+        // The id of a deleted texture is reused by a new texture.
+        texture::log_deleted_id(1);
+        record(&mut deferred, 1);
+        let ids: Vec<_> = deferred
+            .take_sorted()
+            .iter()
+            .map(|command| match command {
+                Command::Draw(draw) => draw.texture_id,
+                _ => None,
+            })
+            .collect();
+        assert_eq!(ids, [Some(2), Some(1)]);
+        assert_eq!(texture::deleted_ids_len(), 0);
+    }
+}
@@ -12,6 +12,16 @@ pub fn bind_state(old_state: &DrawState, new_state: &DrawState, viewport: &Optio
     if old_state.blend != new_state.blend {
         bind_blend(new_state.blend);
     }
+    if old_state.color_mask != new_state.color_mask {
+        bind_color_mask(new_state.color_mask);
+    }
+}
+
+pub fn bind_color_mask(mask: [bool; 4]) {
+    let b = |v: bool| if v { gl::TRUE } else { gl::FALSE };
+    unsafe {
+        gl::ColorMask(b(mask[0]), b(mask[1]), b(mask[2]), b(mask[3]));
+    }
 }
 
 pub fn bind_scissor(rect: Option<[u32; 4]>, viewport: &Option<Viewport>) {
@@ -23,9 +33,9 @@ pub fn bind_scissor(rect: Option<[u32; 4]>, viewport: &Option<Viewport>) {
             // To fix this we need to know height of the viewport
             // so that we can transform y as top measured from top (yt)
             // into y as bottom measured from bottom (yb)
-            // using yb = viewport_height - (yt + rect_height)
+            // using yb = viewport_y + viewport_height - (yt + rect_height)
             let yb = if let Some(vp) = viewport {
-                vp.rect[3] - (r[1] + r[3]) as i32
+                vp.rect[1] + vp.rect[3] - (r[1] + r[3]) as i32
             } else {
                 r[1] as i32
             };
@@ -54,6 +64,11 @@ pub fn bind_stencil(stencil: Option<Stencil>) {
                         gl::StencilMask(255);
                         gl::StencilOp(gl::INCR, gl::KEEP, gl::KEEP);
                     }
+                    Stencil::Decrement => {
+                        gl::StencilFunc(gl::NEVER, 0 as gl::types::GLint, 255);
+                        gl::StencilMask(255);
+                        gl::StencilOp(gl::DECR, gl::KEEP, gl::KEEP);
+                    }
                     Stencil::Clip(val) => {
                         gl::StencilFunc(gl::NEVER, val as gl::types::GLint, 255);
                         gl::StencilMask(255);
@@ -140,6 +155,40 @@ pub fn bind_blend(blend: Option<Blend>) {
                         gl::BlendEquationSeparate(gl::FUNC_SUBTRACT, gl::FUNC_ADD);
                         gl::BlendFuncSeparate(gl::CONSTANT_COLOR, gl::SRC_COLOR, gl::ZERO, gl::ONE);
                     }
+                    Blend::Screen => {
+                        gl::BlendEquationSeparate(gl::FUNC_ADD, gl::FUNC_ADD);
+                        gl::BlendFuncSeparate(
+                            gl::ONE,
+                            gl::ONE_MINUS_SRC_COLOR,
+                            gl::ONE,
+                            gl::ONE_MINUS_SRC_ALPHA,
+                        );
+                    }
+                    Blend::Subtract => {
+                        gl::BlendEquationSeparate(gl::FUNC_REVERSE_SUBTRACT, gl::FUNC_ADD);
+                        gl::BlendFuncSeparate(gl::SRC_ALPHA, gl::ONE, gl::ZERO, gl::ONE);
+                    }
+                    Blend::Darken => {
+                        gl::BlendEquationSeparate(gl::MIN, gl::FUNC_ADD);
+                        gl::BlendFuncSeparate(gl::ONE, gl::ONE, gl::ONE, gl::ONE_MINUS_SRC_ALPHA);
+                    }
+                    Blend::Lighten => {
+                        gl::BlendEquationSeparate(gl::MAX, gl::FUNC_ADD);
+                        gl::BlendFuncSeparate(gl::ONE, gl::ONE, gl::ONE, gl::ONE_MINUS_SRC_ALPHA);
+                    }
+                    Blend::Mask => {
+                        gl::BlendEquationSeparate(gl::FUNC_ADD, gl::FUNC_ADD);
+                        gl::BlendFuncSeparate(gl::ZERO, gl::SRC_ALPHA, gl::ZERO, gl::SRC_ALPHA);
+                    }
+                    Blend::PremultipliedAlpha => {
+                        gl::BlendEquationSeparate(gl::FUNC_ADD, gl::FUNC_ADD);
+                        gl::BlendFuncSeparate(
+                            gl::ONE,
+                            gl::ONE_MINUS_SRC_ALPHA,
+                            gl::ONE,
+                            gl::ONE_MINUS_SRC_ALPHA,
+                        );
+                    }
                 }
             }
             None => gl::Disable(gl::BLEND),
@@ -0,0 +1,145 @@
+//! Dynamic resolution scaling.
+
+use super::{back_end::GlGraphics, render_target::RenderTarget};
+use crate::{
+    graphics::{Context, DrawState, Image, Transformed, Viewport},
+    texture::{ImageSize, TextureSettings},
+};
+
+/// Renders the scene at a scaled down resolution to hold a target frame rate.
+///
+/// The scene is rendered to a render target and upsampled to the window.
+/// Call `update` every frame with the measured frame time,
+/// which adjusts the scale based on recent frame times.
+pub struct DynamicResolution {
+    target: Option<RenderTarget>,
+    scale: f64,
+    min_scale: f64,
+    max_scale: f64,
+    step: f64,
+    target_frame_time: f64,
+    // Exponential moving average of frame times.
+    average_frame_time: f64,
+}
+
+impl DynamicResolution {
+    /// Creates a new dynamic resolution controller for a target frame rate.
+    pub fn new(target_fps: f64) -> DynamicResolution {
+        let target_frame_time = 1.0 / target_fps;
+        DynamicResolution {
+            target: None,
+            scale: 1.0,
+            min_scale: 0.5,
+            max_scale: 1.0,
+            step: 0.05,
+            target_frame_time,
+            average_frame_time: target_frame_time,
+        }
+    }
+
+    /// Sets the minimum scale.
+    pub fn min_scale(mut self, value: f64) -> Self {
+        self.min_scale = value;
+        self.scale = self.scale.max(value);
+        self
+    }
+
+    /// Sets the maximum scale.
+    pub fn max_scale(mut self, value: f64) -> Self {
+        self.max_scale = value;
+        self.scale = self.scale.min(value);
+        self
+    }
+
+    /// Sets how much the scale changes per adjustment.
+    pub fn step(mut self, value: f64) -> Self {
+        self.step = value;
+        self
+    }
+
+    /// Returns the current scale of the resolution.
+    pub fn get_scale(&self) -> f64 {
+        self.scale
+    }
+
+    /// Sets the current scale, clamped to the minimum and maximum scale.
+    pub fn set_scale(&mut self, value: f64) {
+        self.scale = value.max(self.min_scale).min(self.max_scale);
+    }
+
+    /// Returns the average of recent frame times in seconds.
+    pub fn get_average_frame_time(&self) -> f64 {
+        self.average_frame_time
+    }
+
+    /// Updates the scale using the measured frame time in seconds.
+    ///
+    /// Lowers the scale when frames take longer than the target,
+    /// and raises it when there is enough headroom.
+    pub fn update(&mut self, frame_time: f64) {
+        self.average_frame_time += (frame_time - self.average_frame_time) * 0.1;
+        let scale = if self.average_frame_time > self.target_frame_time * 1.05 {
+            self.scale - self.step
+        } else if self.average_frame_time < self.target_frame_time * 0.85 {
+            self.scale + self.step
+        } else {
+            return;
+        };
+        self.set_scale(scale);
+    }
+
+    /// Draws the scene at the current scale and upsamples it to the viewport.
+    ///
+    /// The context passed to the closure uses window coordinates,
+    /// like the context of `GlGraphics::draw`.
+    /// Returns an error when the render target can not be created.
+    pub fn draw<F, U>(&mut self, g: &mut GlGraphics, viewport: Viewport, f: F) -> Result<U, String>
+    where
+        F: FnOnce(Context, &mut GlGraphics) -> U,
+    {
+        let [draw_w, draw_h] = viewport.draw_size;
+        let w = ((draw_w as f64 * self.scale).ceil() as u32).max(1);
+        let h = ((draw_h as f64 * self.scale).ceil() as u32).max(1);
+        let target = match self.target {
+            Some(ref target) if target.get_size() == (w, h) => target,
+            _ => {
+                // Free the old render target first.
+                self.target = None;
+                self.target
+                    .insert(RenderTarget::new(w, h, &TextureSettings::new())?)
+            }
+        };
+
+        let [window_w, window_h] = viewport.window_size;
+        let res = g.draw_to(target, |c, g| {
+            f(c.scale(w as f64 / window_w, h as f64 / window_h), g)
+        });
+        g.draw(viewport, |c, g| {
+            Image::new().rect([0.0, 0.0, window_w, window_h]).draw(
+                target.get_texture(),
+                &DrawState::default(),
+                c.transform.trans(0.0, window_h).flip_v(),
+                g,
+            );
+        });
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scale_controller() {
+        let mut res = DynamicResolution::new(60.0).min_scale(0.6);
+        for _ in 0..100 {
+            res.update(1.0 / 30.0);
+        }
+        assert_eq!(res.get_scale(), 0.6);
+        for _ in 0..100 {
+            res.update(1.0 / 120.0);
+        }
+        assert_eq!(res.get_scale(), 1.0);
+    }
+}
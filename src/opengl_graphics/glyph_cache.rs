@@ -1,172 +1,364 @@
 //! Glyph caching
+//!
+//! Requires the `truetype` feature.
 
-use {rusttype, graphics, Texture, TextureSettings};
-use std::collections::HashMap;
-use graphics::types::Scalar;
+use std::{collections::HashMap, path::Path};
 
-extern crate fnv;
-use self::fnv::FnvHasher;
-use std::hash::BuildHasherDefault;
+use ab_glyph::{point, Font, FontArc, FontVec, GlyphId, GlyphImageFormat, PxScale, ScaleFont};
 
-use std::path::Path;
-use std::io::Read;
-use std::fs::File;
-use error::Error;
-
-pub use graphics::types::FontSize;
-use graphics::character::CharacterCache;
+use super::Texture;
+use crate::{
+    graphics::{
+        character::{CharacterCache, FontMetrics},
+        texture_packer::{PackingHeuristic, TexturePacker},
+        types::{FontSize, Scalar},
+    },
+    texture::{ops, CreateTexture, Format, TextureSettings, UpdateTexture},
+};
 
 /// The type alias for font characters.
-pub type Character<'a> = graphics::character::Character<'a, Texture>;
+pub type Character<'a> = crate::graphics::character::Character<'a, Texture>;
+
+/// The minimum size of glyph atlas pages.
+pub const ATLAS_SIZE: [u32; 2] = [512; 2];
+
+// Font size, character and subpixel offset in 1/256 pixels.
+type Key = (FontSize, char, [u8; 2]);
+
+struct Data {
+    offset: [Scalar; 2],
+    advance_size: [Scalar; 2],
+    atlas_offset: [Scalar; 2],
+    atlas_size: [Scalar; 2],
+    texture: usize,
+    is_invalid: bool,
+    is_color: bool,
+    // The time of last use, for eviction.
+    used: u64,
+}
 
 /// A struct used for caching rendered font.
-pub struct GlyphCache<'a> {
+///
+/// Loads TrueType and OpenType fonts,
+/// and renders glyphs on first use into shared texture atlas pages,
+/// such that text is drawn with few draw calls.
+///
+/// Color bitmap glyphs, e.g. emoji, are supported when stored as PNG
+/// or premultiplied BGRA bitmaps in the font.
+pub struct GlyphCache {
     /// The font.
-    pub font: rusttype::Font<'a>,
-    /// The settings to render the font with.
+    pub font: FontArc,
+    // The settings to render the font with.
     settings: TextureSettings,
-    // Maps from fontsize and character to offset, size and texture.
-    data: HashMap<(FontSize, char),
-                  ([Scalar; 2], [Scalar; 2], Texture),
-                  BuildHasherDefault<FnvHasher>>,
+    texture_packer: TexturePacker<Texture>,
+    // Maps from font size, character and subpixel offset to rendered glyph.
+    data: HashMap<Key, Data>,
+    // Counts glyph lookups.
+    time: u64,
 }
 
-impl<'a> GlyphCache<'a> {
-    /// Constructs a GlyphCache from a Font.
-    pub fn from_font(font: rusttype::Font<'a>, settings: TextureSettings) -> Self {
-        let fnv = BuildHasherDefault::<FnvHasher>::default();
+impl GlyphCache {
+    /// Constructs a glyph cache from a font.
+    pub fn from_font(font: FontArc, settings: TextureSettings) -> GlyphCache {
         GlyphCache {
-            font: font,
-            settings: settings,
-            data: HashMap::with_hasher(fnv),
+            font,
+            settings,
+            texture_packer: TexturePacker::with_heuristic(
+                PackingHeuristic::MaxRectsBestShortSideFit,
+            ),
+            data: HashMap::new(),
+            time: 0,
         }
     }
 
-    /// Constructor for a GlyphCache.
-    pub fn new<P>(font: P, settings: TextureSettings) -> Result<GlyphCache<'static>, Error>
-        where P: AsRef<Path>
+    /// Loads a font file into a new glyph cache.
+    pub fn new<P>(font: P, settings: TextureSettings) -> Result<GlyphCache, String>
+    where
+        P: AsRef<Path>,
     {
-        let fnv = BuildHasherDefault::<FnvHasher>::default();
-        let mut file = try!(File::open(font));
-        let mut file_buffer = Vec::new();
-        try!(file.read_to_end(&mut file_buffer));
-
-        let collection = rusttype::FontCollection::from_bytes(file_buffer);
-        let font = collection.into_font().unwrap();
-        Ok(GlyphCache {
-            font: font,
-            settings: settings,
-            data: HashMap::with_hasher(fnv),
-        })
-    }
-
-    /// Creates a GlyphCache for a font stored in memory.
-    pub fn from_bytes(font: &'a [u8], settings: TextureSettings) -> Result<GlyphCache<'a>, Error> {
-        let collection = rusttype::FontCollection::from_bytes(font);
-        let font = collection.into_font().unwrap();
-        Ok(Self::from_font(font, settings))
+        let bytes = std::fs::read(font).map_err(|err| err.to_string())?;
+        let font = FontVec::try_from_vec(bytes).map_err(|err| err.to_string())?;
+        Ok(GlyphCache::from_font(FontArc::new(font), settings))
     }
 
-    /// Load all characters in the `chars` iterator for `size`
-    pub fn preload_chars<I>(&mut self, size: FontSize, chars: I)
-        where I: Iterator<Item = char>
-    {
-        for ch in chars {
-            self.character(size, ch);
-        }
+    /// Creates a glyph cache for a font stored in memory.
+    pub fn from_bytes(font: &[u8], settings: TextureSettings) -> Result<GlyphCache, String> {
+        let font = FontVec::try_from_vec(font.to_vec()).map_err(|err| err.to_string())?;
+        Ok(GlyphCache::from_font(FontArc::new(font), settings))
     }
 
     /// Load all the printable ASCII characters for `size`. Includes space.
-    pub fn preload_printable_ascii(&mut self, size: FontSize) {
+    pub fn preload_printable_ascii(&mut self, size: FontSize) -> Result<(), String> {
         // [0x20, 0x7F) contains all printable ASCII characters ([' ', '~'])
-        self.preload_chars(size, (0x20u8..0x7F).map(|ch| ch as char));
+        self.preload_chars(size, (0x20u8..0x7F).map(|ch| ch as char))
     }
 
     /// Return `ch` for `size` if it's already cached. Don't load.
     /// See the `preload_*` functions.
-    pub fn opt_character(&self, size: FontSize, ch: char) -> Option<Character> {
-        self.data.get(&(size, ch)).map(|&(offset, size, ref texture)| {
-            Character {
-                offset: offset,
-                size: size,
-                texture: texture,
+    pub fn opt_character(&self, size: FontSize, ch: char) -> Option<Character<'_>> {
+        self.data
+            .get(&(size, ch, [0; 2]))
+            .map(|data| character(&self.texture_packer, data))
+    }
+
+    /// Returns the number of cached glyphs.
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Returns `true` if no glyphs are cached.
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Returns the number of texture atlas pages.
+    pub fn pages(&self) -> usize {
+        self.texture_packer.textures.len()
+    }
+
+    // Gets a glyph, rendering it on first use.
+    fn glyph(&mut self, key: Key) -> Result<Character<'_>, String> {
+        self.time += 1;
+        if !self.data.contains_key(&key) {
+            let data = self.render(key)?;
+            self.data.insert(key, data);
+        }
+        let data = self.data.get_mut(&key).unwrap();
+        data.used = self.time;
+        Ok(character(&self.texture_packer, data))
+    }
+
+    // Renders a glyph into the texture atlas.
+    fn render(&mut self, (size, ch, subpixel): Key) -> Result<Data, String> {
+        let subpixel = [
+            f32::from(subpixel[0]) / 256.0,
+            f32::from(subpixel[1]) / 256.0,
+        ];
+        let font = self.font.as_scaled(scale(size));
+        let mut id = font.glyph_id(ch);
+        // Some fonts do not contain glyph zero as fallback, instead try U+FFFD.
+        if id == GlyphId(0) {
+            id = font.glyph_id('\u{FFFD}');
+        }
+        let advance = Scalar::from(font.h_advance(id));
+        let (offset, size, memory, is_color) = match color_bitmap(&self.font, id, font.scale().y) {
+            // Bitmaps are not rendered at subpixel offsets, so move them instead.
+            Some((offset, size, memory)) => {
+                let offset = [
+                    offset[0] + Scalar::from(subpixel[0]),
+                    offset[1] - Scalar::from(subpixel[1]),
+                ];
+                (offset, size, memory, true)
+            }
+            None => {
+                let outlined = font
+                    .outline_glyph(
+                        id.with_scale_and_position(font.scale(), point(subpixel[0], subpixel[1])),
+                    )
+                    .filter(|g| g.px_bounds().width() >= 1.0 && g.px_bounds().height() >= 1.0);
+                let (offset, size, pixels) = match outlined {
+                    Some(glyph) => {
+                        let bounds = glyph.px_bounds();
+                        let size = [bounds.width() as u32, bounds.height() as u32];
+                        let mut pixels = vec![0; (size[0] * size[1]) as usize];
+                        glyph.draw(|x, y, coverage| {
+                            pixels[(x + y * size[0]) as usize] =
+                                (coverage.clamp(0.0, 1.0) * 255.0).round() as u8;
+                        });
+                        let offset = [Scalar::from(bounds.min.x), -Scalar::from(bounds.min.y)];
+                        (offset, size, pixels)
+                    }
+                    // Whitespace has no outline.
+                    None => ([0.0; 2], [0, 0], vec![]),
+                };
+                (offset, size, ops::alpha_to_rgba8(&pixels, size), false)
+            }
+        };
+        // Keep a transparent border of 1 pixel around glyphs,
+        // such that neighbours in the atlas do not bleed into each other.
+        let offset = [offset[0] - 1.0, offset[1] + 1.0];
+        let (memory, size) = pad(&memory, size);
+        let (texture, atlas_offset) = match self.texture_packer.insert(size) {
+            Some((texture, atlas_offset)) => {
+                UpdateTexture::update(
+                    &mut self.texture_packer.textures[texture],
+                    &mut (),
+                    Format::Rgba8,
+                    &memory,
+                    atlas_offset,
+                    size,
+                )?;
+                (texture, atlas_offset)
             }
+            None => {
+                // Create a new texture atlas page with the glyph in the corner.
+                let page = [size[0].max(ATLAS_SIZE[0]), size[1].max(ATLAS_SIZE[1])];
+                let mut page_memory = vec![0; (page[0] * page[1] * 4) as usize];
+                for (y, row) in memory.chunks_exact((size[0] * 4) as usize).enumerate() {
+                    let start = y * (page[0] * 4) as usize;
+                    page_memory[start..start + row.len()].copy_from_slice(row);
+                }
+                let texture =
+                    Texture::create(&mut (), Format::Rgba8, &page_memory, page, &self.settings)?;
+                (self.texture_packer.create(size, texture), [0, 0])
+            }
+        };
+        Ok(Data {
+            offset,
+            advance_size: [advance, 0.0],
+            atlas_offset: [Scalar::from(atlas_offset[0]), Scalar::from(atlas_offset[1])],
+            atlas_size: [Scalar::from(size[0]), Scalar::from(size[1])],
+            texture,
+            is_invalid: id == GlyphId(0),
+            is_color,
+            used: self.time,
         })
     }
 }
 
-impl<'b> CharacterCache for GlyphCache<'b> {
-    type Texture = Texture;
+// Converts font size in points to a scale in pixels.
+fn scale(size: FontSize) -> PxScale {
+    PxScale::from((size as f32 * 1.333).round())
+}
 
-    fn character<'a>(&'a mut self, size: FontSize, ch: char) -> Character<'a> {
-        use std::collections::hash_map::Entry;
-        use rusttype as rt;
+// Renders the color bitmap of a glyph scaled to pixels per em.
+//
+// Returns the offset, size and RGBA memory of the bitmap.
+fn color_bitmap(font: &FontArc, id: GlyphId, px: f32) -> Option<([Scalar; 2], [u32; 2], Vec<u8>)> {
+    let image = font.glyph_raster_image2(id, px.round() as u16)?;
+    let (w, h, memory) = match image.format {
+        GlyphImageFormat::Png => decode_png(image.data)?,
+        GlyphImageFormat::BitmapPremulBgra32 => {
+            let memory = image
+                .data
+                .chunks_exact(4)
+                .flat_map(|p| {
+                    let unpremultiply = |c: u8| match p[3] {
+                        0 => 0,
+                        a => (u32::from(c) * 255 / u32::from(a)).min(255) as u8,
+                    };
+                    [
+                        unpremultiply(p[2]),
+                        unpremultiply(p[1]),
+                        unpremultiply(p[0]),
+                        p[3],
+                    ]
+                })
+                .collect();
+            (u32::from(image.width), u32::from(image.height), memory)
+        }
+        _ => return None,
+    };
+    let bitmap = image::RgbaImage::from_raw(w, h, memory)?;
+    // Bitmaps are stored for a few sizes only.
+    let s = px / f32::from(image.pixels_per_em.max(1));
+    let size = [
+        ((w as f32 * s).round() as u32).max(1),
+        ((h as f32 * s).round() as u32).max(1),
+    ];
+    let bitmap = image::imageops::resize(
+        &bitmap,
+        size[0],
+        size[1],
+        image::imageops::FilterType::Triangle,
+    );
+    // The origin is the lower left corner relative to the baseline, with y axis pointing up.
+    let offset = [
+        Scalar::from(image.origin.x * s),
+        Scalar::from(image.origin.y * s) + Scalar::from(size[1]),
+    ];
+    Some((offset, size, bitmap.into_raw()))
+}
 
-        let size = ((size as f32) * 1.333).round() as u32; // convert points to pixels
+// Decodes a PNG image to RGBA memory.
+fn decode_png(data: &[u8]) -> Option<(u32, u32, Vec<u8>)> {
+    let image = image::load_from_memory_with_format(data, image::ImageFormat::Png).ok()?;
+    let image = image.into_rgba8();
+    Some((image.width(), image.height(), image.into_raw()))
+}
 
-        match self.data.entry((size, ch)) {
-            //returning `into_mut()' to get reference with 'a lifetime
-            Entry::Occupied(v) => {
-                let &mut (offset, size, ref texture) = v.into_mut();
-                Character {
-                    offset: offset,
-                    size: size,
-                    texture: texture,
-                }
-            }
-            Entry::Vacant(v) => {
-                // this is only None for invalid GlyphIds,
-                // but char is converted to a Codepoint which must result in a glyph.
-                let glyph = self.font.glyph(ch).unwrap();
-                let scale = rt::Scale::uniform(size as f32);
-                let mut glyph = glyph.scaled(scale);
-
-                // some fonts do not contain glyph zero as fallback, instead try U+FFFD.
-                if glyph.id() == rt::GlyphId(0) && glyph.shape().is_none() {
-                    glyph = self.font.glyph('\u{FFFD}').unwrap().scaled(scale);
-                }
+// Adds a transparent border of 1 pixel around RGBA memory.
+fn pad(memory: &[u8], size: [u32; 2]) -> (Vec<u8>, [u32; 2]) {
+    let padded = [size[0] + 2, size[1] + 2];
+    let stride = (padded[0] * 4) as usize;
+    let mut res = vec![0; stride * padded[1] as usize];
+    for (y, row) in memory
+        .chunks_exact((size[0] * 4).max(1) as usize)
+        .enumerate()
+    {
+        let start = (y + 1) * stride + 4;
+        res[start..start + row.len()].copy_from_slice(row);
+    }
+    (res, padded)
+}
 
-                let h_metrics = glyph.h_metrics();
-                let bounding_box = glyph.exact_bounding_box().unwrap_or(rt::Rect {
-                    min: rt::Point { x: 0.0, y: 0.0 },
-                    max: rt::Point { x: 0.0, y: 0.0 },
-                });
-                let glyph = glyph.positioned(rt::point(0.0, 0.0));
-                let pixel_bounding_box = glyph.pixel_bounding_box().unwrap_or(rt::Rect {
-                    min: rt::Point { x: 0, y: 0 },
-                    max: rt::Point { x: 0, y: 0 },
-                });
-                let pixel_bb_width = pixel_bounding_box.width() + 2;
-                let pixel_bb_height = pixel_bounding_box.height() + 2;
-
-                let mut image_buffer = Vec::<u8>::new();
-                image_buffer.resize((pixel_bb_width * pixel_bb_height) as usize, 0);
-                glyph.draw(|x, y, v| {
-                    let pos = ((x + 1) + (y + 1) * (pixel_bb_width as u32)) as usize;
-                    image_buffer[pos] = (255.0 * v) as u8;
-                });
-
-                let &mut (offset, size, ref texture) =
-                    v.insert(([bounding_box.min.x as Scalar - 1.0,
-                               -pixel_bounding_box.min.y as Scalar + 1.0],
-                              [h_metrics.advance_width as Scalar, 0 as Scalar],
-                              {
-                                  if pixel_bb_width == 0 || pixel_bb_height == 0 {
-                                      Texture::empty(&self.settings).unwrap()
-                                  } else {
-                                      Texture::from_memory_alpha(&image_buffer,
-                                                                 pixel_bb_width as u32,
-                                                                 pixel_bb_height as u32,
-                                                                 &self.settings)
-                                          .unwrap()
-                                  }
-                              }));
-                Character {
-                    offset: offset,
-                    size: size,
-                    texture: texture,
-                }
-            }
+fn character<'a>(texture_packer: &'a TexturePacker<Texture>, data: &Data) -> Character<'a> {
+    Character {
+        offset: data.offset,
+        advance_size: data.advance_size,
+        atlas_offset: data.atlas_offset,
+        atlas_size: data.atlas_size,
+        texture: &texture_packer.textures[data.texture],
+        is_invalid: data.is_invalid,
+        is_color: data.is_color,
+    }
+}
+
+impl CharacterCache for GlyphCache {
+    type Texture = Texture;
+    type Error = String;
+
+    fn character(&mut self, size: FontSize, ch: char) -> Result<Character<'_>, String> {
+        self.glyph((size, ch, [0; 2]))
+    }
+
+    fn character_at(
+        &mut self,
+        size: FontSize,
+        ch: char,
+        offset: [Scalar; 2],
+    ) -> Result<Character<'_>, String> {
+        let quantize = |x: Scalar| (x.rem_euclid(1.0) * 256.0).floor().min(255.0) as u8;
+        self.glyph((size, ch, [quantize(offset[0]), quantize(offset[1])]))
+    }
+
+    fn evict(&mut self, max_glyphs: usize) -> Result<usize, String> {
+        if self.data.len() <= max_glyphs {
+            return Ok(0);
         }
+        let mut keys: Vec<(Key, u64)> = self
+            .data
+            .iter()
+            .map(|(&key, data)| (key, data.used))
+            .collect();
+        keys.sort_by_key(|&(_, used)| std::cmp::Reverse(used));
+        let evicted = keys.len() - max_glyphs;
+        // Render the remaining glyphs into new atlas pages,
+        // since space in the old pages can not be reused.
+        self.data.clear();
+        self.texture_packer =
+            TexturePacker::with_heuristic(PackingHeuristic::MaxRectsBestShortSideFit);
+        for &(key, used) in keys[..max_glyphs].iter().rev() {
+            let mut data = self.render(key)?;
+            data.used = used;
+            self.data.insert(key, data);
+        }
+        Ok(evicted)
+    }
+
+    fn kerning(&mut self, size: FontSize, left: char, right: char) -> Result<Scalar, String> {
+        let font = self.font.as_scaled(scale(size));
+        Ok(Scalar::from(
+            font.kern(font.glyph_id(left), font.glyph_id(right)),
+        ))
+    }
+
+    fn metrics(&mut self, size: FontSize) -> Result<FontMetrics, String> {
+        let font = self.font.as_scaled(scale(size));
+        Ok(FontMetrics {
+            ascent: Scalar::from(font.ascent()),
+            descent: -Scalar::from(font.descent()),
+            line_gap: Scalar::from(font.line_gap()),
+        })
     }
 }
@@ -1,14 +1,28 @@
 //! An OpenGL back-end for Rust-Graphics
 
 pub mod error;
+#[cfg(feature = "truetype")]
+pub mod glyph_cache;
 pub mod shader_uniforms;
 pub mod shader_utils;
 
 mod back_end;
+mod deferred;
 mod draw_state;
+mod dynamic_resolution;
+mod query;
+mod render_target;
 mod texture;
+mod upload;
 
 pub use self::{
-    back_end::{Colored, GlGraphics, Textured},
+    back_end::{Colored, GlGraphics, Material, Textured, TexturedColor, VertexLayout},
+    dynamic_resolution::DynamicResolution,
+    query::OcclusionQuery,
+    render_target::RenderTarget,
     texture::Texture,
+    upload::TextureUpload,
 };
+
+#[cfg(feature = "truetype")]
+pub use self::glyph_cache::GlyphCache;
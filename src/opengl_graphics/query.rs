@@ -0,0 +1,68 @@
+//! Occlusion queries.
+
+use gl::{self, types::GLuint};
+
+/// Counts samples passing the depth and stencil tests.
+///
+/// Use `GlGraphics::begin_query` and `GlGraphics::end_query`
+/// around draw calls to count their visible samples,
+/// for example to test visibility of a light source for lens flares.
+/// The query gets deleted when running out of scope.
+pub struct OcclusionQuery {
+    id: GLuint,
+}
+
+impl OcclusionQuery {
+    /// Creates a new occlusion query.
+    pub fn new() -> Self {
+        let mut id = 0;
+        unsafe {
+            gl::GenQueries(1, &mut id);
+        }
+        OcclusionQuery { id }
+    }
+
+    /// Gets the OpenGL id of the query.
+    pub fn get_id(&self) -> GLuint {
+        self.id
+    }
+
+    /// Returns the number of samples passed, if the result is available.
+    ///
+    /// Rendering happens asynchronously, so the result is usually
+    /// not available until a later frame.
+    pub fn result(&self) -> Option<u32> {
+        let mut available = 0;
+        unsafe {
+            gl::GetQueryObjectuiv(self.id, gl::QUERY_RESULT_AVAILABLE, &mut available);
+        }
+        if available == 0 {
+            None
+        } else {
+            Some(self.wait_result())
+        }
+    }
+
+    /// Waits until rendering is done and returns the number of samples passed.
+    pub fn wait_result(&self) -> u32 {
+        let mut samples = 0;
+        unsafe {
+            gl::GetQueryObjectuiv(self.id, gl::QUERY_RESULT, &mut samples);
+        }
+        samples
+    }
+}
+
+impl Default for OcclusionQuery {
+    fn default() -> Self {
+        OcclusionQuery::new()
+    }
+}
+
+impl Drop for OcclusionQuery {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteQueries(1, &self.id);
+        }
+    }
+}
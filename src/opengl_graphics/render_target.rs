@@ -0,0 +1,129 @@
+//! Offscreen render targets.
+
+use gl::{self, types::GLuint};
+
+use super::{back_end::GlGraphics, texture::Texture};
+use crate::{
+    graphics::{math::multiply, render_texture::RenderToTexture, Context},
+    texture::{CreateTexture, Format, ImageSize, TextureSettings},
+};
+
+/// A frame buffer that renders to a texture.
+///
+/// Use `GlGraphics::draw_to` to render to the target,
+/// then draw the texture like any other image.
+///
+/// The texture has the origin in the lower left corner, as usual in OpenGL,
+/// so it appears flipped vertically when drawn as an image.
+/// The render target gets deleted when running out of scope.
+pub struct RenderTarget {
+    fbo: GLuint,
+    // Depth-stencil render buffer, such that clipping works when drawing.
+    depth_stencil: GLuint,
+    texture: Texture,
+}
+
+impl RenderTarget {
+    /// Creates a new render target of size in pixels.
+    pub fn new(width: u32, height: u32, settings: &TextureSettings) -> Result<Self, String> {
+        let memory = vec![0u8; width as usize * height as usize * 4];
+        let texture: Texture =
+            CreateTexture::create(&mut (), Format::Rgba8, &memory, [width, height], settings)?;
+        let (mut fbo, mut depth_stencil) = (0, 0);
+        let mut previous = 0;
+        unsafe {
+            gl::GetIntegerv(gl::FRAMEBUFFER_BINDING, &mut previous);
+            gl::GenFramebuffers(1, &mut fbo);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, fbo);
+            gl::FramebufferTexture2D(
+                gl::FRAMEBUFFER,
+                gl::COLOR_ATTACHMENT0,
+                gl::TEXTURE_2D,
+                texture.get_id(),
+                0,
+            );
+            gl::GenRenderbuffers(1, &mut depth_stencil);
+            gl::BindRenderbuffer(gl::RENDERBUFFER, depth_stencil);
+            gl::RenderbufferStorage(
+                gl::RENDERBUFFER,
+                gl::DEPTH24_STENCIL8,
+                width as i32,
+                height as i32,
+            );
+            gl::FramebufferRenderbuffer(
+                gl::FRAMEBUFFER,
+                gl::DEPTH_STENCIL_ATTACHMENT,
+                gl::RENDERBUFFER,
+                depth_stencil,
+            );
+            let status = gl::CheckFramebufferStatus(gl::FRAMEBUFFER);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, previous as GLuint);
+            if status != gl::FRAMEBUFFER_COMPLETE {
+                gl::DeleteFramebuffers(1, &fbo);
+                gl::DeleteRenderbuffers(1, &depth_stencil);
+                return Err(format!(
+                    "Render target frame buffer is incomplete: {}",
+                    status
+                ));
+            }
+        }
+        Ok(RenderTarget {
+            fbo,
+            depth_stencil,
+            texture,
+        })
+    }
+
+    /// Gets the OpenGL id of the frame buffer.
+    pub fn get_fbo(&self) -> GLuint {
+        self.fbo
+    }
+
+    /// Gets the texture that is rendered to.
+    pub fn get_texture(&self) -> &Texture {
+        &self.texture
+    }
+}
+
+impl ImageSize for RenderTarget {
+    fn get_size(&self) -> (u32, u32) {
+        self.texture.get_size()
+    }
+}
+
+impl Drop for RenderTarget {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteFramebuffers(1, &self.fbo);
+            gl::DeleteRenderbuffers(1, &self.depth_stencil);
+        }
+    }
+}
+
+impl RenderToTexture for GlGraphics {
+    type RenderTarget = RenderTarget;
+
+    fn create_render_target(&mut self, width: u32, height: u32) -> Result<RenderTarget, String> {
+        RenderTarget::new(width, height, &TextureSettings::new())
+    }
+
+    fn render_target_texture(target: &RenderTarget) -> &Texture {
+        target.get_texture()
+    }
+
+    fn draw_to_texture<F, U>(&mut self, target: &mut RenderTarget, f: F) -> U
+    where
+        F: FnOnce(Context, &mut Self) -> U,
+    {
+        self.draw_to(target, |c, g| {
+            // Flip vertically, such that the texture appears upright when drawn as an image.
+            let flip = [[1.0, 0.0, 0.0], [0.0, -1.0, 0.0]];
+            let c = Context {
+                view: multiply(flip, c.view),
+                transform: multiply(flip, c.transform),
+                ..c
+            };
+            f(c, g)
+        })
+    }
+}
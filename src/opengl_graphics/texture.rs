@@ -1,9 +1,16 @@
+use std::cell::RefCell;
 use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
-use gl::{self, types::GLuint};
-use image::{self, DynamicImage, RgbaImage};
+use gl::{
+    self,
+    types::{GLenum, GLint, GLuint},
+};
+use image::DynamicImage;
+use image::RgbaImage;
 
 use crate::texture::{
+    compressed::{CompressedFormat, CompressedImage},
     ops, CreateTexture, Filter, Format, ImageSize, TextureOp, TextureSettings, UpdateTexture, Wrap,
 };
 
@@ -84,13 +91,28 @@ pub struct Texture {
     id: GLuint,
     width: u32,
     height: u32,
+    mipmaps: bool,
+    format: Format,
 }
 
 impl Texture {
     /// Creates a new texture.
     #[inline(always)]
     pub fn new(id: GLuint, width: u32, height: u32) -> Self {
-        Texture { id, width, height }
+        Texture {
+            id,
+            width,
+            height,
+            mipmaps: false,
+            format: Format::Rgba8,
+        }
+    }
+
+    // Creates a new texture with pixels stored in some format.
+    pub(crate) fn with_format(id: GLuint, size: [u32; 2], format: Format) -> Self {
+        let mut texture = Texture::new(id, size[0], size[1]);
+        texture.format = format;
+        texture
     }
 
     /// Gets the OpenGL id of the texture.
@@ -111,12 +133,83 @@ impl Texture {
         height: u32,
         settings: &TextureSettings,
     ) -> Result<Self, String> {
-        let size = [width, height];
-        let buffer = ops::alpha_to_rgba8(buf, size);
-        CreateTexture::create(&mut (), Format::Rgba8, &buffer, size, settings)
+        Texture::from_memory(buf, [width, height], Format::Alpha8, settings)
+    }
+
+    /// Loads image from memory in some format.
+    ///
+    /// The pixels are uploaded without conversion when possible.
+    /// Single channel formats use texture swizzling, which requires OpenGL 3.3,
+    /// and are converted to RGBA on older versions and WebGL.
+    pub fn from_memory(
+        memory: &[u8],
+        size: [u32; 2],
+        format: Format,
+        settings: &TextureSettings,
+    ) -> Result<Self, String> {
+        CreateTexture::create(&mut (), format, memory, size, settings)
+    }
+
+    /// Creates a texture from a block compressed image, keeping it compressed.
+    ///
+    /// Mipmaps are taken from the levels of the image, since compressed
+    /// textures can not generate mipmaps.
+    /// The format must be supported by the driver, e.g. BC on desktop and ETC2 on mobile.
+    pub fn from_compressed(
+        image: &CompressedImage,
+        settings: &TextureSettings,
+    ) -> Result<Self, String> {
+        let srgb = !settings.get_convert_gamma();
+        let internal_format = gl_compressed_format(image.format, srgb);
+        let mut size = image.size;
+        for (level, data) in image.levels.iter().enumerate() {
+            if data.len() < image.format.memory_size(size) {
+                return Err(format!("Compressed level {} does not match size", level));
+            }
+            size = [(size[0] / 2).max(1), (size[1] / 2).max(1)];
+        }
+        let mut id: GLuint = 0;
+        unsafe {
+            gl::GenTextures(1, &mut id);
+        }
+        let texture = Texture::new(id, image.size[0], image.size[1]);
+        let mut error = gl::NO_ERROR;
+        with_texture_bound(id, || unsafe {
+            // Clear an earlier error, to detect unsupported formats.
+            gl::GetError();
+            apply_settings(settings);
+            let mut size = image.size;
+            for (level, data) in image.levels.iter().enumerate() {
+                gl::CompressedTexImage2D(
+                    gl::TEXTURE_2D,
+                    level as GLint,
+                    internal_format,
+                    size[0] as i32,
+                    size[1] as i32,
+                    0,
+                    data.len() as i32,
+                    data.as_ptr() as *const _,
+                );
+                size = [(size[0] / 2).max(1), (size[1] / 2).max(1)];
+            }
+            // Mipmap filters need every level to be present.
+            let max_level = image.levels.len().saturating_sub(1) as GLint;
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAX_LEVEL, max_level);
+            error = gl::GetError();
+        });
+        if error != gl::NO_ERROR {
+            return Err(format!(
+                "Compressed format {:?} is not supported",
+                image.format
+            ));
+        }
+        Ok(texture)
     }
 
-    /// Loads image by relative file name to the asset root.
+    /// Loads a PNG, JPEG or WebP image by relative file name to the asset root.
+    ///
+    /// Decoding requires the `image` feature, which enables the codecs.
+    /// Without it, loading fails with an error.
     pub fn from_path<P>(path: P, settings: &TextureSettings) -> Result<Self, String>
     where
         P: AsRef<Path>,
@@ -125,13 +218,7 @@ impl Texture {
 
         let img = match image::open(path) {
             Ok(img) => img,
-            Err(e) => {
-                return Err(format!(
-                    "Could not load '{:?}': {:?}",
-                    path.file_name().unwrap(),
-                    e
-                ))
-            }
+            Err(e) => return Err(format!("Could not load '{}': {:?}", path.display(), e)),
         };
 
         let img = match img {
@@ -142,7 +229,10 @@ impl Texture {
         Ok(Texture::from_image(&img, settings))
     }
 
-    /// Load image from bytes.
+    /// Loads a PNG, JPEG or WebP image from bytes.
+    ///
+    /// Decoding requires the `image` feature, which enables the codecs.
+    /// Without it, loading fails with an error.
     pub fn from_bytes(bytes: &[u8], settings: &TextureSettings) -> Result<Self, String> {
         let img = match image::load_from_memory(bytes) {
             Ok(img) => img,
@@ -169,10 +259,141 @@ impl Texture {
 
         UpdateTexture::update(self, &mut (), Format::Rgba8, img, [0, 0], [width, height]).unwrap();
     }
+
+    /// Reads the pixels of the texture into an image.
+    ///
+    /// The texture is attached to a temporary frame buffer for reading,
+    /// which also works on OpenGL ES and WebGL.
+    /// Returns an error when the texture can not be rendered to,
+    /// e.g. for compressed textures.
+    /// Single channel textures are read without swizzling,
+    /// e.g. alpha is in the red channel.
+    /// This stalls the pipeline until rendering to the texture is done.
+    pub fn to_image(&self) -> Result<RgbaImage, String> {
+        let mut memory = vec![0u8; Format::Rgba8.memory_size([self.width, self.height])];
+        let (mut fbo, mut previous) = (0, 0);
+        let status = unsafe {
+            gl::GetIntegerv(gl::FRAMEBUFFER_BINDING, &mut previous);
+            gl::GenFramebuffers(1, &mut fbo);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, fbo);
+            gl::FramebufferTexture2D(
+                gl::FRAMEBUFFER,
+                gl::COLOR_ATTACHMENT0,
+                gl::TEXTURE_2D,
+                self.id,
+                0,
+            );
+            let status = gl::CheckFramebufferStatus(gl::FRAMEBUFFER);
+            if status == gl::FRAMEBUFFER_COMPLETE {
+                gl::ReadPixels(
+                    0,
+                    0,
+                    self.width as i32,
+                    self.height as i32,
+                    gl::RGBA,
+                    gl::UNSIGNED_BYTE,
+                    memory.as_mut_ptr() as *mut _,
+                );
+            }
+            gl::BindFramebuffer(gl::FRAMEBUFFER, previous as GLuint);
+            gl::DeleteFramebuffers(1, &fbo);
+            status
+        };
+        if status != gl::FRAMEBUFFER_COMPLETE {
+            return Err(format!(
+                "Could not read texture, frame buffer status {:#x}",
+                status
+            ));
+        }
+        Ok(RgbaImage::from_raw(self.width, self.height, memory).expect("Memory matches size"))
+    }
+
+    /// Generates mipmaps from the base level.
+    ///
+    /// Textures created with mipmaps enabled in the settings
+    /// regenerate them after every update.
+    pub fn generate_mipmaps(&mut self) {
+        self.mipmaps = true;
+        with_texture_bound(self.id, || unsafe {
+            gl::GenerateMipmap(gl::TEXTURE_2D);
+        });
+    }
+
+    /// Changes the filters and wrap modes of the texture.
+    ///
+    /// Generates mipmaps if they are enabled in the settings.
+    pub fn set_settings(&mut self, settings: &TextureSettings) {
+        with_texture_bound(self.id, || apply_settings(settings));
+        if settings.get_generate_mipmap() {
+            self.generate_mipmaps();
+        } else {
+            self.mipmaps = false;
+        }
+    }
+
+    /// Updates a region of the texture with an image, e.g. a tile of an atlas
+    /// or a video frame, without re-creating the texture.
+    pub fn update_region(&mut self, img: &RgbaImage, offset: [u32; 2]) -> Result<(), String> {
+        let (width, height) = img.dimensions();
+        UpdateTexture::update(self, &mut (), Format::Rgba8, img, offset, [width, height])
+    }
+}
+
+// Counts deleted textures, since OpenGL unbinds textures on deletion
+// and might reuse their ids for new textures.
+static DELETED_TEXTURES: AtomicUsize = AtomicUsize::new(0);
+
+// Returns the number of textures deleted so far.
+pub(crate) fn deleted_textures() -> usize {
+    DELETED_TEXTURES.load(Ordering::Relaxed)
+}
+
+thread_local! {
+    // Ids of deleted textures while deferred draw calls are pending,
+    // with the number of command queues watching them.
+    static DELETED_IDS: RefCell<(usize, Vec<GLuint>)> = const { RefCell::new((0, vec![])) };
+}
+
+// Starts logging ids of deleted textures.
+pub(crate) fn watch_deleted_ids() {
+    DELETED_IDS.with(|d| d.borrow_mut().0 += 1);
+}
+
+// Stops logging ids of deleted textures, clearing the log when nobody watches.
+pub(crate) fn unwatch_deleted_ids() {
+    let _ = DELETED_IDS.try_with(|d| {
+        let mut d = d.borrow_mut();
+        d.0 = d.0.saturating_sub(1);
+        if d.0 == 0 {
+            d.1.clear();
+        }
+    });
+}
+
+// Logs the id of a deleted texture, if watched.
+pub(crate) fn log_deleted_id(id: GLuint) {
+    let _ = DELETED_IDS.try_with(|d| {
+        let mut d = d.borrow_mut();
+        if d.0 > 0 {
+            d.1.push(id);
+        }
+    });
+}
+
+// Returns the number of logged ids, to check for deletions later.
+pub(crate) fn deleted_ids_len() -> usize {
+    DELETED_IDS.with(|d| d.borrow().1.len())
+}
+
+// Returns `true` if a texture id was deleted after some number of logged ids.
+pub(crate) fn is_deleted_since(id: GLuint, len: usize) -> bool {
+    DELETED_IDS.with(|d| d.borrow().1.get(len..).is_some_and(|ids| ids.contains(&id)))
 }
 
 impl Drop for Texture {
     fn drop(&mut self) {
+        DELETED_TEXTURES.fetch_add(1, Ordering::Relaxed);
+        log_deleted_id(self.id);
         unsafe {
             let ids = [self.id];
             gl::DeleteTextures(1, ids.as_ptr());
@@ -190,56 +411,196 @@ impl TextureOp<()> for Texture {
     type Error = String;
 }
 
+// Binds texture while calling the closure, then restores the previous binding.
+//
+// This keeps the texture unit state tracked by the back-end valid.
+pub(crate) fn with_texture_bound<F: FnOnce()>(id: GLuint, f: F) {
+    let mut previous: GLint = 0;
+    unsafe {
+        gl::GetIntegerv(gl::TEXTURE_BINDING_2D, &mut previous);
+        gl::BindTexture(gl::TEXTURE_2D, id);
+    }
+    f();
+    unsafe {
+        gl::BindTexture(gl::TEXTURE_2D, previous as GLuint);
+    }
+}
+
+// Sets the filters and wrap modes of the bound texture.
+pub(crate) fn apply_settings(settings: &TextureSettings) {
+    unsafe {
+        gl::TexParameteri(
+            gl::TEXTURE_2D,
+            gl::TEXTURE_MIN_FILTER,
+            settings.get_gl_min() as i32,
+        );
+        gl::TexParameteri(
+            gl::TEXTURE_2D,
+            gl::TEXTURE_MAG_FILTER,
+            settings.get_gl_mag() as i32,
+        );
+        gl::TexParameteri(
+            gl::TEXTURE_2D,
+            gl::TEXTURE_WRAP_S,
+            settings.get_gl_wrap_u() as i32,
+        );
+        gl::TexParameteri(
+            gl::TEXTURE_2D,
+            gl::TEXTURE_WRAP_T,
+            settings.get_gl_wrap_v() as i32,
+        );
+        if settings.get_wrap_u() == Wrap::ClampToBorder
+            || settings.get_wrap_v() == Wrap::ClampToBorder
+        {
+            gl::TexParameterfv(
+                gl::TEXTURE_2D,
+                gl::TEXTURE_BORDER_COLOR,
+                settings.get_border_color().as_ptr(),
+            );
+        }
+    }
+}
+
+// Returns the internal format of a compressed format.
+fn gl_compressed_format(format: CompressedFormat, srgb: bool) -> GLenum {
+    // Extension formats, which are not part of the core profile.
+    const COMPRESSED_RGBA_S3TC_DXT1_EXT: GLenum = 0x83F1;
+    const COMPRESSED_RGBA_S3TC_DXT3_EXT: GLenum = 0x83F2;
+    const COMPRESSED_RGBA_S3TC_DXT5_EXT: GLenum = 0x83F3;
+    const COMPRESSED_SRGB_ALPHA_S3TC_DXT1_EXT: GLenum = 0x8C4D;
+    const COMPRESSED_SRGB_ALPHA_S3TC_DXT3_EXT: GLenum = 0x8C4E;
+    const COMPRESSED_SRGB_ALPHA_S3TC_DXT5_EXT: GLenum = 0x8C4F;
+    const COMPRESSED_RGBA_ASTC_4X4_KHR: GLenum = 0x93B0;
+    const COMPRESSED_SRGB8_ALPHA8_ASTC_4X4_KHR: GLenum = 0x93D0;
+
+    match (format, srgb) {
+        (CompressedFormat::Bc1, false) => COMPRESSED_RGBA_S3TC_DXT1_EXT,
+        (CompressedFormat::Bc1, true) => COMPRESSED_SRGB_ALPHA_S3TC_DXT1_EXT,
+        (CompressedFormat::Bc2, false) => COMPRESSED_RGBA_S3TC_DXT3_EXT,
+        (CompressedFormat::Bc2, true) => COMPRESSED_SRGB_ALPHA_S3TC_DXT3_EXT,
+        (CompressedFormat::Bc3, false) => COMPRESSED_RGBA_S3TC_DXT5_EXT,
+        (CompressedFormat::Bc3, true) => COMPRESSED_SRGB_ALPHA_S3TC_DXT5_EXT,
+        (CompressedFormat::Etc2Rgb, false) => gl::COMPRESSED_RGB8_ETC2,
+        (CompressedFormat::Etc2Rgb, true) => gl::COMPRESSED_SRGB8_ETC2,
+        (CompressedFormat::Etc2Rgba, false) => gl::COMPRESSED_RGBA8_ETC2_EAC,
+        (CompressedFormat::Etc2Rgba, true) => gl::COMPRESSED_SRGB8_ALPHA8_ETC2_EAC,
+        (CompressedFormat::Astc4x4, false) => COMPRESSED_RGBA_ASTC_4X4_KHR,
+        (CompressedFormat::Astc4x4, true) => COMPRESSED_SRGB8_ALPHA8_ASTC_4X4_KHR,
+    }
+}
+
+// Makes single channel formats of the bound texture look like rgba.
+pub(crate) fn apply_swizzle(format: Format, settings: &TextureSettings) {
+    let swizzle = match format {
+        Format::R8 => Some([gl::RED, gl::RED, gl::RED, gl::ONE]),
+        // Premultiplied white has the alpha in every channel.
+        Format::Alpha8 if settings.get_premultiply_alpha() => {
+            Some([gl::RED, gl::RED, gl::RED, gl::RED])
+        }
+        Format::Alpha8 => Some([gl::ONE, gl::ONE, gl::ONE, gl::RED]),
+        _ => None,
+    };
+    if let Some(swizzle) = swizzle {
+        let swizzle = swizzle.map(|c| c as GLint);
+        unsafe {
+            gl::TexParameteriv(gl::TEXTURE_2D, gl::TEXTURE_SWIZZLE_RGBA, swizzle.as_ptr());
+        }
+    }
+}
+
+// Returns `true` when the current context can swizzle texture channels.
+pub(crate) fn supports_swizzle() -> bool {
+    if cfg!(any(
+        all(target_arch = "wasm32", target_os = "unknown"),
+        target_os = "emscripten",
+        feature = "webgl"
+    )) {
+        return false;
+    }
+    let version = unsafe { gl::GetString(gl::VERSION) };
+    if version.is_null() {
+        return false;
+    }
+    let version = unsafe { std::ffi::CStr::from_ptr(version as *const _) };
+    // OpenGL ES has no `TEXTURE_SWIZZLE_RGBA`.
+    let version = match version.to_str() {
+        Ok(v) if !v.starts_with("OpenGL ES") => v,
+        _ => return false,
+    };
+    let mut numbers = version
+        .split(|c: char| !c.is_ascii_digit())
+        .map(|n| n.parse::<u32>().unwrap_or(0));
+    let major = numbers.next().unwrap_or(0);
+    let minor = numbers.next().unwrap_or(0);
+    (major, minor) >= (3, 3)
+}
+
+// Converts single channel formats to RGBA when they can not be swizzled.
+pub(crate) fn texture_format(format: Format) -> Format {
+    match format {
+        Format::R8 | Format::Alpha8 if !supports_swizzle() => Format::Rgba8,
+        _ => format,
+    }
+}
+
+// Returns the internal format, pixel format and pixel type of a texture format.
+pub(crate) fn gl_format(format: Format, srgb: bool) -> (GLenum, GLenum, GLenum) {
+    let rgba = if srgb { gl::SRGB_ALPHA } else { gl::RGBA };
+    match format {
+        Format::Rgba8 => (rgba, gl::RGBA, gl::UNSIGNED_BYTE),
+        Format::Bgra8 => (rgba, gl::BGRA, gl::UNSIGNED_BYTE),
+        Format::Rgb8 => (
+            if srgb { gl::SRGB } else { gl::RGB },
+            gl::RGB,
+            gl::UNSIGNED_BYTE,
+        ),
+        Format::R8 | Format::Alpha8 => (gl::R8, gl::RED, gl::UNSIGNED_BYTE),
+        Format::Rgba16F => (gl::RGBA16F, gl::RGBA, gl::HALF_FLOAT),
+    }
+}
+
 impl CreateTexture<()> for Texture {
     fn create<S: Into<[u32; 2]>>(
         _factory: &mut (),
-        _format: Format,
+        format: Format,
         memory: &[u8],
         size: S,
         settings: &TextureSettings,
     ) -> Result<Self, Self::Error> {
         let size = size.into();
+        if memory.len() < format.memory_size(size) {
+            return Err("Texture memory does not match size".into());
+        }
         let mut id: GLuint = 0;
-        let internal_format = if settings.get_convert_gamma() {
-            gl::RGBA
+        let srgb = !settings.get_convert_gamma();
+        let source_format = format;
+        let format = texture_format(format);
+        let (internal_format, pixel_format, pixel_type) = gl_format(format, srgb);
+        let converted;
+        let memory = if format != source_format {
+            let mut buffer = ops::to_rgba8(source_format, memory, size);
+            if settings.get_premultiply_alpha() {
+                ops::premultiply_alpha(&mut buffer, srgb);
+            }
+            converted = buffer;
+            &converted[..]
+        } else if settings.get_premultiply_alpha()
+            && (format == Format::Rgba8 || format == Format::Bgra8)
+        {
+            let mut buffer = memory.to_vec();
+            ops::premultiply_alpha(&mut buffer, srgb);
+            converted = buffer;
+            &converted[..]
         } else {
-            gl::SRGB_ALPHA
+            memory
         };
         unsafe {
             gl::GenTextures(1, &mut id);
-            gl::BindTexture(gl::TEXTURE_2D, id);
-            gl::TexParameteri(
-                gl::TEXTURE_2D,
-                gl::TEXTURE_MIN_FILTER,
-                settings.get_gl_min() as i32,
-            );
-            gl::TexParameteri(
-                gl::TEXTURE_2D,
-                gl::TEXTURE_MAG_FILTER,
-                settings.get_gl_mag() as i32,
-            );
-            gl::TexParameteri(
-                gl::TEXTURE_2D,
-                gl::TEXTURE_WRAP_S,
-                settings.get_gl_wrap_u() as i32,
-            );
-            gl::TexParameteri(
-                gl::TEXTURE_2D,
-                gl::TEXTURE_WRAP_T,
-                settings.get_gl_wrap_v() as i32,
-            );
-            if settings.get_wrap_u() == Wrap::ClampToBorder
-                || settings.get_wrap_v() == Wrap::ClampToBorder
-            {
-                gl::TexParameterfv(
-                    gl::TEXTURE_2D,
-                    gl::TEXTURE_BORDER_COLOR,
-                    settings.get_border_color().as_ptr(),
-                );
-            }
-            if settings.get_generate_mipmap() {
-                gl::GenerateMipmap(gl::TEXTURE_2D);
-            }
+        }
+        with_texture_bound(id, || unsafe {
+            apply_settings(settings);
+            // Rows of formats with less than 4 bytes per pixel are not padded.
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
             gl::TexImage2D(
                 gl::TEXTURE_2D,
                 0,
@@ -247,13 +608,21 @@ impl CreateTexture<()> for Texture {
                 size[0] as i32,
                 size[1] as i32,
                 0,
-                gl::RGBA,
-                gl::UNSIGNED_BYTE,
+                pixel_format,
+                pixel_type,
                 memory.as_ptr() as *const _,
             );
-        }
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 4);
+            apply_swizzle(format, settings);
+            // Mipmaps are generated from the uploaded base level.
+            if settings.get_generate_mipmap() {
+                gl::GenerateMipmap(gl::TEXTURE_2D);
+            }
+        });
 
-        Ok(Texture::new(id, size[0], size[1]))
+        let mut texture = Texture::with_format(id, size, format);
+        texture.mipmaps = settings.get_generate_mipmap();
+        Ok(texture)
     }
 }
 
@@ -261,15 +630,35 @@ impl UpdateTexture<()> for Texture {
     fn update<O: Into<[u32; 2]>, S: Into<[u32; 2]>>(
         &mut self,
         _factory: &mut (),
-        _format: Format,
+        format: Format,
         memory: &[u8],
         offset: O,
         size: S,
     ) -> Result<(), Self::Error> {
         let offset = offset.into();
         let size = size.into();
-        unsafe {
-            gl::BindTexture(gl::TEXTURE_2D, self.id);
+        // OpenGL reads the whole region from memory, so check bounds first.
+        if u64::from(offset[0]) + u64::from(size[0]) > u64::from(self.width)
+            || u64::from(offset[1]) + u64::from(size[1]) > u64::from(self.height)
+        {
+            return Err("Texture update is out of bounds".into());
+        }
+        if memory.len() < format.memory_size(size) {
+            return Err("Texture memory does not match size".into());
+        }
+        // Single channel memory only fits single channel textures,
+        // and is expanded to RGBA like in the software back-end otherwise.
+        let converted;
+        let (format, memory) = match format {
+            Format::R8 | Format::Alpha8 if format != self.format => {
+                converted = ops::to_rgba8(format, memory, size);
+                (Format::Rgba8, &converted[..])
+            }
+            _ => (format, memory),
+        };
+        let (_, pixel_format, pixel_type) = gl_format(format, false);
+        with_texture_bound(self.id, || unsafe {
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
             gl::TexSubImage2D(
                 gl::TEXTURE_2D,
                 0,
@@ -277,11 +666,15 @@ impl UpdateTexture<()> for Texture {
                 offset[1] as i32,
                 size[0] as i32,
                 size[1] as i32,
-                gl::RGBA,
-                gl::UNSIGNED_BYTE,
+                pixel_format,
+                pixel_type,
                 memory.as_ptr() as *const _,
             );
-        }
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 4);
+            if self.mipmaps {
+                gl::GenerateMipmap(gl::TEXTURE_2D);
+            }
+        });
 
         Ok(())
     }
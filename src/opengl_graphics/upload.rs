@@ -0,0 +1,160 @@
+//! Asynchronous texture uploads.
+
+use std::ptr;
+
+use gl::{
+    self,
+    types::{GLsizeiptr, GLsync, GLuint},
+};
+
+use super::{
+    texture::{apply_settings, apply_swizzle, gl_format, texture_format, with_texture_bound},
+    Texture,
+};
+use crate::texture::{ops, Format, TextureSettings};
+
+/// A texture being uploaded in the background through a pixel buffer object.
+///
+/// The pixels are copied into a buffer owned by the driver,
+/// which transfers them to the texture while the application keeps rendering.
+/// Poll `is_ready` each frame, and call `try_finish` to get the texture.
+/// Uploads get cancelled when running out of scope.
+pub struct TextureUpload {
+    texture: Option<Texture>,
+    buffer: GLuint,
+    fence: GLsync,
+}
+
+impl TextureUpload {
+    /// Starts uploading memory in some format to a new texture.
+    pub fn new(
+        memory: &[u8],
+        size: [u32; 2],
+        format: Format,
+        settings: &TextureSettings,
+    ) -> Result<TextureUpload, String> {
+        if memory.len() < format.memory_size(size) {
+            return Err("Texture memory does not match size".into());
+        }
+        let source_format = format;
+        let format = texture_format(format);
+        let converted;
+        let memory = if format != source_format {
+            converted = ops::to_rgba8(source_format, memory, size);
+            &converted[..]
+        } else {
+            memory
+        };
+        let len = format.memory_size(size);
+        let srgb = !settings.get_convert_gamma();
+        let (internal_format, pixel_format, pixel_type) = gl_format(format, srgb);
+
+        let mut buffer: GLuint = 0;
+        let mapped = unsafe {
+            gl::GenBuffers(1, &mut buffer);
+            gl::BindBuffer(gl::PIXEL_UNPACK_BUFFER, buffer);
+            gl::BufferData(
+                gl::PIXEL_UNPACK_BUFFER,
+                len as GLsizeiptr,
+                ptr::null(),
+                gl::STREAM_DRAW,
+            );
+            gl::MapBufferRange(
+                gl::PIXEL_UNPACK_BUFFER,
+                0,
+                len as GLsizeiptr,
+                gl::MAP_WRITE_BIT | gl::MAP_INVALIDATE_BUFFER_BIT,
+            ) as *mut u8
+        };
+        if mapped.is_null() {
+            unsafe {
+                gl::BindBuffer(gl::PIXEL_UNPACK_BUFFER, 0);
+                gl::DeleteBuffers(1, &buffer);
+            }
+            return Err("Could not map pixel buffer".into());
+        }
+        unsafe {
+            let target = std::slice::from_raw_parts_mut(mapped, len);
+            target.copy_from_slice(&memory[..len]);
+            if settings.get_premultiply_alpha()
+                && (format == Format::Rgba8 || format == Format::Bgra8)
+            {
+                ops::premultiply_alpha(target, srgb);
+            }
+            gl::UnmapBuffer(gl::PIXEL_UNPACK_BUFFER);
+        }
+
+        let mut id: GLuint = 0;
+        unsafe {
+            gl::GenTextures(1, &mut id);
+        }
+        let mut texture = Texture::with_format(id, size, format);
+        with_texture_bound(id, || unsafe {
+            apply_settings(settings);
+            // With a pixel buffer bound, the data pointer is an offset into the buffer.
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                internal_format as i32,
+                size[0] as i32,
+                size[1] as i32,
+                0,
+                pixel_format,
+                pixel_type,
+                ptr::null(),
+            );
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 4);
+            apply_swizzle(format, settings);
+        });
+        unsafe {
+            gl::BindBuffer(gl::PIXEL_UNPACK_BUFFER, 0);
+        }
+        if settings.get_generate_mipmap() {
+            texture.generate_mipmaps();
+        }
+        // Flush to make sure the fence gets signaled without waiting for it.
+        let fence = unsafe {
+            let fence = gl::FenceSync(gl::SYNC_GPU_COMMANDS_COMPLETE, 0);
+            gl::Flush();
+            fence
+        };
+        Ok(TextureUpload {
+            texture: Some(texture),
+            buffer,
+            fence,
+        })
+    }
+
+    /// Returns `true` when the texture is ready to be used without stalling.
+    pub fn is_ready(&self) -> bool {
+        let status = unsafe { gl::ClientWaitSync(self.fence, 0, 0) };
+        status == gl::ALREADY_SIGNALED || status == gl::CONDITION_SATISFIED
+    }
+
+    /// Returns the texture if it is ready, or the upload otherwise.
+    pub fn try_finish(self) -> Result<Texture, TextureUpload> {
+        if self.is_ready() {
+            Ok(self.wait())
+        } else {
+            Err(self)
+        }
+    }
+
+    /// Waits until the upload is done and returns the texture.
+    pub fn wait(mut self) -> Texture {
+        unsafe {
+            gl::ClientWaitSync(self.fence, gl::SYNC_FLUSH_COMMANDS_BIT, u64::MAX);
+        }
+        self.texture.take().expect("Texture was already taken")
+    }
+}
+
+impl Drop for TextureUpload {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteSync(self.fence);
+            gl::DeleteBuffers(1, &self.buffer);
+        }
+    }
+}
@@ -0,0 +1,288 @@
+//! Golden image testing.
+//!
+//! Renders a draw closure with the software back-end
+//! and compares the result against a stored reference PNG.
+//!
+//! When the reference image does not exist,
+//! or when the environment variable `PISTON_BLESS` is set,
+//! the rendered image is saved as the new reference.
+//! On mismatch, the rendered image and a diff image are saved
+//! next to the reference as `<name>.actual.png` and `<name>.diff.png`.
+//!
+//! Requires the `golden` feature, which enables the PNG codec.
+//!
+//! ```ignore
+//! use piston::software_graphics::golden::Golden;
+//!
+//! Golden::new(64, 64).assert("tests/golden/rectangle.png", |c, g| {
+//!     clear([1.0; 4], g);
+//!     rectangle([1.0, 0.0, 0.0, 1.0], [8.0, 8.0, 48.0, 48.0], c.transform, g);
+//! });
+//! ```
+
+use std::{
+    error::Error,
+    fmt,
+    fs::File,
+    io::{self, BufReader},
+    path::{Path, PathBuf},
+};
+
+use image::{ImageError, ImageFormat, Rgba, RgbaImage};
+
+use super::SoftwareGraphics;
+use crate::graphics::Context;
+
+/// The environment variable that updates reference images when set.
+pub const BLESS_VAR: &str = "PISTON_BLESS";
+
+/// Compares rendered images against reference images.
+#[derive(Copy, Clone, Debug)]
+pub struct Golden {
+    /// The size of the rendered image in pixels.
+    pub size: [u32; 2],
+    /// The maximum difference per color channel for matching pixels.
+    pub tolerance: u8,
+    /// The number of pixels that may differ beyond the tolerance.
+    pub max_failing_pixels: usize,
+}
+
+/// The difference between two images.
+#[derive(Clone, Debug)]
+pub struct Diff {
+    /// The number of pixels differing beyond the tolerance.
+    pub failing_pixels: usize,
+    /// The maximum difference of a color channel.
+    pub max_difference: u8,
+    /// Failing pixels in red, over a faded copy of the expected image.
+    pub image: RgbaImage,
+}
+
+/// An error from golden image testing.
+#[derive(Debug)]
+pub enum GoldenError {
+    /// An error happened with I/O.
+    Io(io::Error),
+    /// An error happened when decoding or encoding PNG.
+    Png(String),
+    /// The reference image has a different size.
+    SizeMismatch {
+        /// The size of the reference image.
+        expected: [u32; 2],
+        /// The size of the rendered image.
+        actual: [u32; 2],
+    },
+    /// Too many pixels differ from the reference image.
+    Mismatch {
+        /// The number of pixels differing beyond the tolerance.
+        failing_pixels: usize,
+        /// The maximum difference of a color channel.
+        max_difference: u8,
+        /// Where the rendered image was saved.
+        actual: PathBuf,
+        /// Where the diff image was saved.
+        diff: PathBuf,
+    },
+}
+
+impl fmt::Display for GoldenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GoldenError::Io(err) => write!(f, "I/O error: {}", err),
+            GoldenError::Png(err) => write!(f, "PNG error: {}", err),
+            GoldenError::SizeMismatch { expected, actual } => write!(
+                f,
+                "Expected image of size {:?}, found {:?}",
+                expected, actual
+            ),
+            GoldenError::Mismatch {
+                failing_pixels,
+                max_difference,
+                actual,
+                diff,
+            } => write!(
+                f,
+                "{} pixels differ (max channel difference {}), see `{}` and `{}`",
+                failing_pixels,
+                max_difference,
+                actual.display(),
+                diff.display()
+            ),
+        }
+    }
+}
+
+impl Error for GoldenError {}
+
+impl From<io::Error> for GoldenError {
+    fn from(err: io::Error) -> GoldenError {
+        GoldenError::Io(err)
+    }
+}
+
+impl From<ImageError> for GoldenError {
+    fn from(err: ImageError) -> GoldenError {
+        match err {
+            ImageError::IoError(err) => GoldenError::Io(err),
+            err => GoldenError::Png(err.to_string()),
+        }
+    }
+}
+
+impl Golden {
+    /// Creates a new golden image test of size in pixels.
+    ///
+    /// Uses a tolerance of 2 per color channel and no failing pixels.
+    pub fn new(width: u32, height: u32) -> Golden {
+        Golden {
+            size: [width, height],
+            tolerance: 2,
+            max_failing_pixels: 0,
+        }
+    }
+
+    /// Sets the maximum difference per color channel.
+    pub fn tolerance(mut self, value: u8) -> Self {
+        self.tolerance = value;
+        self
+    }
+
+    /// Sets the number of pixels that may differ beyond the tolerance.
+    pub fn max_failing_pixels(mut self, value: usize) -> Self {
+        self.max_failing_pixels = value;
+        self
+    }
+
+    /// Renders the draw closure with the software back-end.
+    pub fn render<F>(&self, f: F) -> RgbaImage
+    where
+        F: FnOnce(Context, &mut SoftwareGraphics),
+    {
+        let mut g = SoftwareGraphics::new(self.size[0], self.size[1]);
+        g.draw(f);
+        g.to_image()
+    }
+
+    /// Compares two images of same size.
+    pub fn diff(&self, expected: &RgbaImage, actual: &RgbaImage) -> Diff {
+        let mut failing_pixels = 0;
+        let mut max_difference = 0;
+        let mut image = RgbaImage::new(expected.width(), expected.height());
+        for ((e, a), d) in expected
+            .pixels()
+            .zip(actual.pixels())
+            .zip(image.pixels_mut())
+        {
+            let difference =
+                e.0.iter()
+                    .zip(a.0.iter())
+                    .map(|(&e, &a)| (e as i16 - a as i16).unsigned_abs() as u8)
+                    .max()
+                    .unwrap_or(0);
+            max_difference = max_difference.max(difference);
+            *d = if difference > self.tolerance {
+                failing_pixels += 1;
+                Rgba([255, 0, 0, 255])
+            } else {
+                let fade = |c: u8| 191 + c / 4;
+                Rgba([fade(e[0]), fade(e[1]), fade(e[2]), 255])
+            };
+        }
+        Diff {
+            failing_pixels,
+            max_difference,
+            image,
+        }
+    }
+
+    /// Renders the draw closure and compares it against a reference PNG.
+    pub fn check<P, F>(&self, path: P, f: F) -> Result<(), GoldenError>
+    where
+        P: AsRef<Path>,
+        F: FnOnce(Context, &mut SoftwareGraphics),
+    {
+        let path = path.as_ref();
+        let actual = self.render(f);
+        if std::env::var_os(BLESS_VAR).is_some() || !path.exists() {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            return save_png(path, &actual);
+        }
+        let expected = load_png(path)?;
+        if expected.dimensions() != actual.dimensions() {
+            return Err(GoldenError::SizeMismatch {
+                expected: [expected.width(), expected.height()],
+                actual: [actual.width(), actual.height()],
+            });
+        }
+        let diff = self.diff(&expected, &actual);
+        if diff.failing_pixels <= self.max_failing_pixels {
+            return Ok(());
+        }
+        let actual_path = path.with_extension("actual.png");
+        let diff_path = path.with_extension("diff.png");
+        save_png(&actual_path, &actual)?;
+        save_png(&diff_path, &diff.image)?;
+        Err(GoldenError::Mismatch {
+            failing_pixels: diff.failing_pixels,
+            max_difference: diff.max_difference,
+            actual: actual_path,
+            diff: diff_path,
+        })
+    }
+
+    /// Same as `check`, but panics with the error on failure.
+    pub fn assert<P, F>(&self, path: P, f: F)
+    where
+        P: AsRef<Path>,
+        F: FnOnce(Context, &mut SoftwareGraphics),
+    {
+        let path = path.as_ref();
+        if let Err(err) = self.check(path, f) {
+            panic!("Golden image `{}` failed: {}", path.display(), err);
+        }
+    }
+}
+
+/// Loads an image from PNG file.
+pub fn load_png<P: AsRef<Path>>(path: P) -> Result<RgbaImage, GoldenError> {
+    let file = BufReader::new(File::open(path)?);
+    let image = image::load(file, ImageFormat::Png).map_err(GoldenError::from)?;
+    Ok(image.to_rgba8())
+}
+
+/// Saves an image as PNG file.
+pub fn save_png<P: AsRef<Path>>(path: P, image: &RgbaImage) -> Result<(), GoldenError> {
+    image
+        .save_with_format(path, ImageFormat::Png)
+        .map_err(GoldenError::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics::{clear, rectangle};
+
+    #[test]
+    fn test_golden() {
+        let dir = std::env::temp_dir().join(format!("piston_golden_{}", std::process::id()));
+        let path = dir.join("rectangle.png");
+        let golden = Golden::new(8, 8);
+        let draw = |color| {
+            move |c: Context, g: &mut SoftwareGraphics| {
+                clear([1.0; 4], g);
+                rectangle(color, [2.0, 2.0, 4.0, 4.0], c.transform, g);
+            }
+        };
+        // Creates the reference image.
+        golden.check(&path, draw([1.0, 0.0, 0.0, 1.0])).unwrap();
+        assert!(golden.check(&path, draw([1.0, 0.0, 0.0, 1.0])).is_ok());
+        match golden.check(&path, draw([0.0, 0.0, 1.0, 1.0])) {
+            Err(GoldenError::Mismatch { failing_pixels, .. }) => assert_eq!(failing_pixels, 16),
+            x => panic!("Expected mismatch, found {:?}", x),
+        }
+        assert!(dir.join("rectangle.diff.png").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,431 @@
+//! A software rasterizer back-end for Piston-Graphics.
+//!
+//! Renders triangles on the CPU into an image,
+//! which is useful for testing and offscreen rendering without a GPU.
+//!
+//! Blending happens in linear color space and the result is stored as sRGB,
+//! which matches the OpenGL back-end with an sRGB frame buffer.
+//! Textures are sampled with nearest filtering.
+
+use image::RgbaImage;
+
+use crate::{
+    graphics::{
+        capabilities::Capabilities,
+        color::{gamma_linear_to_srgb, gamma_srgb_to_linear},
+        draw_state::{Blend, Stencil},
+        types::Color,
+        Context, DrawState, Graphics, Viewport,
+    },
+    texture::ImageSize,
+};
+
+pub use self::{render_target::RenderTarget, texture::Texture};
+
+#[cfg(any(test, feature = "golden"))]
+pub mod golden;
+mod render_target;
+mod texture;
+
+/// Renders 2D graphics into an image on the CPU.
+pub struct SoftwareGraphics {
+    width: u32,
+    height: u32,
+    // Colors in linear color space.
+    color: Vec<Color>,
+    stencil: Vec<u8>,
+}
+
+impl SoftwareGraphics {
+    /// Creates a new software back-end with transparent black pixels.
+    pub fn new(width: u32, height: u32) -> SoftwareGraphics {
+        let n = width as usize * height as usize;
+        SoftwareGraphics {
+            width,
+            height,
+            color: vec![[0.0; 4]; n],
+            stencil: vec![0; n],
+        }
+    }
+
+    /// Returns the viewport covering the whole image.
+    pub fn viewport(&self) -> Viewport {
+        Viewport {
+            rect: [0, 0, self.width as i32, self.height as i32],
+            draw_size: [self.width, self.height],
+            window_size: [self.width as f64, self.height as f64],
+        }
+    }
+
+    /// Convenience for drawing with a context covering the whole image.
+    pub fn draw<F, U>(&mut self, f: F) -> U
+    where
+        F: FnOnce(Context, &mut Self) -> U,
+    {
+        let c = Context::new_viewport(self.viewport());
+        f(c, self)
+    }
+
+    /// Returns the color of a pixel in sRGB color space.
+    pub fn get_pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        to_srgb8(self.color[(y * self.width + x) as usize])
+    }
+
+    /// Returns the rendered image in sRGB color space.
+    pub fn to_image(&self) -> RgbaImage {
+        let mut image = RgbaImage::new(self.width, self.height);
+        for (pixel, &color) in image.pixels_mut().zip(self.color.iter()) {
+            pixel.0 = to_srgb8(color);
+        }
+        image
+    }
+
+    // Rasterizes triangles, calling `shade` with the triangle start index
+    // and barycentric coordinates to get the linear source color.
+    fn rasterize<F>(&mut self, draw_state: &DrawState, vertices: &[[f32; 2]], mut shade: F)
+    where
+        F: FnMut(usize, [f32; 3]) -> Color,
+    {
+        let (w, h) = (self.width as f32, self.height as f32);
+        let (mut x0, mut y0, mut x1, mut y1) = (0, 0, self.width, self.height);
+        if let Some([sx, sy, sw, sh]) = draw_state.scissor {
+            x0 = sx.min(self.width);
+            y0 = sy.min(self.height);
+            x1 = (sx + sw).min(self.width);
+            y1 = (sy + sh).min(self.height);
+        }
+        for (i, tri) in vertices.chunks_exact(3).enumerate() {
+            let to_pixel = |v: [f32; 2]| [(v[0] + 1.0) * 0.5 * w, (1.0 - v[1]) * 0.5 * h];
+            let mut p = [to_pixel(tri[0]), to_pixel(tri[1]), to_pixel(tri[2])];
+            let mut ind = [0, 1, 2];
+            let mut area = edge(p[0], p[1], p[2]);
+            if area == 0.0 || !area.is_finite() {
+                continue;
+            }
+            // Use the same winding for all triangles, such that the fill rule
+            // assigns pixels on shared edges to exactly one triangle.
+            if area < 0.0 {
+                p.swap(1, 2);
+                ind.swap(1, 2);
+                area = -area;
+            }
+            let min_x = p.iter().fold(w, |a, q| a.min(q[0])).floor().max(x0 as f32) as u32;
+            let min_y = p.iter().fold(h, |a, q| a.min(q[1])).floor().max(y0 as f32) as u32;
+            let max_x = p
+                .iter()
+                .fold(0.0, |a: f32, q| a.max(q[0]))
+                .ceil()
+                .min(x1 as f32) as u32;
+            let max_y = p
+                .iter()
+                .fold(0.0, |a: f32, q| a.max(q[1]))
+                .ceil()
+                .min(y1 as f32) as u32;
+            let edges = [[p[1], p[2]], [p[2], p[0]], [p[0], p[1]]];
+            for y in min_y..max_y {
+                for x in min_x..max_x {
+                    let q = [x as f32 + 0.5, y as f32 + 0.5];
+                    let mut bary = [0.0; 3];
+                    let mut inside = true;
+                    for (j, &[a, b]) in edges.iter().enumerate() {
+                        let e = edge(a, b, q);
+                        let (dx, dy) = (b[0] - a[0], b[1] - a[1]);
+                        if e < 0.0 || (e == 0.0 && !(dy > 0.0 || (dy == 0.0 && dx < 0.0))) {
+                            inside = false;
+                            break;
+                        }
+                        bary[ind[j]] = e / area;
+                    }
+                    if inside {
+                        let index = (y * self.width + x) as usize;
+                        if self.stencil_test(draw_state.stencil, index) {
+                            let src = shade(i * 3, bary);
+                            let dst = &mut self.color[index];
+                            let res = blend(draw_state.blend, src, *dst);
+                            for (i, &write) in draw_state.color_mask.iter().enumerate() {
+                                if write {
+                                    dst[i] = res[i];
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // Tests and updates the stencil buffer, returning `true` if color is written.
+    fn stencil_test(&mut self, stencil: Option<Stencil>, index: usize) -> bool {
+        let value = &mut self.stencil[index];
+        match stencil {
+            None => true,
+            Some(Stencil::Clip(v)) => {
+                *value = v;
+                false
+            }
+            Some(Stencil::Increment) => {
+                *value = value.saturating_add(1);
+                false
+            }
+            Some(Stencil::Decrement) => {
+                *value = value.saturating_sub(1);
+                false
+            }
+            Some(Stencil::Inside(v)) => *value == v,
+            Some(Stencil::Outside(v)) => *value != v,
+        }
+    }
+}
+
+// Twice the signed area of triangle `a, b, p`.
+fn edge(a: [f32; 2], b: [f32; 2], p: [f32; 2]) -> f32 {
+    (b[0] - a[0]) * (p[1] - a[1]) - (b[1] - a[1]) * (p[0] - a[0])
+}
+
+fn to_srgb8(color: Color) -> [u8; 4] {
+    let c = gamma_linear_to_srgb(color);
+    let f = |x: f32| (x.clamp(0.0, 1.0) * 255.0).round() as u8;
+    [f(c[0]), f(c[1]), f(c[2]), f(c[3])]
+}
+
+fn blend(blend: Option<Blend>, s: Color, d: Color) -> Color {
+    let sa = s[3];
+    let rgb = |f: &dyn Fn(f32, f32) -> f32| [f(s[0], d[0]), f(s[1], d[1]), f(s[2], d[2])];
+    let (c, a) = match blend {
+        None => ([s[0], s[1], s[2]], sa),
+        Some(Blend::Alpha) => (rgb(&|s, d| s * sa + d * (1.0 - sa)), sa + d[3]),
+        Some(Blend::Add) => (rgb(&|s, d| s + d), sa + d[3]),
+        Some(Blend::Lighter) => (rgb(&|s, d| s * sa + d), d[3]),
+        Some(Blend::Multiply) => (rgb(&|s, d| s * d), sa * d[3]),
+        // Subtracts destination weighted by source from white source color.
+        Some(Blend::Invert) => (rgb(&|s, d| s - d * s), d[3]),
+        Some(Blend::Screen) => (rgb(&|s, d| s + d * (1.0 - s)), sa + d[3] * (1.0 - sa)),
+        Some(Blend::Subtract) => (rgb(&|s, d| d - s * sa), d[3]),
+        Some(Blend::Darken) => (rgb(&|s, d| s.min(d)), sa + d[3] * (1.0 - sa)),
+        Some(Blend::Lighten) => (rgb(&|s, d| s.max(d)), sa + d[3] * (1.0 - sa)),
+        Some(Blend::Mask) => (rgb(&|_, d| d * sa), d[3] * sa),
+        Some(Blend::PremultipliedAlpha) => {
+            (rgb(&|s, d| s + d * (1.0 - sa)), sa + d[3] * (1.0 - sa))
+        }
+    };
+    let clamp = |x: f32| x.clamp(0.0, 1.0);
+    [clamp(c[0]), clamp(c[1]), clamp(c[2]), clamp(a)]
+}
+
+// Interpolates attributes of a triangle with barycentric coordinates.
+fn interpolate<const N: usize>(a: &[[f32; N]], bary: [f32; 3]) -> [f32; N] {
+    let mut res = [0.0; N];
+    for (i, r) in res.iter_mut().enumerate() {
+        *r = a[0][i] * bary[0] + a[1][i] * bary[1] + a[2][i] * bary[2];
+    }
+    res
+}
+
+fn mul(a: Color, b: Color) -> Color {
+    [a[0] * b[0], a[1] * b[1], a[2] * b[2], a[3] * b[3]]
+}
+
+impl Graphics for SoftwareGraphics {
+    type Texture = Texture;
+
+    fn clear_color(&mut self, color: Color) {
+        let color = gamma_srgb_to_linear(color);
+        for c in &mut self.color {
+            *c = color;
+        }
+    }
+
+    fn clear_stencil(&mut self, value: u8) {
+        for s in &mut self.stencil {
+            *s = value;
+        }
+    }
+
+    fn tri_list<F>(&mut self, draw_state: &DrawState, color: &[f32; 4], mut f: F)
+    where
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]])),
+    {
+        let color = gamma_srgb_to_linear(*color);
+        f(&mut |vertices| self.rasterize(draw_state, vertices, |_, _| color));
+    }
+
+    fn tri_list_c<F>(&mut self, draw_state: &DrawState, mut f: F)
+    where
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]], &[[f32; 4]])),
+    {
+        f(&mut |vertices, colors| {
+            // Interpolate in linear color space, like the OpenGL back-end.
+            let colors: Vec<Color> = colors.iter().map(|&c| gamma_srgb_to_linear(c)).collect();
+            self.rasterize(draw_state, vertices, |i, bary| {
+                interpolate(&colors[i..i + 3], bary)
+            })
+        });
+    }
+
+    fn tri_list_uv<F>(
+        &mut self,
+        draw_state: &DrawState,
+        color: &[f32; 4],
+        texture: &Texture,
+        mut f: F,
+    ) where
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]], &[[f32; 2]])),
+    {
+        let color = gamma_srgb_to_linear(*color);
+        f(&mut |vertices, uvs| {
+            self.rasterize(draw_state, vertices, |i, bary| {
+                mul(texture.sample(interpolate(&uvs[i..i + 3], bary)), color)
+            })
+        });
+    }
+
+    fn tri_list_uv_c<F>(&mut self, draw_state: &DrawState, texture: &Texture, mut f: F)
+    where
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]], &[[f32; 2]], &[[f32; 4]])),
+    {
+        f(&mut |vertices, uvs, colors| {
+            let colors: Vec<Color> = colors.iter().map(|&c| gamma_srgb_to_linear(c)).collect();
+            self.rasterize(draw_state, vertices, |i, bary| {
+                let color = interpolate(&colors[i..i + 3], bary);
+                mul(texture.sample(interpolate(&uvs[i..i + 3], bary)), color)
+            })
+        });
+    }
+
+    fn current_viewport(&self) -> Option<Viewport> {
+        Some(self.viewport())
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            stencil_clip: true,
+            srgb: true,
+            render_to_texture: true,
+            ..Default::default()
+        }
+    }
+}
+
+impl ImageSize for SoftwareGraphics {
+    fn get_size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics::{clear, Rectangle};
+
+    #[test]
+    fn test_rasterize() {
+        let mut g = SoftwareGraphics::new(4, 4);
+        g.draw(|c, g| {
+            clear([0.0, 0.0, 0.0, 1.0], g);
+            Rectangle::new([1.0, 0.0, 0.0, 1.0]).draw(
+                [0.0, 0.0, 2.0, 4.0],
+                &c.draw_state,
+                c.transform,
+                g,
+            );
+            // Shared edges are not blended twice.
+            Rectangle::new([0.0, 0.0, 1.0, 0.5]).draw(
+                [2.0, 0.0, 2.0, 4.0],
+                &c.draw_state,
+                c.transform,
+                g,
+            );
+        });
+        assert_eq!(g.get_pixel(0, 0), [255, 0, 0, 255]);
+        assert_eq!(g.get_pixel(1, 3), [255, 0, 0, 255]);
+        assert_eq!(g.get_pixel(3, 2), [0, 0, 188, 255]);
+        assert_eq!(g.get_pixel(2, 1), g.get_pixel(3, 2));
+    }
+
+    #[test]
+    fn test_blend() {
+        let (s, d) = ([0.5, 0.25, 1.0, 1.0], [0.5, 0.5, 0.0, 1.0]);
+        assert_eq!(blend(Some(Blend::Screen), s, d), [0.75, 0.625, 1.0, 1.0]);
+        assert_eq!(blend(Some(Blend::Subtract), s, d), [0.0, 0.25, 0.0, 1.0]);
+        assert_eq!(blend(Some(Blend::Darken), s, d), [0.5, 0.25, 0.0, 1.0]);
+        assert_eq!(blend(Some(Blend::Lighten), s, d), [0.5, 0.5, 1.0, 1.0]);
+        let s = [0.25, 0.0, 0.0, 0.5];
+        assert_eq!(
+            blend(Some(Blend::PremultipliedAlpha), s, d),
+            [0.5, 0.25, 0.0, 1.0]
+        );
+    }
+
+    #[test]
+    fn test_color_mask() {
+        let mut g = SoftwareGraphics::new(2, 2);
+        g.draw(|c, g| {
+            clear([1.0, 0.0, 0.0, 1.0], g);
+            let draw_state = c.draw_state.blend(Blend::Add).alpha_only();
+            Rectangle::new([0.0, 1.0, 0.0, 0.0]).draw(
+                [0.0, 0.0, 2.0, 2.0],
+                &draw_state,
+                c.transform,
+                g,
+            );
+        });
+        assert_eq!(g.get_pixel(0, 0), [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_premultiplied_texture() {
+        use crate::{
+            graphics::{image, DrawState, Image},
+            texture::{CreateTexture, Format, TextureSettings},
+        };
+
+        // Red at half opacity, premultiplied in linear color space.
+        let memory = [255, 0, 0, 128];
+        let create = |settings: &TextureSettings| {
+            Texture::create(&mut (), Format::Rgba8, &memory, [1, 1], settings).unwrap()
+        };
+        let straight = create(&TextureSettings::new());
+        let premultiplied = create(&TextureSettings::new().premultiply_alpha(true));
+        assert_eq!(
+            premultiplied.get_image().get_pixel(0, 0).0,
+            [188, 0, 0, 128]
+        );
+
+        let mut g = SoftwareGraphics::new(2, 1);
+        g.draw(|c, g| {
+            clear([0.0, 0.0, 1.0, 1.0], g);
+            image(&straight, c.transform, g);
+            Image::new().rect([1.0, 0.0, 1.0, 1.0]).draw(
+                &premultiplied,
+                &DrawState::new_premultiplied(),
+                c.transform,
+                g,
+            );
+        });
+        let (a, b) = (g.get_pixel(0, 0), g.get_pixel(1, 0));
+        for i in 0..4 {
+            assert!((a[i] as i32 - b[i] as i32).abs() <= 1, "{:?} {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn test_line_and_point_list() {
+        let mut g = SoftwareGraphics::new(8, 8);
+        g.draw(|c, g| {
+            let white = [1.0; 4];
+            g.line_list(
+                &c.draw_state,
+                &white,
+                0.5,
+                &[[0.0, 1.0, 8.0, 1.0], [1.0, 0.0, 1.0, 8.0]],
+                c.transform,
+            );
+            g.point_list(&c.draw_state, &white, 1.0, &[[5.0, 5.0]], c.transform);
+        });
+        assert_eq!(g.get_pixel(6, 1), [255; 4]);
+        assert_eq!(g.get_pixel(1, 6), [255; 4]);
+        assert_eq!(g.get_pixel(4, 5), [255; 4]);
+        assert_eq!(g.get_pixel(6, 6), [0; 4]);
+        assert!(g.capabilities().stencil_clip);
+    }
+}
@@ -0,0 +1,82 @@
+//! Offscreen render targets.
+
+use super::{SoftwareGraphics, Texture};
+use crate::{
+    graphics::{render_texture::RenderToTexture, Context},
+    texture::ImageSize,
+};
+
+/// An image that is rendered to, with a texture of the result.
+///
+/// Use `RenderToTexture::draw_to_texture` to render to the target,
+/// which updates the texture afterwards.
+pub struct RenderTarget {
+    graphics: SoftwareGraphics,
+    texture: Texture,
+}
+
+impl RenderTarget {
+    /// Creates a new render target of size in pixels, with transparent black pixels.
+    pub fn new(width: u32, height: u32) -> RenderTarget {
+        let graphics = SoftwareGraphics::new(width, height);
+        let texture = Texture::new(graphics.to_image());
+        RenderTarget { graphics, texture }
+    }
+
+    /// Gets the texture with the result of the last rendering.
+    pub fn get_texture(&self) -> &Texture {
+        &self.texture
+    }
+}
+
+impl ImageSize for RenderTarget {
+    fn get_size(&self) -> (u32, u32) {
+        self.graphics.get_size()
+    }
+}
+
+impl RenderToTexture for SoftwareGraphics {
+    type RenderTarget = RenderTarget;
+
+    fn create_render_target(&mut self, width: u32, height: u32) -> Result<RenderTarget, String> {
+        Ok(RenderTarget::new(width, height))
+    }
+
+    fn render_target_texture(target: &RenderTarget) -> &Texture {
+        target.get_texture()
+    }
+
+    fn draw_to_texture<F, U>(&mut self, target: &mut RenderTarget, f: F) -> U
+    where
+        F: FnOnce(Context, &mut Self) -> U,
+    {
+        let res = target.graphics.draw(f);
+        target.texture = Texture::new(target.graphics.to_image());
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics::{image, rectangle, Transformed};
+
+    #[test]
+    fn test_draw_to_texture() {
+        let mut g = SoftwareGraphics::new(8, 8);
+        let mut target = g.create_render_target(4, 4).unwrap();
+        g.draw_to_texture(&mut target, |c, g| {
+            rectangle([1.0, 0.0, 0.0, 1.0], [0.0, 0.0, 4.0, 2.0], c.transform, g)
+        });
+        g.draw(|c, g| {
+            image(
+                SoftwareGraphics::render_target_texture(&target),
+                c.transform.trans(4.0, 4.0),
+                g,
+            )
+        });
+        assert_eq!(g.get_pixel(5, 4), [255, 0, 0, 255]);
+        assert_eq!(g.get_pixel(5, 7), [0; 4]);
+        assert_eq!(g.get_pixel(1, 1), [0; 4]);
+    }
+}
@@ -0,0 +1,125 @@
+use image::RgbaImage;
+
+use crate::{
+    graphics::{color::gamma_srgb_to_linear, types::Color},
+    texture::{ops, CreateTexture, Format, ImageSize, TextureOp, TextureSettings, UpdateTexture},
+};
+
+/// A texture stored in memory in sRGB color space.
+pub struct Texture {
+    image: RgbaImage,
+}
+
+impl Texture {
+    /// Creates a new texture from image.
+    pub fn new(image: RgbaImage) -> Self {
+        Texture { image }
+    }
+
+    /// Gets the image of the texture.
+    pub fn get_image(&self) -> &RgbaImage {
+        &self.image
+    }
+
+    // Samples the texture with nearest filtering, returning linear color.
+    pub(crate) fn sample(&self, uv: [f32; 2]) -> Color {
+        let (w, h) = self.image.dimensions();
+        if w == 0 || h == 0 {
+            return [0.0; 4];
+        }
+        let x = ((uv[0] * w as f32).floor().max(0.0) as u32).min(w - 1);
+        let y = ((uv[1] * h as f32).floor().max(0.0) as u32).min(h - 1);
+        let p = self.image.get_pixel(x, y).0;
+        let f = |c: u8| c as f32 / 255.0;
+        gamma_srgb_to_linear([f(p[0]), f(p[1]), f(p[2]), f(p[3])])
+    }
+}
+
+impl ImageSize for Texture {
+    fn get_size(&self) -> (u32, u32) {
+        self.image.dimensions()
+    }
+}
+
+impl TextureOp<()> for Texture {
+    type Error = String;
+}
+
+impl CreateTexture<()> for Texture {
+    fn create<S: Into<[u32; 2]>>(
+        _factory: &mut (),
+        format: Format,
+        memory: &[u8],
+        size: S,
+        settings: &TextureSettings,
+    ) -> Result<Self, Self::Error> {
+        let [w, h] = size.into();
+        if memory.len() < format.memory_size([w, h]) {
+            return Err("Texture memory does not match size".into());
+        }
+        let mut memory = ops::to_rgba8(format, memory, [w, h]);
+        if settings.get_premultiply_alpha() {
+            ops::premultiply_alpha(&mut memory, true);
+        }
+        RgbaImage::from_raw(w, h, memory)
+            .map(Texture::new)
+            .ok_or_else(|| "Texture memory does not match size".into())
+    }
+}
+
+impl UpdateTexture<()> for Texture {
+    fn update<O: Into<[u32; 2]>, S: Into<[u32; 2]>>(
+        &mut self,
+        _factory: &mut (),
+        format: Format,
+        memory: &[u8],
+        offset: O,
+        size: S,
+    ) -> Result<(), Self::Error> {
+        let [x, y] = offset.into();
+        let [w, h] = size.into();
+        let (tw, th) = self.image.dimensions();
+        if x + w > tw || y + h > th || memory.len() < format.memory_size([w, h]) {
+            return Err("Texture update is out of bounds".into());
+        }
+        let memory = ops::to_rgba8(format, memory, [w, h]);
+        for iy in 0..h {
+            for ix in 0..w {
+                let i = ((iy * w + ix) * 4) as usize;
+                let p = self.image.get_pixel_mut(x + ix, y + iy);
+                p.0.copy_from_slice(&memory[i..i + 4]);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_formats() {
+        let settings = TextureSettings::new();
+        let create = |format, memory: &[u8]| {
+            let texture: Texture =
+                CreateTexture::create(&mut (), format, memory, [1, 1], &settings).unwrap();
+            texture.get_image().get_pixel(0, 0).0
+        };
+        assert_eq!(create(Format::Bgra8, &[1, 2, 3, 4]), [3, 2, 1, 4]);
+        assert_eq!(create(Format::Rgb8, &[1, 2, 3]), [1, 2, 3, 255]);
+        assert_eq!(create(Format::Alpha8, &[9]), [255, 255, 255, 9]);
+        // 1.0, 0.5, 0.0 and 2.0 as half floats.
+        let half = [0x00, 0x3c, 0x00, 0x38, 0x00, 0x00, 0x00, 0x40];
+        assert_eq!(create(Format::Rgba16F, &half), [255, 128, 0, 255]);
+
+        let mut texture = Texture::new(RgbaImage::new(2, 2));
+        assert!(texture
+            .update(&mut (), Format::R8, &[7], [1, 1], [1, 1])
+            .is_ok());
+        assert_eq!(texture.get_image().get_pixel(1, 1).0, [7, 7, 7, 255]);
+        assert!(texture
+            .update(&mut (), Format::R8, &[7], [1, 1], [1, 2])
+            .is_err());
+    }
+}
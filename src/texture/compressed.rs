@@ -0,0 +1,214 @@
+//! Block compressed images.
+//!
+//! Compressed images stay compressed on the GPU,
+//! which reduces memory usage and load times for large textures.
+//! They are usually stored in DDS or KTX2 containers, created by offline tools.
+
+use std::convert::TryFrom;
+
+/// Block compression format.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CompressedFormat {
+    /// BC1, also known as DXT1, with 1-bit alpha.
+    Bc1,
+    /// BC2, also known as DXT3, with explicit alpha.
+    Bc2,
+    /// BC3, also known as DXT5, with interpolated alpha.
+    Bc3,
+    /// ETC2 without alpha.
+    Etc2Rgb,
+    /// ETC2 with EAC alpha.
+    Etc2Rgba,
+    /// ASTC with 4x4 blocks.
+    Astc4x4,
+}
+
+impl CompressedFormat {
+    /// Returns the number of bytes per block of 4x4 pixels.
+    pub fn bytes_per_block(self) -> usize {
+        match self {
+            CompressedFormat::Bc1 | CompressedFormat::Etc2Rgb => 8,
+            CompressedFormat::Bc2
+            | CompressedFormat::Bc3
+            | CompressedFormat::Etc2Rgba
+            | CompressedFormat::Astc4x4 => 16,
+        }
+    }
+
+    /// Returns the number of bytes of an image of some size.
+    ///
+    /// Saturates at `usize::MAX` for sizes that do not fit in memory.
+    pub fn memory_size(self, size: [u32; 2]) -> usize {
+        self.checked_memory_size(size).unwrap_or(usize::MAX)
+    }
+
+    // Returns the number of bytes of an image of some size, or `None` on overflow.
+    fn checked_memory_size(self, size: [u32; 2]) -> Option<usize> {
+        let blocks = |n: u32| n.div_ceil(4).max(1) as usize;
+        blocks(size[0])
+            .checked_mul(blocks(size[1]))?
+            .checked_mul(self.bytes_per_block())
+    }
+}
+
+/// A compressed image with mipmap levels.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CompressedImage {
+    /// The compression format.
+    pub format: CompressedFormat,
+    /// The size of the first level.
+    pub size: [u32; 2],
+    /// The data of each level, starting with the largest.
+    pub levels: Vec<Vec<u8>>,
+}
+
+impl CompressedImage {
+    /// Loads an image from a DDS or KTX2 container, detected by its header.
+    pub fn from_bytes(bytes: &[u8]) -> Result<CompressedImage, String> {
+        if bytes.starts_with(b"DDS ") {
+            CompressedImage::from_dds(bytes)
+        } else if bytes.starts_with(&KTX2_IDENTIFIER) {
+            CompressedImage::from_ktx2(bytes)
+        } else {
+            Err("Unknown compressed image container".into())
+        }
+    }
+
+    /// Loads an image from a DDS container.
+    pub fn from_dds(bytes: &[u8]) -> Result<CompressedImage, String> {
+        if !bytes.starts_with(b"DDS ") {
+            return Err("Not a DDS file".into());
+        }
+        let height = read_u32(bytes, 12)?;
+        let width = read_u32(bytes, 16)?;
+        let levels = read_u32(bytes, 28)?.max(1);
+        let (format, start) = match bytes.get(84..88) {
+            Some(b"DXT1") => (CompressedFormat::Bc1, 128),
+            Some(b"DXT3") => (CompressedFormat::Bc2, 128),
+            Some(b"DXT5") => (CompressedFormat::Bc3, 128),
+            Some(b"DX10") => {
+                let format = match read_u32(bytes, 128)? {
+                    71 | 72 => CompressedFormat::Bc1,
+                    74 | 75 => CompressedFormat::Bc2,
+                    77 | 78 => CompressedFormat::Bc3,
+                    n => return Err(format!("Unsupported DXGI format {}", n)),
+                };
+                (format, 148)
+            }
+            _ => return Err("Unsupported DDS pixel format".into()),
+        };
+        let mut res = CompressedImage {
+            format,
+            size: [width, height],
+            levels: vec![],
+        };
+        let mut pos: usize = start;
+        for level in 0..levels {
+            let data = format
+                .checked_memory_size(mip_size(res.size, level))
+                .and_then(|len| bytes.get(pos..pos.checked_add(len)?))
+                .ok_or_else(|| "Unexpected end of DDS file".to_string())?;
+            res.levels.push(data.to_vec());
+            pos += data.len();
+        }
+        Ok(res)
+    }
+
+    /// Loads an image from a KTX2 container, without supercompression.
+    pub fn from_ktx2(bytes: &[u8]) -> Result<CompressedImage, String> {
+        if !bytes.starts_with(&KTX2_IDENTIFIER) {
+            return Err("Not a KTX2 file".into());
+        }
+        let format = match read_u32(bytes, 12)? {
+            131..=134 => CompressedFormat::Bc1,
+            135 | 136 => CompressedFormat::Bc2,
+            137 | 138 => CompressedFormat::Bc3,
+            147 | 148 => CompressedFormat::Etc2Rgb,
+            151 | 152 => CompressedFormat::Etc2Rgba,
+            157 | 158 => CompressedFormat::Astc4x4,
+            n => return Err(format!("Unsupported Vulkan format {}", n)),
+        };
+        let size = [read_u32(bytes, 20)?, read_u32(bytes, 24)?];
+        let levels = read_u32(bytes, 40)?.max(1);
+        if read_u32(bytes, 44)? != 0 {
+            return Err("KTX2 supercompression is not supported".into());
+        }
+        let mut res = CompressedImage {
+            format,
+            size,
+            levels: vec![],
+        };
+        for level in 0..levels as usize {
+            let index = 80 + level * 24;
+            let offset = read_u64(bytes, index)?;
+            let len = read_u64(bytes, index + 8)?;
+            let data = usize::try_from(offset)
+                .ok()
+                .zip(usize::try_from(len).ok())
+                .and_then(|(offset, len)| bytes.get(offset..offset.checked_add(len)?))
+                .ok_or_else(|| "Unexpected end of KTX2 file".to_string())?;
+            res.levels.push(data.to_vec());
+        }
+        Ok(res)
+    }
+}
+
+const KTX2_IDENTIFIER: [u8; 12] = [
+    0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+];
+
+// Returns the size of a mipmap level.
+fn mip_size(size: [u32; 2], level: u32) -> [u32; 2] {
+    let f = |n: u32| n.checked_shr(level).unwrap_or(0).max(1);
+    [f(size[0]), f(size[1])]
+}
+
+fn read_u32(bytes: &[u8], pos: usize) -> Result<u32, String> {
+    bytes
+        .get(pos..pos + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(|| "Unexpected end of header".into())
+}
+
+fn read_u64(bytes: &[u8], pos: usize) -> Result<u64, String> {
+    Ok(u64::from(read_u32(bytes, pos)?) | (u64::from(read_u32(bytes, pos + 4)?) << 32))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_containers() {
+        // A 8x4 DXT5 image with 2 levels.
+        let mut dds = vec![0; 128];
+        dds[..4].copy_from_slice(b"DDS ");
+        dds[12..16].copy_from_slice(&4u32.to_le_bytes());
+        dds[16..20].copy_from_slice(&8u32.to_le_bytes());
+        dds[28..32].copy_from_slice(&2u32.to_le_bytes());
+        dds[84..88].copy_from_slice(b"DXT5");
+        dds.extend(std::iter::repeat_n(1, 32 + 16));
+        let image = CompressedImage::from_bytes(&dds).unwrap();
+        assert_eq!(image.format, CompressedFormat::Bc3);
+        assert_eq!(image.size, [8, 4]);
+        assert_eq!(image.levels.len(), 2);
+        assert_eq!(image.levels[1].len(), 16);
+        assert!(CompressedImage::from_bytes(&dds[..150]).is_err());
+        let mut huge = dds.clone();
+        huge[12..20].copy_from_slice(&[0xFF; 8]);
+        assert!(CompressedImage::from_bytes(&huge).is_err());
+
+        // A 4x4 ETC2 image with 1 level.
+        let mut ktx2 = vec![0; 104];
+        ktx2[..12].copy_from_slice(&KTX2_IDENTIFIER);
+        ktx2[12..16].copy_from_slice(&147u32.to_le_bytes());
+        ktx2[20..24].copy_from_slice(&4u32.to_le_bytes());
+        ktx2[24..28].copy_from_slice(&4u32.to_le_bytes());
+        ktx2[80..88].copy_from_slice(&104u64.to_le_bytes());
+        ktx2[88..96].copy_from_slice(&8u64.to_le_bytes());
+        ktx2.extend_from_slice(&[2; 8]);
+        let image = CompressedImage::from_bytes(&ktx2).unwrap();
+        assert_eq!(image.format, CompressedFormat::Etc2Rgb);
+        assert_eq!(image.levels, vec![vec![2; 8]]);
+    }
+}
@@ -8,6 +8,7 @@
 //! For more information, see
 //! [Piston-Graphics](https://github.com/pistondevelopers/graphics).
 
+pub mod compressed;
 pub mod ops;
 
 /// Implemented by all images to be used with generic algorithms.
@@ -30,6 +31,12 @@ pub trait ImageSize {
     }
 }
 
+impl<T: ImageSize> ImageSize for std::rc::Rc<T> {
+    fn get_size(&self) -> (u32, u32) {
+        (**self).get_size()
+    }
+}
+
 /// Texture creation parameters.
 #[derive(Clone, Copy, Debug)]
 pub struct TextureSettings {
@@ -51,6 +58,8 @@ pub struct TextureSettings {
     wrap_v: Wrap,
     // Border Color if ClampToBorder is specified as wrap mode
     border_color: [f32; 4],
+    // Whether to premultiply color by alpha when creating.
+    premultiply_alpha: bool,
 }
 
 impl Default for TextureSettings {
@@ -73,6 +82,7 @@ impl TextureSettings {
             wrap_u: Wrap::ClampToEdge,
             wrap_v: Wrap::ClampToEdge,
             border_color: [0.0, 0.0, 0.0, 1.0],
+            premultiply_alpha: false,
         }
     }
 
@@ -218,13 +228,58 @@ impl TextureSettings {
         self.set_border_color(val);
         self
     }
+
+    /// Gets whether to premultiply color by alpha when creating.
+    pub fn get_premultiply_alpha(&self) -> bool {
+        self.premultiply_alpha
+    }
+    /// Sets premultiply alpha.
+    ///
+    /// Premultiplied textures avoid dark fringes at edges when scaled,
+    /// and should be drawn with `Blend::PremultipliedAlpha`.
+    /// Updates to the texture must be premultiplied, see `ops::premultiply_alpha`.
+    pub fn set_premultiply_alpha(&mut self, val: bool) {
+        self.premultiply_alpha = val;
+    }
+    /// Sets premultiply alpha.
+    pub fn premultiply_alpha(mut self, val: bool) -> Self {
+        self.set_premultiply_alpha(val);
+        self
+    }
 }
 
 /// Texture format.
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum Format {
     /// `(red, green, blue, alpha)` with values 0-255.
     Rgba8,
+    /// `(red, green, blue)` with values 0-255, opaque.
+    Rgb8,
+    /// `(blue, green, red, alpha)` with values 0-255.
+    Bgra8,
+    /// Grayscale with values 0-255, opaque.
+    R8,
+    /// Alpha with values 0-255, white, e.g. for font masks.
+    Alpha8,
+    /// `(red, green, blue, alpha)` as 16-bit little endian half floats.
+    Rgba16F,
+}
+
+impl Format {
+    /// Returns the number of bytes per pixel.
+    pub fn bytes_per_pixel(self) -> usize {
+        match self {
+            Format::Rgba8 | Format::Bgra8 => 4,
+            Format::Rgb8 => 3,
+            Format::R8 | Format::Alpha8 => 1,
+            Format::Rgba16F => 8,
+        }
+    }
+
+    /// Returns the number of bytes of an image of some size.
+    pub fn memory_size(self, size: [u32; 2]) -> usize {
+        size[0] as usize * size[1] as usize * self.bytes_per_pixel()
+    }
 }
 
 /// Implemented by texture operations.
@@ -1,9 +1,11 @@
 //! Image operations for textures.
 
+use super::Format;
+
 /// Flips the image vertically.
 pub fn flip_vertical(memory: &[u8], size: [u32; 2], channels: u8) -> Vec<u8> {
     let (width, height, channels) = (size[0] as usize, size[1] as usize, channels as usize);
-    let mut res = vec![0; width * height];
+    let mut res = vec![0; width * height * channels];
     let stride = width * channels;
     for y in 0..height {
         for x in 0..width {
@@ -32,3 +34,85 @@ pub fn alpha_to_rgba8(memory: &[u8], size: [u32; 2]) -> Vec<u8> {
     }
     res
 }
+
+/// Converts pixels of some format to rgba8.
+///
+/// Half floats are clamped to the range 0-1.
+pub fn to_rgba8(format: Format, memory: &[u8], size: [u32; 2]) -> Vec<u8> {
+    let n = size[0] as usize * size[1] as usize;
+    let mut res = Vec::with_capacity(n * 4);
+    let pixels = memory.chunks_exact(format.bytes_per_pixel()).take(n);
+    for p in pixels {
+        match format {
+            Format::Rgba8 => res.extend_from_slice(p),
+            Format::Rgb8 => res.extend_from_slice(&[p[0], p[1], p[2], 255]),
+            Format::Bgra8 => res.extend_from_slice(&[p[2], p[1], p[0], p[3]]),
+            Format::R8 => res.extend_from_slice(&[p[0], p[0], p[0], 255]),
+            Format::Alpha8 => res.extend_from_slice(&[255, 255, 255, p[0]]),
+            Format::Rgba16F => {
+                for c in p.chunks_exact(2) {
+                    let f = f16_to_f32(u16::from_le_bytes([c[0], c[1]]));
+                    res.push((f.clamp(0.0, 1.0) * 255.0).round() as u8);
+                }
+            }
+        }
+    }
+    res
+}
+
+// Converts a half float to a float.
+fn f16_to_f32(h: u16) -> f32 {
+    let sign = if h & 0x8000 == 0 { 1.0 } else { -1.0 };
+    let exp = i32::from((h >> 10) & 0x1f);
+    let mantissa = f32::from(h & 0x3ff);
+    sign * match exp {
+        0 => mantissa * 2f32.powi(-24),
+        31 if mantissa == 0.0 => f32::INFINITY,
+        31 => f32::NAN,
+        _ => (1.0 + mantissa / 1024.0) * 2f32.powi(exp - 15),
+    }
+}
+
+/// Multiplies the color channels of rgba8 pixels by alpha, in place.
+///
+/// When `srgb` is `true`, the colors are treated as sRGB
+/// and multiplied in linear color space, which is gamma-correct.
+pub fn premultiply_alpha(memory: &mut [u8], srgb: bool) {
+    let to_linear = |f: f32| {
+        if f <= 0.04045 {
+            f / 12.92
+        } else {
+            ((f + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    let to_srgb = |f: f32| {
+        if f <= 0.0031308 {
+            f * 12.92
+        } else {
+            1.055 * f.powf(1.0 / 2.4) - 0.055
+        }
+    };
+    for pixel in memory.chunks_exact_mut(4) {
+        let a = f32::from(pixel[3]) / 255.0;
+        for c in &mut pixel[..3] {
+            let f = f32::from(*c) / 255.0;
+            let f = if srgb {
+                to_srgb(to_linear(f) * a)
+            } else {
+                f * a
+            };
+            *c = (f * 255.0).round() as u8;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_flip_vertical() {
+        let memory = [1, 2, 3, 4, 5, 6, 7, 8];
+        assert_eq!(flip_vertical(&memory, [1, 2], 4), [5, 6, 7, 8, 1, 2, 3, 4]);
+    }
+}
@@ -37,6 +37,84 @@ impl Viewport {
     }
 }
 
+/// How a virtual resolution is scaled to a viewport, see `ViewportFit`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FitMode {
+    /// Scales uniformly to fit inside, with bars on two sides.
+    Fit,
+    /// Scales uniformly to cover, cutting off two sides.
+    Fill,
+    /// Scales each axis to match, distorting the aspect ratio.
+    Stretch,
+    /// Scales uniformly by a whole number of pixels to fit inside,
+    /// which keeps pixel art sharp.
+    ///
+    /// Uses scale 1 when the virtual resolution does not fit.
+    IntegerScale,
+}
+
+/// Fits a fixed virtual resolution into a viewport.
+///
+/// The fitted viewport is centered in the viewport rectangle,
+/// and `abs_transform` maps virtual coordinates to it.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ViewportFit {
+    /// The virtual resolution in virtual units.
+    pub virtual_size: [f64; 2],
+    /// The scaling mode.
+    pub mode: FitMode,
+}
+
+impl ViewportFit {
+    /// Creates a new viewport fit.
+    pub fn new(virtual_size: [f64; 2], mode: FitMode) -> ViewportFit {
+        ViewportFit { virtual_size, mode }
+    }
+
+    /// Computes the scale from virtual units to frame buffer pixels.
+    #[must_use]
+    pub fn scale(&self, viewport: &Viewport) -> [f64; 2] {
+        let [vw, vh] = self.virtual_size;
+        let sx = f64::from(viewport.rect[2]) / vw;
+        let sy = f64::from(viewport.rect[3]) / vh;
+        match self.mode {
+            FitMode::Fit => [sx.min(sy); 2],
+            FitMode::Fill => [sx.max(sy); 2],
+            FitMode::Stretch => [sx, sy],
+            FitMode::IntegerScale => [sx.min(sy).floor().max(1.0); 2],
+        }
+    }
+
+    /// Computes the viewport covered by the virtual resolution.
+    ///
+    /// The rectangle extends outside the viewport with `FitMode::Fill`.
+    #[must_use]
+    pub fn viewport(&self, viewport: &Viewport) -> Viewport {
+        let [sx, sy] = self.scale(viewport);
+        let w = (self.virtual_size[0] * sx).round() as i32;
+        let h = (self.virtual_size[1] * sy).round() as i32;
+        let [x, y, rw, rh] = viewport.rect;
+        Viewport {
+            rect: [x + (rw - w) / 2, y + (rh - h) / 2, w, h],
+            ..*viewport
+        }
+    }
+
+    /// Computes absolute transform for virtual coordinates,
+    /// which uses a row major 2x3 matrix.
+    ///
+    /// Like `Viewport::abs_transform`, the origin is in the upper left corner
+    /// and the y-axis points down, but the units are virtual units
+    /// for the viewport returned by `ViewportFit::viewport`.
+    #[must_use]
+    pub fn abs_transform<T: Float>(&self) -> [[T; 3]; 2] {
+        let sx = 2.0 / self.virtual_size[0];
+        let sy = -2.0 / self.virtual_size[1];
+        let f = |x| FromPrimitive::from_f64(x);
+        [[f(sx), f(0.0), f(-1.0)], [f(0.0), f(sy), f(1.0)]]
+    }
+}
+
 /// Convenience trait for floats.
 pub trait Float:
     'static
@@ -574,3 +652,26 @@ impl FromPrimitive for f32 {
         t as f32
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_viewport_fit() {
+        let viewport = Viewport {
+            rect: [0, 0, 1000, 500],
+            draw_size: [1000, 500],
+            window_size: [1000.0, 500.0],
+        };
+        let fit = |mode| {
+            ViewportFit::new([320.0, 180.0], mode)
+                .viewport(&viewport)
+                .rect
+        };
+        assert_eq!(fit(FitMode::Fit), [55, 0, 889, 500]);
+        assert_eq!(fit(FitMode::Fill), [0, -31, 1000, 563]);
+        assert_eq!(fit(FitMode::Stretch), [0, 0, 1000, 500]);
+        assert_eq!(fit(FitMode::IntegerScale), [180, 70, 640, 360]);
+    }
+}
@@ -327,6 +327,29 @@ pub trait AdvancedWindow: Window + Sized {
         self
     }
 
+    /// Sets whether to confine the cursor to the window.
+    ///
+    /// Unlike capturing, the cursor stays visible and absolute
+    /// mouse events are emitted, but the cursor can not leave the window.
+    /// This is useful for edge scrolling, for example in a strategy game.
+    ///
+    /// When the platform does not support confining the cursor,
+    /// the back-end may emulate it by moving the cursor back inside.
+    /// The default implementation has no effect.
+    fn set_confine_cursor(&mut self, value: bool) {
+        let _ = value;
+    }
+
+    /// Sets whether to confine the cursor to the window.
+    ///
+    /// This method moves the current window data,
+    /// unlike [`set_confine_cursor()`](#tymethod.set_confine_cursor), so
+    /// that it can be used in method chaining.
+    fn confine_cursor(mut self, value: bool) -> Self {
+        self.set_confine_cursor(value);
+        self
+    }
+
     /// Shows the window.
     ///
     /// If the platform does not support this, it will have no effect.